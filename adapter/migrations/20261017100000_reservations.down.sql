@@ -0,0 +1,3 @@
+-- Add down migration script here
+DROP INDEX IF EXISTS reservations_book_id_reserved_at_idx;
+DROP TABLE IF EXISTS reservations;
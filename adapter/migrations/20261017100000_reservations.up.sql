@@ -0,0 +1,22 @@
+-- Add up migration script here
+-- 貸出中の蔵書に対する予約(待ち行列)を管理するテーブルを作成
+-- ready_at/expires_atが設定されている予約は、返却された蔵書を取り置いている状態を表す
+CREATE TABLE IF NOT EXISTS reservations (
+    reservation_id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    book_id UUID NOT NULL,
+    user_id UUID NOT NULL,
+    reserved_at TIMESTAMP(3) WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP(3),
+    ready_at TIMESTAMP(3) WITH TIME ZONE,
+    expires_at TIMESTAMP(3) WITH TIME ZONE,
+
+    UNIQUE (book_id, user_id),
+    FOREIGN KEY (book_id) REFERENCES books(book_id)
+        ON UPDATE CASCADE
+        ON DELETE CASCADE,
+    FOREIGN KEY (user_id) REFERENCES users(user_id)
+        ON UPDATE CASCADE
+        ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS reservations_book_id_reserved_at_idx
+    ON reservations (book_id, reserved_at);
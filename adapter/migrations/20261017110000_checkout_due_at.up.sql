@@ -0,0 +1,12 @@
+-- Add up migration script here
+-- 貸出に返却期限を追加する
+-- 既存の貸出には、貸出日から14日後を返却期限として設定する
+ALTER TABLE checkouts ADD COLUMN IF NOT EXISTS due_at TIMESTAMP(3) WITH TIME ZONE;
+UPDATE checkouts SET due_at = checked_out_at + INTERVAL '14 days' WHERE due_at IS NULL;
+ALTER TABLE checkouts ALTER COLUMN due_at SET NOT NULL;
+
+ALTER TABLE returned_checkouts ADD COLUMN IF NOT EXISTS due_at TIMESTAMP(3) WITH TIME ZONE;
+UPDATE returned_checkouts SET due_at = checked_out_at + INTERVAL '14 days' WHERE due_at IS NULL;
+ALTER TABLE returned_checkouts ALTER COLUMN due_at SET NOT NULL;
+
+CREATE INDEX IF NOT EXISTS checkouts_due_at_idx ON checkouts (due_at);
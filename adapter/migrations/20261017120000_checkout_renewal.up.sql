@@ -0,0 +1,4 @@
+-- Add up migration script here
+-- 貸出を延長した回数を記録する
+ALTER TABLE checkouts ADD COLUMN IF NOT EXISTS renewal_count INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE returned_checkouts ADD COLUMN IF NOT EXISTS renewal_count INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,3 @@
+-- Add down migration script here
+DROP TRIGGER IF EXISTS borrowing_policies_updated_at_trigger ON borrowing_policies;
+DROP TABLE IF EXISTS borrowing_policies;
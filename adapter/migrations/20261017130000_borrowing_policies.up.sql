@@ -0,0 +1,19 @@
+-- Add up migration script here
+-- ロールごとの貸出ポリシーを管理するテーブルを作成
+-- ポリシーが登録されていないロールには、アプリケーションの設定値が既定値として適用される
+CREATE TABLE IF NOT EXISTS borrowing_policies (
+    role_id UUID PRIMARY KEY,
+    max_loans INTEGER NOT NULL CHECK (max_loans >= 0),
+    loan_period_days INTEGER NOT NULL CHECK (loan_period_days > 0),
+    max_renewals INTEGER NOT NULL CHECK (max_renewals >= 0),
+    created_at TIMESTAMP(3) WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP(3),
+    updated_at TIMESTAMP(3) WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP(3),
+
+    FOREIGN KEY (role_id) REFERENCES roles(role_id)
+        ON UPDATE CASCADE
+        ON DELETE CASCADE
+);
+
+CREATE TRIGGER borrowing_policies_updated_at_trigger
+    BEFORE UPDATE ON borrowing_policies FOR EACH ROW
+    EXECUTE PROCEDURE set_updated_at();
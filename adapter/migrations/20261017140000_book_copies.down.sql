@@ -0,0 +1,11 @@
+-- Add down migration script here
+ALTER TABLE returned_checkouts DROP COLUMN IF EXISTS copy_id;
+
+DROP INDEX IF EXISTS checkouts_book_id_idx;
+ALTER TABLE checkouts DROP CONSTRAINT IF EXISTS checkouts_copy_id_fkey;
+ALTER TABLE checkouts DROP CONSTRAINT IF EXISTS checkouts_copy_id_key;
+ALTER TABLE checkouts DROP COLUMN IF EXISTS copy_id;
+ALTER TABLE checkouts ADD CONSTRAINT checkouts_book_id_key UNIQUE (book_id);
+
+DROP INDEX IF EXISTS book_copies_book_id_idx;
+DROP TABLE IF EXISTS book_copies;
@@ -0,0 +1,39 @@
+-- Add up migration script here
+-- 書誌情報(books)と所蔵している冊子(book_copies)を分ける
+CREATE TABLE IF NOT EXISTS book_copies (
+    copy_id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    book_id UUID NOT NULL,
+    created_at TIMESTAMP(3) WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP(3),
+
+    FOREIGN KEY (book_id) REFERENCES books(book_id)
+        ON UPDATE CASCADE
+        ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS book_copies_book_id_idx ON book_copies (book_id);
+
+-- 既存の蔵書には1冊ずつ冊子を登録する
+INSERT INTO book_copies (book_id, created_at)
+SELECT book_id, created_at FROM books;
+
+-- 貸出は蔵書ではなく冊子に対して行う
+-- 同じ蔵書を複数人に貸し出せるよう、book_idのUNIQUE制約をcopy_idに移す
+ALTER TABLE checkouts ADD COLUMN IF NOT EXISTS copy_id UUID;
+UPDATE checkouts AS c SET copy_id = bc.copy_id
+FROM book_copies AS bc WHERE bc.book_id = c.book_id;
+ALTER TABLE checkouts ALTER COLUMN copy_id SET NOT NULL;
+ALTER TABLE checkouts DROP CONSTRAINT IF EXISTS checkouts_book_id_key;
+ALTER TABLE checkouts ADD CONSTRAINT checkouts_copy_id_key UNIQUE (copy_id);
+ALTER TABLE checkouts ADD CONSTRAINT checkouts_copy_id_fkey
+    FOREIGN KEY (copy_id) REFERENCES book_copies(copy_id)
+        ON UPDATE CASCADE
+        ON DELETE CASCADE;
+CREATE INDEX IF NOT EXISTS checkouts_book_id_idx ON checkouts (book_id);
+
+-- 返却済みの貸出にも冊子を記録する
+-- 削除済みの蔵書の履歴には、対応する冊子が存在しないためダミーのIDを割り当てる
+ALTER TABLE returned_checkouts ADD COLUMN IF NOT EXISTS copy_id UUID;
+UPDATE returned_checkouts AS rc SET copy_id = bc.copy_id
+FROM book_copies AS bc WHERE bc.book_id = rc.book_id;
+UPDATE returned_checkouts SET copy_id = gen_random_uuid() WHERE copy_id IS NULL;
+ALTER TABLE returned_checkouts ALTER COLUMN copy_id SET NOT NULL;
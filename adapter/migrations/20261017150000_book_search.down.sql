@@ -0,0 +1,10 @@
+-- Add down migration script here
+DROP INDEX IF EXISTS books_title_sort_idx;
+DROP INDEX IF EXISTS books_search_text_trgm_idx;
+DROP INDEX IF EXISTS books_search_title_trgm_idx;
+ALTER TABLE books DROP COLUMN IF EXISTS search_text;
+ALTER TABLE books DROP COLUMN IF EXISTS search_title;
+ALTER TABLE books DROP COLUMN IF EXISTS title_sort;
+ALTER TABLE books DROP COLUMN IF EXISTS title_kana;
+DROP FUNCTION IF EXISTS search_like_pattern(TEXT);
+DROP FUNCTION IF EXISTS normalize_search_text(TEXT);
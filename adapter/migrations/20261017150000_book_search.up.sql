@@ -0,0 +1,52 @@
+-- Add up migration script here
+-- 日本語の表記ゆれを吸収した蔵書検索のための列とインデックスを追加する
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+-- 検索用に文字列を正規化する関数
+-- 1. NFKC正規化で全角英数字・半角カナなどの表記を揃える
+-- 2. 英字を小文字に揃える
+-- 3. カタカナをひらがなに揃える
+CREATE OR REPLACE FUNCTION normalize_search_text(value TEXT) RETURNS TEXT AS $$
+    SELECT translate(
+        lower(normalize(COALESCE(value, ''), NFKC)),
+        'ァアィイゥウェエォオカガキギクグケゲコゴサザシジスズセゼソゾタダチヂッツヅテデトドナニヌネノハバパヒビピフブプヘベペホボポマミムメモャヤュユョヨラリルレロヮワヰヱヲンヴヵヶヽヾ',
+        'ぁあぃいぅうぇえぉおかがきぎくぐけげこごさざしじすずせぜそぞただちぢっつづてでとどなにぬねのはばぱひびぴふぶぷへべぺほぼぽまみむめもゃやゅゆょよらりるれろゎわゐゑをんゔゕゖゝゞ'
+    );
+$$ LANGUAGE SQL IMMUTABLE PARALLEL SAFE;
+
+-- 検索語を正規化し、ワイルドカード文字をエスケープした部分一致のパターンを作る関数
+CREATE OR REPLACE FUNCTION search_like_pattern(value TEXT) RETURNS TEXT AS $$
+    SELECT '%' || replace(replace(replace(
+        normalize_search_text(value),
+        '\', '\\'), '%', '\%'), '_', '\_') || '%';
+$$ LANGUAGE SQL IMMUTABLE PARALLEL SAFE;
+
+-- 書名の読み(よみがな)を任意で登録できるようにする
+ALTER TABLE books ADD COLUMN IF NOT EXISTS title_kana VARCHAR(255);
+
+-- 五十音順の並び替えに使う列。読みが登録されていない場合は書名を使う
+ALTER TABLE books ADD COLUMN IF NOT EXISTS title_sort TEXT
+    GENERATED ALWAYS AS (
+        normalize_search_text(COALESCE(NULLIF(title_kana, ''), title))
+    ) STORED;
+
+-- 検索の順位付けで書名の一致を優先するための列
+ALTER TABLE books ADD COLUMN IF NOT EXISTS search_title TEXT
+    GENERATED ALWAYS AS (
+        normalize_search_text(title || ' ' || COALESCE(title_kana, ''))
+    ) STORED;
+
+-- 書名・読み・著者・説明をまとめた検索対象の列
+ALTER TABLE books ADD COLUMN IF NOT EXISTS search_text TEXT
+    GENERATED ALWAYS AS (
+        normalize_search_text(
+            title || ' ' || COALESCE(title_kana, '') || ' ' || author || ' ' || description
+        )
+    ) STORED;
+
+CREATE INDEX IF NOT EXISTS books_search_title_trgm_idx
+    ON books USING GIN (search_title gin_trgm_ops);
+CREATE INDEX IF NOT EXISTS books_search_text_trgm_idx
+    ON books USING GIN (search_text gin_trgm_ops);
+CREATE INDEX IF NOT EXISTS books_title_sort_idx
+    ON books (title_sort COLLATE "C");
@@ -0,0 +1,3 @@
+-- Add down migration script here
+-- 正規化前の表記は保存していないため、元に戻す処理はない
+SELECT 1;
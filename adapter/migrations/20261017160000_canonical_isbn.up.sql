@@ -0,0 +1,41 @@
+-- Add up migration script here
+-- 登録済みの蔵書のISBNを、ハイフンを除いたISBN-13の形式(正規形)に揃える
+-- チェックディジットが正しいものだけを変換し、それ以外はそのまま残す
+
+-- 1. ISBN-13：ハイフンと空白を取り除く
+UPDATE books AS b
+SET isbn = d.digits
+FROM (
+    SELECT book_id, regexp_replace(isbn, '[- ]', '', 'g') AS digits
+    FROM books
+) AS d
+WHERE b.book_id = d.book_id
+AND d.digits ~ '^97[89][0-9]{10}$'
+AND b.isbn <> d.digits
+AND (
+    SELECT (10 - SUM(substr(d.digits, i, 1)::int * CASE WHEN i % 2 = 1 THEN 1 ELSE 3 END) % 10) % 10
+    FROM generate_series(1, 12) AS i
+) = substr(d.digits, 13, 1)::int;
+
+-- 2. ISBN-10：先頭に978を付け、チェックディジットを計算し直す
+UPDATE books AS b
+SET isbn = d.body || (
+    SELECT (10 - SUM(substr(d.body, i, 1)::int * CASE WHEN i % 2 = 1 THEN 1 ELSE 3 END) % 10) % 10
+    FROM generate_series(1, 12) AS i
+)::text
+FROM (
+    SELECT
+        book_id,
+        upper(regexp_replace(isbn, '[- ]', '', 'g')) AS digits,
+        '978' || left(regexp_replace(isbn, '[- ]', '', 'g'), 9) AS body
+    FROM books
+) AS d
+WHERE b.book_id = d.book_id
+AND d.digits ~ '^[0-9]{9}[0-9X]$'
+AND (
+    (
+        SELECT SUM(substr(d.digits, i, 1)::int * (11 - i))
+        FROM generate_series(1, 9) AS i
+    )
+    + CASE WHEN right(d.digits, 1) = 'X' THEN 10 ELSE right(d.digits, 1)::int END
+) % 11 = 0;
@@ -0,0 +1,4 @@
+-- Add down migration script here
+DROP TABLE IF EXISTS user_recovery_codes;
+DROP TRIGGER IF EXISTS user_totp_updated_at_trigger ON user_totp;
+DROP TABLE IF EXISTS user_totp;
@@ -0,0 +1,34 @@
+-- Add up migration script here
+-- ユーザーごとのTOTPの秘密鍵を管理するテーブルを作成
+-- 登録を開始した時点で作成し、認証アプリのコードを検証できた時点でenabled_atを設定する
+CREATE TABLE IF NOT EXISTS user_totp (
+    user_id UUID PRIMARY KEY,
+    secret VARCHAR(255) NOT NULL,
+    enabled_at TIMESTAMP(3) WITH TIME ZONE,
+    created_at TIMESTAMP(3) WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP(3),
+    updated_at TIMESTAMP(3) WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP(3),
+
+    FOREIGN KEY (user_id) REFERENCES users(user_id)
+        ON UPDATE CASCADE
+        ON DELETE CASCADE
+);
+
+CREATE TRIGGER user_totp_updated_at_trigger
+    BEFORE UPDATE ON user_totp FOR EACH ROW
+    EXECUTE PROCEDURE set_updated_at();
+
+-- 認証アプリを使えなくなった場合のリカバリーコード
+-- コードはハッシュ化して保存し、使用済みのコードにはused_atを設定する
+CREATE TABLE IF NOT EXISTS user_recovery_codes (
+    recovery_code_id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL,
+    code_hash VARCHAR(255) NOT NULL,
+    used_at TIMESTAMP(3) WITH TIME ZONE,
+    created_at TIMESTAMP(3) WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP(3),
+
+    FOREIGN KEY (user_id) REFERENCES users(user_id)
+        ON UPDATE CASCADE
+        ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS user_recovery_codes_user_id_idx ON user_recovery_codes (user_id);
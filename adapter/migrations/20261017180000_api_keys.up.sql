@@ -0,0 +1,20 @@
+-- Add up migration script here
+-- スクリプトや外部サービスとの連携に使う、ユーザーごとのAPIキーを管理するテーブルを作成
+-- キーそのものは保存せず、SHA-256のハッシュと識別用の先頭の数文字のみを保存する
+CREATE TABLE IF NOT EXISTS api_keys (
+    api_key_id UUID PRIMARY KEY,
+    user_id UUID NOT NULL,
+    name VARCHAR(255) NOT NULL,
+    key_prefix VARCHAR(32) NOT NULL,
+    key_hash VARCHAR(64) NOT NULL UNIQUE,
+    scopes TEXT[] NOT NULL,
+    expires_at TIMESTAMP(3) WITH TIME ZONE,
+    last_used_at TIMESTAMP(3) WITH TIME ZONE,
+    created_at TIMESTAMP(3) WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP(3),
+
+    FOREIGN KEY (user_id) REFERENCES users(user_id)
+        ON UPDATE CASCADE
+        ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS api_keys_user_id_idx ON api_keys (user_id);
@@ -0,0 +1,11 @@
+-- Add up migration script here
+-- ロールに付与する権限("book.update.any"など)を、rolesテーブルに文字列の配列として保存する
+-- Adminロールは、保存された内容によらずすべての権限を持つ
+ALTER TABLE roles ADD COLUMN IF NOT EXISTS permissions TEXT[] NOT NULL DEFAULT '{}';
+
+-- 蔵書の登録はこれまでどおりAdminとUserのどちらにも許可する
+-- 組み込みのロールの権限は変更できないため、ここで付与しておく
+UPDATE roles
+SET permissions = array_append(permissions, 'book.create')
+WHERE name IN ('Admin', 'User')
+AND NOT ('book.create' = ANY(permissions));
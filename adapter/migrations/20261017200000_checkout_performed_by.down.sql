@@ -0,0 +1,4 @@
+-- Add down migration script here
+ALTER TABLE returned_checkouts DROP COLUMN IF EXISTS returned_performed_by;
+ALTER TABLE returned_checkouts DROP COLUMN IF EXISTS checked_out_performed_by;
+ALTER TABLE checkouts DROP COLUMN IF EXISTS checked_out_performed_by;
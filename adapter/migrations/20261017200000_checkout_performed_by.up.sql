@@ -0,0 +1,14 @@
+-- Add up migration script here
+-- 貸出・返却の操作を行ったユーザーを、借りたユーザーとは別に記録する
+-- 職員が代理で貸出・返却した場合に、借りたユーザーと異なる値となる
+ALTER TABLE checkouts ADD COLUMN IF NOT EXISTS checked_out_performed_by UUID;
+UPDATE checkouts SET checked_out_performed_by = user_id WHERE checked_out_performed_by IS NULL;
+ALTER TABLE checkouts ALTER COLUMN checked_out_performed_by SET NOT NULL;
+
+ALTER TABLE returned_checkouts ADD COLUMN IF NOT EXISTS checked_out_performed_by UUID;
+UPDATE returned_checkouts SET checked_out_performed_by = user_id WHERE checked_out_performed_by IS NULL;
+ALTER TABLE returned_checkouts ALTER COLUMN checked_out_performed_by SET NOT NULL;
+
+ALTER TABLE returned_checkouts ADD COLUMN IF NOT EXISTS returned_performed_by UUID;
+UPDATE returned_checkouts SET returned_performed_by = user_id WHERE returned_performed_by IS NULL;
+ALTER TABLE returned_checkouts ALTER COLUMN returned_performed_by SET NOT NULL;
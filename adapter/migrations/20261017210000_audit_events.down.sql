@@ -0,0 +1,5 @@
+-- Add down migration script here
+DROP TRIGGER IF EXISTS audit_events_no_truncate_trigger ON audit_events;
+DROP TRIGGER IF EXISTS audit_events_append_only_trigger ON audit_events;
+DROP FUNCTION IF EXISTS reject_audit_event_modification();
+DROP TABLE IF EXISTS audit_events;
@@ -0,0 +1,32 @@
+-- Add up migration script here
+-- 状態を変更する操作の監査ログ
+-- 操作を行ったユーザーが削除されても記録を残すため、actor_idには外部キー制約を付けない
+CREATE TABLE IF NOT EXISTS audit_events (
+    audit_event_id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    actor_id UUID NOT NULL,
+    action VARCHAR(64) NOT NULL,
+    target_id VARCHAR(255) NOT NULL,
+    before_state JSONB,
+    after_state JSONB,
+    request_id VARCHAR(128),
+    occurred_at TIMESTAMP(3) WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP(3)
+);
+
+CREATE INDEX IF NOT EXISTS audit_events_occurred_at_idx ON audit_events (occurred_at DESC);
+CREATE INDEX IF NOT EXISTS audit_events_actor_id_idx ON audit_events (actor_id);
+CREATE INDEX IF NOT EXISTS audit_events_target_id_idx ON audit_events (target_id);
+
+-- 監査ログは追記のみとし、更新・削除を拒否する
+CREATE OR REPLACE FUNCTION reject_audit_event_modification() RETURNS trigger AS '
+    BEGIN
+        RAISE EXCEPTION ''audit_events is append-only'';
+    END;
+    ' LANGUAGE 'plpgsql';
+
+CREATE TRIGGER audit_events_append_only_trigger
+    BEFORE UPDATE OR DELETE ON audit_events FOR EACH ROW
+    EXECUTE PROCEDURE reject_audit_event_modification();
+
+CREATE TRIGGER audit_events_no_truncate_trigger
+    BEFORE TRUNCATE ON audit_events FOR EACH STATEMENT
+    EXECUTE PROCEDURE reject_audit_event_modification();
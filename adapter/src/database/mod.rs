@@ -36,6 +36,18 @@ impl ConnectionPool {
     }
 }
 
+// トランザクション分離レベルをSERIALIZABLEにする
+// 貸出・返却・予約のように、複数のテーブルの状態を確認してから書き込む処理で使う
+pub(crate) async fn set_transaction_serializable(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+) -> AppResult<()> {
+    sqlx::query!("SET TRANSACTION ISOLATION LEVEL SERIALIZABLE")
+        .execute(&mut **tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+    Ok(())
+}
+
 // 4) Change the return value to `ConnectionPool` and modify the internal implementation accordingly.
 pub fn connect_database_with(cfg: &DatabaseConfig) -> ConnectionPool {
     ConnectionPool(PgPool::connect_lazy_with(make_pg_connect_options(cfg)))
@@ -0,0 +1,77 @@
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+use kernel::model::{
+    api_key::{ApiKey, ApiKeyScope, AuthorizedApiKey},
+    id::{ApiKeyId, UserId},
+};
+use shared::error::AppError;
+
+// スコープはDBに文字列("books:read"など)の配列として保存する
+fn parse_scopes(scopes: Vec<String>) -> Result<Vec<ApiKeyScope>, AppError> {
+    scopes
+        .iter()
+        .map(|scope| {
+            ApiKeyScope::from_str(scope)
+                .map_err(|e| AppError::ConversionEntityError(e.to_string()))
+        })
+        .collect()
+}
+
+pub struct ApiKeyRow {
+    pub api_key_id: ApiKeyId,
+    pub name: String,
+    pub key_prefix: String,
+    pub scopes: Vec<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl TryFrom<ApiKeyRow> for ApiKey {
+    type Error = AppError;
+
+    fn try_from(value: ApiKeyRow) -> Result<Self, Self::Error> {
+        let ApiKeyRow {
+            api_key_id,
+            name,
+            key_prefix,
+            scopes,
+            expires_at,
+            last_used_at,
+            created_at,
+        } = value;
+        Ok(ApiKey {
+            id: api_key_id,
+            name,
+            key_prefix,
+            scopes: parse_scopes(scopes)?,
+            expires_at,
+            last_used_at,
+            created_at,
+        })
+    }
+}
+
+// リクエストに使われたAPIキーを検証する際に使う型
+pub struct AuthorizedApiKeyRow {
+    pub api_key_id: ApiKeyId,
+    pub user_id: UserId,
+    pub scopes: Vec<String>,
+}
+
+impl AuthorizedApiKeyRow {
+    pub fn into_authorized_api_key(self, mfa_required: bool) -> Result<AuthorizedApiKey, AppError> {
+        let AuthorizedApiKeyRow {
+            api_key_id,
+            user_id,
+            scopes,
+        } = self;
+        Ok(AuthorizedApiKey {
+            id: api_key_id,
+            user_id,
+            scopes: parse_scopes(scopes)?,
+            mfa_satisfied: !mfa_required,
+        })
+    }
+}
@@ -0,0 +1,58 @@
+use std::str::FromStr;
+
+use kernel::model::{
+    audit::{AuditAction, AuditEvent},
+    id::{AuditEventId, UserId},
+};
+use shared::error::AppError;
+use sqlx::types::chrono::{DateTime, Utc};
+
+// before・afterはJSONBの列をテキストとして取得し、ここでJSONに変換する
+pub struct AuditEventRow {
+    pub total: i64,
+    pub audit_event_id: AuditEventId,
+    pub actor_id: UserId,
+    pub action: String,
+    pub target_id: String,
+    pub before_state: Option<String>,
+    pub after_state: Option<String>,
+    pub request_id: Option<String>,
+    pub occurred_at: DateTime<Utc>,
+}
+
+fn parse_state(state: Option<String>) -> Result<Option<serde_json::Value>, AppError> {
+    state
+        .map(|s| serde_json::from_str(&s))
+        .transpose()
+        .map_err(|e| AppError::ConversionEntityError(e.to_string()))
+}
+
+impl TryFrom<AuditEventRow> for AuditEvent {
+    type Error = AppError;
+
+    fn try_from(value: AuditEventRow) -> Result<Self, Self::Error> {
+        let AuditEventRow {
+            total: _,
+            audit_event_id,
+            actor_id,
+            action,
+            target_id,
+            before_state,
+            after_state,
+            request_id,
+            occurred_at,
+        } = value;
+
+        Ok(AuditEvent {
+            id: audit_event_id,
+            actor: actor_id,
+            action: AuditAction::from_str(&action)
+                .map_err(|e| AppError::ConversionEntityError(e.to_string()))?,
+            target_id,
+            before: parse_state(before_state)?,
+            after: parse_state(after_state)?,
+            request_id,
+            occurred_at,
+        })
+    }
+}
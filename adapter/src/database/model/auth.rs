@@ -1,10 +1,14 @@
 // 認証に使うデータ型の定義
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
 use shared::error::{AppError, AppResult};
-use std::str::FromStr;
 
 use kernel::model::{
-    auth::{event::CreateToken, AccessToken},
-    id::UserId,
+    auth::{
+        event::CreateToken, AccessToken, AuthorizedSession, PasswordResetToken, RefreshToken,
+        Session,
+    },
+    id::{SessionId, UserId},
 };
 
 use crate::redis::model::{RedisKey, RedisValue};
@@ -14,18 +18,117 @@ pub struct UserItem{
     pub password_hash: String,
 }
 
+// アクセストークン -> ユーザーとセッション
 pub struct AuthorizationKey(String);
-pub struct AuthorizedUserId(UserId);
+#[derive(Serialize, Deserialize)]
+pub struct AuthorizedUserId {
+    pub user_id: UserId,
+    pub session_id: SessionId,
+    // 2段階認証の導入前に発行されたトークンは、2段階認証を経ていないものとして扱う
+    #[serde(default)]
+    pub mfa_verified: bool,
+}
+
+// リフレッシュトークン -> セッション
+// 再発行に使われた後も有効期間の間は残し、使用済みのトークンの再利用を検知できるようにする
+pub struct RefreshTokenKey(String);
+// 再発行に使われたリフレッシュトークン -> セッション
+pub struct UsedRefreshTokenKey(String);
+pub struct StoredSessionId(pub SessionId);
+
+// ユーザー -> ユーザーのセッションの集合
+// 期限切れのセッションは一覧の取得時に取り除く
+pub struct UserSessionsKey(UserId);
+
+// セッション -> セッションの現在の状態
+// セッションを無効にする際に、発行済みのアクセストークンを削除するために保持する
+pub struct SessionKey(SessionId);
+#[derive(Serialize, Deserialize)]
+pub struct SessionState {
+    pub user_id: UserId,
+    pub access_token: String,
+    pub created_at: DateTime<Utc>,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    #[serde(default)]
+    pub mfa_verified: bool,
+}
+
+impl SessionState {
+    pub fn into_session(self, session_id: SessionId, current: &AccessToken) -> Session {
+        Session {
+            id: session_id,
+            created_at: self.created_at,
+            user_agent: self.user_agent,
+            ip_address: self.ip_address,
+            current: self.access_token == current.0,
+        }
+    }
+}
+
+// トークンの発行時にRedisへ保存する、キーとバリューの組
+pub struct TokenEntries {
+    pub authorization: (AuthorizationKey, AuthorizedUserId),
+    pub refresh: (RefreshTokenKey, StoredSessionId),
+    pub session: (SessionKey, SessionState),
+}
+
+// ログインに失敗した回数
+// メールアドレスごとと、接続元のIPアドレスごとに数える
+pub struct LoginFailureKey(String);
+pub struct LoginFailureCount(pub u64);
+
+impl LoginFailureKey {
+    // 大文字・小文字を変えて試されても同じメールアドレスとして数える
+    pub fn email(email: &str) -> Self {
+        Self(format!("email:{}", email.trim().to_lowercase()))
+    }
+
+    pub fn ip_address(ip_address: &str) -> Self {
+        Self(format!("ip:{}", ip_address))
+    }
+}
+
+// パスワードの再設定用のトークン -> ユーザー
+// トークンは取り出すと同時に削除し、一度しか使えないようにする
+pub struct PasswordResetTokenKey(String);
+pub struct PasswordResetUserId(pub UserId);
+
+// ユーザー -> 発行済みのパスワードの再設定用のトークン
+// 新しいトークンを発行する際に、以前のトークンを無効にするために保持する
+pub struct UserPasswordResetKey(UserId);
+pub struct StoredPasswordResetToken(pub String);
 
 // token内容をコピーする
-pub fn from(event:CreateToken) -> (AuthorizationKey, AuthorizedUserId){
-    (
-        AuthorizationKey(event.access_token),
-        AuthorizedUserId(event.user_id),
-    )
+pub fn from(event: CreateToken) -> TokenEntries {
+    TokenEntries {
+        authorization: (
+            AuthorizationKey(event.access_token.clone()),
+            AuthorizedUserId {
+                user_id: event.user_id,
+                session_id: event.session_id,
+                mfa_verified: event.mfa_verified,
+            },
+        ),
+        refresh: (
+            RefreshTokenKey(event.refresh_token),
+            StoredSessionId(event.session_id),
+        ),
+        session: (
+            SessionKey(event.session_id),
+            SessionState {
+                user_id: event.user_id,
+                access_token: event.access_token,
+                created_at: event.created_at,
+                user_agent: event.user_agent,
+                ip_address: event.ip_address,
+                mfa_verified: event.mfa_verified,
+            },
+        ),
+    }
 }
 
-// From: 
+// From:
 impl From<AuthorizationKey> for AccessToken {
     fn from(key: AuthorizationKey) -> Self {
         // 構造体（タプル）を指定する：要素が1つでも0と定義しなければならない
@@ -45,6 +148,36 @@ impl From<&AccessToken> for AuthorizationKey {
     }
 }
 
+impl From<String> for AuthorizationKey {
+    fn from(token: String) -> Self {
+        Self(token)
+    }
+}
+
+impl From<RefreshTokenKey> for RefreshToken {
+    fn from(key: RefreshTokenKey) -> Self {
+        Self(key.0)
+    }
+}
+
+impl From<&RefreshToken> for RefreshTokenKey {
+    fn from(token: &RefreshToken) -> Self {
+        Self(token.0.clone())
+    }
+}
+
+impl From<&RefreshToken> for UsedRefreshTokenKey {
+    fn from(token: &RefreshToken) -> Self {
+        Self(token.0.clone())
+    }
+}
+
+impl From<SessionId> for SessionKey {
+    fn from(session_id: SessionId) -> Self {
+        Self(session_id)
+    }
+}
+
 impl RedisKey for AuthorizationKey {
     type Value = AuthorizedUserId;
 
@@ -53,24 +186,185 @@ impl RedisKey for AuthorizationKey {
     }
 }
 
+impl RedisKey for RefreshTokenKey {
+    type Value = StoredSessionId;
+
+    fn inner(&self) -> String {
+        format!("refresh_token:{}", self.0)
+    }
+}
+
+impl RedisKey for UsedRefreshTokenKey {
+    type Value = StoredSessionId;
+
+    fn inner(&self) -> String {
+        format!("refresh_token_used:{}", self.0)
+    }
+}
+
+impl From<UserId> for UserSessionsKey {
+    fn from(user_id: UserId) -> Self {
+        Self(user_id)
+    }
+}
+
+impl RedisKey for UserSessionsKey {
+    type Value = StoredSessionId;
+
+    fn inner(&self) -> String {
+        format!("user_sessions:{}", self.0)
+    }
+}
+
+impl RedisKey for SessionKey {
+    type Value = SessionState;
+
+    fn inner(&self) -> String {
+        format!("session:{}", self.0)
+    }
+}
+
+impl From<&PasswordResetToken> for PasswordResetTokenKey {
+    fn from(token: &PasswordResetToken) -> Self {
+        Self(token.0.clone())
+    }
+}
+
+impl From<String> for PasswordResetTokenKey {
+    fn from(token: String) -> Self {
+        Self(token)
+    }
+}
+
+impl From<UserId> for UserPasswordResetKey {
+    fn from(user_id: UserId) -> Self {
+        Self(user_id)
+    }
+}
+
+impl RedisKey for PasswordResetTokenKey {
+    type Value = PasswordResetUserId;
+
+    fn inner(&self) -> String {
+        format!("password_reset:{}", self.0)
+    }
+}
+
+impl RedisKey for UserPasswordResetKey {
+    type Value = StoredPasswordResetToken;
+
+    fn inner(&self) -> String {
+        format!("password_reset_user:{}", self.0)
+    }
+}
+
+impl RedisValue for PasswordResetUserId {
+    fn inner(&self) -> String {
+        self.0.to_string()
+    }
+}
+
+impl TryFrom<String> for PasswordResetUserId {
+    type Error = AppError;
+
+    fn try_from(s: String) -> AppResult<Self> {
+        Ok(Self(s.parse::<UserId>().map_err(|e| {
+            AppError::ConversionEntityError(e.to_string())
+        })?))
+    }
+}
+
+impl RedisValue for StoredPasswordResetToken {
+    fn inner(&self) -> String {
+        self.0.clone()
+    }
+}
+
+impl TryFrom<String> for StoredPasswordResetToken {
+    type Error = AppError;
+
+    fn try_from(s: String) -> AppResult<Self> {
+        Ok(Self(s))
+    }
+}
+
+impl RedisKey for LoginFailureKey {
+    type Value = LoginFailureCount;
+
+    fn inner(&self) -> String {
+        format!("login_failures:{}", self.0)
+    }
+}
+
+impl RedisValue for LoginFailureCount {
+    fn inner(&self) -> String {
+        self.0.to_string()
+    }
+}
+
+impl TryFrom<String> for LoginFailureCount {
+    type Error = AppError;
+
+    fn try_from(s: String) -> AppResult<Self> {
+        Ok(Self(s.parse::<u64>().map_err(|e| {
+            AppError::ConversionEntityError(e.to_string())
+        })?))
+    }
+}
+
+// ユーザーIDとセッションIDはどちらもUUIDのため、シリアライズは失敗しない
 impl RedisValue for AuthorizedUserId {
     fn inner(&self) -> String{
+        serde_json::to_string(self).unwrap_or_default()
+    }
+}
+
+impl RedisValue for StoredSessionId {
+    fn inner(&self) -> String {
         self.0.to_string()
     }
 }
 
+impl RedisValue for SessionState {
+    fn inner(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+}
+
 impl TryFrom<String> for AuthorizedUserId {
     type Error = AppError;
 
     fn try_from(s: String) -> AppResult<Self> {
-        Ok(Self(UserId::from_str(&s).map_err(|e|{
+        serde_json::from_str(&s)
+            .map_err(|e| AppError::ConversionEntityError(e.to_string()))
+    }
+}
+
+impl TryFrom<String> for StoredSessionId {
+    type Error = AppError;
+
+    fn try_from(s: String) -> AppResult<Self> {
+        Ok(Self(s.parse::<SessionId>().map_err(|e| {
             AppError::ConversionEntityError(e.to_string())
         })?))
     }
 }
 
+impl TryFrom<String> for SessionState {
+    type Error = AppError;
+
+    fn try_from(s: String) -> AppResult<Self> {
+        serde_json::from_str(&s)
+            .map_err(|e| AppError::ConversionEntityError(e.to_string()))
+    }
+}
+
 impl AuthorizedUserId{
-    pub fn into_inner(self) -> UserId {
-        self.0
+    // 2段階認証が必須でない場合は、2段階認証を経ていないセッションも条件を満たす
+    pub fn into_session(self, mfa_required: bool) -> AuthorizedSession {
+        AuthorizedSession {
+            user_id: self.user_id,
+            mfa_satisfied: self.mfa_verified || !mfa_required,
+        }
     }
-}
\ No newline at end of file
+}
@@ -1,37 +1,47 @@
 use chrono::{DateTime, Utc};
 use kernel::model::{
     book::{Book, Checkout}, 
-    id::{BookId, CheckoutId, UserId},
-    user::{BookOwner, CheckoutUser, User},
+    id::{BookId, CheckoutId, CopyId, UserId},
+    user::{BookOwner, CheckoutUser},
 };
 
+// エクスポートではカーソルから取り出すため、FromRowも導出する
+#[derive(sqlx::FromRow)]
 pub struct BookRow {
     pub book_id: BookId,
     pub title: String,
+    pub title_kana: Option<String>,
     pub author: String,
     pub isbn: String,
     pub description: String,
 
     pub owned_by: UserId,
     pub owner_name: String,
+
+    pub total_copies: i64,
+    pub available_copies: i64,
 }
 
 impl BookRow {
-    pub fn into_book(self, checkout: Option<Checkout>) -> Book {
+    pub fn into_book(self, checkouts: Vec<Checkout>) -> Book {
         // パターンマッチを用いて、`BookRow`の中身を取り出す
         let BookRow {
             book_id,
             title,
+            title_kana,
             author,
             isbn,
             description,
             owned_by,
             owner_name,
+            total_copies,
+            available_copies,
         } = self;
         
         Book {
             id: book_id,
             title,
+            title_kana,
             author,
             isbn,
             description,
@@ -39,7 +49,9 @@ impl BookRow {
                 id: owned_by,
                 name: owner_name,
             },
-            checkout,
+            total_copies,
+            available_copies,
+            checkouts,
         }
     }
 }
@@ -47,34 +59,42 @@ impl BookRow {
 // 貸出情報を格納する方を新規追加　
 pub struct BookCheckoutRow {
     pub checkout_id: CheckoutId,
+    pub copy_id: CopyId,
     pub book_id: BookId,
     pub user_id: UserId,
     pub user_name: String,
     pub checked_out_at: DateTime<Utc>,
+    pub due_at: DateTime<Utc>,
 }
 
 impl From<BookCheckoutRow> for Checkout {
     fn from(value: BookCheckoutRow) -> Self{
         let BookCheckoutRow{
             checkout_id,
-            book_id,
+            copy_id,
+            book_id: _,
             user_id,
             user_name,
             checked_out_at,
+            due_at,
         } = value;
 
         Checkout {
             checkout_id,
+            copy_id,
             checked_out_by: CheckoutUser{
                 id: user_id,
                 name: user_name,
             },
             checked_out_at,
+            due_at,
         }
     }
 }
 
 // ページネーション用のadapter内部の型
+// 検索条件に応じて組み立てるクエリで使うため、FromRowを導出する
+#[derive(sqlx::FromRow)]
 pub struct PaginatedBookRow{
     pub total: i64,
     pub id: BookId,
@@ -1,14 +1,13 @@
-use chrono::Date;
 use kernel::model::{
     checkout::{Checkout, CheckoutBook},
-    id::{BookId, CheckoutId, UserId},
+    id::{BookId, CheckoutId, CopyId, UserId},
 };
 use sqlx::types::chrono::{DateTime, Utc};
 
-// 貸出状態を確認するための型
+// 返却時に貸出状態を確認するための型
 // 蔵書が存在する場合:この型にはまるレコードが存在する
-// 蔵書が貸出中の場合:checkout_idおよびuser_idがNoneではない値
-// 蔵書が貸出中ではない場合：checkout_idもuser_idもNone
+// 指定の貸出IDの貸出が存在する場合:checkout_idおよびuser_idがNoneではない値
+// 指定の貸出IDの貸出が存在しない場合：checkout_idもuser_idもNone
 
 pub struct CheckoutStateRow{
     pub book_id: BookId,
@@ -16,12 +15,46 @@ pub struct CheckoutStateRow{
     pub user_id: Option<UserId>,
 } 
 
+// 蔵書の冊子の貸出・予約の可否を確認するための型
+// 蔵書が存在する場合:この型にはまるレコードが存在する
+pub struct BookAvailabilityRow{
+    pub book_id: BookId,
+    pub total_copies: i64,
+    // 貸出中の冊子の数
+    pub checked_out: i64,
+    // 予約者のために取り置かれている冊子の数
+    pub holds: i64,
+    // 指定のユーザーがこの蔵書を借りているか
+    pub borrowing: bool,
+    // 指定のユーザーのためにこの蔵書が取り置かれているか
+    pub holding: bool,
+}
+
+impl BookAvailabilityRow {
+    // 指定のユーザーが借りられる冊子の数
+    // 他のユーザーのために取り置かれている冊子は借りられない
+    pub fn available_for_user(&self) -> i64 {
+        self.total_copies - self.checked_out - self.holds + i64::from(self.holding)
+    }
+}
+
+// 貸出の延長可否を確認するための型
+pub struct CheckoutRenewalStateRow{
+    pub checkout_id: CheckoutId,
+    pub user_id: UserId,
+    pub renewal_count: i32,
+}
+
 // 貸出中の一覧を取得する際に使う型
 pub struct CheckoutRow{
     pub checkout_id: CheckoutId,
+    pub copy_id: CopyId,
     pub book_id: BookId,
     pub user_id: UserId,
     pub checked_out_at: DateTime<Utc>,
+    pub due_at: DateTime<Utc>,
+    pub renewal_count: i32,
+    pub checked_out_performed_by: UserId,
     pub title: String,
     pub author: String,
     pub isbn: String,
@@ -31,9 +64,13 @@ impl From<CheckoutRow> for Checkout {
     fn from(value: CheckoutRow) -> Self{
         let CheckoutRow {
             checkout_id,
+            copy_id,
             book_id,
             user_id,
             checked_out_at,
+            due_at,
+            renewal_count,
+            checked_out_performed_by,
             title,
             author,
             isbn,
@@ -41,9 +78,14 @@ impl From<CheckoutRow> for Checkout {
 
         Checkout {
             id: checkout_id,
+            copy_id,
             checked_out_by: user_id,
             checked_out_at,
+            due_at,
+            renewal_count,
             returned_at: None,
+            checked_out_performed_by,
+            returned_performed_by: None,
             book: CheckoutBook {
                 book_id,
                 title,
@@ -57,10 +99,15 @@ impl From<CheckoutRow> for Checkout {
 // 返却済みの貸出一覧を取得する際に使う型
 pub struct ReturnedCheckoutRow {
     pub checkout_id: CheckoutId,
+    pub copy_id: CopyId,
     pub book_id: BookId,
     pub user_id: UserId,
     pub checked_out_at: DateTime<Utc>,
+    pub due_at: DateTime<Utc>,
+    pub renewal_count: i32,
     pub returned_at: DateTime<Utc>,
+    pub checked_out_performed_by: UserId,
+    pub returned_performed_by: UserId,
     pub title: String,
     pub author: String,
     pub isbn: String,
@@ -70,10 +117,15 @@ impl From<ReturnedCheckoutRow> for Checkout{
     fn from(value: ReturnedCheckoutRow) -> Self {
         let ReturnedCheckoutRow {
             checkout_id,
+            copy_id,
             book_id,
             user_id,
             checked_out_at,
+            due_at,
+            renewal_count,
             returned_at,
+            checked_out_performed_by,
+            returned_performed_by,
             title,
             author,
             isbn,
@@ -81,10 +133,15 @@ impl From<ReturnedCheckoutRow> for Checkout{
 
         Checkout {
             id: checkout_id,
+            copy_id,
             checked_out_by: user_id,
             checked_out_at,
+            due_at,
+            renewal_count,
             // 返却済みなのでretunred_atには日時データが入る
             returned_at: Some(returned_at),
+            checked_out_performed_by,
+            returned_performed_by: Some(returned_performed_by),
             book: CheckoutBook{
                 book_id,
                 title,
@@ -0,0 +1,104 @@
+// 書誌情報のキャッシュに使うデータ型の定義
+use kernel::model::book::{isbn::Isbn, BookMetadata};
+use serde::{Deserialize, Serialize};
+use shared::error::{AppError, AppResult};
+
+use crate::redis::model::{RedisKey, RedisValue};
+
+// キーはISBN-13の正規形で作るため、ISBN-10で問い合わせた場合も同じキャッシュを使う
+pub struct BookMetadataKey(Isbn);
+
+// Redisにはシリアライズした書誌情報をJSONとして保存する
+#[derive(Serialize, Deserialize)]
+pub struct CachedBookMetadata {
+    pub isbn: Isbn,
+    pub title: String,
+    pub title_kana: Option<String>,
+    pub author: String,
+    pub description: String,
+}
+
+// 外部APIで見つからなかったISBNも、問い合わせを繰り返さないようにNone(JSONのnull)としてキャッシュする
+#[derive(Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct CachedBookMetadataLookup(pub Option<CachedBookMetadata>);
+
+impl From<&Isbn> for BookMetadataKey {
+    fn from(isbn: &Isbn) -> Self {
+        Self(isbn.clone())
+    }
+}
+
+impl RedisKey for BookMetadataKey {
+    type Value = CachedBookMetadataLookup;
+
+    fn inner(&self) -> String {
+        format!("book_metadata:{}", self.0)
+    }
+}
+
+impl RedisValue for CachedBookMetadataLookup {
+    fn inner(&self) -> String {
+        // 文字列とISBNのみからなるため、シリアライズは失敗しない
+        serde_json::to_string(self).unwrap_or_default()
+    }
+}
+
+impl TryFrom<String> for CachedBookMetadataLookup {
+    type Error = AppError;
+
+    fn try_from(s: String) -> AppResult<Self> {
+        serde_json::from_str(&s)
+            .map_err(|e| AppError::ConversionEntityError(e.to_string()))
+    }
+}
+
+impl From<Option<BookMetadata>> for CachedBookMetadataLookup {
+    fn from(value: Option<BookMetadata>) -> Self {
+        Self(value.map(CachedBookMetadata::from))
+    }
+}
+
+impl From<CachedBookMetadataLookup> for Option<BookMetadata> {
+    fn from(value: CachedBookMetadataLookup) -> Self {
+        value.0.map(BookMetadata::from)
+    }
+}
+
+impl From<BookMetadata> for CachedBookMetadata {
+    fn from(value: BookMetadata) -> Self {
+        let BookMetadata {
+            isbn,
+            title,
+            title_kana,
+            author,
+            description,
+        } = value;
+        Self {
+            isbn,
+            title,
+            title_kana,
+            author,
+            description,
+        }
+    }
+}
+
+impl From<CachedBookMetadata> for BookMetadata {
+    fn from(value: CachedBookMetadata) -> Self {
+        let CachedBookMetadata {
+            isbn,
+            title,
+            title_kana,
+            author,
+            description,
+        } = value;
+        Self {
+            isbn,
+            title,
+            title_kana,
+            author,
+            description,
+        }
+    }
+}
@@ -0,0 +1,138 @@
+// 2段階認証に使うデータ型の定義
+use chrono::{DateTime, Utc};
+use kernel::model::{id::UserId, mfa::VerifiedMfaChallenge};
+use serde::{Deserialize, Serialize};
+use shared::error::{AppError, AppResult};
+
+use crate::redis::model::{RedisKey, RedisValue};
+
+pub struct UserTotpRow {
+    // Base32でエンコードした秘密鍵
+    pub secret: String,
+    // 登録の途中の場合はNULL
+    pub enabled_at: Option<DateTime<Utc>>,
+}
+
+pub struct RecoveryCodeRow {
+    pub recovery_code_id: sqlx::types::Uuid,
+    pub code_hash: String,
+}
+
+// 2段階目の認証を待つログイン -> パスワードの検証時の情報
+pub struct MfaChallengeKey(String);
+#[derive(Serialize, Deserialize)]
+pub struct MfaChallengeState {
+    pub user_id: UserId,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+}
+
+// 2段階目の認証に失敗した回数
+pub struct MfaChallengeFailureKey(String);
+pub struct MfaChallengeFailureCount(pub u64);
+
+// 使用済みのTOTPのコード
+// 有効期間内に同じコードを盗み見て使われないよう、一度使ったコードを記録する
+pub struct UsedTotpCodeKey(UserId, String);
+pub struct UsedTotpCode;
+
+impl From<&str> for MfaChallengeKey {
+    fn from(token: &str) -> Self {
+        Self(token.to_string())
+    }
+}
+
+impl From<&str> for MfaChallengeFailureKey {
+    fn from(token: &str) -> Self {
+        Self(token.to_string())
+    }
+}
+
+impl UsedTotpCodeKey {
+    pub fn new(user_id: UserId, code: &str) -> Self {
+        Self(user_id, code.to_string())
+    }
+}
+
+impl From<MfaChallengeState> for VerifiedMfaChallenge {
+    fn from(value: MfaChallengeState) -> Self {
+        let MfaChallengeState {
+            user_id,
+            user_agent,
+            ip_address,
+        } = value;
+        Self {
+            user_id,
+            user_agent,
+            ip_address,
+        }
+    }
+}
+
+impl RedisKey for MfaChallengeKey {
+    type Value = MfaChallengeState;
+
+    fn inner(&self) -> String {
+        format!("mfa_challenge:{}", self.0)
+    }
+}
+
+impl RedisKey for MfaChallengeFailureKey {
+    type Value = MfaChallengeFailureCount;
+
+    fn inner(&self) -> String {
+        format!("mfa_challenge_failures:{}", self.0)
+    }
+}
+
+impl RedisKey for UsedTotpCodeKey {
+    type Value = UsedTotpCode;
+
+    fn inner(&self) -> String {
+        format!("totp_used:{}:{}", self.0, self.1)
+    }
+}
+
+impl RedisValue for MfaChallengeState {
+    fn inner(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+}
+
+impl TryFrom<String> for MfaChallengeState {
+    type Error = AppError;
+
+    fn try_from(s: String) -> AppResult<Self> {
+        serde_json::from_str(&s).map_err(|e| AppError::ConversionEntityError(e.to_string()))
+    }
+}
+
+impl RedisValue for MfaChallengeFailureCount {
+    fn inner(&self) -> String {
+        self.0.to_string()
+    }
+}
+
+impl TryFrom<String> for MfaChallengeFailureCount {
+    type Error = AppError;
+
+    fn try_from(s: String) -> AppResult<Self> {
+        Ok(Self(s.parse::<u64>().map_err(|e| {
+            AppError::ConversionEntityError(e.to_string())
+        })?))
+    }
+}
+
+impl RedisValue for UsedTotpCode {
+    fn inner(&self) -> String {
+        "1".to_string()
+    }
+}
+
+impl TryFrom<String> for UsedTotpCode {
+    type Error = AppError;
+
+    fn try_from(_: String) -> AppResult<Self> {
+        Ok(Self)
+    }
+}
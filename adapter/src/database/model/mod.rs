@@ -1,4 +1,11 @@
 pub mod book;
 pub mod auth;
 pub mod user;
-pub mod checkout;
\ No newline at end of file
+pub mod checkout;
+pub mod reservation;
+pub mod policy;
+pub mod metadata;
+pub mod mfa;
+pub mod api_key;
+pub mod role;
+pub mod audit;
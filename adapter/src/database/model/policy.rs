@@ -0,0 +1,36 @@
+use kernel::model::{policy::BorrowingPolicy, role::Role};
+use shared::error::AppError;
+
+// ロール名と紐づけて貸出ポリシーを取得する際に使う型
+pub struct BorrowingPolicyRow {
+    pub role_name: String,
+    pub max_loans: i32,
+    pub loan_period_days: i32,
+    pub max_renewals: i32,
+}
+
+impl TryFrom<BorrowingPolicyRow> for BorrowingPolicy {
+    type Error = AppError;
+    fn try_from(value: BorrowingPolicyRow) -> Result<Self, Self::Error> {
+        let BorrowingPolicyRow {
+            role_name,
+            max_loans,
+            loan_period_days,
+            max_renewals,
+        } = value;
+
+        Ok(BorrowingPolicy {
+            role: Role::from(role_name),
+            max_loans,
+            loan_period_days,
+            max_renewals,
+        })
+    }
+}
+
+// 貸出・延長の処理中に、ユーザーに適用される貸出ポリシーを取得する際に使う型
+pub struct UserBorrowingPolicyRow {
+    pub max_loans: i32,
+    pub loan_period_days: i32,
+    pub max_renewals: i32,
+}
@@ -0,0 +1,41 @@
+use std::str::FromStr;
+
+use kernel::model::role::{Permission, Role, RoleDetail};
+use shared::error::AppError;
+
+// 権限はDBに文字列("book.update.any"など)の配列として保存する
+pub fn parse_permissions(permissions: Vec<String>) -> Result<Vec<Permission>, AppError> {
+    permissions
+        .iter()
+        .map(|permission| {
+            Permission::from_str(permission)
+                .map_err(|e| AppError::ConversionEntityError(e.to_string()))
+        })
+        .collect()
+}
+
+pub fn to_permission_names(permissions: &[Permission]) -> Vec<String> {
+    permissions
+        .iter()
+        .map(|permission| permission.as_ref().to_string())
+        .collect()
+}
+
+pub struct RoleRow {
+    pub name: String,
+    pub permissions: Vec<String>,
+}
+
+impl TryFrom<RoleRow> for RoleDetail {
+    type Error = AppError;
+
+    fn try_from(value: RoleRow) -> Result<Self, Self::Error> {
+        let RoleRow { name, permissions } = value;
+        let role = Role::from(name);
+        let permissions = match role {
+            Role::Admin => Permission::all(),
+            _ => parse_permissions(permissions)?,
+        };
+        Ok(RoleDetail { role, permissions })
+    }
+}
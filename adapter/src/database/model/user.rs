@@ -1,8 +1,7 @@
 use kernel::model::{id::UserId, role::Role, user::User};
 use shared::error::AppError;
 use sqlx::types::chrono::{DateTime, Utc};
-use std::str::FromStr;
-use tracing::{info, error};
+use tracing::info;
 
 pub struct UserRow{
     pub user_id: UserId,
@@ -26,21 +25,9 @@ impl TryFrom<UserRow> for User {
             ..
         } = value;
 
-        let role = match Role::from_str(role_name.as_str()) {
-            Ok(role) => {
-                // 成功時のログ
-                info!("Successfully converted role_name: {:?} to Role: {:?}", role_name, role);
-                role
-            }
-            Err(e) => {
-                // エラー時のログ
-                error!(
-                    "Failed to convert role_name: {:?} to Role. Error: {:?}",
-                    role_name, e
-                );
-                return Err(AppError::ConversionEntityError(e.to_string()));
-            }
-        };
+        // 組み込みのロール以外は、管理者が作成したロールとして扱う
+        let role = Role::from(role_name);
+        info!("Successfully converted role_name to Role: {:?}", role);
 
         info!("Successfully converted UserRow to User");
 
@@ -1,20 +1,36 @@
 pub mod model;
 
+use std::{future::Future, sync::Arc, time::Instant};
+
 use redis::{AsyncCommands, Client};
-use shared::{config::RedisConfig, error::AppResult};
+use shared::{config::RedisConfig, error::AppResult, metrics::Metrics};
 
 use self::model::{RedisKey, RedisValue};
 
 pub struct RedisClient {
     client: Client,
+    metrics: Arc<Metrics>,
 }
 
 impl RedisClient {
     // newメソッド：Redis接続用のクライアントを初期化する
-    pub fn new(config: &RedisConfig) -> AppResult<Self> {  
+    pub fn new(config: &RedisConfig, metrics: Arc<Metrics>) -> AppResult<Self> {  
         let client = 
             Client::open(format!("redis://{}:{}", config.host, config.port))?;
-            Ok(Self { client })
+            Ok(Self { client, metrics })
+    }
+
+    // 接続の確立を含めたコマンドの所要時間を、コマンド名ごとに記録する
+    async fn observe<T>(
+        &self,
+        command: &str,
+        f: impl Future<Output = AppResult<T>>,
+    ) -> AppResult<T> {
+        let start = Instant::now();
+        let result = f.await;
+        self.metrics
+            .observe_redis_command(command, result.is_ok(), start.elapsed());
+        result
     }
     // 16行目: `Client::open`でRedisサーバーに接続するクライアントを初期化する・戻り値：Result<Client, redis::RedisError>
     // 16行目: `format!("redis://{}:{}", config.host, config.port)`でRedisサーバーのURIを生成する
@@ -28,9 +44,12 @@ impl RedisClient {
         value: &T::Value,
         ttl: u64,
     ) -> AppResult<()> {
-        let mut conn = self.client.get_multiplexed_async_connection().await?;
-        conn.set_ex(key.inner(), value.inner(), ttl).await?;
-        Ok(())
+        self.observe("SETEX", async {
+            let mut conn = self.client.get_multiplexed_async_connection().await?;
+            let _: () = conn.set_ex(key.inner(), value.inner(), ttl).await?;
+            Ok(())
+        })
+        .await
     }
 
     // 30行目：set_exメソッドで、Redisのキーに値を設定する
@@ -43,16 +62,185 @@ impl RedisClient {
         &self,
         key: &T,
     ) -> AppResult<Option<T::Value>> {
-        let mut conn = self.client.get_multiplexed_async_connection().await?;
-        let result: Option<String> = conn.get(key.inner()).await?;
+        let result: Option<String> = self
+            .observe("GET", async {
+                let mut conn = self.client.get_multiplexed_async_connection().await?;
+                Ok(conn.get(key.inner()).await?)
+            })
+            .await?;
         result.map(T::Value::try_from).transpose()
     }
 
+    // キーを指定してバリューを取り出し、同時にキーを削除する
+    // 一度だけ使えるトークンを、複数のリクエストで同時に使われないようにするために使う
+    pub async fn take<T: RedisKey>(&self, key: &T) -> AppResult<Option<T::Value>> {
+        let result: Option<String> = self
+            .observe("GETDEL", async {
+                let mut conn = self.client.get_multiplexed_async_connection().await?;
+                Ok(redis::cmd("GETDEL")
+                    .arg(key.inner())
+                    .query_async(&mut conn)
+                    .await?)
+            })
+            .await?;
+        result.map(T::Value::try_from).transpose()
+    }
+
+    // キーが存在しない場合のみ、期限付きでキーとバリューを保存する
+    // 保存できた場合はtrue、すでにキーが存在した場合はfalseを返す
+    pub async fn set_nx_ex<T: RedisKey>(
+        &self,
+        key: &T,
+        value: &T::Value,
+        ttl: u64,
+    ) -> AppResult<bool> {
+        self.observe("SET", async {
+            let mut conn = self.client.get_multiplexed_async_connection().await?;
+            let result: Option<String> = redis::cmd("SET")
+                .arg(key.inner())
+                .arg(value.inner())
+                .arg("NX")
+                .arg("EX")
+                .arg(ttl)
+                .query_async(&mut conn)
+                .await?;
+            Ok(result.is_some())
+        })
+        .await
+    }
+
+    // キーの有効期限を設定し直す。キーが存在しない場合はfalseを返す
+    pub async fn expire<T: RedisKey>(&self, key: &T, ttl: u64) -> AppResult<bool> {
+        self.observe("EXPIRE", async {
+            let mut conn = self.client.get_multiplexed_async_connection().await?;
+            Ok(conn.expire(key.inner(), ttl as i64).await?)
+        })
+        .await
+    }
+
+    // キーの指す数値を1増やし、有効期限を設定し直す。増やした後の値を返す
+    pub async fn increment<T: RedisKey>(&self, key: &T, ttl: u64) -> AppResult<u64> {
+        self.observe("INCR", async {
+            let mut conn = self.client.get_multiplexed_async_connection().await?;
+            let (count,): (u64,) = redis::pipe()
+                .atomic()
+                .incr(key.inner(), 1)
+                .expire(key.inner(), ttl as i64)
+                .ignore()
+                .query_async(&mut conn)
+                .await?;
+            Ok(count)
+        })
+        .await
+    }
+
+    // 各キーの指す数値が上限に達していない場合に限り、すべてのキーの数値を1増やし有効期限を設定し直す
+    // 確認と更新を1つのスクリプトで行うため、並行して呼び出されても上限を超えて増えることはない
+    // 増やした場合は、増やす前の値の最大値を返す。いずれかのキーが上限に達している場合はNoneを返す
+    pub async fn increment_within_limits<T: RedisKey>(
+        &self,
+        keys: &[(&T, u64)],
+        ttl: u64,
+    ) -> AppResult<Option<u64>> {
+        let script = redis::Script::new(
+            r#"
+                local previous = 0
+                for i, key in ipairs(KEYS) do
+                    local count = tonumber(redis.call('GET', key) or '0')
+                    if count >= tonumber(ARGV[i]) then
+                        return -1
+                    end
+                    previous = math.max(previous, count)
+                end
+                for _, key in ipairs(KEYS) do
+                    redis.call('INCR', key)
+                    redis.call('EXPIRE', key, ARGV[#ARGV])
+                end
+                return previous
+            "#,
+        );
+        let mut invocation = script.prepare_invoke();
+        for (key, limit) in keys {
+            invocation.key(key.inner()).arg(*limit);
+        }
+        invocation.arg(ttl);
+
+        self.observe("EVALSHA", async {
+            let mut conn = self.client.get_multiplexed_async_connection().await?;
+            let previous: i64 = invocation.invoke_async(&mut conn).await?;
+            Ok(u64::try_from(previous).ok())
+        })
+        .await
+    }
+
+    // キーの指す数値を1減らす。キーが存在しない場合や0の場合は何もしない
+    pub async fn decrement<T: RedisKey>(&self, key: &T) -> AppResult<()> {
+        let script = redis::Script::new(
+            r#"
+                if tonumber(redis.call('GET', KEYS[1]) or '0') > 0 then
+                    redis.call('DECR', KEYS[1])
+                end
+            "#,
+        );
+        self.observe("EVALSHA", async {
+            let mut conn = self.client.get_multiplexed_async_connection().await?;
+            let _: () = script.key(key.inner()).invoke_async(&mut conn).await?;
+            Ok(())
+        })
+        .await
+    }
+
+    // キーの指す集合にバリューを加え、集合全体の有効期限を設定し直す
+    pub async fn add_member<T: RedisKey>(
+        &self,
+        key: &T,
+        value: &T::Value,
+        ttl: u64,
+    ) -> AppResult<()> {
+        self.observe("SADD", async {
+            let mut conn = self.client.get_multiplexed_async_connection().await?;
+            redis::pipe()
+                .atomic()
+                .sadd(key.inner(), value.inner())
+                .ignore()
+                .expire(key.inner(), ttl as i64)
+                .ignore()
+                .query_async::<_, ()>(&mut conn)
+                .await?;
+            Ok(())
+        })
+        .await
+    }
+
+    // キーの指す集合のバリューをすべて取り出す
+    pub async fn members<T: RedisKey>(&self, key: &T) -> AppResult<Vec<T::Value>> {
+        let result: Vec<String> = self
+            .observe("SMEMBERS", async {
+                let mut conn = self.client.get_multiplexed_async_connection().await?;
+                Ok(conn.smembers(key.inner()).await?)
+            })
+            .await?;
+        result.into_iter().map(T::Value::try_from).collect()
+    }
+
+    // キーの指す集合からバリューを取り除く
+    pub async fn remove_member<T: RedisKey>(&self, key: &T, value: &T::Value) -> AppResult<()> {
+        self.observe("SREM", async {
+            let mut conn = self.client.get_multiplexed_async_connection().await?;
+            let _: i64 = conn.srem(key.inner(), value.inner()).await?;
+            Ok(())
+        })
+        .await
+    }
+
     // キーを指定して、Redis上の該当のキーとバリューを削除する
     pub async fn delete<T: RedisKey>(&self, key: &T) -> AppResult<()> {
-        let mut conn = self.client.get_multiplexed_async_connection().await?;
-        conn.del(key.inner()).await?;
-        Ok(())
+        self.observe("DEL", async {
+            let mut conn = self.client.get_multiplexed_async_connection().await?;
+            let _: i64 = conn.del(key.inner()).await?;
+            Ok(())
+        })
+        .await
     }
 
     // 接続確認：ヘルスチェック
@@ -0,0 +1,299 @@
+use async_trait::async_trait;
+use derive_new::new;
+use kernel::{
+    model::{
+        api_key::{
+            event::{CreateApiKey, DeleteApiKey},
+            ApiKey, AuthorizedApiKey, CreatedApiKey,
+        },
+        audit::{event::CreateAuditEvent, AuditAction},
+        id::{ApiKeyId, UserId},
+    },
+    repository::api_key::ApiKeyRepository,
+};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use shared::{
+    config::MfaConfig,
+    error::{AppError, AppResult},
+    i18n::Message,
+};
+
+use crate::database::{
+    model::api_key::{ApiKeyRow, AuthorizedApiKeyRow},
+    ConnectionPool,
+};
+use crate::repository::audit::record_audit_event;
+
+// APIキーは十分な長さのランダムな値のため、リクエストのたびに検証できるよう
+// パスワードのような低速なハッシュではなくSHA-256でハッシュ化する
+fn hash_key(key: &str) -> String {
+    format!("{:x}", Sha256::digest(key.as_bytes()))
+}
+
+#[derive(new)]
+pub struct ApiKeyRepositoryImpl {
+    db: ConnectionPool,
+    mfa: MfaConfig,
+}
+
+#[async_trait]
+impl ApiKeyRepository for ApiKeyRepositoryImpl {
+    #[tracing::instrument(skip_all, fields(db.system = "postgresql", db.operation = "INSERT", user_id = %event.user_id))]
+    async fn create(&self, event: CreateApiKey) -> AppResult<CreatedApiKey> {
+        let api_key_id = ApiKeyId::new();
+        let scopes = event
+            .scopes
+            .iter()
+            .map(|scope| scope.as_ref().to_string())
+            .collect::<Vec<_>>();
+
+        let mut tx = self.db.begin().await?;
+
+        let row = sqlx::query_as!(
+            ApiKeyRow,
+            r#"
+                INSERT INTO api_keys(api_key_id, user_id, name, key_prefix, key_hash, scopes, expires_at)
+                VALUES ($1, $2, $3, $4, $5, $6, $7)
+                RETURNING
+                    api_key_id,
+                    name,
+                    key_prefix,
+                    scopes,
+                    expires_at,
+                    last_used_at,
+                    created_at;
+            "#,
+            api_key_id as _,
+            event.user_id as _,
+            event.name,
+            event.key_prefix(),
+            hash_key(&event.key),
+            &scopes,
+            event.expires_at,
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+
+        // キーそのものやハッシュは監査ログに含めない
+        record_audit_event(
+            &mut tx,
+            CreateAuditEvent::new(
+                event.user_id,
+                AuditAction::ApiKeyCreate,
+                api_key_id.to_string(),
+                None,
+                Some(json!({
+                    "name": event.name,
+                    "keyPrefix": event.key_prefix(),
+                    "scopes": scopes,
+                    "expiresAt": event.expires_at,
+                })),
+            ),
+        )
+        .await?;
+
+        tx.commit().await.map_err(AppError::TransactionError)?;
+
+        Ok(CreatedApiKey {
+            api_key: row.try_into()?,
+            key: event.key,
+        })
+    }
+
+    #[tracing::instrument(skip_all, fields(db.system = "postgresql", db.operation = "SELECT", user_id = %user_id))]
+    async fn find_by_user_id(&self, user_id: UserId) -> AppResult<Vec<ApiKey>> {
+        sqlx::query_as!(
+            ApiKeyRow,
+            r#"
+                SELECT
+                    api_key_id,
+                    name,
+                    key_prefix,
+                    scopes,
+                    expires_at,
+                    last_used_at,
+                    created_at
+                FROM api_keys
+                WHERE user_id = $1
+                ORDER BY created_at DESC;
+            "#,
+            user_id as _
+        )
+        .fetch_all(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?
+        .into_iter()
+        .map(ApiKey::try_from)
+        .collect()
+    }
+
+    #[tracing::instrument(skip_all, fields(db.system = "postgresql", db.operation = "DELETE", user_id = %event.user_id, api_key_id = %event.api_key_id))]
+    async fn delete(&self, event: DeleteApiKey) -> AppResult<()> {
+        let mut tx = self.db.begin().await?;
+
+        // 他のユーザーのAPIキーは、存在しないものとして扱う
+        let deleted = sqlx::query!(
+            r#"
+                DELETE FROM api_keys
+                WHERE api_key_id = $1 AND user_id = $2
+                RETURNING name, key_prefix, scopes;
+            "#,
+            event.api_key_id as _,
+            event.user_id as _
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?
+        .ok_or_else(|| {
+            AppError::EntityNotFound(
+                Message::new("api_key_not_found").arg("api_key_id", event.api_key_id),
+            )
+        })?;
+
+        record_audit_event(
+            &mut tx,
+            CreateAuditEvent::new(
+                event.user_id,
+                AuditAction::ApiKeyDelete,
+                event.api_key_id.to_string(),
+                Some(json!({
+                    "name": deleted.name,
+                    "keyPrefix": deleted.key_prefix,
+                    "scopes": deleted.scopes,
+                })),
+                None,
+            ),
+        )
+        .await?;
+
+        tx.commit().await.map_err(AppError::TransactionError)?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip_all, fields(db.system = "postgresql", db.operation = "UPDATE"))]
+    async fn authenticate(&self, key: &str) -> AppResult<Option<AuthorizedApiKey>> {
+        // 検証と同時に最終利用日時を記録する。期限切れのキーは更新されず、Noneとなる
+        sqlx::query_as!(
+            AuthorizedApiKeyRow,
+            r#"
+                UPDATE api_keys SET last_used_at = CURRENT_TIMESTAMP(3)
+                WHERE key_hash = $1
+                AND (expires_at IS NULL OR expires_at > CURRENT_TIMESTAMP(3))
+                RETURNING api_key_id, user_id, scopes;
+            "#,
+            hash_key(key)
+        )
+        .fetch_optional(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?
+        .map(|row| row.into_authorized_api_key(self.mfa.required_for_admin))
+        .transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use chrono::{Duration, Utc};
+    use kernel::model::api_key::ApiKeyScope;
+
+    use super::*;
+
+    fn mfa_config() -> MfaConfig {
+        MfaConfig {
+            required_for_admin: false,
+            challenge_ttl: 300,
+        }
+    }
+
+    fn user_id() -> UserId {
+        UserId::from_str("5b4c96ac-316a-4bee-8e69-cac5eb84ff4c").unwrap()
+    }
+
+    #[sqlx::test(fixtures("common"))]
+    async fn test_create_and_authenticate_api_key(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = ApiKeyRepositoryImpl::new(ConnectionPool::new(pool), mfa_config());
+
+        // 1. 発行したキーそのものは、発行時にのみ返す
+        let created = repo
+            .create(CreateApiKey::new(
+                user_id(),
+                "ci-bot".into(),
+                vec![ApiKeyScope::BooksRead, ApiKeyScope::CheckoutsWrite],
+                None,
+            ))
+            .await?;
+        assert!(created.key.starts_with(&created.api_key.key_prefix));
+        assert_eq!(created.api_key.last_used_at, None);
+
+        // 2. キーで認証すると、持ち主とスコープが取得でき、最終利用日時が記録される
+        let authorized = repo.authenticate(&created.key).await?.unwrap();
+        assert_eq!(authorized.user_id, user_id());
+        assert_eq!(
+            authorized.scopes,
+            vec![ApiKeyScope::BooksRead, ApiKeyScope::CheckoutsWrite]
+        );
+        let keys = repo.find_by_user_id(user_id()).await?;
+        assert_eq!(keys.len(), 1);
+        assert!(keys[0].last_used_at.is_some());
+
+        // 3. 存在しないキーでは認証できない
+        assert!(repo.authenticate("bmk_unknown").await?.is_none());
+
+        // 4. 失効させたキーでは認証できない
+        repo.delete(DeleteApiKey {
+            user_id: user_id(),
+            api_key_id: created.api_key.id,
+        })
+        .await?;
+        assert!(repo.authenticate(&created.key).await?.is_none());
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common"))]
+    async fn test_expired_api_key_is_rejected(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = ApiKeyRepositoryImpl::new(ConnectionPool::new(pool), mfa_config());
+
+        let created = repo
+            .create(CreateApiKey::new(
+                user_id(),
+                "expired".into(),
+                vec![ApiKeyScope::BooksRead],
+                Some(Utc::now() - Duration::days(1)),
+            ))
+            .await?;
+        assert!(repo.authenticate(&created.key).await?.is_none());
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common"))]
+    async fn test_delete_other_users_api_key(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = ApiKeyRepositoryImpl::new(ConnectionPool::new(pool), mfa_config());
+
+        let created = repo
+            .create(CreateApiKey::new(
+                user_id(),
+                "slack-bot".into(),
+                vec![ApiKeyScope::BooksRead],
+                None,
+            ))
+            .await?;
+        // 他のユーザーのキーは失効させられない
+        let res = repo
+            .delete(DeleteApiKey {
+                user_id: UserId::new(),
+                api_key_id: created.api_key.id,
+            })
+            .await;
+        assert!(matches!(res, Err(AppError::EntityNotFound(_))));
+        assert!(repo.authenticate(&created.key).await?.is_some());
+
+        Ok(())
+    }
+}
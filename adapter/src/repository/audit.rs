@@ -0,0 +1,296 @@
+// 監査ログのDBとのやりとりを描く
+// 監査ログの追記は、各リポジトリの変更操作のトランザクション内からrecord_audit_eventで行う
+
+use async_trait::async_trait;
+use derive_new::new;
+use kernel::model::{
+    audit::{event::CreateAuditEvent, AuditEvent, AuditEventListOptions},
+    list::PaginatedList,
+};
+use kernel::repository::audit::AuditRepository;
+use shared::{
+    error::{AppError, AppResult},
+    request_id,
+};
+
+use crate::database::{model::audit::AuditEventRow, ConnectionPool};
+
+#[derive(new)]
+pub struct AuditRepositoryImpl {
+    db: ConnectionPool,
+}
+
+#[async_trait]
+impl AuditRepository for AuditRepositoryImpl {
+    #[tracing::instrument(skip_all, fields(db.system = "postgresql", db.operation = "SELECT", limit = options.limit, offset = options.offset))]
+    async fn find_all(
+        &self,
+        options: AuditEventListOptions,
+    ) -> AppResult<PaginatedList<AuditEvent>> {
+        let AuditEventListOptions {
+            limit,
+            offset,
+            actor,
+            action,
+            target_id,
+            from,
+            to,
+        } = options;
+
+        // 総件数は各行に含めて取得するため、該当する行がない場合は0件とする
+        let rows = sqlx::query_as!(
+            AuditEventRow,
+            r#"
+                SELECT
+                    COUNT(*) OVER() AS "total!",
+                    audit_event_id,
+                    actor_id,
+                    action,
+                    target_id,
+                    before_state::TEXT AS before_state,
+                    after_state::TEXT AS after_state,
+                    request_id,
+                    occurred_at
+                FROM audit_events
+                WHERE ($1::UUID IS NULL OR actor_id = $1)
+                AND ($2::TEXT IS NULL OR action = $2)
+                AND ($3::TEXT IS NULL OR target_id = $3)
+                AND ($4::TIMESTAMPTZ IS NULL OR occurred_at >= $4)
+                AND ($5::TIMESTAMPTZ IS NULL OR occurred_at < $5)
+                ORDER BY occurred_at DESC, audit_event_id ASC
+                LIMIT $6
+                OFFSET $7
+            "#,
+            actor as _,
+            action.as_ref().map(|a| a.as_ref()),
+            target_id,
+            from,
+            to,
+            limit,
+            offset,
+        )
+        .fetch_all(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+
+        let total = rows.first().map(|r| r.total).unwrap_or_default();
+        let items = rows
+            .into_iter()
+            .map(AuditEvent::try_from)
+            .collect::<AppResult<Vec<_>>>()?;
+
+        Ok(PaginatedList {
+            total,
+            limit,
+            offset,
+            items,
+        })
+    }
+}
+
+// 変更操作のトランザクション内から呼び出し、操作と同時に監査ログを追記する
+// 操作が取り消された場合は、監査ログも一緒に取り消される
+pub(crate) async fn record_audit_event(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    event: CreateAuditEvent,
+) -> AppResult<()> {
+    let CreateAuditEvent {
+        actor,
+        action,
+        target_id,
+        before,
+        after,
+    } = event;
+
+    // sqlxのJSON型は使わず、テキストとして渡してDB側でJSONBに変換する
+    sqlx::query!(
+        r#"
+            INSERT INTO audit_events
+            (actor_id, action, target_id, before_state, after_state, request_id)
+            VALUES ($1, $2, $3, $4::TEXT::JSONB, $5::TEXT::JSONB, $6)
+        "#,
+        actor as _,
+        action.as_ref(),
+        target_id,
+        before.map(|v| v.to_string()),
+        after.map(|v| v.to_string()),
+        request_id::current(),
+    )
+    .execute(&mut **tx)
+    .await
+    .map_err(AppError::SpecificOperationError)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use kernel::{
+        model::{
+            audit::AuditAction,
+            book::event::{DeleteBook, UpdateBook},
+            id::{BookId, UserId},
+            role::Role,
+            user::event::{CreateUser, UpdateUserRole},
+        },
+        repository::{book::BookRepository, user::UserRepository},
+    };
+    use serde_json::json;
+
+    use super::*;
+    use crate::repository::{book::BookRepositoryImpl, user::UserRepositoryImpl};
+
+    fn admin_id() -> UserId {
+        UserId::from_str("5b4c96ac-316a-4bee-8e69-cac5eb84ff4c").unwrap()
+    }
+
+    fn book_id() -> BookId {
+        BookId::from_str("9890736e-a4e4-461a-a77d-eac3517ef11b").unwrap()
+    }
+
+    #[sqlx::test(fixtures("common", "book"))]
+    async fn test_state_changes_are_recorded(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let db = ConnectionPool::new(pool);
+        let book_repo = BookRepositoryImpl::new(db.clone());
+        let user_repo = UserRepositoryImpl::new(db.clone());
+        let repo = AuditRepositoryImpl::new(db);
+
+        // 1. 蔵書の更新と削除を行う
+        let book = book_repo.find_by_id(book_id()).await?.unwrap();
+        book_repo
+            .update(UpdateBook {
+                book_id: book.id,
+                title: book.title.clone(),
+                author: "更新後の著者名".into(),
+                isbn: book.isbn.parse()?,
+                description: book.description.clone(),
+                requested_user: admin_id(),
+                any_owner: false,
+            })
+            .await?;
+        book_repo
+            .delete(DeleteBook {
+                book_id: book.id,
+                requested_user: admin_id(),
+                any_owner: false,
+            })
+            .await?;
+
+        // 2. 蔵書の操作が、操作を行ったユーザーと前後の状態とともに記録されている
+        let events = repo
+            .find_all(AuditEventListOptions {
+                limit: 10,
+                target_id: Some(book_id().to_string()),
+                ..Default::default()
+            })
+            .await?;
+        assert_eq!(events.total, 2);
+        let actions = events.items.iter().map(|e| e.action).collect::<Vec<_>>();
+        assert!(actions.contains(&AuditAction::BookUpdate));
+        assert!(actions.contains(&AuditAction::BookDelete));
+        let update = events
+            .items
+            .iter()
+            .find(|e| e.action == AuditAction::BookUpdate)
+            .unwrap();
+        assert_eq!(update.actor, admin_id());
+        assert_eq!(
+            update.before.as_ref().unwrap()["author"],
+            json!(book.author)
+        );
+        assert_eq!(
+            update.after.as_ref().unwrap()["author"],
+            json!("更新後の著者名")
+        );
+        let delete = events
+            .items
+            .iter()
+            .find(|e| e.action == AuditAction::BookDelete)
+            .unwrap();
+        assert_eq!(delete.before.as_ref().unwrap()["title"], json!(book.title));
+        assert!(delete.after.is_none());
+
+        // 3. ユーザーの登録とロールの変更を行う
+        let user = user_repo
+            .create(CreateUser {
+                name: "監査対象ユーザー".into(),
+                email: "audit-target@example.com".into(),
+                password: "password".into(),
+                requested_user: admin_id(),
+            })
+            .await?;
+        user_repo
+            .update_role(UpdateUserRole {
+                user_id: user.id,
+                role: Role::Admin,
+                requested_user: admin_id(),
+            })
+            .await?;
+
+        // 4. 操作の種類で絞り込むと、ロールの変更のみが取得できる
+        let events = repo
+            .find_all(AuditEventListOptions {
+                limit: 10,
+                action: Some(AuditAction::UserRoleUpdate),
+                ..Default::default()
+            })
+            .await?;
+        assert_eq!(events.total, 1);
+        let event = &events.items[0];
+        assert_eq!(event.target_id, user.id.to_string());
+        assert_eq!(event.before, Some(json!({ "role": "User" })));
+        assert_eq!(event.after, Some(json!({ "role": "Admin" })));
+
+        // 5. 操作を行ったユーザーで絞り込み、ページングできる
+        let events = repo
+            .find_all(AuditEventListOptions {
+                limit: 2,
+                offset: 2,
+                actor: Some(admin_id()),
+                ..Default::default()
+            })
+            .await?;
+        assert_eq!(events.total, 4);
+        assert_eq!(events.items.len(), 2);
+        let events = repo
+            .find_all(AuditEventListOptions {
+                limit: 10,
+                actor: Some(UserId::new()),
+                ..Default::default()
+            })
+            .await?;
+        assert_eq!(events.total, 0);
+        assert!(events.items.is_empty());
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common", "book"))]
+    async fn test_audit_events_are_append_only(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let db = ConnectionPool::new(pool.clone());
+        let book_repo = BookRepositoryImpl::new(db);
+        book_repo
+            .delete(DeleteBook {
+                book_id: book_id(),
+                requested_user: admin_id(),
+                any_owner: false,
+            })
+            .await?;
+
+        // 記録済みの監査ログは、更新も削除もできない
+        let res = sqlx::query("UPDATE audit_events SET actor_id = gen_random_uuid()")
+            .execute(&pool)
+            .await;
+        assert!(res.is_err());
+        let res = sqlx::query("DELETE FROM audit_events").execute(&pool).await;
+        assert!(res.is_err());
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM audit_events")
+            .fetch_one(&pool)
+            .await?;
+        assert_eq!(count, 1);
+
+        Ok(())
+    }
+}
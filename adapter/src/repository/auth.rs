@@ -1,81 +1,415 @@
-use std::sync::Arc;
+use std::{
+    cmp::Reverse,
+    sync::{Arc, OnceLock},
+    time::Duration,
+};
 
 use async_trait::async_trait;
 use derive_new::new;
 use kernel::{
     model::{
-        auth::{event::CreateToken, AccessToken},
-        id::UserId,
+        auth::{
+            event::{CreatePasswordReset, CreateToken, ResetPassword, VerifyUser},
+            AccessToken, AuthTokens, AuthorizedSession, PasswordReset, PasswordResetToken, RefreshToken, Session,
+        },
+        id::{SessionId, UserId},
     },
     repository::auth::AuthRepository,
 };
-use shared::error::{AppError, AppResult};
+use shared::{
+    config::AuthConfig,
+    error::{AppError, AppResult},
+    i18n::Message,
+};
+use tracing::warn;
 
 use crate::{
     database::{
-        model::auth::{from, AuthorizationKey, AuthorizedUserId, UserItem},
+        model::auth::{
+            from, AuthorizationKey, LoginFailureKey,
+            PasswordResetTokenKey, PasswordResetUserId, StoredPasswordResetToken,
+            StoredSessionId, RefreshTokenKey, SessionKey, TokenEntries, UsedRefreshTokenKey,
+            UserItem, UserPasswordResetKey, UserSessionsKey,
+        },
         ConnectionPool,
     },
     redis::RedisClient,
 };
 
+// 失敗が続いた場合に待たせる時間の上限
+const MAX_LOGIN_DELAY: Duration = Duration::from_secs(5);
+
+// 存在しないメールアドレスでも、パスワードの検証と同じだけ時間をかけるためのハッシュ
+// 応答時間の差から、登録済みのメールアドレスを推測されないようにする
+fn dummy_password_hash() -> &'static str {
+    static HASH: OnceLock<String> = OnceLock::new();
+    HASH.get_or_init(|| {
+        bcrypt::hash("dummy-password", bcrypt::DEFAULT_COST).unwrap_or_default()
+    })
+}
+
 #[derive(new)]
 pub struct AuthRepositoryImpl {
     db: ConnectionPool,
     kv: Arc<RedisClient>,
-    ttl: u64,
+    config: AuthConfig,
+}
+
+impl AuthRepositoryImpl {
+    // ログインの失敗を数えるキー。IPアドレスが分からない場合はメールアドレスでのみ数える
+    fn login_failure_keys(event: &VerifyUser) -> (LoginFailureKey, Option<LoginFailureKey>) {
+        (
+            LoginFailureKey::email(&event.email),
+            event.ip_address.as_deref().map(LoginFailureKey::ip_address),
+        )
+    }
+
+    // セッションと、セッションに発行済みのアクセストークンを削除する
+    // 以降はセッションのリフレッシュトークンも使えなくなる
+    async fn revoke_session(&self, session_id: SessionId) -> AppResult<()> {
+        let key = SessionKey::from(session_id);
+        if let Some(session) = self.kv.get(&key).await? {
+            self.kv
+                .delete(&AuthorizationKey::from(session.access_token))
+                .await?;
+            self.kv
+                .remove_member(
+                    &UserSessionsKey::from(session.user_id),
+                    &StoredSessionId(session_id),
+                )
+                .await?;
+        }
+        self.kv.delete(&key).await
+    }
 }
 
 #[async_trait]
 impl AuthRepository for AuthRepositoryImpl {
-    async fn fetch_user_id_from_token(
+    #[tracing::instrument(skip_all, fields(db.system = "redis", db.operation = "GET"))]
+    async fn fetch_session_from_token(
         &self,
         access_token: &AccessToken,
-    ) -> AppResult<Option<UserId>> {
+    ) -> AppResult<Option<AuthorizedSession>> {
         let key: AuthorizationKey = access_token.into();
-        self.kv 
+        let mfa_required = self.config.mfa.required_for_admin;
+        self.kv
             .get(&key)
             .await
-            .map(|x| x.map(AuthorizedUserId::into_inner))
+            .map(|x| x.map(|authorized| authorized.into_session(mfa_required)))
     }
 
-    async fn verify_user (
-        &self,
-        email: &str,
-        password: &str,
-    ) -> AppResult<UserId> {
+    #[tracing::instrument(skip_all, fields(db.system = "postgresql", db.operation = "SELECT"))]
+    async fn verify_user (&self, event: VerifyUser) -> AppResult<UserId> {
+        let config = self.config.login;
+        let (email_key, ip_key) = Self::login_failure_keys(&event);
+
+        // 1. 失敗が上限に達しているメールアドレス・IPアドレスからは受け付けない
+        // 上限の確認と同時に回数を増やしておき、並行した試行がまとめて確認を通り抜けないようにする
+        // 回数は検証に成功した場合に戻す
+        let mut limits = vec![(&email_key, config.max_failures)];
+        if let Some(key) = &ip_key {
+            limits.push((key, config.max_failures_per_ip));
+        }
+        let Some(failures) = self
+            .kv
+            .increment_within_limits(&limits, config.lockout_ttl)
+            .await?
+        else {
+            warn!("Login was rejected because of too many failures");
+            return Err(AppError::TooManyLoginAttempts);
+        };
+
+        // 2. 失敗が続いている場合は、失敗の回数に応じて待たせる
+        if failures > 0 {
+            let delay = Duration::from_millis(config.delay_ms)
+                .saturating_mul(1 << (failures - 1).min(16))
+                .min(MAX_LOGIN_DELAY);
+            tokio::time::sleep(delay).await;
+        }
+
+        // 3. メールアドレスが存在しない場合もパスワードを検証し、応答時間と応答内容を揃える
         let user_item = sqlx::query_as!(
             UserItem,
             r#"
                 SELECT user_id, password_hash FROM users
                 WHERE email = $1;
             "#,
-            email
+            event.email
         )
-        .fetch_one(self.db.inner_ref())
+        .fetch_optional(self.db.inner_ref())
         .await
         .map_err(AppError::SpecificOperationError)?;
 
-        let valid = bcrypt::verify(password, &user_item.password_hash)?;      
+        let password_hash = user_item
+            .as_ref()
+            .map_or(dummy_password_hash(), |item| item.password_hash.as_str());
+        let valid = bcrypt::verify(&event.password, password_hash)?;
 
-        if !valid{
-            return Err(AppError::UnauthenticatedError);
+        match user_item {
+            Some(user_item) if valid => {
+                // IPアドレスの回数は、他のアカウントへの試行を含むためリセットせず、今回の分だけ戻す
+                self.kv.delete(&email_key).await?;
+                if let Some(key) = &ip_key {
+                    self.kv.decrement(key).await?;
+                }
+                Ok(user_item.user_id)
+            }
+            // 失敗の回数は、1.で増やしたものをそのまま残す
+            _ => Err(AppError::UnauthenticatedError),
         }
+    }
 
-        Ok(user_item.user_id)
+    #[tracing::instrument(skip_all, fields(db.system = "redis", db.operation = "DEL", user_id = %user_id))]
+    async fn unlock_user(&self, user_id: UserId) -> AppResult<()> {
+        let email = sqlx::query_scalar!(
+            r#"
+                SELECT email FROM users
+                WHERE user_id = $1;
+            "#,
+            user_id as _
+        )
+        .fetch_optional(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?
+        .ok_or_else(|| {
+            AppError::EntityNotFound(Message::new("user_not_found").arg("user", user_id))
+        })?;
+
+        self.kv.delete(&LoginFailureKey::email(&email)).await
     }
 
+    #[tracing::instrument(skip_all, fields(db.system = "redis", db.operation = "SET", user_id = %event.user_id, session_id = %event.session_id))]
     async fn create_token(
         &self,
         event: CreateToken,
-    ) -> AppResult<AccessToken> {
-        let (key, value) = from(event);
-        self.kv.set_ex(&key, &value, self.ttl).await?;
-        Ok(key.into())
+    ) -> AppResult<AuthTokens> {
+        let user_id = event.user_id;
+        let session_id = event.session_id;
+        let TokenEntries {
+            authorization: (access_key, authorized),
+            refresh: (refresh_key, refresh_session),
+            session: (session_key, session),
+        } = from(event);
+
+        self.kv.set_ex(&access_key, &authorized, self.config.ttl).await?;
+        self.kv
+            .set_ex(&refresh_key, &refresh_session, self.config.refresh_ttl)
+            .await?;
+        // セッションはリフレッシュトークンと同じ期間だけ保持する
+        self.kv
+            .set_ex(&session_key, &session, self.config.refresh_ttl)
+            .await?;
+        // ユーザーのセッションの一覧から、すべてのセッションを辿れるようにする
+        self.kv
+            .add_member(
+                &UserSessionsKey::from(user_id),
+                &StoredSessionId(session_id),
+                self.config.refresh_ttl,
+            )
+            .await?;
+
+        Ok(AuthTokens {
+            user_id,
+            access_token: access_key.into(),
+            refresh_token: refresh_key.into(),
+        })
     }
 
+    #[tracing::instrument(skip_all, fields(db.system = "redis", db.operation = "SET"))]
+    async fn refresh_token(&self, refresh_token: RefreshToken) -> AppResult<AuthTokens> {
+        let session_id = self
+            .kv
+            .get(&RefreshTokenKey::from(&refresh_token))
+            .await?
+            .ok_or(AppError::UnauthenticatedError)?
+            .0;
+
+        // 使用済みの印をアトミックに付け、すでに付いていた場合は再利用とみなす
+        // 盗まれたトークンが使われた可能性があるため、正規の利用者の側も含めてセッションごと無効にする
+        let first_use = self
+            .kv
+            .set_nx_ex(
+                &UsedRefreshTokenKey::from(&refresh_token),
+                &StoredSessionId(session_id),
+                self.config.refresh_ttl,
+            )
+            .await?;
+        if !first_use {
+            warn!(
+                "Reuse of a rotated refresh token was detected, revoking the session: session_id={}",
+                session_id
+            );
+            self.revoke_session(session_id).await?;
+            return Err(AppError::UnauthenticatedError);
+        }
+
+        // ログアウトなどでセッションが無効になっている場合は再発行しない
+        let session_key = SessionKey::from(session_id);
+        let session = self
+            .kv
+            .get(&session_key)
+            .await?
+            .ok_or(AppError::UnauthenticatedError)?;
+
+        // 古いアクセストークンは、新しいトークンの組を発行する時点で使えなくする
+        self.kv
+            .delete(&AuthorizationKey::from(session.access_token))
+            .await?;
+        // セッションの作成日時や接続元は、ログイン時のものを引き継ぐ
+        self.create_token(CreateToken {
+            session_id,
+            created_at: session.created_at,
+            user_agent: session.user_agent,
+            ip_address: session.ip_address,
+            mfa_verified: session.mfa_verified,
+            ..CreateToken::new(session.user_id, None, None)
+        })
+        .await
+    }
+
+    #[tracing::instrument(skip_all, fields(db.system = "redis", db.operation = "EXPIRE"))]
+    async fn extend_token(&self, access_token: &AccessToken) -> AppResult<()> {
+        if !self.config.sliding_expiration {
+            return Ok(());
+        }
+        let key: AuthorizationKey = access_token.into();
+        self.kv.expire(&key, self.config.ttl).await?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip_all, fields(db.system = "redis", db.operation = "DEL"))]
     async fn delete_token(&self, access_token: AccessToken) -> AppResult<()> {
         let key: AuthorizationKey = access_token.into();
+        // ログアウトしたセッションのリフレッシュトークンも使えなくする
+        if let Some(authorized) = self.kv.get(&key).await? {
+            self.revoke_session(authorized.session_id).await?;
+        }
         self.kv.delete(&key).await
     }
-}
\ No newline at end of file
+
+    #[tracing::instrument(skip_all, fields(db.system = "redis", db.operation = "SMEMBERS", user_id = %user_id))]
+    async fn find_sessions(
+        &self,
+        user_id: UserId,
+        current: &AccessToken,
+    ) -> AppResult<Vec<Session>> {
+        let index = UserSessionsKey::from(user_id);
+        let mut sessions = Vec::new();
+        for StoredSessionId(session_id) in self.kv.members(&index).await? {
+            match self.kv.get(&SessionKey::from(session_id)).await? {
+                Some(session) => sessions.push(session.into_session(session_id, current)),
+                // 有効期限が切れたセッションは一覧からも取り除く
+                None => {
+                    self.kv
+                        .remove_member(&index, &StoredSessionId(session_id))
+                        .await?
+                }
+            }
+        }
+        // 新しいセッションから順に並べる
+        sessions.sort_by_key(|session| Reverse(session.created_at));
+        Ok(sessions)
+    }
+
+    #[tracing::instrument(skip_all, fields(db.system = "redis", db.operation = "DEL", user_id = %user_id, session_id = %session_id))]
+    async fn delete_session(&self, user_id: UserId, session_id: SessionId) -> AppResult<()> {
+        // 他のユーザーのセッションは、存在しないものとして扱う
+        match self.kv.get(&SessionKey::from(session_id)).await? {
+            Some(session) if session.user_id == user_id => self.revoke_session(session_id).await,
+            _ => Err(AppError::EntityNotFound(
+                Message::new("session_not_found").arg("session_id", session_id),
+            )),
+        }
+    }
+
+    #[tracing::instrument(skip_all, fields(db.system = "redis", db.operation = "DEL", user_id = %user_id))]
+    async fn delete_all_sessions(&self, user_id: UserId) -> AppResult<()> {
+        let index = UserSessionsKey::from(user_id);
+        for StoredSessionId(session_id) in self.kv.members(&index).await? {
+            self.revoke_session(session_id).await?;
+        }
+        self.kv.delete(&index).await
+    }
+
+    #[tracing::instrument(skip_all, fields(db.system = "redis", db.operation = "SET"))]
+    async fn create_password_reset(
+        &self,
+        event: CreatePasswordReset,
+    ) -> AppResult<Option<PasswordReset>> {
+        let Some(user) = sqlx::query!(
+            r#"
+                SELECT user_id, email FROM users
+                WHERE email = $1;
+            "#,
+            event.email
+        )
+        .fetch_optional(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?
+        else {
+            return Ok(None);
+        };
+        let user_id = UserId::from(user.user_id);
+        let ttl = self.config.password_reset_ttl;
+
+        // 発行済みのトークンは、新しいトークンを発行した時点で使えなくする
+        let user_key = UserPasswordResetKey::from(user_id);
+        if let Some(StoredPasswordResetToken(token)) = self.kv.get(&user_key).await? {
+            self.kv.delete(&PasswordResetTokenKey::from(token)).await?;
+        }
+
+        let token = PasswordResetToken(event.token);
+        self.kv
+            .set_ex(
+                &PasswordResetTokenKey::from(&token),
+                &PasswordResetUserId(user_id),
+                ttl,
+            )
+            .await?;
+        self.kv
+            .set_ex(&user_key, &StoredPasswordResetToken(token.0.clone()), ttl)
+            .await?;
+
+        Ok(Some(PasswordReset {
+            user_id,
+            email: user.email,
+            token,
+            expires_in: ttl,
+        }))
+    }
+
+    #[tracing::instrument(skip_all, fields(db.system = "postgresql", db.operation = "UPDATE"))]
+    async fn reset_password(&self, event: ResetPassword) -> AppResult<UserId> {
+        // 取り出すと同時に削除し、同じトークンで二度再設定できないようにする
+        let PasswordResetUserId(user_id) = self
+            .kv
+            .take(&PasswordResetTokenKey::from(event.token))
+            .await?
+            .ok_or_else(|| {
+                AppError::UnprocessableEntity(Message::new("invalid_password_reset_token"))
+            })?;
+        self.kv.delete(&UserPasswordResetKey::from(user_id)).await?;
+
+        let password_hash = bcrypt::hash(&event.new_password, bcrypt::DEFAULT_COST)?;
+        let email = sqlx::query_scalar!(
+            r#"
+                UPDATE users SET password_hash = $2
+                WHERE user_id = $1
+                RETURNING email;
+            "#,
+            user_id as _,
+            password_hash,
+        )
+        .fetch_optional(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?
+        .ok_or_else(|| {
+            AppError::EntityNotFound(Message::new("user_not_found").arg("user", user_id))
+        })?;
+
+        // メールアドレスの持ち主であることが確認できたため、ログインのロックも解除する
+        self.kv.delete(&LoginFailureKey::email(&email)).await?;
+
+        Ok(user_id)
+    }
+}
@@ -1,25 +1,32 @@
 use async_trait::async_trait;
 use derive_new::new;
-use tracing::info;
+use tracing::{info, warn};
 
 use kernel::model::{
+    audit::{event::CreateAuditEvent, AuditAction},
     id::{BookId, UserId},
-    book::{event::DeleteBook, Checkout}, 
-    list::PaginatedList,
+    book::{event::{AddBookCopies, DeleteBook}, Checkout}, 
+    list::{PaginatedList, SortDirection},
 };
 use kernel::{
     model::book::{
-        event::{CreateBook, UpdateBook},
-        Book, BookListOptions,
+        event::{CreateBook, ImportBook, UpdateBook},
+        Book, BookImportOutcome, BookListOptions, BookSearchOptions, BookSortKey,
+        BookStream,
     },
     repository::book::BookRepository,
 };
 use shared::error::{AppError, AppResult};
+use shared::i18n::Message;
 
+use futures::{stream, StreamExt, TryStreamExt};
+use serde_json::json;
+use sqlx::{Postgres, QueryBuilder};
 use std::collections::HashMap;
 
 use crate::database::model::book::{BookRow, BookCheckoutRow,PaginatedBookRow};
-use crate::database::ConnectionPool;
+use crate::database::{set_transaction_serializable, ConnectionPool};
+use crate::repository::audit::record_audit_event;
 
 #[derive(new)]
 pub struct BookRepositoryImpl {
@@ -29,27 +36,31 @@ pub struct BookRepositoryImpl {
 
 #[async_trait]
 impl BookRepository for BookRepositoryImpl {
+    #[tracing::instrument(skip_all, fields(db.system = "postgresql", db.operation = "INSERT", user_id = %user_id, isbn = %event.isbn))]
     async fn create(
         &self, 
         event: CreateBook, 
         user_id: UserId,
     ) -> AppResult<()> {
-        sqlx::query!(
-            // SQLクエリ
-            r#"
-                INSERT INTO books (title, author, isbn, description, user_id)
-                VALUES($1, $2, $3, $4, $5)
-            "#,
-            event.title,
-            event.author,
-            event.isbn,
-            event.description,
-            user_id as _
+        // 書誌情報と冊子を同時に登録するため、トランザクションを用いる
+        // 同じISBNの蔵書の二重登録を防ぐため、SERIALIZABLEで確認と登録を行う
+        let mut tx = self.db.begin().await?;
+        set_transaction_serializable(&mut tx).await?;
+
+        let book_id = insert_book(&mut tx, &event, user_id).await?;
+        record_audit_event(
+            &mut tx,
+            CreateAuditEvent::new(
+                user_id,
+                AuditAction::BookCreate,
+                book_id.to_string(),
+                None,
+                Some(created_book_state(&event, user_id)),
+            ),
         )
-        .execute(self.db.inner_ref()) // "execute"：SQLクエリをDBに送信し、結果を実行するメソッド
-        .await // "await"との違いは、errをどう返すか
-        // sqlx::Error型をAppError型に変換
-        .map_err(AppError::SpecificOperationError)?;
+        .await?;
+
+        tx.commit().await.map_err(AppError::TransactionError)?;
 
         info!("Book created successfully: title='{}', author='{}', isbn='{}', user_id={}", 
         event.title, 
@@ -59,6 +70,86 @@ impl BookRepository for BookRepositoryImpl {
 
         Ok(())
     }
+
+    // 一括登録では、登録できない行があっても他の行の登録は続ける
+    // ただしDBのエラーが発生した場合は、トランザクションごと取り消す
+    #[tracing::instrument(skip_all, fields(db.system = "postgresql", db.operation = "INSERT", rows = events.len()))]
+    async fn create_many(
+        &self,
+        events: Vec<ImportBook>,
+        requested_user: UserId,
+    ) -> AppResult<Vec<BookImportOutcome>> {
+        let mut tx = self.db.begin().await?;
+        set_transaction_serializable(&mut tx).await?;
+
+        // 所有者のメールアドレスからユーザーIDをまとめて引いておく
+        let emails = events
+            .iter()
+            .map(|e| e.owner_email.clone())
+            .collect::<Vec<_>>();
+        let owners: HashMap<String, UserId> = sqlx::query!(
+            r#"
+                SELECT email, user_id AS "user_id: UserId"
+                FROM users
+                WHERE email = ANY($1)
+            "#,
+            &emails
+        )
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?
+        .into_iter()
+        .map(|r| (r.email, r.user_id))
+        .collect();
+
+        let mut outcomes = Vec::with_capacity(events.len());
+        for ImportBook { owner_email, book } in events {
+            let Some(user_id) = owners.get(&owner_email).copied() else {
+                outcomes.push(BookImportOutcome::Rejected(
+                    Message::new("user_not_found")
+                        .arg("user", owner_email)
+                        .to_string(),
+                ));
+                continue;
+            };
+            match insert_book(&mut tx, &book, user_id).await {
+                Ok(book_id) => {
+                    record_audit_event(
+                        &mut tx,
+                        CreateAuditEvent::new(
+                            requested_user,
+                            AuditAction::BookCreate,
+                            book_id.to_string(),
+                            None,
+                            Some(created_book_state(&book, user_id)),
+                        ),
+                    )
+                    .await?;
+                    outcomes.push(BookImportOutcome::Created(book_id))
+                }
+                Err(AppError::UnprocessableEntity(reason)) => {
+                    outcomes.push(BookImportOutcome::Rejected(reason.to_string()))
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        tx.commit().await.map_err(AppError::TransactionError)?;
+
+        info!(
+            "Books imported successfully: created={}, rejected={}",
+            outcomes
+                .iter()
+                .filter(|o| matches!(o, BookImportOutcome::Created(_)))
+                .count(),
+            outcomes
+                .iter()
+                .filter(|o| matches!(o, BookImportOutcome::Rejected(_)))
+                .count(),
+        );
+
+        Ok(outcomes)
+    }
     
     // A,B,C,Dという処理が存在する。Aの処理に異常な時間がかかるとする。
     // その時、Aをfeatureに入れ処理する。また、B,C,Dに関してはAと並行して処理を進める。
@@ -71,29 +162,91 @@ impl BookRepository for BookRepositoryImpl {
     // 総件数を取得する時、1つ目のクエリのレコードカラムに総件数が含まれるような実装であるため、このクエリ結果のレコードが0件の時は総件数も取得できない。
     // その時は、総件数も0件として返す仕様としている 
 
+    // 検索条件は利用者の入力をそのままSQLに埋め込まないよう、QueryBuilderでバインドする
+    // 並び替えの列名は列挙型から固定の文字列に変換したものだけを使う
+
+    #[tracing::instrument(skip_all, fields(db.system = "postgresql", db.operation = "SELECT", limit = options.limit, offset = options.offset))]
     async fn find_all(
         &self,
         options: BookListOptions,
     ) -> AppResult<PaginatedList<Book>> {
-        let BookListOptions { limit, offset } = options;
+        let BookListOptions {
+            limit,
+            offset,
+            q,
+            isbn,
+            author,
+            owner,
+            available,
+            sort,
+            direction,
+        } = options;
 
-        let rows: Vec<PaginatedBookRow> = sqlx::query_as!(
-            PaginatedBookRow,
+        let mut query = QueryBuilder::<Postgres>::new(
             r#"
                 SELECT 
-                    COUNT(*) OVER() AS "total!",
+                    COUNT(*) OVER() AS total,
                     b.book_id AS id                
                 FROM books as b
-                ORDER BY b.created_at DESC
-                LIMIT $1
-                OFFSET $2
+                WHERE TRUE
             "#,
-            limit,
-            offset
-        )
-        .fetch_all(self.db.inner_ref())
-        .await
-        .map_err(AppError::SpecificOperationError)?;
+        );
+        if let Some(q) = q {
+            // 書名・読み・著者・説明を正規化した列に対して部分一致で検索する
+            query
+                .push(" AND b.search_text LIKE search_like_pattern(")
+                .push_bind(q)
+                .push(")");
+        }
+        if let Some(isbn) = isbn {
+            query.push(" AND b.isbn = ").push_bind(isbn.into_inner());
+        }
+        if let Some(author) = author {
+            query
+                .push(" AND b.author ILIKE ")
+                .push_bind(like_pattern(&author));
+        }
+        if let Some(owner) = owner {
+            query.push(" AND b.user_id = ").push_bind(owner);
+        }
+        if let Some(available) = available {
+            // 貸出中でも取り置き中でもない冊子が残っているか
+            query.push(if available { " AND " } else { " AND NOT " }).push(
+                r#"(
+                    (SELECT COUNT(*) FROM book_copies AS bc WHERE bc.book_id = b.book_id)
+                    - (SELECT COUNT(*) FROM checkouts AS c WHERE c.book_id = b.book_id)
+                    - (
+                        SELECT COUNT(*) FROM reservations AS r
+                        WHERE r.book_id = b.book_id AND r.ready_at IS NOT NULL
+                    )
+                ) > 0"#,
+            );
+        }
+        let column = match sort {
+            // 読みを優先して正規化した書名を、文字コード順(五十音順)で並べる
+            BookSortKey::Title => r#"b.title_sort COLLATE "C""#,
+            BookSortKey::Author => "b.author",
+            BookSortKey::CreatedAt => "b.created_at",
+        };
+        let direction = match direction {
+            SortDirection::Asc => "ASC",
+            SortDirection::Desc => "DESC",
+        };
+        // 同じ値の蔵書がある場合もページ間で順序が変わらないよう、蔵書IDを第2キーにする
+        query
+            .push(format!(
+                " ORDER BY {column} {direction}, b.book_id {direction}"
+            ))
+            .push(" LIMIT ")
+            .push_bind(limit)
+            .push(" OFFSET ")
+            .push_bind(offset);
+
+        let rows: Vec<PaginatedBookRow> = query
+            .build_query_as()
+            .fetch_all(self.db.inner_ref())
+            .await
+            .map_err(AppError::SpecificOperationError)?;
 
         // レコードが1つもない時はtotalも0にする
         let total = rows.first().map(|r| r.total).unwrap_or_default();
@@ -101,41 +254,67 @@ impl BookRepository for BookRepositoryImpl {
 
         info!("The number of total books were successfully counted: find_all(1/2)");
 
-        let rows: Vec<BookRow> = sqlx::query_as!(
-            BookRow,
+        let items = self.find_by_ids(&book_ids).await?;
+
+        info!("Books was successfully selected: find_all(2/2)");
+
+        Ok(PaginatedList {
+            total,
+            limit,
+            offset,
+            items,
+        })
+    }
+
+    // 検索語と蔵書の双方をnormalize_search_text関数で正規化してから比較する
+    // 1. 部分一致、またはトライグラムの類似度が閾値を超える蔵書を対象とする
+    // 2. 書名(読みを含む)に部分一致するものを優先し、類似度の高い順に並べる
+    #[tracing::instrument(skip_all, fields(db.system = "postgresql", db.operation = "SELECT", limit = options.limit, offset = options.offset))]
+    async fn search(
+        &self,
+        options: BookSearchOptions,
+    ) -> AppResult<PaginatedList<Book>> {
+        let BookSearchOptions { q, limit, offset } = options;
+
+        let rows: Vec<PaginatedBookRow> = sqlx::query_as!(
+            PaginatedBookRow,
             r#"
-                SELECT 
-                    b.book_id AS book_id,
-                    b.title AS title,
-                    b.author AS author,
-                    b.isbn AS isbn,
-                    b.description AS description,
-                    u.user_id AS owned_by,
-                    u.name AS owner_name
-                FROM books AS b
-                INNER JOIN users AS u using(user_id)
-                WHERE b.book_id IN (SELECT * FROM UNNEST($1::uuid[]))
-                ORDER BY b.created_at DESC
+                WITH query AS (
+                    SELECT
+                        normalize_search_text($1) AS term,
+                        search_like_pattern($1) AS pattern
+                )
+                SELECT
+                    COUNT(*) OVER() AS "total!",
+                    b.book_id AS id
+                FROM books AS b, query AS q
+                WHERE b.search_text LIKE q.pattern
+                OR q.term <% b.search_text
+                ORDER BY
+                    (b.search_title LIKE q.pattern) DESC,
+                    word_similarity(q.term, b.search_title) DESC,
+                    word_similarity(q.term, b.search_text) DESC,
+                    b.title_sort COLLATE "C" ASC,
+                    b.book_id ASC
+                LIMIT $2
+                OFFSET $3
             "#,
-            &book_ids as _
+            q,
+            limit,
+            offset
         )
         .fetch_all(self.db.inner_ref())
         .await
         .map_err(AppError::SpecificOperationError)?;
 
-        let book_ids = 
-            rows.iter().map(|book| book.book_id).collect::<Vec<_>>();
+        let total = rows.first().map(|r| r.total).unwrap_or_default();
+        let book_ids = rows.into_iter().map(|r| r.id).collect::<Vec<BookId>>();
 
-        let mut checkouts = self.find_checkouts(&book_ids).await?;
-        let items = rows
-            .into_iter()
-            .map(|row|{
-                let checkout = checkouts.remove(&row.book_id);
-                row.into_book(checkout)
-            })
-            .collect();
+        info!("The number of matched books were successfully counted: search(1/2)");
 
-        info!("Books was successfully selected: find_all(2/2)");
+        let items = self.find_by_ids(&book_ids).await?;
+
+        info!("Books was successfully selected: search(2/2)");
 
         Ok(PaginatedList {
             total,
@@ -145,6 +324,7 @@ impl BookRepository for BookRepositoryImpl {
         })
     }
 
+    #[tracing::instrument(skip_all, fields(db.system = "postgresql", db.operation = "SELECT", book_id = %book_id))]
     async fn find_by_id(&self, book_id: BookId) -> AppResult<Option<Book>> {
         let row: Option<BookRow> = sqlx::query_as!(
             BookRow,
@@ -152,11 +332,31 @@ impl BookRepository for BookRepositoryImpl {
                 SELECT 
                     b.book_id AS book_id,
                     b.title AS title,
+                    b.title_kana AS title_kana,
                     b.author AS author,
                     b.isbn AS isbn,
                     b.description AS description,
                     u.user_id AS owned_by,
-                    u.name AS owner_name
+                    u.name AS owner_name,
+                    (
+                        SELECT COUNT(*) FROM book_copies AS bc
+                        WHERE bc.book_id = b.book_id
+                    ) AS "total_copies!",
+                    GREATEST(
+                        0,
+                        (
+                            SELECT COUNT(*) FROM book_copies AS bc
+                            WHERE bc.book_id = b.book_id
+                        )
+                        - (
+                            SELECT COUNT(*) FROM checkouts AS c
+                            WHERE c.book_id = b.book_id
+                        )
+                        - (
+                            SELECT COUNT(*) FROM reservations AS r
+                            WHERE r.book_id = b.book_id AND r.ready_at IS NOT NULL
+                        )
+                    ) AS "available_copies!"
                 FROM books AS b
                 INNER JOIN users AS u USING(user_id)
                 WHERE book_id = $1
@@ -171,18 +371,121 @@ impl BookRepository for BookRepositoryImpl {
 
         match row {
             Some(r) => {
-                let checkout = self
+                let checkouts = self
                     .find_checkouts(&[r.book_id])
                     .await?
-                    .remove(&r.book_id);
-                Ok(Some(r.into_book(checkout)))
+                    .remove(&r.book_id)
+                    .unwrap_or_default();
+                Ok(Some(r.into_book(checkouts)))
             }
             None => Ok(None),
         }
     }
 
+    // 全件をメモリに載せないよう、サーバーサイドカーソルで一定件数ずつ取り出して返す
+    // カーソルはトランザクション内でのみ有効なため、ストリームがトランザクションを保持する
+    #[tracing::instrument(skip_all, fields(db.system = "postgresql", db.operation = "DECLARE CURSOR"))]
+    async fn export_all(&self) -> AppResult<BookStream> {
+        let mut tx = self
+            .db
+            .inner_ref()
+            .begin()
+            .await
+            .map_err(AppError::TransactionError)?;
+
+        sqlx::query(&format!(
+            r#"
+                DECLARE {} NO SCROLL CURSOR FOR
+                SELECT 
+                    b.book_id AS book_id,
+                    b.title AS title,
+                    b.title_kana AS title_kana,
+                    b.author AS author,
+                    b.isbn AS isbn,
+                    b.description AS description,
+                    u.user_id AS owned_by,
+                    u.name AS owner_name,
+                    (
+                        SELECT COUNT(*) FROM book_copies AS bc
+                        WHERE bc.book_id = b.book_id
+                    ) AS total_copies,
+                    GREATEST(
+                        0,
+                        (
+                            SELECT COUNT(*) FROM book_copies AS bc
+                            WHERE bc.book_id = b.book_id
+                        )
+                        - (
+                            SELECT COUNT(*) FROM checkouts AS c
+                            WHERE c.book_id = b.book_id
+                        )
+                        - (
+                            SELECT COUNT(*) FROM reservations AS r
+                            WHERE r.book_id = b.book_id AND r.ready_at IS NOT NULL
+                        )
+                    ) AS available_copies
+                FROM books AS b
+                INNER JOIN users AS u USING(user_id)
+                ORDER BY b.created_at ASC, b.book_id ASC
+            "#,
+            EXPORT_CURSOR
+        ))
+        .execute(&mut *tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+
+        info!("Export cursor was declared: export_all");
+
+        let stream = stream::try_unfold(Some(tx), |tx| async move {
+            let Some(mut tx) = tx else {
+                return Ok::<_, AppError>(None);
+            };
+            match fetch_export_batch(&mut tx).await? {
+                Some(books) => Ok(Some((
+                    stream::iter(books.into_iter().map(Ok::<_, AppError>)),
+                    Some(tx),
+                ))),
+                // 全件を取り出し終えたら、トランザクションを終了してカーソルを閉じる
+                None => {
+                    tx.commit().await.map_err(AppError::TransactionError)?;
+                    info!("Books were successfully exported: export_all");
+                    Ok(None)
+                }
+            }
+        })
+        .try_flatten()
+        .boxed();
+
+        Ok(stream)
+    }
+
+    #[tracing::instrument(skip_all, fields(db.system = "postgresql", db.operation = "UPDATE", book_id = %event.book_id, user_id = %event.requested_user))]
     async fn update(&self, event: UpdateBook) -> AppResult<()> {
-        let res = sqlx::query!(
+        let mut tx = self.db.begin().await?;
+
+        // 監査ログに変更前の書誌情報を記録するため、対象の蔵書をロックして取得する
+        let before = sqlx::query!(
+            r#"
+                SELECT title, author, isbn, description
+                FROM books
+                WHERE book_id = $1
+                AND (user_id = $2 OR $3)
+                FOR UPDATE
+            "#,
+            event.book_id as _,
+            event.requested_user as _,
+            event.any_owner
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?
+        .ok_or_else(|| {
+            AppError::EntityNotFound(
+                Message::new("book_not_found").arg("book_id", event.book_id),
+            )
+        })?;
+
+        sqlx::query!(
             r#"
                 UPDATE books
                 SET
@@ -191,24 +494,40 @@ impl BookRepository for BookRepositoryImpl {
                     isbn = $3,
                     description = $4
                 WHERE book_id = $5
-                AND user_id = $6
             "#,
             event.title,
             event.author,
-            event.isbn,
+            event.isbn.as_str(),
             event.description,
             event.book_id as _,
-            event.requested_user as _
         )
-        .execute(self.db.inner_ref())
+        .execute(&mut *tx)
         .await
         .map_err(AppError::SpecificOperationError)?;
 
-        if res.rows_affected() < 1{
-            return Err(AppError::EntityNotFound(
-                "specified book not found".into(),  
-            ));
-        }
+        record_audit_event(
+            &mut tx,
+            CreateAuditEvent::new(
+                event.requested_user,
+                AuditAction::BookUpdate,
+                event.book_id.to_string(),
+                Some(json!({
+                    "title": before.title,
+                    "author": before.author,
+                    "isbn": before.isbn,
+                    "description": before.description,
+                })),
+                Some(json!({
+                    "title": event.title,
+                    "author": event.author,
+                    "isbn": event.isbn.as_str(),
+                    "description": event.description,
+                })),
+            ),
+        )
+        .await?;
+
+        tx.commit().await.map_err(AppError::TransactionError)?;
 
         // 成功時のログ内容
         info!(
@@ -221,64 +540,353 @@ impl BookRepository for BookRepositoryImpl {
         Ok(())
     }
 
+    #[tracing::instrument(skip_all, fields(db.system = "postgresql", db.operation = "DELETE", book_id = %event.book_id, user_id = %event.requested_user))]
     async fn delete(&self, event: DeleteBook) -> AppResult<()> {
-        let res = sqlx::query!(
+        let mut tx = self.db.begin().await?;
+
+        // 削除した蔵書の書誌情報を監査ログに残す
+        let before = sqlx::query!(
             r#"
                 DELETE FROM books
                 WHERE book_id = $1
-                AND   user_id = $2
+                AND   (user_id = $2 OR $3)
+                RETURNING title, author, isbn, description, user_id AS "user_id: UserId"
+            "#,
+            event.book_id as _,
+            event.requested_user as _,
+            event.any_owner
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?
+        .ok_or_else(|| {
+            AppError::EntityNotFound(
+                Message::new("book_not_found").arg("book_id", event.book_id),
+            )
+        })?;
+
+        record_audit_event(
+            &mut tx,
+            CreateAuditEvent::new(
+                event.requested_user,
+                AuditAction::BookDelete,
+                event.book_id.to_string(),
+                Some(json!({
+                    "title": before.title,
+                    "author": before.author,
+                    "isbn": before.isbn,
+                    "description": before.description,
+                    "ownerId": before.user_id,
+                })),
+                None,
+            ),
+        )
+        .await?;
+
+        tx.commit().await.map_err(AppError::TransactionError)?;
+
+        info!("The book successfully deleted: book_id = {}", event.book_id);
+
+        Ok(())
+        
+    }
+
+    #[tracing::instrument(skip_all, fields(db.system = "postgresql", db.operation = "INSERT", book_id = %event.book_id, user_id = %event.requested_user))]
+    async fn add_copies(&self, event: AddBookCopies) -> AppResult<()> {
+        let mut tx = self.db.begin().await?;
+
+        // 冊子を追加できるのは蔵書の所有者のみとする
+        let res = sqlx::query!(
+            r#"
+                SELECT book_id FROM books
+                WHERE book_id = $1
+                AND user_id = $2
+                FOR UPDATE
             "#,
             event.book_id as _,
             event.requested_user as _
         )
-        .execute(self.db.inner_ref())
+        .fetch_optional(&mut *tx)
         .await
         .map_err(AppError::SpecificOperationError)?;
 
-        if res.rows_affected() < 1 {
+        if res.is_none() {
             return Err(AppError::EntityNotFound(
-                "specified book not found".into(),
+                Message::new("book_not_found").arg("book_id", event.book_id),
             ));
         }
 
-        info!("The book successfully deleted: book_id = {}", event.book_id);
+        insert_copies(&mut tx, event.book_id, event.copies).await?;
+        record_audit_event(
+            &mut tx,
+            CreateAuditEvent::new(
+                event.requested_user,
+                AuditAction::BookCopiesAdd,
+                event.book_id.to_string(),
+                None,
+                Some(json!({ "copies": event.copies })),
+            ),
+        )
+        .await?;
+
+        tx.commit().await.map_err(AppError::TransactionError)?;
+
+        info!(
+            "Book copies added successfully: book_id={}, copies={}",
+            event.book_id, event.copies
+        );
 
         Ok(())
-        
     }
 }
 
 impl BookRepositoryImpl{
-    // 指定されたbook_idが貸出中の場合に貸出情報を返すメソッドを追加する
-    async fn find_checkouts(
-        &self,
-        book_ids: &[BookId],
-    ) -> AppResult<HashMap<BookId, Checkout>> {
-        let res = sqlx::query_as!(
-            BookCheckoutRow,
+    // 蔵書IDのリストの順序を保ったまま、蔵書のレコードデータと貸出情報を取得する
+    #[tracing::instrument(skip_all, fields(db.system = "postgresql", db.operation = "SELECT", books = book_ids.len()))]
+    async fn find_by_ids(&self, book_ids: &[BookId]) -> AppResult<Vec<Book>> {
+        let rows: Vec<BookRow> = sqlx::query_as!(
+            BookRow,
             r#"
                 SELECT 
-                    c.checkout_id,
-                    c.book_id,
-                    u.user_id,
-                    u.name AS user_name,
-                    c.checked_out_at
-                FROM checkouts AS c
+                    b.book_id AS book_id,
+                    b.title AS title,
+                    b.title_kana AS title_kana,
+                    b.author AS author,
+                    b.isbn AS isbn,
+                    b.description AS description,
+                    u.user_id AS owned_by,
+                    u.name AS owner_name,
+                    (
+                        SELECT COUNT(*) FROM book_copies AS bc
+                        WHERE bc.book_id = b.book_id
+                    ) AS "total_copies!",
+                    GREATEST(
+                        0,
+                        (
+                            SELECT COUNT(*) FROM book_copies AS bc
+                            WHERE bc.book_id = b.book_id
+                        )
+                        - (
+                            SELECT COUNT(*) FROM checkouts AS c
+                            WHERE c.book_id = b.book_id
+                        )
+                        - (
+                            SELECT COUNT(*) FROM reservations AS r
+                            WHERE r.book_id = b.book_id AND r.ready_at IS NOT NULL
+                        )
+                    ) AS "available_copies!"
+                FROM books AS b
                 INNER JOIN users AS u using(user_id)
-                WHERE book_id = ANY($1)
-                ;
+                WHERE b.book_id IN (SELECT * FROM UNNEST($1::uuid[]))
+                ORDER BY array_position($1::uuid[], b.book_id)
             "#,
             book_ids as _
         )
         .fetch_all(self.db.inner_ref())
         .await
-        .map_err(AppError::SpecificOperationError)?
+        .map_err(AppError::SpecificOperationError)?;
+
+        let book_ids = 
+            rows.iter().map(|book| book.book_id).collect::<Vec<_>>();
+
+        let mut checkouts = self.find_checkouts(&book_ids).await?;
+        let items = rows
+            .into_iter()
+            .map(|row|{
+                let checkouts = checkouts.remove(&row.book_id).unwrap_or_default();
+                row.into_book(checkouts)
+            })
+            .collect();
+
+        Ok(items)
+    }
+
+    // 指定されたbook_idの冊子が貸出中の場合に貸出情報を返すメソッドを追加する
+    #[tracing::instrument(skip_all, fields(db.system = "postgresql", db.operation = "SELECT", books = book_ids.len()))]
+    async fn find_checkouts(
+        &self,
+        book_ids: &[BookId],
+    ) -> AppResult<HashMap<BookId, Vec<Checkout>>> {
+        select_checkouts(self.db.inner_ref(), book_ids).await
+    }
+}
+
+// 蔵書ごとの貸出情報を取得する
+// エクスポートではカーソルと同じトランザクション内から呼び出すため、Executorを受け取る
+async fn select_checkouts<'e, E: sqlx::PgExecutor<'e>>(
+    executor: E,
+    book_ids: &[BookId],
+) -> AppResult<HashMap<BookId, Vec<Checkout>>> {
+    let mut res: HashMap<BookId, Vec<Checkout>> = HashMap::new();
+    let rows = sqlx::query_as!(
+        BookCheckoutRow,
+        r#"
+            SELECT 
+                c.checkout_id,
+                c.copy_id,
+                c.book_id,
+                u.user_id,
+                u.name AS user_name,
+                c.checked_out_at,
+                c.due_at
+            FROM checkouts AS c
+            INNER JOIN users AS u using(user_id)
+            WHERE book_id = ANY($1)
+            ORDER BY c.checked_out_at ASC
+            ;
+        "#,
+        book_ids as _
+    )
+    .fetch_all(executor)
+    .await
+    .map_err(AppError::SpecificOperationError)?;
+
+    for row in rows {
+        res.entry(row.book_id).or_default().push(Checkout::from(row));
+    }
+
+    Ok(res)
+}
+
+// エクスポートで使うカーソルの名前と、1回に取り出す件数
+const EXPORT_CURSOR: &str = "book_export_cursor";
+const EXPORT_BATCH_SIZE: i64 = 100;
+
+// カーソルから次の蔵書をまとめて取り出す。取り出し終えた場合はNoneを返す
+async fn fetch_export_batch(
+    tx: &mut sqlx::Transaction<'static, sqlx::Postgres>,
+) -> AppResult<Option<Vec<Book>>> {
+    let rows: Vec<BookRow> = sqlx::query_as(&format!(
+        "FETCH {} FROM {}",
+        EXPORT_BATCH_SIZE, EXPORT_CURSOR
+    ))
+    .fetch_all(&mut **tx)
+    .await
+    .map_err(AppError::SpecificOperationError)?;
+
+    if rows.is_empty() {
+        return Ok(None);
+    }
+
+    let book_ids = rows.iter().map(|row| row.book_id).collect::<Vec<_>>();
+    let mut checkouts = select_checkouts(&mut **tx, &book_ids).await?;
+    let books = rows
         .into_iter()
-        .map(|checkout| (checkout.book_id, Checkout::from(checkout)))
+        .map(|row| {
+            let checkouts = checkouts.remove(&row.book_id).unwrap_or_default();
+            row.into_book(checkouts)
+        })
         .collect();
 
-        Ok(res)
+    Ok(Some(books))
+}
+
+// LIKE検索用に、ワイルドカード文字をエスケープした部分一致のパターンを作る
+fn like_pattern(value: &str) -> String {
+    let escaped = value
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    format!("%{escaped}%")
+}
+
+// 監査ログに記録する、登録した蔵書の状態
+fn created_book_state(event: &CreateBook, owner: UserId) -> serde_json::Value {
+    json!({
+        "title": event.title,
+        "author": event.author,
+        "isbn": event.isbn.as_str(),
+        "description": event.description,
+        "copies": event.copies,
+        "ownerId": owner,
+    })
+}
+
+// 蔵書の書誌情報と冊子を登録する
+// 登録と一括登録の各トランザクション内から呼び出す
+async fn insert_book(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    event: &CreateBook,
+    user_id: UserId,
+) -> AppResult<BookId> {
+    // 同じユーザーが同じISBNの蔵書を所有している場合は登録を拒否する
+    // 同じ本を複数冊所有する場合は、冊子の追加で対応する
+    let duplicated = sqlx::query_scalar!(
+        r#"
+            SELECT book_id AS "book_id: BookId" FROM books
+            WHERE isbn = $1
+            AND user_id = $2
+            LIMIT 1
+        "#,
+        event.isbn.as_str(),
+        user_id as _
+    )
+    .fetch_optional(&mut **tx)
+    .await
+    .map_err(AppError::SpecificOperationError)?;
+
+    if let Some(book_id) = duplicated {
+        warn!(
+            "Duplicated ISBN was rejected: isbn='{}', user_id={}, book_id={}",
+            event.isbn, user_id, book_id
+        );
+        return Err(AppError::UnprocessableEntity(
+            Message::new("book_already_registered")
+                .arg("isbn", &event.isbn)
+                .arg("book_id", book_id),
+        ));
     }
+
+    let book_id = BookId::new();
+    sqlx::query!(
+        // SQLクエリ
+        r#"
+            INSERT INTO books (book_id, title, title_kana, author, isbn, description, user_id)
+            VALUES($1, $2, NULLIF(normalize_search_text($3), ''), $4, $5, $6, $7)
+        "#,
+        book_id as _,
+        event.title,
+        event.title_kana,
+        event.author,
+        event.isbn.as_str(),
+        event.description,
+        user_id as _
+    )
+    .execute(&mut **tx) // "execute"：SQLクエリをDBに送信し、結果を実行するメソッド
+    .await // "await"との違いは、errをどう返すか
+    // sqlx::Error型をAppError型に変換
+    .map_err(AppError::SpecificOperationError)?;
+
+    insert_copies(tx, book_id, event.copies).await?;
+
+    Ok(book_id)
+}
+
+// 蔵書に指定の数の冊子を登録する
+async fn insert_copies(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    book_id: BookId,
+    copies: i32,
+) -> AppResult<()> {
+    let res = sqlx::query!(
+        r#"
+            INSERT INTO book_copies (book_id)
+            SELECT $1 FROM generate_series(1, $2);
+        "#,
+        book_id as _,
+        copies
+    )
+    .execute(&mut **tx)
+    .await
+    .map_err(AppError::SpecificOperationError)?;
+
+    if res.rows_affected() < 1 {
+        return Err(AppError::NoRowsAffectedError(
+            "No book copy has been created".into(),
+        ));
+    }
+
+    Ok(())
 }
 
 #[cfg(test)]
@@ -303,9 +911,10 @@ mod tests {
             book_id: book.id,
             title: book.title,
             author: NEW_AUTHOR.into(),
-            isbn: book.isbn,
+            isbn: book.isbn.parse()?,
             description: book.description,
             requested_user: UserId::from_str("5b4c96ac-316a-4bee-8e69-cac5eb84ff4c").unwrap(),
+            any_owner: false,
         };
         repo.update(update_book).await.unwrap();
 
@@ -315,4 +924,345 @@ mod tests {
 
         Ok(())
     }
+
+    #[sqlx::test(fixtures("common", "book"))]
+    async fn test_update_and_delete_book_of_other_user(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = BookRepositoryImpl::new(ConnectionPool::new(pool.clone()));
+
+        let book_id = BookId::from_str("9890736e-a4e4-461a-a77d-eac3517ef11b").unwrap();
+        let book = repo.find_by_id(book_id).await?.unwrap();
+        let other_user = UserId::new();
+        let update_book = |any_owner| -> anyhow::Result<UpdateBook> {
+            Ok(UpdateBook {
+                book_id,
+                title: book.title.clone(),
+                author: "更新後の著者名".into(),
+                isbn: book.isbn.parse()?,
+                description: book.description.clone(),
+                requested_user: other_user,
+                any_owner,
+            })
+        };
+
+        // 1. 所有者以外は、権限がなければ更新・削除できない
+        let res = repo.update(update_book(false)?).await;
+        assert!(matches!(res, Err(AppError::EntityNotFound(_))));
+        let res = repo
+            .delete(DeleteBook {
+                book_id,
+                requested_user: other_user,
+                any_owner: false,
+            })
+            .await;
+        assert!(matches!(res, Err(AppError::EntityNotFound(_))));
+
+        // 2. 権限があれば、他のユーザーが所有する蔵書も更新・削除できる
+        repo.update(update_book(true)?).await?;
+        repo.delete(DeleteBook {
+            book_id,
+            requested_user: other_user,
+            any_owner: true,
+        })
+        .await?;
+        assert!(repo.find_by_id(book_id).await?.is_none());
+
+        Ok(())
+    }
+
+    fn list_options() -> BookListOptions {
+        BookListOptions {
+            limit: 20,
+            offset: 0,
+            q: None,
+            isbn: None,
+            author: None,
+            owner: None,
+            available: None,
+            sort: BookSortKey::CreatedAt,
+            direction: SortDirection::Desc,
+        }
+    }
+
+    fn titles(list: &PaginatedList<Book>) -> Vec<&str> {
+        list.items.iter().map(|b| b.title.as_str()).collect()
+    }
+
+    #[sqlx::test(fixtures("common", "book", "book_search"))]
+    async fn test_find_all_with_filters(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = BookRepositoryImpl::new(ConnectionPool::new(pool.clone()));
+
+        // 1. タイトル・著者・説明の部分一致(大文字小文字を区別しない)
+        let res = repo
+            .find_all(BookListOptions {
+                q: Some("rust".into()),
+                ..list_options()
+            })
+            .await?;
+        assert_eq!(res.total, 3);
+
+        // 2. ワイルドカード文字はそのままの文字として扱う
+        let res = repo
+            .find_all(BookListOptions {
+                q: Some("100%".into()),
+                ..list_options()
+            })
+            .await?;
+        assert_eq!(titles(&res), vec!["プログラミング言語Go"]);
+        let res = repo
+            .find_all(BookListOptions {
+                q: Some("%".into()),
+                author: Some("_".into()),
+                ..list_options()
+            })
+            .await?;
+        assert_eq!(res.total, 0);
+
+        // 3. ISBN・著者・所有者による絞り込み
+        let res = repo
+            .find_all(BookListOptions {
+                isbn: Some("978-4065301951".parse()?),
+                ..list_options()
+            })
+            .await?;
+        assert_eq!(res.total, 1);
+        let res = repo
+            .find_all(BookListOptions {
+                author: Some("豊田".into()),
+                ..list_options()
+            })
+            .await?;
+        assert_eq!(
+            titles(&res),
+            vec!["RustによるWebアプリケーション開発　設計からリリース・運用まで"]
+        );
+        let res = repo
+            .find_all(BookListOptions {
+                owner: Some(UserId::from_str("c2d1e9a4-6f1b-4c3e-8a57-0e4b9d6f2a13")?),
+                ..list_options()
+            })
+            .await?;
+        assert_eq!(titles(&res), vec!["プログラミング言語Go"]);
+
+        // 4. 貸出可否による絞り込み
+        let res = repo
+            .find_all(BookListOptions {
+                available: Some(false),
+                ..list_options()
+            })
+            .await?;
+        assert_eq!(
+            titles(&res),
+            vec!["RustによるWebアプリケーション開発　設計からリリース・運用まで"]
+        );
+        assert_eq!(res.items[0].available_copies, 0);
+        let res = repo
+            .find_all(BookListOptions {
+                available: Some(true),
+                ..list_options()
+            })
+            .await?;
+        assert_eq!(res.total, 3);
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common", "book", "book_search"))]
+    async fn test_find_all_with_sort(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = BookRepositoryImpl::new(ConnectionPool::new(pool.clone()));
+
+        // 著者名の昇順・降順で並び替え、ページネーションの後も順序が保たれることを検証する
+        let res = repo
+            .find_all(BookListOptions {
+                sort: BookSortKey::Author,
+                direction: SortDirection::Asc,
+                ..list_options()
+            })
+            .await?;
+        let authors = res.items.iter().map(|b| b.author.as_str()).collect::<Vec<_>>();
+        assert_eq!(
+            authors,
+            vec!["Alan A. A. Donovan他", "初田直也他", "豊田優貴他", "高野祐輝"]
+        );
+
+        let res = repo
+            .find_all(BookListOptions {
+                limit: 2,
+                offset: 1,
+                sort: BookSortKey::Author,
+                direction: SortDirection::Desc,
+                ..list_options()
+            })
+            .await?;
+        assert_eq!(res.total, 4);
+        let authors = res.items.iter().map(|b| b.author.as_str()).collect::<Vec<_>>();
+        assert_eq!(authors, vec!["豊田優貴他", "初田直也他"]);
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common", "book", "book_search"))]
+    async fn test_search_normalizes_japanese(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = BookRepositoryImpl::new(ConnectionPool::new(pool.clone()));
+        let search = |q: &str| BookSearchOptions {
+            q: q.into(),
+            limit: 20,
+            offset: 0,
+        };
+
+        // 1. 半角カナ・ひらがなで、カタカナの書名を検索できる
+        let res = repo.search(search("ｾﾞﾛから")).await?;
+        assert_eq!(
+            res.items[0].title,
+            "ゼロから学ぶRust　システムプログラミングの基礎から線形型システムまで"
+        );
+        let res = repo.search(search("しすてむぷろぐらみんぐ")).await?;
+        assert_eq!(res.total, 1);
+
+        // 2. 全角英字でも大文字小文字を区別せずに検索できる
+        let res = repo.search(search("ＲＵＳＴ")).await?;
+        assert_eq!(res.total, 3);
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common", "book", "book_search"))]
+    async fn test_sort_by_title_kana(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = BookRepositoryImpl::new(ConnectionPool::new(pool.clone()));
+
+        // 1. 読みを指定して蔵書を登録すると、ひらがなに正規化して保存される
+        let user_id = UserId::from_str("5b4c96ac-316a-4bee-8e69-cac5eb84ff4c")?;
+        repo.create(
+            CreateBook {
+                title: "型システム入門".into(),
+                title_kana: Some("カタシステムニュウモン".into()),
+                author: "Benjamin C. Pierce".into(),
+                isbn: "978-4274069116".parse()?,
+                description: "".into(),
+                copies: 1,
+            },
+            user_id,
+        )
+        .await?;
+
+        // 2. 書名で並び替えると、読みがある蔵書は読みの五十音順に並ぶ
+        let res = repo
+            .find_all(BookListOptions {
+                sort: BookSortKey::Title,
+                direction: SortDirection::Asc,
+                ..list_options()
+            })
+            .await?;
+        assert_eq!(
+            titles(&res),
+            vec![
+                "RustによるWebアプリケーション開発　設計からリリース・運用まで",
+                "型システム入門",
+                "ゼロから学ぶRust　システムプログラミングの基礎から線形型システムまで",
+                "プログラミング言語Go",
+                "実践Rustプログラミング入門",
+            ]
+        );
+        assert_eq!(
+            res.items[1].title_kana.as_deref(),
+            Some("かたしすてむにゅうもん")
+        );
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common", "book", "book_search"))]
+    async fn test_create_rejects_duplicated_isbn(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = BookRepositoryImpl::new(ConnectionPool::new(pool.clone()));
+        let create_book = || -> anyhow::Result<CreateBook> {
+            Ok(CreateBook {
+                title: "実践Rustプログラミング入門".into(),
+                title_kana: None,
+                author: "初田直也他".into(),
+                // fixtures/book.sqlの蔵書のISBN-13をISBN-10で表記したもの
+                isbn: "4-7980-6170-0".parse()?,
+                description: "".into(),
+                copies: 1,
+            })
+        };
+
+        // 1. 同じユーザーが同じISBNの蔵書を登録しようとすると拒否される
+        let owner = UserId::from_str("5b4c96ac-316a-4bee-8e69-cac5eb84ff4c")?;
+        let res = repo.create(create_book()?, owner).await;
+        assert!(matches!(res, Err(AppError::UnprocessableEntity(_))));
+
+        // 2. 別のユーザーであれば登録でき、ISBN-13の正規形で保存される
+        let other = UserId::from_str("c2d1e9a4-6f1b-4c3e-8a57-0e4b9d6f2a13")?;
+        repo.create(create_book()?, other).await?;
+        let res = repo
+            .find_all(BookListOptions {
+                isbn: Some("9784798061702".parse()?),
+                ..list_options()
+            })
+            .await?;
+        assert_eq!(res.total, 2);
+        assert!(res.items.iter().all(|b| b.isbn == "9784798061702"));
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common", "book"))]
+    async fn test_create_many_reports_each_row(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = BookRepositoryImpl::new(ConnectionPool::new(pool.clone()));
+        let import_book = |owner_email: &str, isbn: &str| -> anyhow::Result<ImportBook> {
+            Ok(ImportBook {
+                owner_email: owner_email.into(),
+                book: CreateBook {
+                    title: "型システム入門".into(),
+                    title_kana: None,
+                    author: "Benjamin C. Pierce".into(),
+                    isbn: isbn.parse()?,
+                    description: "".into(),
+                    copies: 1,
+                },
+            })
+        };
+
+        // 1. 登録できる行、所有者が存在しない行、所有済みのISBNの行、同じ一括登録内で重複する行
+        let outcomes = repo
+            .create_many(
+                vec![
+                    import_book("eleazar.fig@example.com", "978-4274069116")?,
+                    import_book("nobody@example.com", "978-4274069116")?,
+                    import_book("eleazar.fig@example.com", "978-4798061702")?,
+                    import_book("eleazar.fig@example.com", "4-274-06911-7")?,
+                ],
+                UserId::from_str("5b4c96ac-316a-4bee-8e69-cac5eb84ff4c")?,
+            )
+            .await?;
+
+        // 2. 入力と同じ順序で、1行ごとの結果が返る
+        assert_eq!(outcomes.len(), 4);
+        assert!(matches!(outcomes[0], BookImportOutcome::Created(_)));
+        assert!(matches!(outcomes[1], BookImportOutcome::Rejected(_)));
+        assert!(matches!(outcomes[2], BookImportOutcome::Rejected(_)));
+        assert!(matches!(outcomes[3], BookImportOutcome::Rejected(_)));
+
+        // 3. 登録できた行のみが保存されている
+        let res = repo.find_all(list_options()).await?;
+        assert_eq!(res.total, 4);
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common", "book", "book_search"))]
+    async fn test_export_all(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = BookRepositoryImpl::new(ConnectionPool::new(pool.clone()));
+
+        // カーソルから全件を取り出し、貸出情報も含めて返されることを検証する
+        let books = repo.export_all().await?.try_collect::<Vec<_>>().await?;
+        assert_eq!(books.len(), 4);
+        let checked_out = books
+            .iter()
+            .find(|b| b.id == BookId::from_str("17afb850-c786-49c5-a303-a3a443a2212c").unwrap())
+            .unwrap();
+        assert_eq!(checked_out.checkouts.len(), 1);
+        assert_eq!(checked_out.owner.name, "Eleazar Fig");
+
+        Ok(())
+    }
 }
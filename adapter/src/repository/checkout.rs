@@ -3,90 +3,158 @@
 // → トランザクションを用いて複数回のクエリ操作を行う
 
 use crate::database::{
-    model::checkout::{CheckoutRow, CheckoutStateRow, ReturnedCheckoutRow},
-    ConnectionPool
+    model::checkout::{
+        BookAvailabilityRow, CheckoutRenewalStateRow, CheckoutRow, CheckoutStateRow,
+        ReturnedCheckoutRow,
+    },
+    set_transaction_serializable, ConnectionPool
+};
+use crate::repository::{
+    audit::record_audit_event, policy::fetch_user_borrowing_policy,
+    reservation::refresh_reservation_queue,
 };
 use async_trait::async_trait;
 use derive_new::new;
+use kernel::model::audit::{event::CreateAuditEvent, AuditAction};
 use kernel::model::checkout::{
-    event::{CreateCheckout, UpdateReturned},
+    event::{CreateCheckout, RenewCheckout, UpdateReturned},
     Checkout,
 };
 use kernel::model::id::{BookId, CheckoutId, UserId};
 use kernel::repository::checkout::CheckoutRepository;
-use shared::error::{AppError, AppResult};
+use serde_json::json;
+use shared::{
+    config::CheckoutConfig,
+    error::{AppError, AppResult},
+    i18n::Message,
+};
 
 #[derive(new)]
 pub struct CheckoutRepositoryImpl {
     db: ConnectionPool,
+    // 返却された蔵書を予約者のために取り置いておく期間(秒)
+    hold_ttl: u64,
+    // 貸出ポリシーが登録されていないロールに適用する既定値
+    default_policy: CheckoutConfig,
 }
 
 #[async_trait]
 impl CheckoutRepository for CheckoutRepositoryImpl {
     // 貸出操作を行う　
+    #[tracing::instrument(skip_all, fields(db.system = "postgresql", db.operation = "INSERT", book_id = %event.book_id, user_id = %event.checked_out_by))]
     async fn create(&self, event: CreateCheckout) -> AppResult<()>{
         let mut tx = self.db.begin().await?;
 
         // トランザクション分離レベルをSERIALIZABLEに設定する
-        self.set_transaction_serializable(&mut tx).await?;
+        set_transaction_serializable(&mut tx).await?;
+
+        // 予約の待ち行列を最新の状態にしてから貸出可否を調べる
+        refresh_reservation_queue(
+            &mut tx,
+            event.book_id,
+            event.checked_out_at,
+            self.hold_ttl,
+        )
+        .await?;
 
         // 事前のチェックとして以下を調べる　
         // - 指定の蔵書IDを持つ蔵書が存在するか　
-        // - 存在した場合：この蔵書は貸出中ではないか
+        // - 存在した場合：このユーザーがすでに借りていないか
+        // - 貸出中でも他のユーザーのために取り置かれてもいない冊子が残っているか
 
         // 上記がYESだった場合、このブロック以降の処理に進む
         {
-            let res = sqlx::query_as!(
-                CheckoutStateRow,
-                r#"
-                    SELECT 
-                        b.book_id,
-                        c.checkout_id AS "checkout_id?: CheckoutId",
-                        NULL AS "user_id?: UserId"
-                    FROM books AS b
-                    LEFT OUTER JOIN checkouts AS c USING(book_id)
-                    WHERE book_id = $1;
-                "#,
-                event.book_id as _
+            let res = fetch_book_availability(
+                &mut tx,
+                event.book_id,
+                event.checked_out_by,
             )
-            .fetch_optional(&mut *tx)
-            .await
-            .map_err(AppError::SpecificOperationError)?;
+            .await?;
 
             match res {
                 // 指定した書籍が存在しない場合　
                 None => {
-                    return Err(AppError::EntityNotFound(format!(
-                        "書籍({})が見つかりませんでした。",
-                        event.book_id
-                    )))
+                    return Err(AppError::EntityNotFound(
+                        Message::new("book_not_found").arg("book_id", event.book_id),
+                    ))
+                }
+                // 同じ書籍をすでに借りている場合
+                Some(BookAvailabilityRow { borrowing: true, .. }) => {
+                    return Err(AppError::UnprocessableEntity(
+                        Message::new("book_already_checked_out_by_user")
+                            .arg("book_id", event.book_id)
+                            .arg("user_id", event.checked_out_by),
+                    ))
+                }
+                // 全ての冊子が貸出中の場合
+                Some(ref row) if row.checked_out >= row.total_copies => {
+                    return Err(AppError::UnprocessableEntity(
+                        Message::new("checkout_already_exists").arg("book_id", event.book_id),
+                    ))
+                }
+                // 残りの冊子が他のユーザーのために取り置かれている場合
+                Some(ref row) if row.available_for_user() <= 0 => {
+                    return Err(AppError::UnprocessableEntity(
+                        Message::new("book_on_hold_for_other_user").arg("book_id", event.book_id),
+                    ))
                 }
-                // 指定した書籍が存在するが貸出中の場合
-                Some(CheckoutStateRow{
-                    checkout_id: Some(_),
-                    .. 
-                }) => {
-                    return Err(AppError::UnprocessableEntity(format!(
-                        "書籍({})に対する貸出がすでに存在しています。",
-                        event.book_id
-                    )))
-                } 
                 _ => {} //それ以外は処理続行
             }
         }
 
-        // 貸出処理を行う checkoutsテーブルにレコードを追加する
+        // 借りるユーザーのロールに応じた貸出ポリシーを適用する
+        // - 同時に借りている冊数が上限に達していないか
+        // - 返却期限は貸出日から貸出期間分とする
+        let policy = fetch_user_borrowing_policy(
+            &mut tx,
+            event.checked_out_by,
+            &self.default_policy,
+        )
+        .await?;
+        {
+            let loans = sqlx::query!(
+                r#"
+                    SELECT COUNT(*) AS "count!" FROM checkouts WHERE user_id = $1;
+                "#,
+                event.checked_out_by as _
+            )
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(AppError::SpecificOperationError)?
+            .count;
+
+            if loans >= i64::from(policy.max_loans) {
+                return Err(AppError::UnprocessableEntity(
+                    Message::new("loan_limit_reached")
+                        .arg("user_id", event.checked_out_by)
+                        .arg("max_loans", policy.max_loans),
+                ));
+            }
+        }
+        let due_at = event.checked_out_at
+            + chrono::Duration::days(i64::from(policy.loan_period_days));
+
+        // 貸出処理を行う 貸出中ではない冊子を1冊選び、checkoutsテーブルにレコードを追加する
         let checkout_id = CheckoutId::new();
         let res = sqlx::query!(
             r#"
                 INSERT INTO checkouts
-                (checkout_id, book_id, user_id, checked_out_at)
-                VALUES ($1, $2, $3, $4);
+                (checkout_id, copy_id, book_id, user_id, checked_out_at, due_at, checked_out_performed_by)
+                SELECT $1, bc.copy_id, bc.book_id, $3, $4, $5, $6
+                FROM book_copies AS bc
+                WHERE bc.book_id = $2
+                AND NOT EXISTS (
+                    SELECT 1 FROM checkouts AS c WHERE c.copy_id = bc.copy_id
+                )
+                ORDER BY bc.created_at ASC
+                LIMIT 1;
             "#,
             checkout_id as _,
             event.book_id as _,
             event.checked_out_by as _,
             event.checked_out_at,
+            due_at,
+            event.performed_by as _,
         )
         .execute(&mut *tx)
         .await
@@ -98,22 +166,204 @@ impl CheckoutRepository for CheckoutRepositoryImpl {
             ));
         }
 
+        // 借りたユーザーの予約(取り置きを含む)は完了したので取り除く
+        sqlx::query!(
+            r#"
+                DELETE FROM reservations
+                WHERE book_id = $1
+                AND user_id = $2;
+            "#,
+            event.book_id as _,
+            event.checked_out_by as _,
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+
+        record_audit_event(
+            &mut tx,
+            CreateAuditEvent::new(
+                event.performed_by,
+                AuditAction::CheckoutCreate,
+                checkout_id.to_string(),
+                None,
+                Some(json!({
+                    "bookId": event.book_id,
+                    "checkedOutBy": event.checked_out_by,
+                    "checkedOutAt": event.checked_out_at,
+                    "dueAt": due_at,
+                })),
+            ),
+        )
+        .await?;
+
+        tx.commit().await.map_err(AppError::TransactionError)?;
+
+        Ok(())
+    }
+
+    // 貸出の延長操作を行う
+    #[tracing::instrument(skip_all, fields(db.system = "postgresql", db.operation = "UPDATE", checkout_id = %event.checkout_id, user_id = %event.renewed_by))]
+    async fn renew(&self, event: RenewCheckout) -> AppResult<()> {
+        let mut tx = self.db.begin().await?;
+
+        // トランザクションの分離レベルをSERIALIZABLEに設定する
+        set_transaction_serializable(&mut tx).await?;
+
+        // 借りたユーザーのロールに応じた貸出ポリシーを適用する
+        let policy = fetch_user_borrowing_policy(
+            &mut tx,
+            event.renewed_by,
+            &self.default_policy,
+        )
+        .await?;
+
+        // 延長操作時のチェック項目
+        // - 指定した貸出IDを持つ貸出が、指定の蔵書に対して存在するか
+        // - 存在した場合
+        //  - 借りたユーザーが指定のユーザーと同じか
+        //  - 延長回数が上限に達していないか
+        {
+            let res = sqlx::query_as!(
+                CheckoutRenewalStateRow,
+                r#"
+                    SELECT 
+                        checkout_id,
+                        user_id,
+                        renewal_count
+                    FROM checkouts
+                    WHERE checkout_id = $1
+                    AND book_id = $2;
+                "#,
+                event.checkout_id as _,
+                event.book_id as _,
+            )
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(AppError::SpecificOperationError)?;
+
+            match res {
+                // 指定した貸出がない場合
+                None => {
+                    return Err(AppError::EntityNotFound(
+                        Message::new("checkout_not_found").arg("checkout_id", event.checkout_id),
+                    ))
+                }
+                // 借りたユーザーが異なる場合
+                Some(CheckoutRenewalStateRow { user_id, .. })
+                    if user_id != event.renewed_by =>
+                {
+                    return Err(AppError::UnprocessableEntity(
+                        Message::new("checkout_not_renewable")
+                            .arg("checkout_id", event.checkout_id)
+                            .arg("user_id", event.renewed_by)
+                            .arg("book_id", event.book_id),
+                    ))
+                }
+                // 延長回数が上限に達している場合
+                Some(CheckoutRenewalStateRow { renewal_count, .. })
+                    if renewal_count >= policy.max_renewals =>
+                {
+                    return Err(AppError::UnprocessableEntity(
+                        Message::new("renewal_limit_reached")
+                            .arg("checkout_id", event.checkout_id)
+                            .arg("max_renewals", policy.max_renewals),
+                    ))
+                }
+                _ => {} // それ以外は処理続行
+            }
+        }
+
+        // 他のユーザーが予約の順番を待っている蔵書は延長できない
+        // (取り置き済みの予約者にはすでに冊子が確保されている)
+        let reserved = sqlx::query!(
+            r#"
+                SELECT EXISTS (
+                    SELECT 1 FROM reservations
+                    WHERE book_id = $1
+                    AND user_id <> $2
+                    AND ready_at IS NULL
+                ) AS "reserved!";
+            "#,
+            event.book_id as _,
+            event.renewed_by as _,
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?
+        .reserved;
+
+        if reserved {
+            return Err(AppError::UnprocessableEntity(
+                Message::new("book_reserved_by_other_user").arg("book_id", event.book_id),
+            ));
+        }
+
+        // 延長後の返却期限は、延長した日から貸出期間分とする
+        // 返却期限が短くならないよう、現在の返却期限と延長後の返却期限の遅い方を採用する
+        let due_at = event.renewed_at
+            + chrono::Duration::days(i64::from(policy.loan_period_days));
+        // 監査ログに延長前後の返却期限と延長回数を記録するため、更新前の値も返す
+        let renewed = sqlx::query!(
+            r#"
+                UPDATE checkouts AS c
+                SET
+                    due_at = GREATEST(c.due_at, $2),
+                    renewal_count = c.renewal_count + 1
+                FROM checkouts AS old
+                WHERE c.checkout_id = $1
+                AND old.checkout_id = c.checkout_id
+                RETURNING
+                    old.due_at AS before_due_at,
+                    old.renewal_count AS before_renewal_count,
+                    c.due_at,
+                    c.renewal_count;
+            "#,
+            event.checkout_id as _,
+            due_at,
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?
+        .ok_or_else(|| {
+            AppError::NoRowsAffectedError("No checkout record has been renewed".into())
+        })?;
+
+        record_audit_event(
+            &mut tx,
+            CreateAuditEvent::new(
+                event.renewed_by,
+                AuditAction::CheckoutRenew,
+                event.checkout_id.to_string(),
+                Some(json!({
+                    "dueAt": renewed.before_due_at,
+                    "renewalCount": renewed.before_renewal_count,
+                })),
+                Some(json!({
+                    "dueAt": renewed.due_at,
+                    "renewalCount": renewed.renewal_count,
+                })),
+            ),
+        )
+        .await?;
+
         tx.commit().await.map_err(AppError::TransactionError)?;
 
         Ok(())
     }
 
     // 返却操作を行う
+    #[tracing::instrument(skip_all, fields(db.system = "postgresql", db.operation = "INSERT", checkout_id = %event.checkout_id, user_id = %event.returned_by))]
     async fn update_returned(&self, event: UpdateReturned) -> AppResult<()>{
         let mut tx = self.db.begin().await?;
 
         // トランザクションの分離レベルをSERIALIZABLEに設定する
-        self.set_transaction_serializable(&mut tx).await?;
+        set_transaction_serializable(&mut tx).await?;
 
         // 返却操作時のチェック項目
         // - 指定した蔵書IDを持つ蔵書が存在するのか
         // - 存在した場合
-        //  - この蔵書は貸出中、かつ、借りたユーザーが指定のユーザーと同じか
+        //  - この蔵書は貸出中、かつ、借りたユーザーが指定のユーザーと同じか(強制返却の場合は問わない)
 
         // 上記がYesの場合このブロック以降の処理に進む
         {
@@ -125,10 +375,12 @@ impl CheckoutRepository for CheckoutRepositoryImpl {
                         c.checkout_id AS "checkout_id?: CheckoutId",
                         c.user_id AS "user_id?: UserId"
                     FROM books AS b
-                    LEFT OUTER JOIN checkouts AS c USING(book_id)
-                    WHERE book_id = $1;
+                    LEFT OUTER JOIN checkouts AS c
+                        ON c.book_id = b.book_id AND c.checkout_id = $2
+                    WHERE b.book_id = $1;
                 "#,
                 event.book_id as _,
+                event.checkout_id as _,
             )
             .fetch_optional(&mut *tx)
             .await
@@ -137,50 +389,80 @@ impl CheckoutRepository for CheckoutRepositoryImpl {
             match res {
                 // 指定した書籍がない場合　
                 None => {
-                    return Err(AppError::EntityNotFound(format!(
-                        "書籍({})が見つかりませんでした。",event.book_id
-                    )))
+                    return Err(AppError::EntityNotFound(
+                        Message::new("book_not_found").arg("book_id", event.book_id),
+                    ))
                 }
 
-                // 指定した書籍が貸出中であり、貸出IDまたは借りたユーザーが異なる場合
+                // 指定した貸出IDの貸出がない、または借りたユーザーが異なる場合
+                // 職員による強制返却(any_borrower)の場合は、借りたユーザーを問わない
+                Some(CheckoutStateRow {
+                    checkout_id: None,
+                    ..
+                }) => {
+                    return Err(AppError::UnprocessableEntity(
+                        Message::new("checkout_not_returnable")
+                            .arg("checkout_id", event.checkout_id)
+                            .arg("user_id", event.returned_by)
+                            .arg("book_id", event.book_id),
+                    ))
+                }
                 Some(CheckoutStateRow {
-                    checkout_id: Some(c), 
                     user_id: Some(u),
                     .. 
-                }) if(c, u) != (event.checkout_id, event.returned_by) => {
-                    return Err(AppError::UnprocessableEntity(format!(
-                        "指定の貸出ID(({}), ユーザー({}), 書籍({}))は返却できません",
-                        event.checkout_id,
-                        event.returned_by,
-                        event.book_id
-                    )))
+                }) if !event.any_borrower && u != event.returned_by => {
+                    return Err(AppError::UnprocessableEntity(
+                        Message::new("checkout_not_returnable")
+                            .arg("checkout_id", event.checkout_id)
+                            .arg("user_id", event.returned_by)
+                            .arg("book_id", event.book_id),
+                    ))
                 }
                 _ => {} // それ以外は処理続行
             }      
         }
 
         // DB上の返却操作として、checkoutsテーブルにアツ当該当貸出IDのレコードを、returned_atを追加して、returned_checkoutsテーブルにINSERTする。
-        let res = sqlx::query!(
+        let returned = sqlx::query!(
             r#"
                 INSERT INTO returned_checkouts
-                (checkout_id, book_id, user_id, checked_out_at, returned_at)
-                SELECT checkout_id, book_id, user_id, checked_out_at, $2
+                (checkout_id, copy_id, book_id, user_id, checked_out_at, due_at, renewal_count, returned_at, checked_out_performed_by, returned_performed_by)
+                SELECT checkout_id, copy_id, book_id, user_id, checked_out_at, due_at, renewal_count, $2, checked_out_performed_by, $3
                 FROM checkouts 
                 WHERE checkout_id = $1
+                RETURNING user_id AS "user_id: UserId", due_at
                 ;
             "#,
             event.checkout_id as _,
             event.returned_at,
+            event.returned_by as _,
         )
-        .execute(&mut *tx)
+        .fetch_optional(&mut *tx)
         .await
-        .map_err(AppError::SpecificOperationError)?;
-
-        if res.rows_affected() < 1 {
-            return Err(AppError::NoRowsAffectedError(
-                "No returning record has been updated".into(),
-            ));
-        }
+        .map_err(AppError::SpecificOperationError)?
+        .ok_or_else(|| {
+            AppError::NoRowsAffectedError("No returning record has been updated".into())
+        })?;
+
+        // 強制返却の場合は、借りたユーザーと返却の操作を行ったユーザーが異なる
+        record_audit_event(
+            &mut tx,
+            CreateAuditEvent::new(
+                event.returned_by,
+                AuditAction::CheckoutReturn,
+                event.checkout_id.to_string(),
+                Some(json!({
+                    "bookId": event.book_id,
+                    "checkedOutBy": returned.user_id,
+                    "dueAt": returned.due_at,
+                })),
+                Some(json!({
+                    "returnedAt": event.returned_at,
+                    "forced": event.any_borrower,
+                })),
+            ),
+        )
+        .await?;
 
         // 上記処理が成功したら、checkoutsテーブルから該当貸出IDのレコードを削除する　
         let res = sqlx::query!(
@@ -199,12 +481,22 @@ impl CheckoutRepository for CheckoutRepositoryImpl {
             ));
         } 
 
+        // 予約がある場合は、待ち行列の先頭のユーザーのために取り置く
+        refresh_reservation_queue(
+            &mut tx,
+            event.book_id,
+            event.returned_at,
+            self.hold_ttl,
+        )
+        .await?;
+
         tx.commit().await.map_err(AppError::TransactionError)?;
 
         Ok(())
     }
 
     // 全ての未返却の貸出情報を取得する。
+    #[tracing::instrument(skip_all, fields(db.system = "postgresql", db.operation = "SELECT"))]
     async fn find_unreturned_all(&self) -> AppResult<Vec<Checkout>> {
         // checkoutsテーブルにあるレコードを全件抽出する
         // booksテーブルとINNTER JOINし、蔵書の情報も一緒に抽出する
@@ -214,9 +506,13 @@ impl CheckoutRepository for CheckoutRepositoryImpl {
             r#"
                 SELECT 
                     c.checkout_id,
+                    c.copy_id,
                     c.book_id,
                     c.user_id,
                     c.checked_out_at,
+                    c.due_at,
+                    c.renewal_count,
+                    c.checked_out_performed_by,
                     b.title,
                     b.author,
                     b.isbn
@@ -232,7 +528,41 @@ impl CheckoutRepository for CheckoutRepositoryImpl {
         .map_err(AppError::SpecificOperationError)
     }
 
+    // 返却期限を過ぎた未返却の貸出情報を取得する。
+    #[tracing::instrument(skip_all, fields(db.system = "postgresql", db.operation = "SELECT"))]
+    async fn find_overdue_all(&self) -> AppResult<Vec<Checkout>> {
+        // find_unreturned_allのSQLに返却期限で絞り込むWHERE句を追加したものである。
+        // 出力するレコードは、返却期限の古い順(延滞期間の長い順)に並べる
+        sqlx::query_as!(
+            CheckoutRow,
+            r#"
+                SELECT 
+                    c.checkout_id,
+                    c.copy_id,
+                    c.book_id,
+                    c.user_id,
+                    c.checked_out_at,
+                    c.due_at,
+                    c.renewal_count,
+                    c.checked_out_performed_by,
+                    b.title,
+                    b.author,
+                    b.isbn
+                FROM checkouts AS c
+                INNER JOIN books AS b USING(book_id)
+                WHERE c.due_at < CURRENT_TIMESTAMP
+                ORDER BY c.due_at ASC
+                ;
+            "#,
+        )
+        .fetch_all(self.db.inner_ref())
+        .await
+        .map(|rows| rows.into_iter().map(Checkout::from).collect())
+        .map_err(AppError::SpecificOperationError)
+    }
+
     // ユーザーIDに紐づく未返却の貸出情報を取得する。
+    #[tracing::instrument(skip_all, fields(db.system = "postgresql", db.operation = "SELECT", user_id = %user_id))]
     async fn find_unreturned_by_user_id(
         &self,
         user_id: UserId,
@@ -243,9 +573,13 @@ impl CheckoutRepository for CheckoutRepositoryImpl {
             r#"
                 SELECT 
                     c.checkout_id,
+                    c.copy_id,
                     c.book_id,
                     c.user_id,
                     c.checked_out_at,
+                    c.due_at,
+                    c.renewal_count,
+                    c.checked_out_performed_by,
                     b.title,
                     b.author,
                     b.isbn
@@ -264,13 +598,13 @@ impl CheckoutRepository for CheckoutRepositoryImpl {
     }
 
     // 蔵書の貸出履歴(返却済みも含む)を取得する。
+    #[tracing::instrument(skip_all, fields(db.system = "postgresql", db.operation = "SELECT", book_id = %book_id))]
     async fn find_history_by_book_id(
         &self,
         book_id: BookId,
     ) -> AppResult<Vec<Checkout>> {
-        // 未返却の貸出情報を取得
-        let checkout: Option<Checkout> =
-            self.find_unreturned_by_book_id(book_id).await?;
+        // 未返却の貸出情報を取得。冊子が複数あれば複数件となる
+        let mut checkouts: Vec<Checkout> = self.find_unreturned_by_book_id(book_id).await?;
 
         // 返却済みの貸出情報を取得　
         let mut checkout_histories: Vec<Checkout> = sqlx::query_as!(
@@ -278,10 +612,15 @@ impl CheckoutRepository for CheckoutRepositoryImpl {
             r#"
                 SELECT 
                     rc.checkout_id,
+                    rc.copy_id,
                     rc.book_id,
                     rc.user_id,
                     rc.checked_out_at,
+                    rc.due_at,
+                    rc.renewal_count,
                     rc.returned_at,
+                    rc.checked_out_performed_by,
+                    rc.returned_performed_by,
                     b.title,
                     b.author,
                     b.isbn
@@ -299,57 +638,322 @@ impl CheckoutRepository for CheckoutRepositoryImpl {
         .map(Checkout::from)
         .collect();
 
-        // 貸出中である場合は返却済みの履歴の先頭に追加する
-        if let Some(co) = checkout {
-            checkout_histories.insert(0, co);
-        }
+        // 貸出中のものを返却済みの履歴より前に並べる
+        checkouts.append(&mut checkout_histories);
 
-        Ok(checkout_histories)
-        
+        Ok(checkouts)
     }
 }
 
 impl CheckoutRepositoryImpl {
-    // トランザクション分離レベルをSERIALIZABLEにするために内部的に使うメソッド
-
-    async fn set_transaction_serializable (
-        &self,
-        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
-    ) -> AppResult<()> {
-        sqlx::query!("SET TRANSACTION ISOLATION LEVEL SERIALIZABLE")
-            .execute(&mut **tx)
-            .await
-            .map_err(AppError::SpecificOperationError)?;
-        Ok(())
-    }
-
     // find_history_by_book_idで内部的に使うメソッド
+    #[tracing::instrument(skip_all, fields(db.system = "postgresql", db.operation = "SELECT", book_id = %book_id))]
     async fn find_unreturned_by_book_id(
         &self,
         book_id: BookId
-    ) -> AppResult<Option<Checkout>> {
+    ) -> AppResult<Vec<Checkout>> {
         let res = sqlx::query_as!(
             CheckoutRow,
             r#"
                 SELECT 
                     c.checkout_id,
+                    c.copy_id,
                     c.book_id,
                     c.user_id,
                     c.checked_out_at,
+                    c.due_at,
+                    c.renewal_count,
+                    c.checked_out_performed_by,
                     b.title,
                     b.author,
                     b.isbn
                 FROM checkouts AS c
                 INNER JOIN books AS b USING(book_id)
                 WHERE c.book_id = $1
+                ORDER BY c.checked_out_at DESC
             "#,
             book_id as _,
         )
-        .fetch_optional(self.db.inner_ref())
+        .fetch_all(self.db.inner_ref())
         .await
         .map_err(AppError::SpecificOperationError)?
-        .map(Checkout::from);
+        .into_iter()
+        .map(Checkout::from)
+        .collect();
 
         Ok(res)
     }
 }
+
+// 蔵書の冊子の貸出状況と、指定のユーザーの利用状況を取得する
+// 貸出・予約のトランザクション内から呼び出す
+pub(crate) async fn fetch_book_availability(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    book_id: BookId,
+    user_id: UserId,
+) -> AppResult<Option<BookAvailabilityRow>> {
+    sqlx::query_as!(
+        BookAvailabilityRow,
+        r#"
+            SELECT
+                b.book_id,
+                (
+                    SELECT COUNT(*) FROM book_copies AS bc
+                    WHERE bc.book_id = b.book_id
+                ) AS "total_copies!",
+                (
+                    SELECT COUNT(*) FROM checkouts AS c
+                    WHERE c.book_id = b.book_id
+                ) AS "checked_out!",
+                (
+                    SELECT COUNT(*) FROM reservations AS r
+                    WHERE r.book_id = b.book_id AND r.ready_at IS NOT NULL
+                ) AS "holds!",
+                EXISTS (
+                    SELECT 1 FROM checkouts AS c
+                    WHERE c.book_id = b.book_id AND c.user_id = $2
+                ) AS "borrowing!",
+                EXISTS (
+                    SELECT 1 FROM reservations AS r
+                    WHERE r.book_id = b.book_id AND r.user_id = $2
+                    AND r.ready_at IS NOT NULL
+                ) AS "holding!"
+            FROM books AS b
+            WHERE b.book_id = $1;
+        "#,
+        book_id as _,
+        user_id as _,
+    )
+    .fetch_optional(&mut **tx)
+    .await
+    .map_err(AppError::SpecificOperationError)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use std::str::FromStr;
+
+    #[sqlx::test(fixtures("common", "book", "reservation"))]
+    async fn test_renew_up_to_max_renewals(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let default_policy = CheckoutConfig {
+            max_loans: 5,
+            loan_period_days: 30,
+            max_renewals: 1,
+        };
+        let repo = CheckoutRepositoryImpl::new(
+            ConnectionPool::new(pool.clone()),
+            3600,
+            default_policy,
+        );
+
+        // 1. fixtures/reservation.sqlで作成済みの貸出を延長する
+        let book_id = BookId::from_str("9890736e-a4e4-461a-a77d-eac3517ef11b")?;
+        let checkout_id = CheckoutId::from_str("a7e8ba04-dc3f-4e6f-9d5c-a5e8b0e2d6c1")?;
+        let user_id = UserId::from_str("5b4c96ac-316a-4bee-8e69-cac5eb84ff4c")?;
+        repo.renew(RenewCheckout::new(checkout_id, book_id, user_id, Utc::now()))
+            .await?;
+
+        // 2. 延長後の返却期限と延長回数を検証する
+        let checkouts = repo.find_unreturned_by_user_id(user_id).await?;
+        assert_eq!(checkouts[0].renewal_count, 1);
+        assert!(checkouts[0].due_at > Utc::now() + chrono::Duration::days(29));
+
+        // 3. 上限を超える延長はできない
+        let res = repo
+            .renew(RenewCheckout::new(checkout_id, book_id, user_id, Utc::now()))
+            .await;
+        assert!(matches!(res, Err(AppError::UnprocessableEntity(_))));
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common", "book", "reservation"))]
+    async fn test_staff_checkout_and_force_return(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = CheckoutRepositoryImpl::new(
+            ConnectionPool::new(pool.clone()),
+            3600,
+            CheckoutConfig {
+                max_loans: 5,
+                loan_period_days: 14,
+                max_renewals: 2,
+            },
+        );
+
+        // fixtures/reservation.sqlで職員(staff)が借りている書籍を、まず通常どおり返却しておく
+        let book_id = BookId::from_str("9890736e-a4e4-461a-a77d-eac3517ef11b")?;
+        let staff = UserId::from_str("5b4c96ac-316a-4bee-8e69-cac5eb84ff4c")?;
+        let borrower = UserId::from_str("9582f9de-0fd1-4892-b20c-70139a7eb95b")?;
+        repo.update_returned(UpdateReturned::new(
+            CheckoutId::from_str("a7e8ba04-dc3f-4e6f-9d5c-a5e8b0e2d6c1")?,
+            book_id,
+            staff,
+            Utc::now(),
+            false,
+        ))
+        .await?;
+
+        // 1. 職員が代理で貸し出すと、借りたユーザーと操作したユーザーが別々に記録される
+        repo.create(CreateCheckout::new(book_id, borrower, Utc::now(), staff))
+            .await?;
+        let checkouts = repo.find_unreturned_by_user_id(borrower).await?;
+        assert_eq!(checkouts.len(), 1);
+        assert_eq!(checkouts[0].checked_out_by, borrower);
+        assert_eq!(checkouts[0].checked_out_performed_by, staff);
+        let checkout_id = checkouts[0].id;
+
+        // 2. 借りたユーザー以外は、通常の返却はできない
+        let res = repo
+            .update_returned(UpdateReturned::new(
+                checkout_id,
+                book_id,
+                staff,
+                Utc::now(),
+                false,
+            ))
+            .await;
+        assert!(matches!(res, Err(AppError::UnprocessableEntity(_))));
+
+        // 3. 強制返却では借りたユーザーを問わず、操作したユーザーが記録される
+        repo.update_returned(UpdateReturned::new(
+            checkout_id,
+            book_id,
+            staff,
+            Utc::now(),
+            true,
+        ))
+        .await?;
+        assert!(repo.find_unreturned_by_user_id(borrower).await?.is_empty());
+
+        let history = repo.find_history_by_book_id(book_id).await?;
+        let returned = history.iter().find(|c| c.id == checkout_id).unwrap();
+        assert_eq!(returned.checked_out_by, borrower);
+        assert_eq!(returned.checked_out_performed_by, staff);
+        assert_eq!(returned.returned_performed_by, Some(staff));
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common", "book", "reservation"))]
+    async fn test_history_includes_every_active_checkout(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = CheckoutRepositoryImpl::new(
+            ConnectionPool::new(pool.clone()),
+            3600,
+            CheckoutConfig {
+                max_loans: 5,
+                loan_period_days: 14,
+                max_renewals: 2,
+            },
+        );
+
+        // fixtures/reservation.sqlで1冊目が貸出中の書籍に、2冊目の冊子を追加する
+        let book_id = BookId::from_str("9890736e-a4e4-461a-a77d-eac3517ef11b")?;
+        let borrower = UserId::from_str("9582f9de-0fd1-4892-b20c-70139a7eb95b")?;
+        sqlx::query("INSERT INTO book_copies (book_id) VALUES ($1)")
+            .bind(book_id.raw())
+            .execute(&pool)
+            .await?;
+
+        // 2冊目を別のユーザーが借りると、どちらの貸出も履歴に含まれる
+        repo.create(CreateCheckout::new(book_id, borrower, Utc::now(), borrower))
+            .await?;
+        let history = repo.find_history_by_book_id(book_id).await?;
+        assert_eq!(history.len(), 2);
+        // 貸出日時の新しい順に並ぶ
+        assert_eq!(history[0].checked_out_by, borrower);
+        assert!(history[0].checked_out_at >= history[1].checked_out_at);
+
+        Ok(())
+    }
+    #[sqlx::test(fixtures("common", "book", "reservation"))]
+    async fn test_find_overdue_all(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = CheckoutRepositoryImpl::new(
+            ConnectionPool::new(pool.clone()),
+            3600,
+            CheckoutConfig {
+                max_loans: 5,
+                loan_period_days: 14,
+                max_renewals: 2,
+            },
+        );
+
+        // fixtures/reservation.sqlの貸出(返却期限は14日後)に加え、返却期限を1日過ぎた貸出を作成する
+        let overdue_id = CheckoutId::from_str("b1f2c3d4-5e6f-4a7b-8c9d-0e1f2a3b4c5d")?;
+        let borrower = UserId::from_str("9582f9de-0fd1-4892-b20c-70139a7eb95b")?;
+        sqlx::query(
+            r#"
+                INSERT INTO checkouts
+                (checkout_id, copy_id, book_id, user_id, checked_out_at, due_at, checked_out_performed_by)
+                VALUES ($1, '3c1f6a2e-8b4d-4e6a-9f0c-1d2e3f4a5b62', 'f397b83a-dd2a-4a01-9e77-db1eea7de5b6', $2, now() - INTERVAL '15 days', now() - INTERVAL '1 day', $2)
+            "#,
+        )
+        .bind(overdue_id.raw())
+        .bind(borrower.raw())
+        .execute(&pool)
+        .await?;
+
+        // 返却期限を過ぎた貸出のみが返り、APIの延滞フラグと判断が一致する
+        let now = Utc::now();
+        let overdue = repo.find_overdue_all().await?;
+        assert_eq!(overdue.len(), 1);
+        assert_eq!(overdue[0].id, overdue_id);
+        assert!(overdue[0].is_overdue(now));
+
+        let not_due = repo
+            .find_unreturned_by_user_id(UserId::from_str("5b4c96ac-316a-4bee-8e69-cac5eb84ff4c")?)
+            .await?;
+        assert_eq!(not_due.len(), 1);
+        assert!(!not_due[0].is_overdue(now));
+
+        Ok(())
+    }
+    #[sqlx::test(fixtures("common", "book", "reservation"))]
+    async fn test_checkout_rejected_past_max_loans(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = CheckoutRepositoryImpl::new(
+            ConnectionPool::new(pool.clone()),
+            3600,
+            CheckoutConfig {
+                max_loans: 5,
+                loan_period_days: 14,
+                max_renewals: 2,
+            },
+        );
+
+        // 1. Userロールの貸出ポリシーを、1冊まで・7日間とする
+        sqlx::query(
+            r#"
+                INSERT INTO borrowing_policies (role_id, max_loans, loan_period_days, max_renewals)
+                SELECT role_id, 1, 7, 0 FROM roles WHERE name = 'User'
+            "#,
+        )
+        .execute(&pool)
+        .await?;
+
+        // 2. 1冊目は借りられ、返却期限はポリシーの貸出期間で決まる
+        let borrower = UserId::from_str("9582f9de-0fd1-4892-b20c-70139a7eb95b")?;
+        let checked_out_at = Utc::now();
+        let first = BookId::from_str("f397b83a-dd2a-4a01-9e77-db1eea7de5b6")?;
+        repo.create(CreateCheckout::new(first, borrower, checked_out_at, borrower))
+            .await?;
+        let checkouts = repo.find_unreturned_by_user_id(borrower).await?;
+        assert_eq!(checkouts.len(), 1);
+        assert_eq!(
+            checkouts[0].due_at.timestamp(),
+            (checked_out_at + chrono::Duration::days(7)).timestamp()
+        );
+
+        // 3. 上限(max_loans + 1冊目)を超える貸出はできない
+        let second = BookId::from_str("17afb850-c786-49c5-a303-a3a443a2212c")?;
+        let res = repo
+            .create(CreateCheckout::new(second, borrower, Utc::now(), borrower))
+            .await;
+        assert!(matches!(
+            res,
+            Err(AppError::UnprocessableEntity(ref message)) if message.code() == "loan_limit_reached"
+        ));
+        assert_eq!(repo.find_unreturned_by_user_id(borrower).await?.len(), 1);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,53 @@
+INSERT INTO users(user_id, name, email, password_hash, role_id)
+SELECT
+    'c2d1e9a4-6f1b-4c3e-8a57-0e4b9d6f2a13'
+    , 'Suzuki Hanako'
+    , 'suzuki.hanako@example.com'
+    , 'atodehenkou'
+    , role_id
+FROM roles WHERE name = 'User';
+
+INSERT INTO
+  books (
+    book_id,
+    title,
+    author,
+    isbn,
+    description,
+    user_id,
+    created_at,
+    updated_at
+  )
+VALUES
+  (
+    '6a0e4f8b-2c7d-4b1e-9d3a-5f8c1b2e7a40',
+    'プログラミング言語Go',
+    'Alan A. A. Donovan他',
+    '9784621300251',
+    'Goの基本から並行処理まで、100%の理解を目指す定番の解説書。',
+    'c2d1e9a4-6f1b-4c3e-8a57-0e4b9d6f2a13',
+    now(),
+    now()
+  ) ON CONFLICT DO NOTHING;
+
+INSERT INTO
+  book_copies (copy_id, book_id, created_at)
+VALUES
+  (
+    '3c1f6a2e-8b4d-4e6a-9f0c-1d2e3f4a5b64',
+    '6a0e4f8b-2c7d-4b1e-9d3a-5f8c1b2e7a40',
+    now()
+  ) ON CONFLICT DO NOTHING;
+
+INSERT INTO
+  checkouts (checkout_id, copy_id, book_id, user_id, checked_out_at, due_at, checked_out_performed_by)
+VALUES
+  (
+    'e1b7c3d9-4a2f-4e8b-b6c0-7d9f1a3e5b28',
+    '3c1f6a2e-8b4d-4e6a-9f0c-1d2e3f4a5b63',
+    '17afb850-c786-49c5-a303-a3a443a2212c',
+    'c2d1e9a4-6f1b-4c3e-8a57-0e4b9d6f2a13',
+    now(),
+    now() + INTERVAL '14 days',
+    'c2d1e9a4-6f1b-4c3e-8a57-0e4b9d6f2a13'
+  ) ON CONFLICT DO NOTHING;
@@ -0,0 +1,21 @@
+INSERT INTO users(user_id, name, email, password_hash, role_id)
+SELECT
+    '9582f9de-0fd1-4892-b20c-70139a7eb95b'
+    , 'Yamada Taro'
+    , 'yamada.taro@example.com'
+    , 'atodehenkou'
+    , role_id
+FROM roles WHERE name = 'User';
+
+INSERT INTO
+  checkouts (checkout_id, copy_id, book_id, user_id, checked_out_at, due_at, checked_out_performed_by)
+VALUES
+  (
+    'a7e8ba04-dc3f-4e6f-9d5c-a5e8b0e2d6c1',
+    '3c1f6a2e-8b4d-4e6a-9f0c-1d2e3f4a5b61',
+    '9890736e-a4e4-461a-a77d-eac3517ef11b',
+    '5b4c96ac-316a-4bee-8e69-cac5eb84ff4c',
+    now(),
+    now() + INTERVAL '14 days',
+    '5b4c96ac-316a-4bee-8e69-cac5eb84ff4c'
+  ) ON CONFLICT DO NOTHING;
@@ -15,6 +15,7 @@ pub struct HealthCheckRepositoryImpl {
 #[async_trait]
 // 3. `HealthCheckRepository`を実装する
 impl HealthCheckRepository for HealthCheckRepositoryImpl {
+    #[tracing::instrument(skip_all, fields(db.system = "postgresql", db.operation = "SELECT"))]
     async fn check_db(&self) -> bool {
         // 4. クエリ実行結果は`Result`型であるため
         // `OK` -> `true`
@@ -0,0 +1,208 @@
+// SMTPサーバーを経由したメールの送信を描く
+// 本文はリクエストの言語で組み立てる
+
+use async_trait::async_trait;
+use kernel::{model::auth::PasswordReset, repository::mailer::Mailer};
+use lettre::{
+    message::{header::ContentType, Mailbox},
+    transport::smtp::authentication::Credentials,
+    AsyncSmtpTransport, AsyncTransport, Message as Mail, Tokio1Executor,
+};
+use shared::{
+    config::MailConfig,
+    error::{AppError, AppResult},
+    i18n::Message,
+};
+use tracing::info;
+
+pub struct SmtpMailer {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+    password_reset_url: String,
+}
+
+impl SmtpMailer {
+    pub fn new(config: &MailConfig) -> AppResult<Self> {
+        // 接続は送信時に行われるため、ここではSMTPサーバーに接続しない
+        let mut builder = if config.smtp_starttls {
+            AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.smtp_host)
+                .map_err(|e| AppError::MailDeliveryError(e.to_string()))?
+        } else {
+            AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&config.smtp_host)
+        }
+        .port(config.smtp_port);
+        if let (Some(username), Some(password)) = (&config.smtp_username, &config.smtp_password) {
+            builder = builder.credentials(Credentials::new(username.clone(), password.clone()));
+        }
+
+        Ok(Self {
+            transport: builder.build(),
+            from: parse_mailbox(&config.from)?,
+            password_reset_url: config.password_reset_url.clone(),
+        })
+    }
+
+    fn build_password_reset(&self, reset: &PasswordReset) -> AppResult<Mail> {
+        let url = format!("{}{}", self.password_reset_url, reset.token.0);
+        let body = Message::new("password_reset_mail_body")
+            .arg("minutes", reset.expires_in / 60)
+            .arg("url", url);
+
+        Mail::builder()
+            .from(self.from.clone())
+            .to(parse_mailbox(&reset.email)?)
+            .subject(Message::new("password_reset_mail_subject").to_string())
+            .header(ContentType::TEXT_PLAIN)
+            .body(body.to_string())
+            .map_err(|e| AppError::MailDeliveryError(e.to_string()))
+    }
+}
+
+fn parse_mailbox(address: &str) -> AppResult<Mailbox> {
+    address
+        .parse()
+        .map_err(|e: lettre::address::AddressError| AppError::MailDeliveryError(e.to_string()))
+}
+
+#[async_trait]
+impl Mailer for SmtpMailer {
+    #[tracing::instrument(skip_all, fields(user_id = %reset.user_id))]
+    async fn send_password_reset(&self, reset: &PasswordReset) -> AppResult<()> {
+        let mail = self.build_password_reset(reset)?;
+        self.transport
+            .send(mail)
+            .await
+            .map_err(|e| AppError::MailDeliveryError(e.to_string()))?;
+        info!("Password reset mail was sent");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kernel::model::{auth::PasswordResetToken, id::UserId};
+    use shared::i18n::{self, Language};
+    use tokio::{
+        io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+        net::TcpListener,
+        sync::oneshot,
+    };
+
+    use super::*;
+
+    // SMTPサーバーの代わりに、受け取ったメールのデータを返すだけのサーバーをローカルで起動する
+    async fn spawn_smtp_sink() -> anyhow::Result<(u16, oneshot::Receiver<String>)> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let port = listener.local_addr()?.port();
+        let (tx, rx) = oneshot::channel();
+
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await?;
+            let (reader, mut writer) = stream.into_split();
+            let mut lines = BufReader::new(reader).lines();
+            writer.write_all(b"220 localhost ESMTP sink\r\n").await?;
+
+            let mut data: Option<String> = None;
+            while let Some(line) = lines.next_line().await? {
+                // DATAの後は、"."だけの行までをメールの内容として受け取る
+                if let Some(received) = data.as_mut() {
+                    if line == "." {
+                        writer.write_all(b"250 OK\r\n").await?;
+                        let _ = tx.send(std::mem::take(received));
+                        break;
+                    }
+                    received.push_str(&line);
+                    received.push('\n');
+                    continue;
+                }
+                let command = line.to_uppercase();
+                if command.starts_with("DATA") {
+                    data = Some(String::new());
+                    writer.write_all(b"354 End data with <CR><LF>.<CR><LF>\r\n").await?;
+                } else {
+                    writer.write_all(b"250 OK\r\n").await?;
+                }
+            }
+            // QUITに応答して接続を閉じる
+            if lines.next_line().await?.is_some() {
+                writer.write_all(b"221 Bye\r\n").await?;
+            }
+            anyhow::Ok(())
+        });
+
+        Ok((port, rx))
+    }
+
+    // 本文はquoted-printableで送られるため、"="のエスケープと行の折り返しを元に戻す
+    fn decode_quoted_printable(body: &str) -> String {
+        let joined = body.replace("=\n", "");
+        let bytes = joined.as_bytes();
+        let mut decoded = Vec::with_capacity(bytes.len());
+        let mut i = 0;
+        while i < bytes.len() {
+            let escaped = (bytes[i] == b'=')
+                .then(|| bytes.get(i + 1..i + 3))
+                .flatten()
+                .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+            match escaped {
+                Some(byte) => {
+                    decoded.push(byte);
+                    i += 3;
+                }
+                None => {
+                    decoded.push(bytes[i]);
+                    i += 1;
+                }
+            }
+        }
+        String::from_utf8_lossy(&decoded).into_owned()
+    }
+
+    fn config(port: u16) -> MailConfig {
+        MailConfig {
+            smtp_host: "127.0.0.1".into(),
+            smtp_port: port,
+            smtp_username: None,
+            smtp_password: None,
+            smtp_starttls: false,
+            from: "library@example.com".into(),
+            password_reset_url: "https://library.example.com/password-reset?token=".into(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_send_password_reset_to_smtp_sink() -> anyhow::Result<()> {
+        let (port, received) = spawn_smtp_sink().await?;
+        let mailer = SmtpMailer::new(&config(port))?;
+        let reset = PasswordReset {
+            user_id: UserId::new(),
+            email: "eleazar.fig@example.com".into(),
+            token: PasswordResetToken("0123456789abcdef".into()),
+            expires_in: 1800,
+        };
+
+        i18n::scope(Language::En, mailer.send_password_reset(&reset)).await?;
+
+        // 宛先と、トークンを含む再設定画面のURLが送られている
+        let data = received.await?;
+        let (headers, body) = data.split_once("\n\n").unwrap_or_default();
+        assert!(headers.contains("To: eleazar.fig@example.com"));
+        assert!(headers.contains("Subject: Reset your password"));
+        let body = decode_quoted_printable(body);
+        assert!(body.contains("https://library.example.com/password-reset?token=0123456789abcdef"));
+        assert!(body.contains("within 30 minutes"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_from_address_is_rejected() {
+        let config = MailConfig {
+            from: "not an address".into(),
+            ..config(25)
+        };
+        assert!(matches!(
+            SmtpMailer::new(&config),
+            Err(AppError::MailDeliveryError(_))
+        ));
+    }
+}
@@ -0,0 +1,268 @@
+// 外部の書誌情報APIとのやりとりを描く
+// Google Books APIと同じ形式の`GET {base_url}/volumes?q=isbn:{isbn}`に問い合わせる
+// 取得した書誌情報はRedisにキャッシュし、同じISBNの問い合わせでは外部APIを呼ばない
+// 見つからなかった場合も短い期間だけキャッシュする
+
+use std::{sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use kernel::model::book::{isbn::Isbn, BookMetadata};
+use kernel::repository::metadata::BookMetadataProvider;
+use serde::Deserialize;
+use shared::{config::BookMetadataConfig, error::AppResult};
+use tracing::{info, warn};
+
+use crate::{
+    database::model::metadata::{BookMetadataKey, CachedBookMetadataLookup},
+    redis::RedisClient,
+};
+
+pub struct BookMetadataProviderImpl {
+    http: reqwest::Client,
+    base_url: String,
+    kv: Arc<RedisClient>,
+    cache_ttl: u64,
+    not_found_cache_ttl: u64,
+}
+
+impl BookMetadataProviderImpl {
+    pub fn new(kv: Arc<RedisClient>, config: &BookMetadataConfig) -> AppResult<Self> {
+        // 蔵書の登録の処理から呼ばれるため、外部APIの応答が遅くても処理が止まり続けないようにする
+        let http = reqwest::Client::builder()
+            .connect_timeout(Duration::from_millis(config.connect_timeout_ms))
+            .timeout(Duration::from_millis(config.timeout_ms))
+            .build()?;
+
+        Ok(Self {
+            http,
+            base_url: config.base_url.trim_end_matches('/').to_string(),
+            kv,
+            cache_ttl: config.cache_ttl,
+            not_found_cache_ttl: config.not_found_cache_ttl,
+        })
+    }
+
+    // 外部APIに問い合わせ、最初に見つかった書籍の情報を返す
+    async fn fetch(&self, isbn: &Isbn) -> AppResult<Option<BookMetadata>> {
+        let res: VolumesResponse = self
+            .http
+            .get(format!("{}/volumes", self.base_url))
+            .query(&[("q", format!("isbn:{}", isbn))])
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        Ok(res
+            .items
+            .into_iter()
+            .next()
+            .map(|item| item.volume_info.into_book_metadata(isbn.clone())))
+    }
+}
+
+#[async_trait]
+impl BookMetadataProvider for BookMetadataProviderImpl {
+    async fn find_by_isbn(&self, isbn: &Isbn) -> AppResult<Option<BookMetadata>> {
+        let key = BookMetadataKey::from(isbn);
+
+        // キャッシュの読み書きに失敗しても、外部APIから取得できれば結果を返す
+        match self.kv.get(&key).await {
+            Ok(Some(cached)) => return Ok(cached.into()),
+            Ok(None) => {}
+            Err(e) => warn!("Failed to read book metadata cache: {}", e),
+        }
+
+        let metadata = self.fetch(isbn).await?;
+        info!(
+            "Book metadata was fetched: isbn={}, found={}",
+            isbn,
+            metadata.is_some()
+        );
+
+        let ttl = if metadata.is_some() {
+            self.cache_ttl
+        } else {
+            self.not_found_cache_ttl
+        };
+        let value = CachedBookMetadataLookup::from(metadata.clone());
+        if let Err(e) = self.kv.set_ex(&key, &value, ttl).await {
+            warn!("Failed to write book metadata cache: {}", e);
+        }
+
+        Ok(metadata)
+    }
+}
+
+// 外部APIのレスポンスのうち、利用する項目のみを定義する
+#[derive(Deserialize)]
+struct VolumesResponse {
+    #[serde(default)]
+    items: Vec<VolumeItem>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct VolumeItem {
+    volume_info: VolumeInfo,
+}
+
+#[derive(Deserialize)]
+struct VolumeInfo {
+    title: String,
+    subtitle: Option<String>,
+    #[serde(default)]
+    authors: Vec<String>,
+    #[serde(default)]
+    description: String,
+}
+
+impl VolumeInfo {
+    fn into_book_metadata(self, isbn: Isbn) -> BookMetadata {
+        let VolumeInfo {
+            title,
+            subtitle,
+            authors,
+            description,
+        } = self;
+
+        // 副題がある場合は、書名と全角空白で区切って連結する
+        let title = match subtitle {
+            Some(subtitle) => format!("{}　{}", title, subtitle),
+            None => title,
+        };
+
+        BookMetadata {
+            isbn,
+            title,
+            title_kana: None,
+            author: authors.join("、"),
+            description,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{extract::Query, routing::get, Json, Router};
+    use crate::redis::model::RedisValue;
+    use shared::{config::RedisConfig, error::AppError, metrics::Metrics};
+    use std::collections::HashMap;
+
+    // 外部APIの代わりに、ローカルでスタブサーバーを起動する
+    async fn spawn_stub_server() -> anyhow::Result<String> {
+        async fn volumes(
+            Query(query): Query<HashMap<String, String>>,
+        ) -> Json<serde_json::Value> {
+            match query.get("q").map(String::as_str) {
+                Some("isbn:9784065369579") => Json(serde_json::json!({
+                    "totalItems": 1,
+                    "items": [{
+                        "volumeInfo": {
+                            "title": "RustによるWebアプリケーション開発",
+                            "subtitle": "設計からリリース・運用まで",
+                            "authors": ["豊田優貴", "松本健太郎", "吉川哲史"],
+                            "description": "「蔵書管理アプリケーション」の実装を通じて学ぶ"
+                        }
+                    }]
+                })),
+                // 応答の遅い外部APIを再現する
+                Some("isbn:9784873119786") => {
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                    Json(serde_json::json!({ "totalItems": 0 }))
+                }
+                _ => Json(serde_json::json!({ "totalItems": 0 })),
+            }
+        }
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        let app = Router::new().route("/volumes", get(volumes));
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        Ok(format!("http://{}/", addr))
+    }
+
+    fn provider(base_url: String, timeout_ms: u64) -> anyhow::Result<BookMetadataProviderImpl> {
+        // Redisへの接続はキャッシュの読み書き時に行われるため、fetchの検証には不要
+        let kv = Arc::new(RedisClient::new(
+            &RedisConfig {
+                host: "localhost".into(),
+                port: 6379,
+            },
+            Arc::new(Metrics::new()?),
+        )?);
+        Ok(BookMetadataProviderImpl::new(
+            kv,
+            &BookMetadataConfig {
+                base_url,
+                cache_ttl: 60,
+                not_found_cache_ttl: 10,
+                connect_timeout_ms: 1000,
+                timeout_ms,
+            },
+        )?)
+    }
+
+    #[tokio::test]
+    async fn test_fetch_from_stub_server() -> anyhow::Result<()> {
+        let base_url = spawn_stub_server().await?;
+        let provider = provider(base_url, 1000)?;
+
+        // 1. ISBN-10で問い合わせても、ISBN-13の正規形で取得できる
+        let isbn: Isbn = "4-06-536957-6".parse()?;
+        let metadata = provider.fetch(&isbn).await?.unwrap();
+        assert_eq!(metadata.isbn.as_str(), "9784065369579");
+        assert_eq!(
+            metadata.title,
+            "RustによるWebアプリケーション開発　設計からリリース・運用まで"
+        );
+        assert_eq!(metadata.author, "豊田優貴、松本健太郎、吉川哲史");
+
+        // 2. 見つからない場合はNoneを返す
+        let isbn: Isbn = "9784798061702".parse()?;
+        assert!(provider.fetch(&isbn).await?.is_none());
+
+        Ok(())
+    }
+    #[tokio::test]
+    async fn test_fetch_times_out() -> anyhow::Result<()> {
+        let base_url = spawn_stub_server().await?;
+        let provider = provider(base_url, 200)?;
+
+        // 外部APIの応答がtimeout_msを過ぎても返らない場合は、待たずにエラーとする
+        let isbn: Isbn = "9784873119786".parse()?;
+        let started = std::time::Instant::now();
+        let res = provider.fetch(&isbn).await;
+        assert!(matches!(res, Err(AppError::ExternalServiceError(e)) if e.is_timeout()));
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_cached_lookup_round_trip() -> anyhow::Result<()> {
+        // 1. 見つからなかったことはnullとして保存し、Noneとして読み出す
+        let not_found = CachedBookMetadataLookup::from(None);
+        assert_eq!(not_found.inner(), "null");
+        let cached = CachedBookMetadataLookup::try_from(not_found.inner())?;
+        assert!(Option::<BookMetadata>::from(cached).is_none());
+
+        // 2. 見つかった書誌情報はそのまま読み出せる
+        let metadata = BookMetadata {
+            isbn: "9784065369579".parse()?,
+            title: "RustによるWebアプリケーション開発".into(),
+            title_kana: None,
+            author: "豊田優貴".into(),
+            description: String::new(),
+        };
+        let found = CachedBookMetadataLookup::from(Some(metadata));
+        let cached = CachedBookMetadataLookup::try_from(found.inner())?;
+        let metadata = Option::<BookMetadata>::from(cached).unwrap();
+        assert_eq!(metadata.title, "RustによるWebアプリケーション開発");
+
+        Ok(())
+    }
+}
@@ -0,0 +1,71 @@
+use async_trait::async_trait;
+use derive_new::new;
+use kernel::model::metrics::{DbPoolStatus, MetricsSnapshot};
+use kernel::repository::metrics::MetricsRepository;
+use shared::error::{AppError, AppResult};
+
+use crate::database::ConnectionPool;
+
+#[derive(new)]
+pub struct MetricsRepositoryImpl {
+    db: ConnectionPool,
+}
+
+#[async_trait]
+impl MetricsRepository for MetricsRepositoryImpl {
+    #[tracing::instrument(skip_all, fields(db.system = "postgresql", db.operation = "SELECT"))]
+    async fn snapshot(&self) -> AppResult<MetricsSnapshot> {
+        // 集計のための接続を取得する前に、プールの状態を読み取る
+        let pool = self.db.inner_ref();
+        let db_pool = DbPoolStatus {
+            size: pool.size(),
+            idle: pool.num_idle(),
+            max: pool.options().get_max_connections(),
+        };
+
+        // checkoutsテーブルには未返却の貸出のみが残るため、全件が貸出中となる
+        let row = sqlx::query!(
+            r#"
+                SELECT
+                    COUNT(*) AS "active!",
+                    COUNT(*) FILTER (WHERE due_at < CURRENT_TIMESTAMP) AS "overdue!"
+                FROM checkouts
+            "#
+        )
+        .fetch_one(pool)
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+
+        Ok(MetricsSnapshot {
+            db_pool,
+            active_checkouts: row.active,
+            overdue_checkouts: row.overdue,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[sqlx::test(fixtures("common", "book", "book_search"))]
+    async fn test_snapshot(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = MetricsRepositoryImpl::new(ConnectionPool::new(pool.clone()));
+
+        // 1. 返却期限内の貸出のみの場合は、延滞は0件となる
+        let snapshot = repo.snapshot().await?;
+        assert_eq!(snapshot.active_checkouts, 1);
+        assert_eq!(snapshot.overdue_checkouts, 0);
+        assert!(snapshot.db_pool.max > 0);
+
+        // 2. 返却期限を過ぎると、延滞として数えられる
+        sqlx::query("UPDATE checkouts SET due_at = now() - INTERVAL '1 day'")
+            .execute(&pool)
+            .await?;
+        let snapshot = repo.snapshot().await?;
+        assert_eq!(snapshot.active_checkouts, 1);
+        assert_eq!(snapshot.overdue_checkouts, 1);
+
+        Ok(())
+    }
+}
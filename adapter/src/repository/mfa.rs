@@ -0,0 +1,379 @@
+// TOTP(RFC 6238)による2段階認証
+// 秘密鍵とリカバリーコードはDBに、2段階目の認証を待つログインはRedisに保存する
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use derive_new::new;
+use kernel::{
+    model::{
+        id::UserId,
+        mfa::{
+            event::{CreateMfaChallenge, EnrollTotp, VerifyMfaChallenge, VerifyTotp},
+            MfaChallenge, TotpEnrollment, VerifiedMfaChallenge,
+        },
+    },
+    repository::mfa::MfaRepository,
+};
+use shared::{
+    config::MfaConfig,
+    error::{AppError, AppResult},
+    i18n::Message,
+};
+use totp_rs::{Algorithm, Secret, TOTP};
+use tracing::warn;
+
+use crate::{
+    database::{
+        model::mfa::{
+            MfaChallengeFailureKey, MfaChallengeKey, MfaChallengeState, RecoveryCodeRow,
+            UsedTotpCode, UsedTotpCodeKey, UserTotpRow,
+        },
+        ConnectionPool,
+    },
+    redis::RedisClient,
+};
+
+// 認証アプリに表示するサービス名
+const TOTP_ISSUER: &str = "BookManagement";
+const TOTP_DIGITS: usize = 6;
+// 端末の時計のずれを考慮し、前後1つ分の時間帯のコードも受け付ける
+const TOTP_SKEW: u8 = 1;
+const TOTP_STEP: u64 = 30;
+// この回数だけ2段階目の認証に失敗した場合は、パスワードの検証からやり直させる
+const MAX_CHALLENGE_ATTEMPTS: u64 = 5;
+// リカバリーコードは十分な長さのランダムな値のため、検証のたびにすべてのコードと
+// 照合できるよう、パスワードよりも軽いコストでハッシュ化する
+const RECOVERY_CODE_COST: u32 = 6;
+
+fn totp(secret: Vec<u8>, account_name: String) -> AppResult<TOTP> {
+    TOTP::new(
+        Algorithm::SHA1,
+        TOTP_DIGITS,
+        TOTP_SKEW,
+        TOTP_STEP,
+        secret,
+        Some(TOTP_ISSUER.to_string()),
+        account_name,
+    )
+    .map_err(|e| AppError::ConversionEntityError(e.to_string()))
+}
+
+// 大文字・小文字や区切りの有無を変えて入力されても、同じコードとして扱う
+fn normalize_recovery_code(code: &str) -> String {
+    code.trim().to_lowercase().replace(' ', "")
+}
+
+#[derive(new)]
+pub struct MfaRepositoryImpl {
+    db: ConnectionPool,
+    kv: Arc<RedisClient>,
+    config: MfaConfig,
+}
+
+impl MfaRepositoryImpl {
+    async fn find_totp(&self, user_id: UserId) -> AppResult<Option<UserTotpRow>> {
+        sqlx::query_as!(
+            UserTotpRow,
+            r#"
+                SELECT secret, enabled_at FROM user_totp
+                WHERE user_id = $1;
+            "#,
+            user_id as _
+        )
+        .fetch_optional(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)
+    }
+
+    // 認証アプリに表示されたコードを検証する。一度使ったコードは、有効期間内でも受け付けない
+    async fn check_totp(&self, user_id: UserId, secret: &str, code: &str) -> AppResult<bool> {
+        let secret = Secret::Encoded(secret.to_string())
+            .to_bytes()
+            .map_err(|e| AppError::ConversionEntityError(e.to_string()))?;
+        let valid = totp(secret, String::new())?
+            .check_current(code.trim())
+            .map_err(|e| AppError::ConversionEntityError(e.to_string()))?;
+        if !valid {
+            return Ok(false);
+        }
+        self.kv
+            .set_nx_ex(
+                &UsedTotpCodeKey::new(user_id, code.trim()),
+                &UsedTotpCode,
+                TOTP_STEP * (2 * TOTP_SKEW as u64 + 1),
+            )
+            .await
+    }
+
+    // 未使用のリカバリーコードと照合し、一致したコードを使用済みにする
+    async fn use_recovery_code(&self, user_id: UserId, code: &str) -> AppResult<bool> {
+        let code = normalize_recovery_code(code);
+        let rows = sqlx::query_as!(
+            RecoveryCodeRow,
+            r#"
+                SELECT recovery_code_id, code_hash FROM user_recovery_codes
+                WHERE user_id = $1 AND used_at IS NULL;
+            "#,
+            user_id as _
+        )
+        .fetch_all(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+
+        for row in rows {
+            if !bcrypt::verify(&code, &row.code_hash)? {
+                continue;
+            }
+            // 同時に同じコードが使われた場合は、先に使用済みにした方のみを通す
+            let res = sqlx::query!(
+                r#"
+                    UPDATE user_recovery_codes SET used_at = CURRENT_TIMESTAMP(3)
+                    WHERE recovery_code_id = $1 AND used_at IS NULL;
+                "#,
+                row.recovery_code_id
+            )
+            .execute(self.db.inner_ref())
+            .await
+            .map_err(AppError::SpecificOperationError)?;
+            return Ok(res.rows_affected() == 1);
+        }
+        Ok(false)
+    }
+
+    // 登録済みのTOTPのコード、またはリカバリーコードを検証する
+    async fn verify_code(&self, user_id: UserId, code: &str) -> AppResult<bool> {
+        let Some(UserTotpRow {
+            secret,
+            enabled_at: Some(_),
+        }) = self.find_totp(user_id).await?
+        else {
+            return Ok(false);
+        };
+        if code.trim().len() == TOTP_DIGITS && code.trim().chars().all(|c| c.is_ascii_digit()) {
+            self.check_totp(user_id, &secret, code).await
+        } else {
+            self.use_recovery_code(user_id, code).await
+        }
+    }
+}
+
+#[async_trait]
+impl MfaRepository for MfaRepositoryImpl {
+    #[tracing::instrument(skip_all, fields(db.system = "postgresql", db.operation = "INSERT", user_id = %event.user_id))]
+    async fn enroll_totp(&self, event: EnrollTotp) -> AppResult<TotpEnrollment> {
+        let secret = Secret::generate_secret()
+            .to_bytes()
+            .map_err(|e| AppError::ConversionEntityError(e.to_string()))?;
+        let totp = totp(secret, event.account_name)?;
+        let encoded_secret = totp.get_secret_base32();
+        let code_hashes = event
+            .recovery_codes
+            .iter()
+            .map(|code| bcrypt::hash(normalize_recovery_code(code), RECOVERY_CODE_COST))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut tx = self.db.begin().await?;
+
+        // 登録を完了したTOTPは、解除してからでないと登録し直せない
+        // 登録の途中の場合は、新しい秘密鍵で登録をやり直す
+        let res = sqlx::query!(
+            r#"
+                INSERT INTO user_totp(user_id, secret)
+                VALUES ($1, $2)
+                ON CONFLICT (user_id) DO UPDATE
+                SET secret = EXCLUDED.secret
+                WHERE user_totp.enabled_at IS NULL;
+            "#,
+            event.user_id as _,
+            encoded_secret,
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+        if res.rows_affected() < 1 {
+            return Err(AppError::UnprocessableEntity(Message::new(
+                "mfa_already_enabled",
+            )));
+        }
+
+        sqlx::query!(
+            r#"
+                DELETE FROM user_recovery_codes WHERE user_id = $1;
+            "#,
+            event.user_id as _
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+        sqlx::query!(
+            r#"
+                INSERT INTO user_recovery_codes(user_id, code_hash)
+                SELECT $1, * FROM UNNEST($2::VARCHAR[]);
+            "#,
+            event.user_id as _,
+            &code_hashes,
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+
+        tx.commit().await.map_err(AppError::TransactionError)?;
+
+        Ok(TotpEnrollment {
+            otpauth_uri: totp.get_url(),
+            secret: encoded_secret,
+            recovery_codes: event.recovery_codes,
+        })
+    }
+
+    #[tracing::instrument(skip_all, fields(db.system = "postgresql", db.operation = "UPDATE", user_id = %event.user_id))]
+    async fn confirm_totp(&self, event: VerifyTotp) -> AppResult<()> {
+        let totp = match self.find_totp(event.user_id).await? {
+            None => {
+                return Err(AppError::UnprocessableEntity(Message::new(
+                    "mfa_not_enrolled",
+                )))
+            }
+            Some(UserTotpRow {
+                enabled_at: Some(_),
+                ..
+            }) => {
+                return Err(AppError::UnprocessableEntity(Message::new(
+                    "mfa_already_enabled",
+                )))
+            }
+            Some(totp) => totp,
+        };
+
+        if !self
+            .check_totp(event.user_id, &totp.secret, &event.code)
+            .await?
+        {
+            return Err(AppError::UnprocessableEntity(Message::new(
+                "invalid_mfa_code",
+            )));
+        }
+
+        sqlx::query!(
+            r#"
+                UPDATE user_totp SET enabled_at = CURRENT_TIMESTAMP(3)
+                WHERE user_id = $1 AND enabled_at IS NULL;
+            "#,
+            event.user_id as _
+        )
+        .execute(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip_all, fields(db.system = "postgresql", db.operation = "DELETE", user_id = %event.user_id))]
+    async fn disable_totp(&self, event: VerifyTotp) -> AppResult<()> {
+        if !self.is_totp_enabled(event.user_id).await? {
+            return Err(AppError::UnprocessableEntity(Message::new(
+                "mfa_not_enabled",
+            )));
+        }
+        // 認証アプリを使えなくなった場合に備え、リカバリーコードでも解除できる
+        if !self.verify_code(event.user_id, &event.code).await? {
+            return Err(AppError::UnprocessableEntity(Message::new(
+                "invalid_mfa_code",
+            )));
+        }
+
+        let mut tx = self.db.begin().await?;
+        sqlx::query!(
+            r#"
+                DELETE FROM user_recovery_codes WHERE user_id = $1;
+            "#,
+            event.user_id as _
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+        sqlx::query!(
+            r#"
+                DELETE FROM user_totp WHERE user_id = $1;
+            "#,
+            event.user_id as _
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+        tx.commit().await.map_err(AppError::TransactionError)?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip_all, fields(db.system = "postgresql", db.operation = "SELECT", user_id = %user_id))]
+    async fn is_totp_enabled(&self, user_id: UserId) -> AppResult<bool> {
+        Ok(self
+            .find_totp(user_id)
+            .await?
+            .is_some_and(|totp| totp.enabled_at.is_some()))
+    }
+
+    #[tracing::instrument(skip_all, fields(db.system = "redis", db.operation = "SET", user_id = %event.user_id))]
+    async fn create_challenge(&self, event: CreateMfaChallenge) -> AppResult<MfaChallenge> {
+        let CreateMfaChallenge {
+            user_id,
+            challenge_token,
+            user_agent,
+            ip_address,
+        } = event;
+        self.kv
+            .set_ex(
+                &MfaChallengeKey::from(challenge_token.as_str()),
+                &MfaChallengeState {
+                    user_id,
+                    user_agent,
+                    ip_address,
+                },
+                self.config.challenge_ttl,
+            )
+            .await?;
+        Ok(MfaChallenge {
+            challenge_token,
+            expires_in: self.config.challenge_ttl,
+        })
+    }
+
+    #[tracing::instrument(skip_all, fields(db.system = "redis", db.operation = "GETDEL"))]
+    async fn verify_challenge(
+        &self,
+        event: VerifyMfaChallenge,
+    ) -> AppResult<VerifiedMfaChallenge> {
+        let key = MfaChallengeKey::from(event.challenge_token.as_str());
+        let failure_key = MfaChallengeFailureKey::from(event.challenge_token.as_str());
+        let challenge = self
+            .kv
+            .get(&key)
+            .await?
+            .ok_or(AppError::UnauthenticatedError)?;
+
+        if !self.verify_code(challenge.user_id, &event.code).await? {
+            let failures = self
+                .kv
+                .increment(&failure_key, self.config.challenge_ttl)
+                .await?;
+            if failures >= MAX_CHALLENGE_ATTEMPTS {
+                warn!(
+                    "MFA challenge was discarded because of too many failures: user_id={}",
+                    challenge.user_id
+                );
+                self.kv.delete(&key).await?;
+                self.kv.delete(&failure_key).await?;
+            }
+            return Err(AppError::UnauthenticatedError);
+        }
+
+        // 同じ2段階目の認証から、複数のトークンを発行しないようにする
+        let challenge = self
+            .kv
+            .take(&key)
+            .await?
+            .ok_or(AppError::UnauthenticatedError)?;
+        self.kv.delete(&failure_key).await?;
+        Ok(challenge.into())
+    }
+}
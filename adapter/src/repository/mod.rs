@@ -2,4 +2,13 @@ pub mod book;
 pub mod health;
 pub mod auth;
 pub mod user;
-pub mod checkout;
\ No newline at end of file
+pub mod checkout;
+pub mod reservation;
+pub mod policy;
+pub mod metadata;
+pub mod metrics;
+pub mod mailer;
+pub mod mfa;
+pub mod api_key;
+pub mod role;
+pub mod audit;
@@ -0,0 +1,237 @@
+// 貸出ポリシーとDBとのやりとりを描く
+// ポリシーが登録されていないロールには、設定値(CheckoutConfig)を既定値として適用する
+
+use async_trait::async_trait;
+use derive_new::new;
+use kernel::model::{
+    audit::{event::CreateAuditEvent, AuditAction},
+    id::UserId,
+    policy::{event::UpdateBorrowingPolicy, BorrowingPolicy},
+};
+use kernel::repository::policy::PolicyRepository;
+use shared::{
+    config::CheckoutConfig,
+    error::{AppError, AppResult},
+    i18n::Message,
+};
+use serde_json::json;
+
+use crate::database::{
+    model::policy::{BorrowingPolicyRow, UserBorrowingPolicyRow},
+    ConnectionPool,
+};
+use crate::repository::audit::record_audit_event;
+
+#[derive(new)]
+pub struct PolicyRepositoryImpl {
+    db: ConnectionPool,
+    default_policy: CheckoutConfig,
+}
+
+#[async_trait]
+impl PolicyRepository for PolicyRepositoryImpl {
+    // 全てのロールの貸出ポリシーを取得する
+    #[tracing::instrument(skip_all, fields(db.system = "postgresql", db.operation = "SELECT"))]
+    async fn find_all(&self) -> AppResult<Vec<BorrowingPolicy>> {
+        sqlx::query_as!(
+            BorrowingPolicyRow,
+            r#"
+                SELECT
+                    r.name AS role_name,
+                    COALESCE(p.max_loans, $1) AS "max_loans!",
+                    COALESCE(p.loan_period_days, $2) AS "loan_period_days!",
+                    COALESCE(p.max_renewals, $3) AS "max_renewals!"
+                FROM roles AS r
+                LEFT OUTER JOIN borrowing_policies AS p USING(role_id)
+                ORDER BY r.name ASC;
+            "#,
+            self.default_policy.max_loans,
+            self.default_policy.loan_period_days,
+            self.default_policy.max_renewals,
+        )
+        .fetch_all(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?
+        .into_iter()
+        .map(BorrowingPolicy::try_from)
+        .collect()
+    }
+
+    // ロールの貸出ポリシーを登録・更新する
+    #[tracing::instrument(skip_all, fields(db.system = "postgresql", db.operation = "UPDATE", role = ?event.role))]
+    async fn update(&self, event: UpdateBorrowingPolicy) -> AppResult<()> {
+        let mut tx = self.db.begin().await?;
+
+        // 監査ログに変更前の貸出ポリシー(未登録の場合は既定値)を記録する
+        let before = sqlx::query!(
+            r#"
+                SELECT
+                    COALESCE(p.max_loans, $2) AS "max_loans!",
+                    COALESCE(p.loan_period_days, $3) AS "loan_period_days!",
+                    COALESCE(p.max_renewals, $4) AS "max_renewals!"
+                FROM roles AS r
+                LEFT OUTER JOIN borrowing_policies AS p USING(role_id)
+                WHERE r.name = $1;
+            "#,
+            event.role.as_ref(),
+            self.default_policy.max_loans,
+            self.default_policy.loan_period_days,
+            self.default_policy.max_renewals,
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?
+        .ok_or_else(|| {
+            AppError::EntityNotFound(Message::new("role_not_found").arg("role", event.role.as_ref()))
+        })?;
+
+        let res = sqlx::query!(
+            r#"
+                INSERT INTO borrowing_policies
+                (role_id, max_loans, loan_period_days, max_renewals)
+                SELECT role_id, $2, $3, $4 FROM roles WHERE name = $1
+                ON CONFLICT (role_id) DO UPDATE SET
+                    max_loans = EXCLUDED.max_loans,
+                    loan_period_days = EXCLUDED.loan_period_days,
+                    max_renewals = EXCLUDED.max_renewals;
+            "#,
+            event.role.as_ref(),
+            event.max_loans,
+            event.loan_period_days,
+            event.max_renewals,
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+
+        if res.rows_affected() < 1 {
+            return Err(AppError::EntityNotFound(
+                Message::new("role_not_found").arg("role", event.role.as_ref()),
+            ));
+        }
+
+        record_audit_event(
+            &mut tx,
+            CreateAuditEvent::new(
+                event.requested_user,
+                AuditAction::PolicyUpdate,
+                event.role.as_ref().to_string(),
+                Some(json!({
+                    "maxLoans": before.max_loans,
+                    "loanPeriodDays": before.loan_period_days,
+                    "maxRenewals": before.max_renewals,
+                })),
+                Some(json!({
+                    "maxLoans": event.max_loans,
+                    "loanPeriodDays": event.loan_period_days,
+                    "maxRenewals": event.max_renewals,
+                })),
+            ),
+        )
+        .await?;
+
+        tx.commit().await.map_err(AppError::TransactionError)?;
+
+        Ok(())
+    }
+}
+
+// 貸出・延長のトランザクション内で、ユーザーに適用される貸出ポリシーを取得する
+pub(crate) async fn fetch_user_borrowing_policy(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    user_id: UserId,
+    default_policy: &CheckoutConfig,
+) -> AppResult<UserBorrowingPolicyRow> {
+    sqlx::query_as!(
+        UserBorrowingPolicyRow,
+        r#"
+            SELECT
+                COALESCE(p.max_loans, $2) AS "max_loans!",
+                COALESCE(p.loan_period_days, $3) AS "loan_period_days!",
+                COALESCE(p.max_renewals, $4) AS "max_renewals!"
+            FROM users AS u
+            LEFT OUTER JOIN borrowing_policies AS p USING(role_id)
+            WHERE u.user_id = $1;
+        "#,
+        user_id as _,
+        default_policy.max_loans,
+        default_policy.loan_period_days,
+        default_policy.max_renewals,
+    )
+    .fetch_optional(&mut **tx)
+    .await
+    .map_err(AppError::SpecificOperationError)?
+    .ok_or_else(|| {
+        AppError::EntityNotFound(Message::new("user_not_found").arg("user", user_id))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kernel::model::role::Role;
+    use std::str::FromStr;
+
+    #[sqlx::test(fixtures("common"))]
+    async fn test_update_and_find_all(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = PolicyRepositoryImpl::new(
+            ConnectionPool::new(pool),
+            CheckoutConfig {
+                max_loans: 5,
+                loan_period_days: 14,
+                max_renewals: 2,
+            },
+        );
+        let admin = UserId::from_str("5b4c96ac-316a-4bee-8e69-cac5eb84ff4c")?;
+        let find = |policies: &[BorrowingPolicy], role: Role| {
+            policies
+                .iter()
+                .find(|p| p.role == role)
+                .map(|p| (p.max_loans, p.loan_period_days, p.max_renewals))
+        };
+
+        // 1. 登録前は全てのロールに既定値が適用される
+        let policies = repo.find_all().await?;
+        assert_eq!(find(&policies, Role::User), Some((5, 14, 2)));
+        assert_eq!(find(&policies, Role::Admin), Some((5, 14, 2)));
+
+        // 2. 登録すると、そのロールのみ登録した値になる
+        repo.update(UpdateBorrowingPolicy {
+            role: Role::User,
+            max_loans: 1,
+            loan_period_days: 7,
+            max_renewals: 0,
+            requested_user: admin,
+        })
+        .await?;
+        let policies = repo.find_all().await?;
+        assert_eq!(find(&policies, Role::User), Some((1, 7, 0)));
+        assert_eq!(find(&policies, Role::Admin), Some((5, 14, 2)));
+
+        // 3. 登録済みのロールは上書きされる
+        repo.update(UpdateBorrowingPolicy {
+            role: Role::User,
+            max_loans: 3,
+            loan_period_days: 21,
+            max_renewals: 1,
+            requested_user: admin,
+        })
+        .await?;
+        let policies = repo.find_all().await?;
+        assert_eq!(find(&policies, Role::User), Some((3, 21, 1)));
+
+        // 4. 存在しないロールは登録できない
+        let res = repo
+            .update(UpdateBorrowingPolicy {
+                role: Role::Custom("Librarian".into()),
+                max_loans: 3,
+                loan_period_days: 21,
+                max_renewals: 1,
+                requested_user: admin,
+            })
+            .await;
+        assert!(matches!(res, Err(AppError::EntityNotFound(_))));
+
+        Ok(())
+    }
+}
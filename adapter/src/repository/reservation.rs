@@ -0,0 +1,415 @@
+// 予約(待ち行列)とDBとのやりとりを描く
+// 予約の状態は貸出状態と組み合わせて判断する必要があるため、
+// 書き込みを伴う操作はSERIALIZABLEなトランザクション内で行う
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use derive_new::new;
+use kernel::model::{
+    audit::{event::CreateAuditEvent, AuditAction},
+    id::{BookId, ReservationId},
+    reservation::{
+        event::{CreateReservation, DeleteReservation},
+        Reservation,
+    },
+};
+use kernel::repository::reservation::ReservationRepository;
+use serde_json::json;
+use shared::error::{AppError, AppResult};
+use shared::i18n::Message;
+
+use crate::database::{
+    model::reservation::ReservationRow, set_transaction_serializable,
+    ConnectionPool,
+};
+use crate::repository::{audit::record_audit_event, checkout::fetch_book_availability};
+
+#[derive(new)]
+pub struct ReservationRepositoryImpl {
+    db: ConnectionPool,
+    hold_ttl: u64,
+}
+
+#[async_trait]
+impl ReservationRepository for ReservationRepositoryImpl {
+    // 貸出中の蔵書に予約を追加する
+    #[tracing::instrument(skip_all, fields(db.system = "postgresql", db.operation = "INSERT", book_id = %event.book_id, user_id = %event.reserved_by))]
+    async fn create(&self, event: CreateReservation) -> AppResult<()> {
+        let mut tx = self.db.begin().await?;
+        set_transaction_serializable(&mut tx).await?;
+
+        // 取り置き期限切れの予約を片付けてから状態を確認する
+        refresh_reservation_queue(
+            &mut tx,
+            event.book_id,
+            event.reserved_at,
+            self.hold_ttl,
+        )
+        .await?;
+
+        // 事前のチェックとして以下を調べる
+        // - 指定の蔵書IDを持つ蔵書が存在するか
+        // - 存在した場合：自分が借りている、または自分のために取り置かれていないか
+        // - 借りられる冊子が残っていないか(貸出可能な蔵書は予約せずに借りればよい)
+        {
+            let res = fetch_book_availability(
+                &mut tx,
+                event.book_id,
+                event.reserved_by,
+            )
+            .await?;
+
+            match res {
+                // 指定した書籍が存在しない場合
+                None => {
+                    return Err(AppError::EntityNotFound(
+                        Message::new("book_not_found").arg("book_id", event.book_id),
+                    ))
+                }
+                // 自分が借りている、または自分のために取り置かれている場合
+                Some(ref row) if row.borrowing || row.holding => {
+                    return Err(AppError::UnprocessableEntity(
+                        Message::new("book_already_available")
+                            .arg("book_id", event.book_id)
+                            .arg("user_id", event.reserved_by),
+                    ))
+                }
+                // 借りられる冊子が残っている場合
+                Some(ref row) if row.available_for_user() > 0 => {
+                    return Err(AppError::UnprocessableEntity(
+                        Message::new("book_available_for_checkout").arg("book_id", event.book_id),
+                    ))
+                }
+                _ => {} // それ以外は処理続行
+            }
+        }
+
+        // 同じ蔵書に対する二重の予約はUNIQUE制約で弾く
+        let reservation_id = sqlx::query_scalar!(
+            r#"
+                INSERT INTO reservations (book_id, user_id, reserved_at)
+                VALUES ($1, $2, $3)
+                ON CONFLICT (book_id, user_id) DO NOTHING
+                RETURNING reservation_id AS "reservation_id: ReservationId";
+            "#,
+            event.book_id as _,
+            event.reserved_by as _,
+            event.reserved_at,
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?
+        .ok_or_else(|| {
+            AppError::UnprocessableEntity(
+                Message::new("reservation_already_exists").arg("book_id", event.book_id),
+            )
+        })?;
+
+        record_audit_event(
+            &mut tx,
+            CreateAuditEvent::new(
+                event.reserved_by,
+                AuditAction::ReservationCreate,
+                reservation_id.to_string(),
+                None,
+                Some(json!({
+                    "bookId": event.book_id,
+                    "reservedAt": event.reserved_at,
+                })),
+            ),
+        )
+        .await?;
+
+        tx.commit().await.map_err(AppError::TransactionError)?;
+
+        Ok(())
+    }
+
+    // 自分の予約を取り消す
+    #[tracing::instrument(skip_all, fields(db.system = "postgresql", db.operation = "DELETE", book_id = %event.book_id, user_id = %event.requested_user))]
+    async fn delete(&self, event: DeleteReservation) -> AppResult<()> {
+        let mut tx = self.db.begin().await?;
+        set_transaction_serializable(&mut tx).await?;
+
+        let deleted = sqlx::query!(
+            r#"
+                DELETE FROM reservations
+                WHERE book_id = $1
+                AND user_id = $2
+                RETURNING reservation_id AS "reservation_id: ReservationId", reserved_at, ready_at;
+            "#,
+            event.book_id as _,
+            event.requested_user as _,
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?
+        .ok_or_else(|| {
+            AppError::EntityNotFound(
+                Message::new("reservation_not_found").arg("book_id", event.book_id),
+            )
+        })?;
+
+        record_audit_event(
+            &mut tx,
+            CreateAuditEvent::new(
+                event.requested_user,
+                AuditAction::ReservationDelete,
+                deleted.reservation_id.to_string(),
+                Some(json!({
+                    "bookId": event.book_id,
+                    "reservedAt": deleted.reserved_at,
+                    "readyAt": deleted.ready_at,
+                })),
+                None,
+            ),
+        )
+        .await?;
+
+        // 取り置き中の予約が取り消された場合は、次の予約者に取り置きを回す
+        refresh_reservation_queue(
+            &mut tx,
+            event.book_id,
+            event.deleted_at,
+            self.hold_ttl,
+        )
+        .await?;
+
+        tx.commit().await.map_err(AppError::TransactionError)?;
+
+        Ok(())
+    }
+
+    // 蔵書に対する予約を予約日時の古い順(待ち行列の順)に取得する
+    #[tracing::instrument(skip_all, fields(db.system = "postgresql", db.operation = "SELECT", book_id = %book_id))]
+    async fn find_by_book_id(
+        &self,
+        book_id: BookId,
+    ) -> AppResult<Vec<Reservation>> {
+        // 取り置き期限が切れた予約は含めない
+        sqlx::query_as!(
+            ReservationRow,
+            r#"
+                SELECT
+                    reservation_id,
+                    book_id,
+                    user_id,
+                    reserved_at,
+                    ready_at,
+                    expires_at
+                FROM reservations
+                WHERE book_id = $1
+                AND (expires_at IS NULL OR expires_at > CURRENT_TIMESTAMP)
+                ORDER BY reserved_at ASC
+                ;
+            "#,
+            book_id as _
+        )
+        .fetch_all(self.db.inner_ref())
+        .await
+        .map(|rows| rows.into_iter().map(Reservation::from).collect())
+        .map_err(AppError::SpecificOperationError)
+    }
+
+    // 取り置き期限の切れた予約がある蔵書ごとに、待ち行列を最新の状態にする
+    #[tracing::instrument(skip_all, fields(db.system = "postgresql", db.operation = "DELETE"))]
+    async fn expire_holds(&self, now: DateTime<Utc>) -> AppResult<()> {
+        let mut tx = self.db.begin().await?;
+        set_transaction_serializable(&mut tx).await?;
+
+        let book_ids = sqlx::query_scalar!(
+            r#"
+                SELECT DISTINCT book_id AS "book_id: BookId"
+                FROM reservations
+                WHERE expires_at <= $1;
+            "#,
+            now,
+        )
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+
+        for book_id in book_ids {
+            refresh_reservation_queue(&mut tx, book_id, now, self.hold_ttl).await?;
+        }
+
+        tx.commit().await.map_err(AppError::TransactionError)?;
+
+        Ok(())
+    }
+}
+
+// 予約の待ち行列を最新の状態にする
+// 1. 取り置き期限の切れた予約を削除する
+// 2. 貸出中でも取り置き中でもない冊子の数だけ、待ち行列の先頭から予約を取り置き状態にする
+// 返却・貸出・予約の各操作と、定期的な取り置き期限の確認のトランザクション内から呼び出す
+pub(crate) async fn refresh_reservation_queue(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    book_id: BookId,
+    now: DateTime<Utc>,
+    hold_ttl: u64,
+) -> AppResult<()> {
+    sqlx::query!(
+        r#"
+            DELETE FROM reservations
+            WHERE book_id = $1
+            AND expires_at <= $2;
+        "#,
+        book_id as _,
+        now,
+    )
+    .execute(&mut **tx)
+    .await
+    .map_err(AppError::SpecificOperationError)?;
+
+    let expires_at = now + chrono::Duration::seconds(hold_ttl as i64);
+    sqlx::query!(
+        r#"
+            UPDATE reservations
+            SET ready_at = $2, expires_at = $3
+            WHERE reservation_id IN (
+                SELECT r.reservation_id
+                FROM reservations AS r
+                WHERE r.book_id = $1
+                AND r.ready_at IS NULL
+                ORDER BY r.reserved_at ASC
+                LIMIT GREATEST(
+                    0,
+                    (SELECT COUNT(*) FROM book_copies WHERE book_id = $1)
+                    - (SELECT COUNT(*) FROM checkouts WHERE book_id = $1)
+                    - (
+                        SELECT COUNT(*) FROM reservations
+                        WHERE book_id = $1 AND ready_at IS NOT NULL
+                    )
+                )
+            );
+        "#,
+        book_id as _,
+        now,
+        expires_at,
+    )
+    .execute(&mut **tx)
+    .await
+    .map_err(AppError::SpecificOperationError)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::checkout::CheckoutRepositoryImpl;
+    use kernel::model::{
+        checkout::event::{CreateCheckout, UpdateReturned},
+        id::{CheckoutId, UserId},
+    };
+    use kernel::repository::checkout::CheckoutRepository;
+    use shared::config::CheckoutConfig;
+    use std::str::FromStr;
+
+    #[sqlx::test(fixtures("common", "book", "reservation"))]
+    async fn test_return_promotes_first_reservation(
+        pool: sqlx::PgPool,
+    ) -> anyhow::Result<()> {
+        let reservation_repo =
+            ReservationRepositoryImpl::new(ConnectionPool::new(pool.clone()), 3600);
+        let checkout_repo = CheckoutRepositoryImpl::new(
+            ConnectionPool::new(pool.clone()),
+            3600,
+            CheckoutConfig {
+                max_loans: 5,
+                loan_period_days: 14,
+                max_renewals: 2,
+            },
+        );
+
+        // 1. fixtures/reservation.sqlで貸出中にした書籍を、別のユーザーが予約する
+        let book_id = BookId::from_str("9890736e-a4e4-461a-a77d-eac3517ef11b")?;
+        let borrower = UserId::from_str("5b4c96ac-316a-4bee-8e69-cac5eb84ff4c")?;
+        let reserver = UserId::from_str("9582f9de-0fd1-4892-b20c-70139a7eb95b")?;
+        reservation_repo
+            .create(CreateReservation::new(book_id, reserver, Utc::now()))
+            .await?;
+        let reservations = reservation_repo.find_by_book_id(book_id).await?;
+        assert_eq!(reservations.len(), 1);
+        assert!(!reservations[0].is_ready());
+
+        // 2. 返却すると、待ち行列の先頭の予約が取り置き状態になる
+        let checkout_id = CheckoutId::from_str("a7e8ba04-dc3f-4e6f-9d5c-a5e8b0e2d6c1")?;
+        checkout_repo
+            .update_returned(UpdateReturned::new(
+                checkout_id,
+                book_id,
+                borrower,
+                Utc::now(),
+                false,
+            ))
+            .await?;
+        let reservations = reservation_repo.find_by_book_id(book_id).await?;
+        assert!(reservations[0].is_ready());
+
+        // 3. 取り置き中は予約者以外は借りられず、予約者は借りられる
+        let res = checkout_repo
+            .create(CreateCheckout::new(book_id, borrower, Utc::now(), borrower))
+            .await;
+        assert!(matches!(res, Err(AppError::UnprocessableEntity(_))));
+        checkout_repo
+            .create(CreateCheckout::new(book_id, reserver, Utc::now(), reserver))
+            .await?;
+
+        // 4. 貸出が完了した予約は待ち行列から取り除かれる
+        let reservations = reservation_repo.find_by_book_id(book_id).await?;
+        assert!(reservations.is_empty());
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common", "book", "reservation"))]
+    async fn test_expired_hold_promotes_next_reservation(
+        pool: sqlx::PgPool,
+    ) -> anyhow::Result<()> {
+        let reservation_repo =
+            ReservationRepositoryImpl::new(ConnectionPool::new(pool.clone()), 3600);
+        let checkout_repo = CheckoutRepositoryImpl::new(
+            ConnectionPool::new(pool.clone()),
+            3600,
+            CheckoutConfig {
+                max_loans: 5,
+                loan_period_days: 14,
+                max_renewals: 2,
+            },
+        );
+
+        // 1. 予約した書籍が返却され、先頭の予約者のために取り置かれる
+        let book_id = BookId::from_str("9890736e-a4e4-461a-a77d-eac3517ef11b")?;
+        let borrower = UserId::from_str("5b4c96ac-316a-4bee-8e69-cac5eb84ff4c")?;
+        let reserver = UserId::from_str("9582f9de-0fd1-4892-b20c-70139a7eb95b")?;
+        reservation_repo
+            .create(CreateReservation::new(book_id, reserver, Utc::now()))
+            .await?;
+        checkout_repo
+            .update_returned(UpdateReturned::new(
+                CheckoutId::from_str("a7e8ba04-dc3f-4e6f-9d5c-a5e8b0e2d6c1")?,
+                book_id,
+                borrower,
+                Utc::now(),
+                false,
+            ))
+            .await?;
+
+        // 2. 取り置き中の冊子しかないため、返却したユーザーは待ち行列の2番目に並ぶ
+        reservation_repo
+            .create(CreateReservation::new(book_id, borrower, Utc::now()))
+            .await?;
+
+        // 3. 蔵書に対する操作がないまま取り置き期限が過ぎても、定期的な確認で次の予約者に取り置きが回る
+        let later = Utc::now() + chrono::Duration::seconds(3601);
+        reservation_repo.expire_holds(later).await?;
+        let reservations = reservation_repo.find_by_book_id(book_id).await?;
+        assert_eq!(reservations.len(), 1);
+        assert_eq!(reservations[0].reserved_by, borrower);
+        assert!(reservations[0].is_ready());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,388 @@
+// ロールと権限のDBとのやりとりを描く
+// Adminロールは常にすべての権限を持ち、組み込みのロール(Admin・User)は変更・削除できない
+
+use async_trait::async_trait;
+use derive_new::new;
+use kernel::model::{
+    audit::{event::CreateAuditEvent, AuditAction},
+    role::{
+        event::{CreateRole, DeleteRole, UpdateRolePermissions},
+        Permission, Role, RoleDetail,
+    },
+};
+use kernel::repository::role::RoleRepository;
+use serde_json::json;
+use shared::{
+    error::{AppError, AppResult},
+    i18n::Message,
+};
+
+use crate::database::{
+    model::role::{parse_permissions, to_permission_names, RoleRow},
+    ConnectionPool,
+};
+use crate::repository::audit::record_audit_event;
+
+#[derive(new)]
+pub struct RoleRepositoryImpl {
+    db: ConnectionPool,
+}
+
+fn reject_builtin(role: &Role) -> AppResult<()> {
+    if role.is_builtin() {
+        return Err(AppError::UnprocessableEntity(
+            Message::new("builtin_role_cannot_be_modified").arg("role", role.as_ref()),
+        ));
+    }
+    Ok(())
+}
+
+#[async_trait]
+impl RoleRepository for RoleRepositoryImpl {
+    #[tracing::instrument(skip_all, fields(db.system = "postgresql", db.operation = "SELECT"))]
+    async fn find_all(&self) -> AppResult<Vec<RoleDetail>> {
+        sqlx::query_as!(
+            RoleRow,
+            r#"
+                SELECT name, permissions
+                FROM roles
+                ORDER BY name ASC;
+            "#
+        )
+        .fetch_all(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?
+        .into_iter()
+        .map(RoleDetail::try_from)
+        .collect()
+    }
+
+    #[tracing::instrument(skip_all, fields(db.system = "postgresql", db.operation = "SELECT", role = ?role))]
+    async fn find_permissions(&self, role: &Role) -> AppResult<Vec<Permission>> {
+        if *role == Role::Admin {
+            return Ok(Permission::all());
+        }
+
+        let row = sqlx::query!(
+            r#"
+                SELECT permissions FROM roles WHERE name = $1
+            "#,
+            role.as_ref()
+        )
+        .fetch_optional(self.db.inner_ref())
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+
+        match row {
+            Some(row) => parse_permissions(row.permissions),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    #[tracing::instrument(skip_all, fields(db.system = "postgresql", db.operation = "INSERT", role = ?event.role))]
+    async fn create(&self, event: CreateRole) -> AppResult<()> {
+        let mut tx = self.db.begin().await?;
+
+        let res = sqlx::query!(
+            r#"
+                INSERT INTO roles(name, permissions)
+                VALUES ($1, $2)
+                ON CONFLICT (name) DO NOTHING;
+            "#,
+            event.role.as_ref(),
+            &to_permission_names(&event.permissions),
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+
+        if res.rows_affected() < 1 {
+            return Err(AppError::UnprocessableEntity(
+                Message::new("role_already_exists").arg("role", event.role.as_ref()),
+            ));
+        }
+
+        record_audit_event(
+            &mut tx,
+            CreateAuditEvent::new(
+                event.requested_user,
+                AuditAction::RoleCreate,
+                event.role.as_ref().to_string(),
+                None,
+                Some(json!({ "permissions": event.permissions })),
+            ),
+        )
+        .await?;
+
+        tx.commit().await.map_err(AppError::TransactionError)?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip_all, fields(db.system = "postgresql", db.operation = "UPDATE", role = ?event.role))]
+    async fn update_permissions(&self, event: UpdateRolePermissions) -> AppResult<()> {
+        reject_builtin(&event.role)?;
+
+        let mut tx = self.db.begin().await?;
+
+        // 監査ログに変更前の権限を記録するため、ロールをロックして取得する
+        let before = sqlx::query_scalar!(
+            r#"
+                SELECT permissions FROM roles
+                WHERE name = $1
+                FOR UPDATE
+            "#,
+            event.role.as_ref()
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?
+        .ok_or_else(|| {
+            AppError::EntityNotFound(
+                Message::new("role_not_found").arg("role", event.role.as_ref()),
+            )
+        })?;
+
+        sqlx::query!(
+            r#"
+                UPDATE roles SET permissions = $2
+                WHERE name = $1;
+            "#,
+            event.role.as_ref(),
+            &to_permission_names(&event.permissions),
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+
+        record_audit_event(
+            &mut tx,
+            CreateAuditEvent::new(
+                event.requested_user,
+                AuditAction::RolePermissionsUpdate,
+                event.role.as_ref().to_string(),
+                Some(json!({ "permissions": before })),
+                Some(json!({ "permissions": event.permissions })),
+            ),
+        )
+        .await?;
+
+        tx.commit().await.map_err(AppError::TransactionError)?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip_all, fields(db.system = "postgresql", db.operation = "DELETE", role = ?event.role))]
+    async fn delete(&self, event: DeleteRole) -> AppResult<()> {
+        reject_builtin(&event.role)?;
+
+        let mut tx = self.db.begin().await?;
+
+        // usersはロールの削除に連動して削除されるため、ユーザーが割り当てられているロールは削除しない
+        // ロックしている間は、他のトランザクションからこのロールをユーザーに割り当てられない
+        let role = sqlx::query!(
+            r#"
+                SELECT
+                    role_id,
+                    permissions,
+                    EXISTS(
+                        SELECT 1 FROM users AS u WHERE u.role_id = r.role_id
+                    ) AS "in_use!"
+                FROM roles AS r
+                WHERE name = $1
+                FOR UPDATE
+            "#,
+            event.role.as_ref()
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?
+        .ok_or_else(|| {
+            AppError::EntityNotFound(
+                Message::new("role_not_found").arg("role", event.role.as_ref()),
+            )
+        })?;
+
+        if role.in_use {
+            return Err(AppError::UnprocessableEntity(
+                Message::new("role_in_use").arg("role", event.role.as_ref()),
+            ));
+        }
+
+        sqlx::query!(
+            r#"
+                DELETE FROM roles WHERE role_id = $1;
+            "#,
+            role.role_id
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?;
+
+        record_audit_event(
+            &mut tx,
+            CreateAuditEvent::new(
+                event.requested_user,
+                AuditAction::RoleDelete,
+                event.role.as_ref().to_string(),
+                Some(json!({ "permissions": role.permissions })),
+                None,
+            ),
+        )
+        .await?;
+
+        tx.commit().await.map_err(AppError::TransactionError)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use kernel::{
+        model::{id::UserId, user::event::UpdateUserRole},
+        repository::user::UserRepository,
+    };
+
+    use super::*;
+    use crate::repository::user::UserRepositoryImpl;
+
+    fn librarian() -> Role {
+        Role::from("Librarian")
+    }
+
+    fn admin_id() -> UserId {
+        UserId::from_str("5b4c96ac-316a-4bee-8e69-cac5eb84ff4c").unwrap()
+    }
+
+    #[sqlx::test(fixtures("common"))]
+    async fn test_custom_role_lifecycle(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = RoleRepositoryImpl::new(ConnectionPool::new(pool));
+
+        // 1. 権限を付与したロールを作成できる
+        repo.create(CreateRole {
+            role: librarian(),
+            permissions: vec![Permission::CheckoutReadAny],
+            requested_user: admin_id(),
+        })
+        .await?;
+        assert_eq!(
+            repo.find_permissions(&librarian()).await?,
+            vec![Permission::CheckoutReadAny]
+        );
+        let roles = repo.find_all().await?;
+        assert!(roles.contains(&RoleDetail {
+            role: librarian(),
+            permissions: vec![Permission::CheckoutReadAny],
+        }));
+
+        // 2. 同じ名前のロールは作成できない
+        let res = repo
+            .create(CreateRole {
+                role: librarian(),
+                permissions: vec![],
+                requested_user: admin_id(),
+            })
+            .await;
+        assert!(matches!(res, Err(AppError::UnprocessableEntity(_))));
+
+        // 3. 権限を変更できる
+        repo.update_permissions(UpdateRolePermissions {
+            role: librarian(),
+            permissions: vec![Permission::CheckoutReadAny, Permission::CheckoutReturnAny],
+            requested_user: admin_id(),
+        })
+        .await?;
+        assert_eq!(
+            repo.find_permissions(&librarian()).await?,
+            vec![Permission::CheckoutReadAny, Permission::CheckoutReturnAny]
+        );
+
+        // 4. 削除したロールは権限を持たない
+        repo.delete(DeleteRole {
+            role: librarian(),
+            requested_user: admin_id(),
+        })
+        .await?;
+        assert!(repo.find_permissions(&librarian()).await?.is_empty());
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common"))]
+    async fn test_builtin_roles_cannot_be_modified(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = RoleRepositoryImpl::new(ConnectionPool::new(pool));
+
+        // Adminは常にすべての権限を持ち、Userは既定では権限を持たない
+        assert_eq!(
+            repo.find_permissions(&Role::Admin).await?,
+            Permission::all()
+        );
+        assert!(repo.find_permissions(&Role::User).await?.is_empty());
+
+        let res = repo
+            .update_permissions(UpdateRolePermissions {
+                role: Role::User,
+                permissions: vec![Permission::UserManage],
+                requested_user: admin_id(),
+            })
+            .await;
+        assert!(matches!(res, Err(AppError::UnprocessableEntity(_))));
+
+        let res = repo
+            .delete(DeleteRole {
+                role: Role::Admin,
+                requested_user: admin_id(),
+            })
+            .await;
+        assert!(matches!(res, Err(AppError::UnprocessableEntity(_))));
+
+        Ok(())
+    }
+
+    #[sqlx::test(fixtures("common"))]
+    async fn test_role_in_use_cannot_be_deleted(pool: sqlx::PgPool) -> anyhow::Result<()> {
+        let repo = RoleRepositoryImpl::new(ConnectionPool::new(pool.clone()));
+        let user_repo = UserRepositoryImpl::new(ConnectionPool::new(pool));
+
+        repo.create(CreateRole {
+            role: librarian(),
+            permissions: vec![Permission::CheckoutReturnAny],
+            requested_user: admin_id(),
+        })
+        .await?;
+        let user_id = admin_id();
+        user_repo
+            .update_role(UpdateUserRole {
+                user_id,
+                role: librarian(),
+                requested_user: admin_id(),
+            })
+            .await?;
+
+        // ユーザーに割り当てたロールは削除できず、ユーザーも残る
+        let res = repo
+            .delete(DeleteRole {
+                role: librarian(),
+                requested_user: admin_id(),
+            })
+            .await;
+        assert!(matches!(res, Err(AppError::UnprocessableEntity(_))));
+        let user = user_repo.find_current_user(user_id).await?.unwrap();
+        assert_eq!(user.role, librarian());
+
+        // 存在しないロールは割り当てられない
+        let res = user_repo
+            .update_role(UpdateUserRole {
+                user_id,
+                role: Role::from("Unknown"),
+                requested_user: admin_id(),
+            })
+            .await;
+        assert!(matches!(res, Err(AppError::EntityNotFound(_))));
+
+        Ok(())
+    }
+}
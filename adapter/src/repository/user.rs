@@ -1,5 +1,6 @@
 use async_trait::async_trait;
 use derive_new::new;
+use kernel::model::audit::{event::CreateAuditEvent, AuditAction};
 use kernel::model::id::UserId;
 use kernel::model::role::Role;
 use kernel::model::user::{
@@ -8,8 +9,11 @@ use kernel::model::user::{
 };
 use kernel::repository::user::UserRepository;
 use shared::error::{AppError, AppResult};
+use shared::i18n::Message;
+use serde_json::json;
 
 use crate::database::{model::user::UserRow, ConnectionPool};
+use crate::repository::audit::record_audit_event;
 
 use tracing::info;
 
@@ -22,6 +26,7 @@ pub struct UserRepositoryImpl{
 impl UserRepository for UserRepositoryImpl{
 
     // 現在のユーザーを返すAPI
+    #[tracing::instrument(skip_all, fields(db.system = "postgresql", db.operation = "SELECT", user_id = %current_user_id))]
     async fn find_current_user(
         &self,
         current_user_id: UserId,
@@ -58,6 +63,7 @@ impl UserRepository for UserRepositoryImpl{
     }
 
     // 全てのユーザーを返すAPI
+    #[tracing::instrument(skip_all, fields(db.system = "postgresql", db.operation = "SELECT"))]
     async fn find_all(&self) -> AppResult<Vec<User>> {
         let users = sqlx::query_as!(
             UserRow,
@@ -84,6 +90,7 @@ impl UserRepository for UserRepositoryImpl{
     }
 
     // ユーザー作成
+    #[tracing::instrument(skip_all, fields(db.system = "postgresql", db.operation = "INSERT"))]
     async fn create(&self, event: CreateUser) -> AppResult<User> {
         let user_id  = UserId::new();
         let hashed_password = hash_password(&event.password)?;
@@ -91,6 +98,8 @@ impl UserRepository for UserRepositoryImpl{
         // ユーザーを追加するときは管理者ではなく一般のユーザー権限とする。
         let role = Role::User;
 
+        let mut tx = self.db.begin().await?;
+
         let res = sqlx::query!(
             r#"
                 INSERT INTO users(user_id, name, email, password_hash, role_id)
@@ -102,7 +111,7 @@ impl UserRepository for UserRepositoryImpl{
             hashed_password,
             role.as_ref()
         )
-        .execute(self.db.inner_ref())
+        .execute(&mut *tx)
         .await 
         .map_err(AppError::SpecificOperationError)?;
         
@@ -112,6 +121,25 @@ impl UserRepository for UserRepositoryImpl{
             ));
         }
 
+        // パスワードは監査ログに含めない
+        record_audit_event(
+            &mut tx,
+            CreateAuditEvent::new(
+                event.requested_user,
+                AuditAction::UserCreate,
+                user_id.to_string(),
+                None,
+                Some(json!({
+                    "name": event.name,
+                    "email": event.email,
+                    "role": role.as_ref(),
+                })),
+            ),
+        )
+        .await?;
+
+        tx.commit().await.map_err(AppError::TransactionError)?;
+
         Ok(User {
             id: user_id,
             name: event.name,
@@ -121,6 +149,7 @@ impl UserRepository for UserRepositoryImpl{
     }
 
     // パスワード変更
+    #[tracing::instrument(skip_all, fields(db.system = "postgresql", db.operation = "UPDATE", user_id = %event.user_id))]
     async fn update_password(
         &self,
         event: UpdateUserPassword,
@@ -162,49 +191,119 @@ impl UserRepository for UserRepositoryImpl{
     }
 
     // 権限変更
+    #[tracing::instrument(skip_all, fields(db.system = "postgresql", db.operation = "UPDATE", user_id = %event.user_id, role = ?event.role))]
     async fn update_role(&self, event: UpdateUserRole) -> AppResult<()> {
+        let mut tx = self.db.begin().await?;
+
+        // 管理者が作成したロールも割り当てられるため、ロールが存在するかを先に確認する
+        let role = sqlx::query!(
+            r#"
+                SELECT role_id FROM roles WHERE name = $1
+            "#,
+            event.role.as_ref()
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?
+        .ok_or_else(|| {
+            AppError::EntityNotFound(Message::new("role_not_found").arg("role", event.role.as_ref()))
+        })?;
+
+        // 監査ログに変更前のロールを記録するため、対象のユーザーをロックして取得する
+        let before = sqlx::query_scalar!(
+            r#"
+                SELECT r.name
+                FROM users AS u
+                INNER JOIN roles AS r USING(role_id)
+                WHERE u.user_id = $1
+                FOR UPDATE OF u
+            "#,
+            event.user_id as _
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(AppError::SpecificOperationError)?
+        .ok_or_else(|| {
+            AppError::EntityNotFound(Message::new("user_not_found").arg("user", event.user_id))
+        })?;
+
         let res = sqlx::query!(
             r#"
                 UPDATE users
-                SET role_id = (
-                    SELECT role_id FROM roles WHERE name = $2
-                )
+                SET role_id = $2
                 WHERE user_id = $1
             "#,
             event.user_id as _,
-            event.role.as_ref()
+            role.role_id
         )
-        .execute(self.db.inner_ref())
+        .execute(&mut *tx)
         .await
         .map_err(AppError::SpecificOperationError)?;
 
         if res.rows_affected() < 1{ // rows_affectedの記述で変更されたレコード数を取得できる
             return Err(AppError::EntityNotFound(
-                "Specified user not found" .into(),
+                Message::new("user_not_found").arg("user", event.user_id),
             ));
         }
 
+        record_audit_event(
+            &mut tx,
+            CreateAuditEvent::new(
+                event.requested_user,
+                AuditAction::UserRoleUpdate,
+                event.user_id.to_string(),
+                Some(json!({ "role": before })),
+                Some(json!({ "role": event.role.as_ref() })),
+            ),
+        )
+        .await?;
+
+        tx.commit().await.map_err(AppError::TransactionError)?;
+
         Ok(())
     }
 
     // ユーザー削除
+    #[tracing::instrument(skip_all, fields(db.system = "postgresql", db.operation = "DELETE", user_id = %event.user_id))]
     async fn delete(&self,event: DeleteUser) -> AppResult<()>{
-        let res = sqlx::query!(
+        let mut tx = self.db.begin().await?;
+
+        // 削除したユーザーの情報を監査ログに残す
+        let before = sqlx::query!(
             r#"
-                DELETE FROM users
-                WHERE user_id = $1
+                DELETE FROM users AS u
+                USING roles AS r
+                WHERE u.user_id = $1
+                AND r.role_id = u.role_id
+                RETURNING u.name, u.email, r.name AS role
             "#,
             event.user_id as _
         )
-        .execute(self.db.inner_ref())
+        .fetch_optional(&mut *tx)
         .await
-        .map_err(AppError::SpecificOperationError)?;
+        .map_err(AppError::SpecificOperationError)?
+        .ok_or_else(|| {
+            AppError::EntityNotFound(Message::new("user_not_found").arg("user", event.user_id))
+        })?;
+
+        record_audit_event(
+            &mut tx,
+            CreateAuditEvent::new(
+                event.requested_user,
+                AuditAction::UserDelete,
+                event.user_id.to_string(),
+                Some(json!({
+                    "name": before.name,
+                    "email": before.email,
+                    "role": before.role,
+                })),
+                None,
+            ),
+        )
+        .await?;
+
+        tx.commit().await.map_err(AppError::TransactionError)?;
 
-        if res.rows_affected() < 1{
-            return Err(AppError::EntityNotFound(
-                "Specified user not found" .into(),
-            ));
-        }
         Ok(())
     }
 }
@@ -1,22 +1,33 @@
-use axum::extract::FromRequestParts;
-use axum::http::request::Parts;
+use std::marker::PhantomData;
+use std::net::{IpAddr, SocketAddr};
+use std::ops::Deref;
+use std::sync::Arc;
+
+use axum::extract::{ConnectInfo, FromRequestParts, OriginalUri};
+use axum::http::{header, request::Parts, Method};
 use axum::{async_trait, RequestPartsExt};
 use axum_extra::headers::authorization::Bearer;
 use axum_extra::headers::Authorization;
 use axum_extra::TypedHeader;
-use kernel::model::auth::AccessToken;
-use kernel::model::id::UserId;
-use kernel::model::role::Role;
+use kernel::model::api_key::{event::API_KEY_PREFIX, ApiKeyScope};
+use kernel::model::auth::{AccessToken, AuthorizedSession};
+use kernel::model::id::{ApiKeyId, UserId};
+use kernel::model::role::{Permission, Role};
 use kernel::model::user::User;
-use shared::error::AppError;
+use shared::error::{AppError, AppResult};
 
 use registry::AppRegistry;
-use tracing::{info, error};
+use tracing::{error, info, warn};
 
-// a) リクエストの前処理を実行後、handlerに渡す構造体を定義　
-pub struct AuthorizedUser{
+// a) リクエストの前処理を実行後、handlerに渡す構造体を定義
+pub struct AuthorizedUser {
     pub access_token: AccessToken,
     pub user: User,
+    // 2段階認証に関する設定を満たしたセッションかどうか
+    pub mfa_satisfied: bool,
+    // APIキーで認証した場合は、そのキーのID
+    // この場合、access_tokenにはAPIキーそのものが入る
+    pub api_key_id: Option<ApiKeyId>,
 }
 
 impl AuthorizedUser {
@@ -24,8 +35,97 @@ impl AuthorizedUser {
         self.user.id
     }
 
-    pub fn is_admin(&self) -> bool{
-        self.user.role == Role::Admin
+    // ユーザーのロールが権限を持つかどうか
+    // 2段階認証が必須の設定では、Adminは2段階認証を経てログインしたセッションでのみ権限を行使できる
+    pub async fn has_permission(
+        &self,
+        registry: &AppRegistry,
+        permission: Permission,
+    ) -> AppResult<bool> {
+        if self.user.role == Role::Admin && !self.mfa_satisfied {
+            return Ok(false);
+        }
+        let permissions = registry
+            .role_repository()
+            .find_permissions(&self.user.role)
+            .await?;
+        Ok(permissions.contains(&permission))
+    }
+}
+
+// RequirePermissionで要求する権限を、型として指定するためのトレイト
+pub trait RequiredPermission: Send + Sync {
+    const PERMISSION: Permission;
+}
+
+// 権限ごとに、RequirePermissionの型引数に指定する型を定義する
+pub mod permission {
+    use super::{Permission, RequiredPermission};
+
+    macro_rules! define_permission {
+        ($($name: ident),* $(,)?) => {
+            $(
+                pub struct $name;
+
+                impl RequiredPermission for $name {
+                    const PERMISSION: Permission = Permission::$name;
+                }
+            )*
+        };
+    }
+
+    define_permission!(
+        BookCreate,
+        BookImport,
+        BookUpdateAny,
+        BookDeleteAny,
+        CheckoutReadAny,
+        CheckoutCreateAny,
+        CheckoutReturnAny,
+        UserManage,
+        RoleManage,
+        PolicyManage,
+        AuditRead,
+    );
+}
+
+// 認証に加えて、ユーザーのロールが権限Pを持つことを要求する
+// 例: `user: RequirePermission<permission::UserManage>`
+// AuthorizedUserと同じように使えるよう、Derefを実装している
+pub struct RequirePermission<P> {
+    pub user: AuthorizedUser,
+    _permission: PhantomData<P>,
+}
+
+impl<P> Deref for RequirePermission<P> {
+    type Target = AuthorizedUser;
+
+    fn deref(&self) -> &Self::Target {
+        &self.user
+    }
+}
+
+#[async_trait]
+impl<P: RequiredPermission> FromRequestParts<AppRegistry> for RequirePermission<P> {
+    type Rejection = AppError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        registry: &AppRegistry,
+    ) -> Result<Self, Self::Rejection> {
+        let user = AuthorizedUser::from_request_parts(parts, registry).await?;
+        if !user.has_permission(registry, P::PERMISSION).await? {
+            warn!(
+                "User does not have the permission: user_id={}, permission={}",
+                user.id(),
+                P::PERMISSION.as_ref()
+            );
+            return Err(AppError::ForbiddenOperation);
+        }
+        Ok(Self {
+            user,
+            _permission: PhantomData,
+        })
     }
 }
 
@@ -38,23 +138,32 @@ impl FromRequestParts<AppRegistry> for AuthorizedUser {
         parts: &mut Parts,
         registry: &AppRegistry,
     ) -> Result<Self, Self::Rejection> {
-        // b) HTTPヘッダからアクセストークンを取り出す　
-        let TypedHeader(Authorization(bearer)) = parts// 取り出したtokenが正しいかを型(TypedHeader)で判断
-            .extract::<TypedHeader<Authorization<Bearer>>>()
-            .await
-            .map_err(|_| AppError::UnauthorizedError)?;
+        // b) HTTPヘッダからアクセストークンを取り出す
+        let TypedHeader(Authorization(bearer)) =
+            parts // 取り出したtokenが正しいかを型(TypedHeader)で判断
+                .extract::<TypedHeader<Authorization<Bearer>>>()
+                .await
+                .map_err(|_| AppError::UnauthorizedError)?;
         let access_token = AccessToken(bearer.token().to_string());
         info!("Successfully Extracted AccessToken from Header: 1/3");
 
-        // アクセストークンが紐づくユーザーIDを抽出する　
-        let user_id = registry
+        // APIキーの場合は、キーに付与したスコープでリクエストを許可するかを判断する
+        if access_token.0.starts_with(API_KEY_PREFIX) {
+            return authorize_api_key(parts, registry, access_token).await;
+        }
+
+        // アクセストークンが紐づくユーザーIDを抽出する
+        let AuthorizedSession {
+            user_id,
+            mfa_satisfied,
+        } = registry
             .auth_repository()
-            .fetch_user_id_from_token(&access_token)
+            .fetch_session_from_token(&access_token)
             .await?
             .ok_or(AppError::UnauthenticatedError)?;
         info!("Successfully extracted UserId linked AccessToken: 2/3");
 
-        // ユーザーIDでDBからユーザーのレコードを引く　
+        // ユーザーIDでDBからユーザーのレコードを引く
 
         let user = match registry.user_repository().find_current_user(user_id).await {
             Ok(Some(user)) => {
@@ -74,9 +183,157 @@ impl FromRequestParts<AppRegistry> for AuthorizedUser {
             }
         };
 
-info!("Successfully completed user lookup process for UserID: {:?} 3/3", user_id);
-Ok(Self { access_token, user })
+        info!(
+            "Successfully completed user lookup process for UserID: {:?} 3/3",
+            user_id
+        );
+
+        // 操作があったため、設定に応じてアクセストークンの有効期限を延長する
+        registry
+            .auth_repository()
+            .extend_token(&access_token)
+            .await?;
+        Ok(Self {
+            access_token,
+            user,
+            mfa_satisfied,
+            api_key_id: None,
+        })
+    }
+}
+
+// APIキーで認証する。アクセストークンと異なり、操作があっても有効期限は延長しない
+async fn authorize_api_key(
+    parts: &Parts,
+    registry: &AppRegistry,
+    key: AccessToken,
+) -> Result<AuthorizedUser, AppError> {
+    let api_key = registry
+        .api_key_repository()
+        .authenticate(&key.0)
+        .await?
+        .ok_or(AppError::UnauthenticatedError)?;
+
+    // ルーターに宣言されたスコープで判断する。宣言されていないルートはAPIキーでは実行できない
+    let allowed = parts
+        .extensions
+        .get::<ApiKeyScopes>()
+        .is_some_and(|scopes| api_key.scopes.contains(&scopes.required_for(&parts.method)));
+    if !allowed {
+        // ネストしたルーターでは`parts.uri`から上位のパスが取り除かれるため、元のパスを記録する
+        let path = parts
+            .extensions
+            .get::<OriginalUri>()
+            .map_or(parts.uri.path(), |OriginalUri(uri)| uri.path());
+        warn!(
+            "API key does not have the scope for the request: api_key_id={}, method={}, path={}",
+            api_key.id,
+            parts.method,
+            path
+        );
+        return Err(AppError::ForbiddenOperation);
+    }
+
+    let user = registry
+        .user_repository()
+        .find_current_user(api_key.user_id)
+        .await?
+        .ok_or(AppError::UnauthenticatedError)?;
+
+    Ok(AuthorizedUser {
+        access_token: key,
+        user,
+        mfa_satisfied: api_key.mfa_satisfied,
+        api_key_id: Some(api_key.id),
+    })
+}
+
+// ルートの実行に必要なAPIキーのスコープ。参照系のメソッドはreadを、それ以外はwriteを求める
+// 各ルーターに`route_layer(Extension(...))`で宣言し、宣言のないルートはAPIキーでは実行できない
+// 例: `.route_layer(Extension(ApiKeyScopes::new(BooksRead, BooksWrite)))`
+#[derive(Clone, Copy)]
+pub struct ApiKeyScopes {
+    read: ApiKeyScope,
+    write: ApiKeyScope,
+}
+
+impl ApiKeyScopes {
+    pub fn new(read: ApiKeyScope, write: ApiKeyScope) -> Self {
+        Self { read, write }
+    }
+
+    fn required_for(&self, method: &Method) -> ApiKeyScope {
+        if method == Method::GET || method == Method::HEAD {
+            self.read
+        } else {
+            self.write
+        }
+    }
+}
+
+// `X-Forwarded-For`を信頼するリバースプロキシのアドレス
+// 起動時にレイヤーとしてリクエストに含め、ClientInfoから参照する
+#[derive(Clone, Default)]
+pub struct TrustedProxies(pub Arc<Vec<IpAddr>>);
+
+// ログインした端末を識別するための、リクエストの接続元の情報
+// 接続元は`ConnectInfo`のアドレスとし、信頼するプロキシからの接続の場合に限り`X-Forwarded-For`を参照する
+pub struct ClientInfo {
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for ClientInfo {
+    type Rejection = std::convert::Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let header_value = |name: header::HeaderName| {
+            parts
+                .headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+        };
+
+        let user_agent = header_value(header::USER_AGENT).map(ToOwned::to_owned);
+        let trusted_proxies = parts
+            .extensions
+            .get::<TrustedProxies>()
+            .cloned()
+            .unwrap_or_default();
+        let forwarded_for = header_value(header::HeaderName::from_static("x-forwarded-for"));
+        let ip_address =
+            parts
+                .extensions
+                .get::<ConnectInfo<SocketAddr>>()
+                .map(|ConnectInfo(addr)| {
+                    client_ip(addr.ip(), forwarded_for, &trusted_proxies.0).to_string()
+                });
+
+        Ok(Self {
+            user_agent,
+            ip_address,
+        })
+    }
+}
 
-        
+// `X-Forwarded-For`は右端が直前のプロキシが付け加えたアドレスとなる
+// 右から順に、信頼するプロキシを経由している間だけ遡り、最初の信頼しないアドレスを接続元とする
+fn client_ip(peer: IpAddr, forwarded_for: Option<&str>, trusted_proxies: &[IpAddr]) -> IpAddr {
+    let mut client = peer;
+    let hops = forwarded_for
+        .into_iter()
+        .flat_map(|value| value.rsplit(','));
+    for hop in hops {
+        if !trusted_proxies.contains(&client) {
+            break;
+        }
+        match hop.trim().parse::<IpAddr>() {
+            Ok(ip) => client = ip,
+            Err(_) => break,
+        }
     }
+    client
 }
@@ -0,0 +1,101 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use garde::Validate;
+use kernel::model::{api_key::event::DeleteApiKey, id::ApiKeyId};
+use registry::AppRegistry;
+use shared::error::AppResult;
+
+use crate::{
+    extractor::AuthorizedUser,
+    model::api_key::{
+        ApiKeyResponse, ApiKeysResponse, CreateApiKeyRequest, CreateApiKeyRequestWithUserId,
+        CreatedApiKeyResponse,
+    },
+};
+
+/// ユーザーが自分自身のAPIキーの一覧を取得する
+#[utoipa::path(
+    get,
+    path = "/api/v1/users/me/api-keys",
+    tag = "users",
+    responses(
+        (status = 200, description = "発行済みのAPIキーの一覧(新しい順)", body = ApiKeysResponse),
+        (status = 401, description = "認証されていない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn list_api_keys(
+    user: AuthorizedUser,
+    State(registry): State<AppRegistry>,
+) -> AppResult<Json<ApiKeysResponse>> {
+    let items = registry
+        .api_key_repository()
+        .find_by_user_id(user.id())
+        .await?
+        .into_iter()
+        .map(ApiKeyResponse::from)
+        .collect();
+
+    Ok(Json(ApiKeysResponse { items }))
+}
+
+/// ユーザーが自分自身のAPIキーを発行する
+/// キーそのものはこの応答でのみ返し、サーバーにはハッシュ値のみを保存する
+#[utoipa::path(
+    post,
+    path = "/api/v1/users/me/api-keys",
+    tag = "users",
+    request_body = CreateApiKeyRequest,
+    responses(
+        (status = 201, description = "APIキーを発行した", body = CreatedApiKeyResponse),
+        (status = 400, description = "リクエストの形式が正しくない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 401, description = "認証されていない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn create_api_key(
+    user: AuthorizedUser,
+    State(registry): State<AppRegistry>,
+    Json(req): Json<CreateApiKeyRequest>,
+) -> AppResult<(StatusCode, Json<CreatedApiKeyResponse>)> {
+    req.validate(&())?;
+
+    registry
+        .api_key_repository()
+        .create(CreateApiKeyRequestWithUserId::new(user.id(), req).into())
+        .await
+        .map(|created| (StatusCode::CREATED, Json(created.into())))
+}
+
+/// ユーザーが自分自身のAPIキーを失効させる
+#[utoipa::path(
+    delete,
+    path = "/api/v1/users/me/api-keys/{api_key_id}",
+    tag = "users",
+    params(("api_key_id" = ApiKeyId, Path, description = "APIキーのID")),
+    responses(
+        (status = 204, description = "APIキーを失効させた"),
+        (status = 400, description = "IDの形式が正しくない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 401, description = "認証されていない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "APIキーが見つからない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn delete_api_key(
+    user: AuthorizedUser,
+    Path(api_key_id): Path<ApiKeyId>,
+    State(registry): State<AppRegistry>,
+) -> AppResult<StatusCode> {
+    registry
+        .api_key_repository()
+        .delete(DeleteApiKey {
+            user_id: user.id(),
+            api_key_id,
+        })
+        .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
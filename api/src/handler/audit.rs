@@ -0,0 +1,42 @@
+// 監査ログの参照を行うエンドポイントを作成する
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use garde::Validate;
+use registry::AppRegistry;
+use shared::error::AppResult;
+
+use crate::{
+    extractor::{permission, RequirePermission},
+    model::audit::{AuditEventListQuery, PaginatedAuditEventResponse},
+};
+
+/// 監査ログを新しい順に取得する(audit.readの権限が必要)
+#[utoipa::path(
+    get,
+    path = "/api/v1/audit",
+    tag = "audit",
+    params(AuditEventListQuery),
+    responses(
+        (status = 200, description = "監査ログの一覧", body = PaginatedAuditEventResponse),
+        (status = 400, description = "クエリの形式が正しくない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 401, description = "認証されていない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "権限を持たないユーザーによる実行", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn list_audit_events(
+    _user: RequirePermission<permission::AuditRead>,
+    Query(query): Query<AuditEventListQuery>,
+    State(registry): State<AppRegistry>,
+) -> AppResult<Json<PaginatedAuditEventResponse>> {
+    query.validate(&())?;
+
+    registry
+        .audit_repository()
+        .find_all(query.into())
+        .await
+        .map(PaginatedAuditEventResponse::from)
+        .map(Json)
+}
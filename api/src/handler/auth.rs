@@ -1,34 +1,154 @@
 // loginメソッドの実装　
-use axum::{extract::State, http::StatusCode, Json};
-use kernel::model::auth::event::CreateToken;
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use garde::Validate;
+use kernel::model::{
+    auth::{
+        event::{CreateToken, VerifyUser},
+        RefreshToken,
+    },
+    mfa::event::CreateMfaChallenge,
+};
 use registry::AppRegistry;
-use shared::error::AppResult;
+use shared::{
+    error::AppResult,
+    i18n,
+};
 
 use crate::{
-    extractor::AuthorizedUser,
-    model::auth::{AccessTokenResponse, LoginRequest},
+    extractor::{AuthorizedUser, ClientInfo},
+    model::auth::{
+        AccessTokenResponse, ConfirmPasswordResetRequest, LoginRequest, MfaChallengeResponse,
+        MfaLoginRequest, PasswordResetRequest, RefreshTokenRequest,
+    },
 };
 
-use tracing::{info, error};
+use tracing::{info, error, warn};
 
+#[utoipa::path(
+    post,
+    path = "/auth/login",
+    tag = "auth",
+    request_body = LoginRequest,
+    responses(
+        (status = 200, description = "ログインに成功し、アクセストークンとリフレッシュトークンを発行した", body = AccessTokenResponse),
+        (status = 202, description = "パスワードの検証に成功した。2段階認証を有効にしているため、`/auth/login/mfa`で2段階目の認証を行う", body = MfaChallengeResponse),
+        (status = 403, description = "メールアドレスまたはパスワードが誤っている", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 429, description = "ログインの失敗が続いたため、一時的にロックされている", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
 pub async fn login(
+    client: ClientInfo,
     State(registry): State<AppRegistry>,
     Json(req): Json<LoginRequest>,
-) -> AppResult<Json<AccessTokenResponse>> {
+) -> AppResult<Response> {
     let user_id = registry
         .auth_repository()
-        .verify_user(&req.email, &req.password)
+        .verify_user(VerifyUser {
+            email: req.email,
+            password: req.password,
+            ip_address: client.ip_address.clone(),
+        })
         .await?;
-    let access_token = registry
+
+    // 2段階認証を有効にしているユーザーには、2段階目の認証に成功するまでトークンを発行しない
+    if registry.mfa_repository().is_totp_enabled(user_id).await? {
+        let challenge = registry
+            .mfa_repository()
+            .create_challenge(CreateMfaChallenge::new(
+                user_id,
+                client.user_agent,
+                client.ip_address,
+            ))
+            .await?;
+        return Ok((
+            StatusCode::ACCEPTED,
+            Json(MfaChallengeResponse::from(challenge)),
+        )
+            .into_response());
+    }
+
+    let tokens = registry
         .auth_repository()
-        .create_token(CreateToken::new(user_id))
+        .create_token(CreateToken::new(
+            user_id,
+            client.user_agent,
+            client.ip_address,
+        ))
         .await?;
-    Ok(Json(AccessTokenResponse {
-        user_id, 
-        access_token: access_token.0,
-    }))
+    Ok(Json(AccessTokenResponse::from(tokens)).into_response())
+}
+
+// パスワードの検証に続けて、認証アプリのコードまたはリカバリーコードを検証し、トークンを発行する
+#[utoipa::path(
+    post,
+    path = "/auth/login/mfa",
+    tag = "auth",
+    request_body = MfaLoginRequest,
+    responses(
+        (status = 200, description = "2段階目の認証に成功し、アクセストークンとリフレッシュトークンを発行した", body = AccessTokenResponse),
+        (status = 400, description = "リクエストの形式が正しくない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "コードが正しくない、またはチャレンジトークンが無効・期限切れ", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+pub async fn login_mfa(
+    State(registry): State<AppRegistry>,
+    Json(req): Json<MfaLoginRequest>,
+) -> AppResult<Json<AccessTokenResponse>> {
+    req.validate(&())?;
+
+    let verified = registry
+        .mfa_repository()
+        .verify_challenge(req.into())
+        .await?;
+    // 接続元はパスワードを検証した時点のものをセッションに記録する
+    let tokens = registry
+        .auth_repository()
+        .create_token(CreateToken {
+            mfa_verified: true,
+            ..CreateToken::new(verified.user_id, verified.user_agent, verified.ip_address)
+        })
+        .await?;
+    Ok(Json(tokens.into()))
+}
+
+// リフレッシュトークンを使って、アクセストークンとリフレッシュトークンを再発行する
+// 使ったリフレッシュトークンは以降使えなくなる
+#[utoipa::path(
+    post,
+    path = "/auth/refresh",
+    tag = "auth",
+    request_body = RefreshTokenRequest,
+    responses(
+        (status = 200, description = "トークンを再発行した", body = AccessTokenResponse),
+        (status = 403, description = "リフレッシュトークンが無効、期限切れ、または使用済み", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+pub async fn refresh(
+    State(registry): State<AppRegistry>,
+    Json(req): Json<RefreshTokenRequest>,
+) -> AppResult<Json<AccessTokenResponse>> {
+    registry
+        .auth_repository()
+        .refresh_token(RefreshToken(req.refresh_token))
+        .await
+        .map(|tokens| Json(tokens.into()))
 }
 
+#[utoipa::path(
+    post,
+    path = "/auth/logout",
+    tag = "auth",
+    responses(
+        (status = 204, description = "ログアウトに成功した"),
+        (status = 401, description = "認証されていない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("bearer_auth" = []))
+)]
 pub async fn logout (
     user: AuthorizedUser,
     State(registry): State<AppRegistry>,
@@ -51,4 +171,76 @@ pub async fn logout (
             }
         }
     
-}
\ No newline at end of file
+}
+
+// パスワードの再設定を受け付け、再設定用のトークンをメールで送る
+// 登録済みのメールアドレスかどうかを推測されないよう、登録の有無に関わらず同じ応答を返す
+#[utoipa::path(
+    post,
+    path = "/auth/password-reset/request",
+    tag = "auth",
+    request_body = PasswordResetRequest,
+    responses(
+        (status = 202, description = "再設定を受け付けた。メールアドレスが登録されている場合はメールを送る"),
+        (status = 400, description = "リクエストの形式が正しくない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+pub async fn request_password_reset(
+    State(registry): State<AppRegistry>,
+    Json(req): Json<PasswordResetRequest>,
+) -> AppResult<StatusCode> {
+    req.validate(&())?;
+
+    let Some(reset) = registry
+        .auth_repository()
+        .create_password_reset(req.into())
+        .await?
+    else {
+        return Ok(StatusCode::ACCEPTED);
+    };
+
+    // 送信を待つと応答時間から登録の有無が分かるため、応答を返した後に送る
+    // メールの本文はリクエストの言語で組み立てる
+    let language = i18n::current();
+    tokio::spawn(i18n::scope(language, async move {
+        if let Err(e) = registry.mailer().send_password_reset(&reset).await {
+            warn!(
+                "Failed to send password reset mail: user_id={}, error={}",
+                reset.user_id, e
+            );
+        }
+    }));
+
+    Ok(StatusCode::ACCEPTED)
+}
+
+// メールで送られたトークンを使ってパスワードを再設定する
+// 再設定前のパスワードでログインした端末を含め、すべてのセッションを無効にする
+#[utoipa::path(
+    post,
+    path = "/auth/password-reset/confirm",
+    tag = "auth",
+    request_body = ConfirmPasswordResetRequest,
+    responses(
+        (status = 204, description = "パスワードを再設定した"),
+        (status = 400, description = "リクエストの形式が正しくない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "トークンが無効、期限切れ、または使用済み", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+pub async fn confirm_password_reset(
+    State(registry): State<AppRegistry>,
+    Json(req): Json<ConfirmPasswordResetRequest>,
+) -> AppResult<StatusCode> {
+    req.validate(&())?;
+
+    let user_id = registry
+        .auth_repository()
+        .reset_password(req.into())
+        .await?;
+    registry
+        .auth_repository()
+        .delete_all_sessions(user_id)
+        .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -1,24 +1,55 @@
 use axum::{
+    body::Body,
     extract::{Path, Query, State},
-    http::StatusCode,
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
     Json,
 };
+use futures::{stream, StreamExt};
 use garde::Validate;
-use kernel::model::{book::event::DeleteBook, id::BookId};
+use kernel::model::{
+    book::{
+        event::{DeleteBook, ImportBook, UpdateBook},
+        isbn::Isbn,
+        BookImportOutcome,
+    },
+    id::BookId,
+    role::Permission,
+};
 use registry::AppRegistry;
-use shared::error::{AppError, AppResult};
+use shared::{
+    error::{AppError, AppResult},
+    i18n::{self, localize_validation_message, Message},
+};
 
 use crate::{
-    extractor::AuthorizedUser,
+    extractor::{permission, AuthorizedUser, RequirePermission},
     model::book::{
-        BookListQuery, BookResponse, CreateBookRequest, PaginatedBookResponse,
-        UpdateBookRequest, UpdateBookRequestWithIds
+        AddBookCopiesRequest, AddBookCopiesRequestWithIds, BookListQuery,
+        BookLookupQuery, BookResponse, BookSearchQuery, CreateBookRequest,
+        ImportBookCsvRow, ImportBookRowReport, ImportBooksResponse,
+        PaginatedBookResponse, UpdateBookRequest, UpdateBookRequestWithIds
     },
+    model::export::ExportQuery,
 };
 
-// 蔵書を登録するAPIを作成
+// 蔵書を登録するAPIを作成(book.createの権限が必要)
+#[utoipa::path(
+    post,
+    path = "/api/v1/books",
+    tag = "books",
+    request_body = CreateBookRequest,
+    responses(
+        (status = 201, description = "蔵書の登録に成功した"),
+        (status = 400, description = "リクエストの形式が正しくない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 401, description = "認証されていない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "権限を持たないユーザーによる実行", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "同じ所有者が同じISBNの蔵書を登録済み", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("bearer_auth" = []))
+)]
 pub async fn register_book(
-    user: AuthorizedUser,
+    user: RequirePermission<permission::BookCreate>,
     State(registry): State<AppRegistry>, // Appregistryを参照
     Json(req): Json<CreateBookRequest>,  // JSONデータから変換する構造体を指定する
 ) -> AppResult<StatusCode> {
@@ -31,12 +62,140 @@ pub async fn register_book(
         .map(|_| StatusCode::CREATED)
 }
 
+// CSVで蔵書を一括登録するAPI(book.importの権限が必要)
+// 列はtitle,author,isbn,description,owner_emailで、1行目はヘッダー行とする
+// 各行はCreateBookRequestと同じ規則で検証し、検証を通った行を1つのトランザクションで登録する
+#[utoipa::path(
+    post,
+    path = "/api/v1/books/import",
+    tag = "books",
+    request_body(content = String, content_type = "text/csv", description = "title,author,isbn,description,owner_emailのCSV"),
+    responses(
+        (status = 200, description = "行ごとの登録結果", body = ImportBooksResponse),
+        (status = 400, description = "CSVの形式が正しくない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 401, description = "認証されていない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "権限を持たないユーザーによる実行", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn import_books(
+    user: RequirePermission<permission::BookImport>,
+    State(registry): State<AppRegistry>,
+    body: String,
+) -> AppResult<Json<ImportBooksResponse>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(body.as_bytes());
+    let headers = reader
+        .headers()
+        .map_err(|e| {
+            AppError::UnprocessableEntity(Message::new("invalid_csv").arg("reason", e))
+        })?
+        .clone();
+
+    let mut reports = Vec::new();
+    let mut lines = Vec::new();
+    let mut events = Vec::new();
+    for record in reader.records() {
+        let record = match record {
+            Ok(record) => record,
+            Err(e) => {
+                let line = e.position().map(|p| p.line()).unwrap_or_default();
+                reports.push(ImportBookRowReport::rejected(line, vec![e.to_string()]));
+                continue;
+            }
+        };
+        let line = record.position().map(|p| p.line()).unwrap_or_default();
+
+        match parse_import_row(&record, &headers) {
+            Ok(event) => {
+                lines.push(line);
+                events.push(event);
+            }
+            Err(errors) => reports.push(ImportBookRowReport::rejected(line, errors)),
+        }
+    }
+
+    let outcomes = registry
+        .book_repository()
+        .create_many(events, user.id())
+        .await?;
+    reports.extend(lines.into_iter().zip(outcomes).map(|(line, outcome)| {
+        match outcome {
+            BookImportOutcome::Created(book_id) => {
+                ImportBookRowReport::accepted(line, book_id)
+            }
+            BookImportOutcome::Rejected(reason) => {
+                ImportBookRowReport::rejected(line, vec![reason])
+            }
+        }
+    }));
+    reports.sort_by_key(|r| r.line);
+
+    Ok(Json(reports.into()))
+}
+
+// CSVの1行を登録用の型に変換し、CreateBookRequestと同じ規則で検証する
+fn parse_import_row(
+    record: &csv::StringRecord,
+    headers: &csv::StringRecord,
+) -> Result<ImportBook, Vec<String>> {
+    let ImportBookCsvRow {
+        title,
+        author,
+        isbn,
+        description,
+        owner_email,
+    } = record
+        .deserialize(Some(headers))
+        .map_err(|e| vec![e.to_string()])?;
+
+    let isbn = isbn.parse::<Isbn>().map_err(|e| vec![e.to_string()])?;
+    let req = CreateBookRequest {
+        title,
+        title_kana: None,
+        author,
+        isbn,
+        description,
+        copies: 1,
+    };
+    req.validate(&()).map_err(|report| {
+        report
+            .iter()
+            .map(|(path, error)| {
+                format!(
+                    "{}: {}",
+                    path,
+                    localize_validation_message(error.message(), i18n::current())
+                )
+            })
+            .collect::<Vec<_>>()
+    })?;
+
+    Ok(ImportBook {
+        owner_email,
+        book: req.into(),
+    })
+}
+
 // リクエストが正しく受け取れた場合
 // メソッドがコールされる：引数にreqとしてリクエストデータにアクセスできるようになる
 // メソッド内の処理
 // State<AppRegistry>型のデータとしてAppRegistryの参照が引数で渡される -> トレイトメソッド越しにadapterのメソッドcreateを呼び出す(32行目)。
 
 // 蔵書の一覧を取得するAPIを作成
+#[utoipa::path(
+    get,
+    path = "/api/v1/books",
+    tag = "books",
+    params(BookListQuery),
+    responses(
+        (status = 200, description = "蔵書の一覧", body = PaginatedBookResponse),
+        (status = 400, description = "クエリの形式が正しくない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 401, description = "認証されていない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("bearer_auth" = []))
+)]
 pub async fn show_book_list(
     _user: AuthorizedUser,
     Query(query): Query<BookListQuery>,
@@ -52,7 +211,127 @@ pub async fn show_book_list(
         .map(Json) // 成功時の戻り値をJSON型で包んでいる:"Result<Vec<BookResponse>" -> "Result<Json<Vec<BookResponse>>"
 }
 
+// 表記ゆれを吸収して蔵書を検索し、関連度の高い順に返すAPI
+#[utoipa::path(
+    get,
+    path = "/api/v1/books/search",
+    tag = "books",
+    params(BookSearchQuery),
+    responses(
+        (status = 200, description = "関連度の高い順の検索結果", body = PaginatedBookResponse),
+        (status = 400, description = "クエリの形式が正しくない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 401, description = "認証されていない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn search_books(
+    _user: AuthorizedUser,
+    Query(query): Query<BookSearchQuery>,
+    State(registry): State<AppRegistry>,
+) -> AppResult<Json<PaginatedBookResponse>> {
+    query.validate(&())?;
+
+    registry
+        .book_repository()
+        .search(query.into())
+        .await
+        .map(PaginatedBookResponse::from)
+        .map(Json)
+}
+
+// ISBNから書誌情報を検索し、蔵書の登録リクエストの形で返すAPI
+#[utoipa::path(
+    get,
+    path = "/api/v1/books/lookup",
+    tag = "books",
+    params(BookLookupQuery),
+    responses(
+        (status = 200, description = "書誌情報を埋めた登録リクエスト", body = CreateBookRequest),
+        (status = 400, description = "ISBNの形式が正しくない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 401, description = "認証されていない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "書誌情報が見つからない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 502, description = "外部の書誌情報APIとの通信に失敗した", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn lookup_book(
+    _user: AuthorizedUser,
+    Query(query): Query<BookLookupQuery>,
+    State(registry): State<AppRegistry>,
+) -> AppResult<Json<CreateBookRequest>> {
+    registry
+        .book_metadata_provider()
+        .find_by_isbn(&query.isbn)
+        .await
+        .and_then(|metadata| match metadata {
+            Some(metadata) => Ok(Json(metadata.into())),
+            None => Err(AppError::EntityNotFound(
+                Message::new("book_metadata_not_found").arg("isbn", &query.isbn),
+            )),
+        })
+}
+
+// 蔵書の全件を指定の形式で書き出すAPI
+// 全件をメモリに載せず、データベースから読み出した順にレスポンスへ流す
+#[utoipa::path(
+    get,
+    path = "/api/v1/books/export",
+    tag = "books",
+    params(ExportQuery),
+    responses(
+        (status = 200, description = "蔵書の全件", content(
+            ("text/csv" = String),
+            ("application/x-ndjson" = String),
+            ("application/marcxml+xml" = String),
+        )),
+        (status = 400, description = "クエリの形式が正しくない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 401, description = "認証されていない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn export_books(
+    _user: AuthorizedUser,
+    Query(query): Query<ExportQuery>,
+    State(registry): State<AppRegistry>,
+) -> AppResult<Response> {
+    let format = query.format;
+    let header_chunk = format.header()?;
+    let footer_chunk = format.footer();
+
+    let books = registry.book_repository().export_all().await?;
+    let body = stream::once(async move { Ok(header_chunk) })
+        .chain(books.map(move |book| {
+            book.and_then(|book| format.encode(&BookResponse::from(book)))
+        }))
+        .chain(stream::once(async move { Ok(footer_chunk) }));
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, format.content_type().to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", format.file_name()),
+            ),
+        ],
+        Body::from_stream(body),
+    )
+        .into_response())
+}
+
 // idから蔵書を取得するAPI
+#[utoipa::path(
+    get,
+    path = "/api/v1/books/{book_id}",
+    tag = "books",
+    params(("book_id" = BookId, Path, description = "蔵書のID")),
+    responses(
+        (status = 200, description = "蔵書の詳細", body = BookResponse),
+        (status = 400, description = "IDの形式が正しくない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 401, description = "認証されていない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "蔵書が見つからない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("bearer_auth" = []))
+)]
 pub async fn show_book(
     _user: AuthorizedUser,
     Path(book_id): Path<BookId>, // パスパラメーター取得のため:URLのパス構成(/books/uuid)となっていて、uuidの部分をuuidとして取得することができる
@@ -64,11 +343,26 @@ pub async fn show_book(
         .await
         .and_then(|bc| match bc {
             Some(bc) => Ok(Json(bc.into())),
-            None => Err(AppError::EntityNotFound("not found".into())),
+            None => Err(AppError::EntityNotFound(
+                Message::new("book_not_found").arg("book_id", book_id),
+            )),
         })
-        .map_err(AppError::from)
 }
 
+#[utoipa::path(
+    put,
+    path = "/api/v1/books/{book_id}",
+    tag = "books",
+    params(("book_id" = BookId, Path, description = "蔵書のID")),
+    request_body = UpdateBookRequest,
+    responses(
+        (status = 200, description = "蔵書の更新に成功した"),
+        (status = 400, description = "リクエストの形式が正しくない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 401, description = "認証されていない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "所有する蔵書が見つからない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("bearer_auth" = []))
+)]
 pub async fn update_book(
     user: AuthorizedUser,
     Path(book_id): Path<BookId>,
@@ -77,15 +371,34 @@ pub async fn update_book(
 ) -> AppResult<StatusCode> {
     req.validate(&())?;
 
-    let update_book = UpdateBookRequestWithIds::new(book_id, user.id(), req);
+    let update_book = UpdateBook {
+        // book.update.anyの権限を持つ場合は、他のユーザーが所有する蔵書も更新できる
+        any_owner: user
+            .has_permission(&registry, Permission::BookUpdateAny)
+            .await?,
+        ..UpdateBookRequestWithIds::new(book_id, user.id(), req).into()
+    };
 
     registry   
         .book_repository()
-        .update(update_book.into())
+        .update(update_book)
         .await
         .map(|_| StatusCode::OK)
 }
 
+#[utoipa::path(
+    delete,
+    path = "/api/v1/books/{book_id}",
+    tag = "books",
+    params(("book_id" = BookId, Path, description = "蔵書のID")),
+    responses(
+        (status = 200, description = "蔵書の削除に成功した"),
+        (status = 400, description = "IDの形式が正しくない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 401, description = "認証されていない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "所有する蔵書が見つからない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("bearer_auth" = []))
+)]
 pub async fn delete_book(
     user: AuthorizedUser, 
     Path(book_id): Path<BookId>,
@@ -94,10 +407,46 @@ pub async fn delete_book(
     let delete_book = DeleteBook {
         book_id,
         requested_user: user.id(),
+        // book.delete.anyの権限を持つ場合は、他のユーザーが所有する蔵書も削除できる
+        any_owner: user
+            .has_permission(&registry, Permission::BookDeleteAny)
+            .await?,
     };
     registry 
         .book_repository()
         .delete(delete_book)
         .await
         .map(|_| StatusCode::OK)
-}
\ No newline at end of file
+}
+
+// 蔵書に冊子を追加するAPI
+#[utoipa::path(
+    post,
+    path = "/api/v1/books/{book_id}/copies",
+    tag = "books",
+    params(("book_id" = BookId, Path, description = "蔵書のID")),
+    request_body = AddBookCopiesRequest,
+    responses(
+        (status = 201, description = "冊子の追加に成功した"),
+        (status = 400, description = "リクエストの形式が正しくない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 401, description = "認証されていない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "所有する蔵書が見つからない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn add_book_copies(
+    user: AuthorizedUser,
+    Path(book_id): Path<BookId>,
+    State(registry): State<AppRegistry>,
+    Json(req): Json<AddBookCopiesRequest>,
+) -> AppResult<StatusCode> {
+    req.validate(&())?;
+
+    let add_copies = AddBookCopiesRequestWithIds::new(book_id, user.id(), req);
+
+    registry
+        .book_repository()
+        .add_copies(add_copies.into())
+        .await
+        .map(|_| StatusCode::CREATED)
+}
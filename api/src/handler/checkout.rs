@@ -1,25 +1,43 @@
 //　ユーザーリクエストを処理するエンドポイントを作成する
-use crate::{extractor::AuthorizedUser, model::checkout::CheckoutsResponse};
+use crate::{
+    extractor::{permission, AuthorizedUser, RequirePermission},
+    model::checkout::{CheckoutOnBehalfRequest, CheckoutsResponse},
+};
 use axum::{
     extract::{Path, State},
     http::StatusCode,
     Json,
 };
 use kernel::model::{
-    checkout::event::{CreateCheckout, UpdateReturned},
+    checkout::event::{CreateCheckout, RenewCheckout, UpdateReturned},
     id::{BookId, CheckoutId},
 };
 use registry::AppRegistry;
 use shared::error::AppResult;
 use tracing::info;
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/books/{book_id}/checkouts",
+    tag = "checkouts",
+    params(("book_id" = BookId, Path, description = "蔵書のID")),
+    responses(
+        (status = 200, description = "貸出に成功した"),
+        (status = 400, description = "IDの形式が正しくない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 401, description = "認証されていない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "蔵書が見つからない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "貸出可能な冊子がない、または貸出上限に達している", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("bearer_auth" = []))
+)]
 pub async fn checkout_book(
     user: AuthorizedUser,
     Path(book_id): Path<BookId>,// HTTPのパスパラメーターから`book_id`を取得している
     State(registry): State<AppRegistry>,
 ) -> AppResult<StatusCode> {
-    let create_checkout_history = 
-        CreateCheckout::new(book_id, user.id(), chrono::Utc::now());
+    // 返却期限は借りるユーザーの貸出ポリシーに基づいて算出される
+    let create_checkout_history =
+        CreateCheckout::new(book_id, user.id(), chrono::Utc::now(), user.id());
 
     let result = registry
         .checkout_repository()
@@ -32,6 +50,59 @@ pub async fn checkout_book(
         result
 }
 
+#[utoipa::path(
+    put,
+    path = "/api/v1/books/{book_id}/checkouts/{checkout_id}/renew",
+    tag = "checkouts",
+    params(
+        ("book_id" = BookId, Path, description = "蔵書のID"),
+        ("checkout_id" = CheckoutId, Path, description = "貸出のID"),
+    ),
+    responses(
+        (status = 200, description = "貸出の延長に成功した"),
+        (status = 400, description = "IDの形式が正しくない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 401, description = "認証されていない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "貸出が見つからない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "延長の上限に達している、または予約がある", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn renew_checkout(
+    user: AuthorizedUser,
+    Path((book_id, checkout_id)): Path<(BookId, CheckoutId)>,
+    State(registry): State<AppRegistry>,
+) -> AppResult<StatusCode> {
+    let renew_checkout =
+        RenewCheckout::new(checkout_id, book_id, user.id(), chrono::Utc::now());
+
+    let result = registry
+        .checkout_repository()
+        .renew(renew_checkout)
+        .await
+        .map(|_| StatusCode::OK);
+
+    info!("The endpoint of renew_checkout request successfully worked.");
+
+    result
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/books/{book_id}/checkouts/{checkout_id}/returned",
+    tag = "checkouts",
+    params(
+        ("book_id" = BookId, Path, description = "蔵書のID"),
+        ("checkout_id" = CheckoutId, Path, description = "貸出のID"),
+    ),
+    responses(
+        (status = 200, description = "返却に成功した"),
+        (status = 400, description = "IDの形式が正しくない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 401, description = "認証されていない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "貸出が見つからない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "借りたユーザー以外による返却", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("bearer_auth" = []))
+)]
 pub async fn return_book(
     user: AuthorizedUser,
     Path((book_id, checkout_id,)): Path<(BookId, CheckoutId)>,
@@ -42,6 +113,7 @@ pub async fn return_book(
         book_id,
         user.id(),
         chrono::Utc::now(),
+        false,
     );
 
     let result = registry
@@ -53,6 +125,98 @@ pub async fn return_book(
     result
 }
 
+// 職員が指定のユーザーに代わって蔵書を貸し出す(checkout.create.anyの権限が必要)
+// 貸出上限や返却期限は、借りるユーザーの貸出ポリシーに基づく
+#[utoipa::path(
+    post,
+    path = "/api/v1/books/{book_id}/checkouts/on-behalf",
+    tag = "checkouts",
+    params(("book_id" = BookId, Path, description = "蔵書のID")),
+    request_body = CheckoutOnBehalfRequest,
+    responses(
+        (status = 200, description = "貸出に成功した"),
+        (status = 400, description = "IDの形式が正しくない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 401, description = "認証されていない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "権限を持たないユーザーによる実行", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "蔵書またはユーザーが見つからない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "貸出可能な冊子がない、または貸出上限に達している", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn checkout_book_on_behalf(
+    user: RequirePermission<permission::CheckoutCreateAny>,
+    Path(book_id): Path<BookId>,
+    State(registry): State<AppRegistry>,
+    Json(req): Json<CheckoutOnBehalfRequest>,
+) -> AppResult<StatusCode> {
+    let create_checkout =
+        CreateCheckout::new(book_id, req.user_id, chrono::Utc::now(), user.id());
+
+    let result = registry
+        .checkout_repository()
+        .create(create_checkout)
+        .await
+        .map(|_| StatusCode::OK);
+
+    info!("The endpoint of checkout_book_on_behalf request successfully worked.");
+
+    result
+}
+
+// 借りたユーザーを問わずに返却する(checkout.return.anyの権限が必要)
+// 共用の棚に置かれたままの蔵書などを、職員が返却済みにするために使う
+#[utoipa::path(
+    put,
+    path = "/api/v1/books/{book_id}/checkouts/{checkout_id}/force-returned",
+    tag = "checkouts",
+    params(
+        ("book_id" = BookId, Path, description = "蔵書のID"),
+        ("checkout_id" = CheckoutId, Path, description = "貸出のID"),
+    ),
+    responses(
+        (status = 200, description = "返却に成功した"),
+        (status = 400, description = "IDの形式が正しくない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 401, description = "認証されていない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "権限を持たないユーザーによる実行", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "蔵書が見つからない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "指定の貸出が存在しない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn force_return_book(
+    user: RequirePermission<permission::CheckoutReturnAny>,
+    Path((book_id, checkout_id)): Path<(BookId, CheckoutId)>,
+    State(registry): State<AppRegistry>,
+) -> AppResult<StatusCode> {
+    let update_returned = UpdateReturned::new(
+        checkout_id,
+        book_id,
+        user.id(),
+        chrono::Utc::now(),
+        true,
+    );
+
+    let result = registry
+        .checkout_repository()
+        .update_returned(update_returned)
+        .await
+        .map(|_| StatusCode::OK);
+
+    info!("The endpoint of force_return_book request successfully worked.");
+
+    result
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/books/checkouts",
+    tag = "checkouts",
+    responses(
+        (status = 200, description = "未返却の貸出の一覧", body = CheckoutsResponse),
+        (status = 401, description = "認証されていない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("bearer_auth" = []))
+)]
 pub async fn show_checked_out_list(
     _user: AuthorizedUser,
     State(registry): State<AppRegistry>,
@@ -69,6 +233,46 @@ pub async fn show_checked_out_list(
     result
 }
 
+// 返却期限を過ぎた貸出の一覧を取得する(checkout.read.anyの権限が必要)
+#[utoipa::path(
+    get,
+    path = "/api/v1/books/checkouts/overdue",
+    tag = "checkouts",
+    responses(
+        (status = 200, description = "返却期限を過ぎた貸出の一覧", body = CheckoutsResponse),
+        (status = 401, description = "認証されていない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "権限を持たないユーザーによる実行", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn show_overdue_list(
+    _user: RequirePermission<permission::CheckoutReadAny>,
+    State(registry): State<AppRegistry>,
+) -> AppResult<Json<CheckoutsResponse>> {
+    let result = registry
+        .checkout_repository()
+        .find_overdue_all()
+        .await
+        .map(CheckoutsResponse::from)
+        .map(Json);
+
+    info!("The endpoint of show_overdue_list request successfully worked.");
+
+    result
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/books/{book_id}/checkout-history",
+    tag = "checkouts",
+    params(("book_id" = BookId, Path, description = "蔵書のID")),
+    responses(
+        (status = 200, description = "蔵書の貸出履歴", body = CheckoutsResponse),
+        (status = 400, description = "IDの形式が正しくない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 401, description = "認証されていない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("bearer_auth" = []))
+)]
 pub async fn checkout_history(
     _user: AuthorizedUser,
     Path(book_id): Path<BookId>,
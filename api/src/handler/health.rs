@@ -1,11 +1,26 @@
 use axum::{extract::State, http::StatusCode};
 use registry::AppRegistry;
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/health",
+    tag = "health",
+    responses((status = 200, description = "サーバーが起動している"))
+)]
 pub async fn health_check() -> StatusCode {
     StatusCode::OK
 }
 
 // 1. `State`に登録されている`AppRegistry`を取り出す
+#[utoipa::path(
+    get,
+    path = "/api/v1/health/db",
+    tag = "health",
+    responses(
+        (status = 200, description = "データベースに接続できる"),
+        (status = 500, description = "データベースに接続できない"),
+    )
+)]
 pub async fn health_check_db(State(registry): State<AppRegistry>) -> StatusCode {
     // 2. health_check_repositoryメソッドを経由してリポジトリの処理を呼び出す
     if registry.health_check_repository().check_db().await {
@@ -0,0 +1,31 @@
+use axum::{extract::State, http::header, response::IntoResponse};
+use registry::AppRegistry;
+use shared::{
+    error::AppResult,
+    metrics::METRICS_CONTENT_TYPE,
+};
+
+// Prometheusが収集するメトリクスを返す
+// 貸出件数やコネクションプールの状態は、取得のたびに現在値へ更新する
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    tag = "metrics",
+    responses(
+        (status = 200, description = "Prometheusのテキスト形式のメトリクス", content_type = "text/plain", body = String),
+        (status = 500, description = "メトリクスを集計できない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+pub async fn render_metrics(State(registry): State<AppRegistry>) -> AppResult<impl IntoResponse> {
+    let snapshot = registry.metrics_repository().snapshot().await?;
+
+    let metrics = registry.metrics();
+    metrics.set_db_pool_connections(
+        snapshot.db_pool.size,
+        snapshot.db_pool.idle,
+        snapshot.db_pool.max,
+    );
+    metrics.set_checkouts(snapshot.active_checkouts, snapshot.overdue_checkouts);
+
+    Ok(([(header::CONTENT_TYPE, METRICS_CONTENT_TYPE)], metrics.render()?))
+}
@@ -0,0 +1,88 @@
+use axum::{extract::State, http::StatusCode, Json};
+use garde::Validate;
+use kernel::model::mfa::event::EnrollTotp;
+use registry::AppRegistry;
+use shared::error::AppResult;
+
+use crate::{
+    extractor::AuthorizedUser,
+    model::mfa::{TotpEnrollmentResponse, VerifyTotpRequest, VerifyTotpRequestWithUserId},
+};
+
+/// ユーザーが自分自身のTOTPの登録を開始する
+/// 返したコードを`/users/me/mfa/totp/confirm`で検証するまでは、ログインに2段階目の認証は求めない
+#[utoipa::path(
+    post,
+    path = "/api/v1/users/me/mfa/totp",
+    tag = "users",
+    responses(
+        (status = 201, description = "登録を開始した。秘密鍵とリカバリーコードはこの応答でのみ返す", body = TotpEnrollmentResponse),
+        (status = 401, description = "認証されていない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "2段階認証はすでに有効になっている", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn enroll_totp(
+    user: AuthorizedUser,
+    State(registry): State<AppRegistry>,
+) -> AppResult<(StatusCode, Json<TotpEnrollmentResponse>)> {
+    registry
+        .mfa_repository()
+        .enroll_totp(EnrollTotp::new(user.id(), user.user.email.clone()))
+        .await
+        .map(|enrollment| (StatusCode::CREATED, Json(enrollment.into())))
+}
+
+/// ユーザーが認証アプリに表示されたコードを送り、TOTPの登録を完了する
+#[utoipa::path(
+    post,
+    path = "/api/v1/users/me/mfa/totp/confirm",
+    tag = "users",
+    request_body = VerifyTotpRequest,
+    responses(
+        (status = 204, description = "2段階認証を有効にした"),
+        (status = 401, description = "認証されていない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "コードが正しくない、または登録が開始されていない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn confirm_totp(
+    user: AuthorizedUser,
+    State(registry): State<AppRegistry>,
+    Json(req): Json<VerifyTotpRequest>,
+) -> AppResult<StatusCode> {
+    req.validate(&())?;
+
+    registry
+        .mfa_repository()
+        .confirm_totp(VerifyTotpRequestWithUserId::new(user.id(), req).into())
+        .await
+        .map(|_| StatusCode::NO_CONTENT)
+}
+
+/// ユーザーが自分自身の2段階認証を解除する
+#[utoipa::path(
+    delete,
+    path = "/api/v1/users/me/mfa/totp",
+    tag = "users",
+    request_body = VerifyTotpRequest,
+    responses(
+        (status = 204, description = "2段階認証を解除した"),
+        (status = 401, description = "認証されていない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "コードが正しくない、または2段階認証が有効になっていない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn disable_totp(
+    user: AuthorizedUser,
+    State(registry): State<AppRegistry>,
+    Json(req): Json<VerifyTotpRequest>,
+) -> AppResult<StatusCode> {
+    req.validate(&())?;
+
+    registry
+        .mfa_repository()
+        .disable_totp(VerifyTotpRequestWithUserId::new(user.id(), req).into())
+        .await
+        .map(|_| StatusCode::NO_CONTENT)
+}
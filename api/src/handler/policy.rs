@@ -0,0 +1,74 @@
+// 貸出ポリシーの参照・変更を行うエンドポイントを作成する
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use garde::Validate;
+use registry::AppRegistry;
+use shared::error::AppResult;
+
+use crate::{
+    extractor::{permission, AuthorizedUser, RequirePermission},
+    model::{
+        policy::{
+            BorrowingPoliciesResponse, UpdateBorrowingPolicyRequest,
+            UpdateBorrowingPolicyRequestWithIds,
+        },
+        user::RoleName,
+    },
+};
+
+/// ロールごとの貸出ポリシーの一覧を取得する
+#[utoipa::path(
+    get,
+    path = "/api/v1/policies",
+    tag = "policies",
+    responses(
+        (status = 200, description = "ロールごとの貸出ポリシー", body = BorrowingPoliciesResponse),
+        (status = 401, description = "認証されていない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn list_policies(
+    _user: AuthorizedUser,
+    State(registry): State<AppRegistry>,
+) -> AppResult<Json<BorrowingPoliciesResponse>> {
+    registry
+        .policy_repository()
+        .find_all()
+        .await
+        .map(BorrowingPoliciesResponse::from)
+        .map(Json)
+}
+
+/// ロールの貸出ポリシーを変更する(policy.manageの権限が必要)
+#[utoipa::path(
+    put,
+    path = "/api/v1/policies/{role}",
+    tag = "policies",
+    params(("role" = RoleName, Path, description = "ロール名")),
+    request_body = UpdateBorrowingPolicyRequest,
+    responses(
+        (status = 200, description = "貸出ポリシーの変更に成功した"),
+        (status = 400, description = "リクエストの形式が正しくない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 401, description = "認証されていない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "権限を持たないユーザーによる実行", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn update_policy(
+    user: RequirePermission<permission::PolicyManage>,
+    Path(role): Path<RoleName>,
+    State(registry): State<AppRegistry>,
+    Json(req): Json<UpdateBorrowingPolicyRequest>,
+) -> AppResult<StatusCode> {
+    req.validate(&())?;
+
+    registry
+        .policy_repository()
+        .update(UpdateBorrowingPolicyRequestWithIds::new(role, user.id(), req).into())
+        .await?;
+
+    Ok(StatusCode::OK)
+}
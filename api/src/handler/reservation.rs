@@ -0,0 +1,108 @@
+// 蔵書の予約に関するリクエストを処理するエンドポイントを作成する
+use crate::{extractor::AuthorizedUser, model::reservation::ReservationsResponse};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use kernel::model::{
+    id::BookId,
+    reservation::event::{CreateReservation, DeleteReservation},
+};
+use registry::AppRegistry;
+use shared::error::AppResult;
+use tracing::info;
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/books/{book_id}/reservations",
+    tag = "reservations",
+    params(("book_id" = BookId, Path, description = "蔵書のID")),
+    responses(
+        (status = 201, description = "予約に成功した"),
+        (status = 400, description = "IDの形式が正しくない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 401, description = "認証されていない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "蔵書が見つからない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "予約済み、または貸出可能な冊子がある", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn reserve_book(
+    user: AuthorizedUser,
+    Path(book_id): Path<BookId>,
+    State(registry): State<AppRegistry>,
+) -> AppResult<StatusCode> {
+    let create_reservation =
+        CreateReservation::new(book_id, user.id(), chrono::Utc::now());
+
+    let result = registry
+        .reservation_repository()
+        .create(create_reservation)
+        .await
+        .map(|_| StatusCode::CREATED);
+
+    info!("The endpoint of reserve_book request successfully worked.");
+
+    result
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/books/{book_id}/reservations",
+    tag = "reservations",
+    params(("book_id" = BookId, Path, description = "蔵書のID")),
+    responses(
+        (status = 204, description = "予約の取り消しに成功した"),
+        (status = 400, description = "IDの形式が正しくない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 401, description = "認証されていない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "予約が見つからない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn cancel_reservation(
+    user: AuthorizedUser,
+    Path(book_id): Path<BookId>,
+    State(registry): State<AppRegistry>,
+) -> AppResult<StatusCode> {
+    let delete_reservation =
+        DeleteReservation::new(book_id, user.id(), chrono::Utc::now());
+
+    let result = registry
+        .reservation_repository()
+        .delete(delete_reservation)
+        .await
+        .map(|_| StatusCode::NO_CONTENT);
+
+    info!("The endpoint of cancel_reservation request successfully worked.");
+
+    result
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/books/{book_id}/reservations",
+    tag = "reservations",
+    params(("book_id" = BookId, Path, description = "蔵書のID")),
+    responses(
+        (status = 200, description = "予約の一覧(予約順)", body = ReservationsResponse),
+        (status = 400, description = "IDの形式が正しくない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 401, description = "認証されていない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn show_reservation_list(
+    _user: AuthorizedUser,
+    Path(book_id): Path<BookId>,
+    State(registry): State<AppRegistry>,
+) -> AppResult<Json<ReservationsResponse>> {
+    let result = registry
+        .reservation_repository()
+        .find_by_book_id(book_id)
+        .await
+        .map(ReservationsResponse::from)
+        .map(Json);
+
+    info!("The endpoint of show_reservation_list request successfully worked.");
+
+    result
+}
@@ -0,0 +1,136 @@
+// ロールと権限の参照・変更を行うエンドポイントを作成する
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use garde::Validate;
+use kernel::model::role::{event::DeleteRole, Role};
+use registry::AppRegistry;
+use shared::error::AppResult;
+
+use crate::{
+    extractor::{permission, RequirePermission},
+    model::{
+        role::{
+            CreateRoleRequest, CreateRoleRequestWithUserId, RolesResponse, UpdateRolePermissionsRequest,
+            UpdateRolePermissionsRequestWithIds,
+        },
+        user::RoleName,
+    },
+};
+
+/// ロールと、付与された権限の一覧を取得する(role.manageの権限が必要)
+#[utoipa::path(
+    get,
+    path = "/api/v1/roles",
+    tag = "roles",
+    responses(
+        (status = 200, description = "ロールと権限の一覧", body = RolesResponse),
+        (status = 401, description = "認証されていない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "権限を持たないユーザーによる実行", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn list_roles(
+    _user: RequirePermission<permission::RoleManage>,
+    State(registry): State<AppRegistry>,
+) -> AppResult<Json<RolesResponse>> {
+    registry
+        .role_repository()
+        .find_all()
+        .await
+        .map(RolesResponse::from)
+        .map(Json)
+}
+
+/// 権限を付与したロールを作成する(role.manageの権限が必要)
+#[utoipa::path(
+    post,
+    path = "/api/v1/roles",
+    tag = "roles",
+    request_body = CreateRoleRequest,
+    responses(
+        (status = 201, description = "ロールを作成した"),
+        (status = 400, description = "リクエストの形式が正しくない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 401, description = "認証されていない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "権限を持たないユーザーによる実行", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "同じ名前のロールがすでに存在する", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn create_role(
+    user: RequirePermission<permission::RoleManage>,
+    State(registry): State<AppRegistry>,
+    Json(req): Json<CreateRoleRequest>,
+) -> AppResult<StatusCode> {
+    req.validate(&())?;
+
+    registry
+        .role_repository()
+        .create(CreateRoleRequestWithUserId::new(user.id(), req).into())
+        .await
+        .map(|_| StatusCode::CREATED)
+}
+
+/// ロールに付与する権限を変更する(role.manageの権限が必要)
+#[utoipa::path(
+    put,
+    path = "/api/v1/roles/{role}",
+    tag = "roles",
+    params(("role" = RoleName, Path, description = "ロール名")),
+    request_body = UpdateRolePermissionsRequest,
+    responses(
+        (status = 200, description = "権限の変更に成功した"),
+        (status = 400, description = "リクエストの形式が正しくない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 401, description = "認証されていない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "権限を持たないユーザーによる実行", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "ロールが見つからない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "組み込みのロールは変更できない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn update_role_permissions(
+    user: RequirePermission<permission::RoleManage>,
+    Path(role): Path<RoleName>,
+    State(registry): State<AppRegistry>,
+    Json(req): Json<UpdateRolePermissionsRequest>,
+) -> AppResult<StatusCode> {
+    registry
+        .role_repository()
+        .update_permissions(UpdateRolePermissionsRequestWithIds::new(role, user.id(), req).into())
+        .await
+        .map(|_| StatusCode::OK)
+}
+
+/// ロールを削除する(role.manageの権限が必要)
+/// ユーザーが割り当てられているロールは削除できない
+#[utoipa::path(
+    delete,
+    path = "/api/v1/roles/{role}",
+    tag = "roles",
+    params(("role" = RoleName, Path, description = "ロール名")),
+    responses(
+        (status = 204, description = "ロールを削除した"),
+        (status = 401, description = "認証されていない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "権限を持たないユーザーによる実行", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "ロールが見つからない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "組み込みのロール、またはユーザーが割り当てられているロール", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn delete_role(
+    user: RequirePermission<permission::RoleManage>,
+    Path(role): Path<RoleName>,
+    State(registry): State<AppRegistry>,
+) -> AppResult<StatusCode> {
+    registry
+        .role_repository()
+        .delete(DeleteRole {
+            role: Role::from(role),
+            requested_user: user.id(),
+        })
+        .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -1,8 +1,6 @@
 // トレイトのメソッドを実行して結果を受け取る
 // リクエストを受け取る関数
 
-use std::result;
-
 use axum::{
     extract::{Path, State},
     http::StatusCode,
@@ -10,42 +8,67 @@ use axum::{
 };
 
 use garde::Validate;
-use kernel::model::{id::UserId, user::event::DeleteUser};
+use kernel::model::{
+    id::{SessionId, UserId},
+    user::event::DeleteUser,
+};
 use registry::AppRegistry;
-use shared::error::{AppError, AppResult};
+use shared::error::AppResult;
 
 use crate::{
-    extractor::AuthorizedUser,
+    extractor::{permission, AuthorizedUser, RequirePermission},
     model::user::{
-        CreateUserRequest, UpdateUserPasswordRequest,
+        CreateUserRequest, CreateUserRequestWithUserId, UpdateUserPasswordRequest,
         UpdateUserPasswordRequestWithUserId, UpdateUserRoleRequest,
-        UpdateuserRoleRequestWithUserId, UserResponse, UsersResponse
+        UpdateUserRoleRequestWithIds, UserResponse, UsersResponse
     },
     model::checkout::CheckoutsResponse,
+    model::auth::{SessionResponse, SessionsResponse},
 };
 
 use tracing::info;
 
-//ユーザーを追加する　(Admin only)
+//ユーザーを追加する　(user.manageの権限が必要)
+#[utoipa::path(
+    post,
+    path = "/api/v1/users",
+    tag = "users",
+    request_body = CreateUserRequest,
+    responses(
+        (status = 200, description = "登録したユーザー", body = UserResponse),
+        (status = 400, description = "リクエストの形式が正しくない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 401, description = "認証されていない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "権限を持たないユーザーによる実行", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("bearer_auth" = []))
+)]
 pub async fn register_user (
-    user: AuthorizedUser,
+    user: RequirePermission<permission::UserManage>,
     State(registry): State<AppRegistry>,
     Json(req): Json<CreateUserRequest>,
 ) -> AppResult<Json<UserResponse>>{
-    // AuthorizedUserの権限がAdminの時のみ実行可能とする
-    if !user.is_admin(){
-        return Err(AppError::ForbiddenOperation);
-    }
-
     req.validate(&())?;
 
     let registered_user = 
-        registry.user_repository().create(req.into()).await?;
+        registry
+            .user_repository()
+            .create(CreateUserRequestWithUserId::new(user.id(), req).into())
+            .await?;
     Ok(Json(registered_user.into()))
     
 }
 
 /// ユーザーの一覧を取得する
+#[utoipa::path(
+    get,
+    path = "/api/v1/users",
+    tag = "users",
+    responses(
+        (status = 200, description = "ユーザーの一覧", body = UsersResponse),
+        (status = 401, description = "認証されていない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("bearer_auth" = []))
+)]
 pub async fn list_users(
     _user: AuthorizedUser,
     State(registry): State<AppRegistry>,
@@ -61,51 +84,133 @@ pub async fn list_users(
     Ok(Json(UsersResponse { items }))
 }
 
-/// ユーザーを削除する(Admin Only)
+/// ユーザーを削除する(user.manageの権限が必要)
+#[utoipa::path(
+    delete,
+    path = "/api/v1/users/{user_id}",
+    tag = "users",
+    params(("user_id" = UserId, Path, description = "ユーザーのID")),
+    responses(
+        (status = 200, description = "ユーザーの削除に成功した"),
+        (status = 400, description = "IDの形式が正しくない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 401, description = "認証されていない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "権限を持たないユーザーによる実行", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "ユーザーが見つからない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("bearer_auth" = []))
+)]
 pub async fn delete_user(
-    user: AuthorizedUser,
+    user: RequirePermission<permission::UserManage>,
     Path(user_id): Path<UserId>,
     State(registry): State<AppRegistry>,
 ) -> AppResult<StatusCode> {
-    // AuthorizedUserの権限がAdminの時のみ実行可能
-    if !user.is_admin() {
-        return Err(AppError::ForbiddenOperation);
-    }
-
     registry 
         .user_repository()
-        .delete(DeleteUser { user_id })
+        .delete(DeleteUser {
+            user_id,
+            requested_user: user.id(),
+        })
+        .await?;
+
+    // 削除したユーザーのトークンが使われ続けないよう、すべてのセッションを無効にする
+    registry
+        .auth_repository()
+        .delete_all_sessions(user_id)
         .await?;
 
     Ok(StatusCode::OK)
 }
 
-/// ユーザーのロールを変更する
+/// ユーザーのロールを変更する(role.manageの権限が必要)
+#[utoipa::path(
+    put,
+    path = "/api/v1/users/{user_id}/role",
+    tag = "users",
+    params(("user_id" = UserId, Path, description = "ユーザーのID")),
+    request_body = UpdateUserRoleRequest,
+    responses(
+        (status = 200, description = "ロールの変更に成功した"),
+        (status = 400, description = "リクエストの形式が正しくない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 401, description = "認証されていない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "権限を持たないユーザーによる実行", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "ユーザーが見つからない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("bearer_auth" = []))
+)]
 pub async fn change_role(
-    user: AuthorizedUser,
+    user: RequirePermission<permission::RoleManage>,
     Path(user_id): Path<UserId>,
     State(registry): State<AppRegistry>,
     Json(req): Json<UpdateUserRoleRequest>,
 ) -> AppResult<StatusCode> {
-    // AuthorizedUserの権限がAdminのときのみ実行可能とする
-    if !user.is_admin() {
-        return Err(AppError::ForbiddenOperation);
-    }
-
     registry
         .user_repository()
-        .delete(DeleteUser { user_id })
+        .update_role(UpdateUserRoleRequestWithIds::new(user_id, user.id(), req).into())
+        .await?;
+
+    // 変更前のロールで発行したトークンは使えなくし、ログインし直してもらう
+    registry
+        .auth_repository()
+        .delete_all_sessions(user_id)
         .await?;
 
     Ok(StatusCode::OK)
 }
 
+/// ログインの失敗によるユーザーのロックを解除する(user.manageの権限が必要)
+#[utoipa::path(
+    delete,
+    path = "/api/v1/users/{user_id}/lockout",
+    tag = "users",
+    params(("user_id" = UserId, Path, description = "ユーザーのID")),
+    responses(
+        (status = 204, description = "ロックを解除した"),
+        (status = 400, description = "IDの形式が正しくない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 401, description = "認証されていない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "権限を持たないユーザーによる実行", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "ユーザーが見つからない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn unlock_user(
+    _user: RequirePermission<permission::UserManage>,
+    Path(user_id): Path<UserId>,
+    State(registry): State<AppRegistry>,
+) -> AppResult<StatusCode> {
+    registry.auth_repository().unlock_user(user_id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
 /// ユーザーが自分自身のユーザー情報を取得する
+#[utoipa::path(
+    get,
+    path = "/api/v1/users/me",
+    tag = "users",
+    responses(
+        (status = 200, description = "ログイン中のユーザー", body = UserResponse),
+        (status = 401, description = "認証されていない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("bearer_auth" = []))
+)]
 pub async fn get_current_user(user: AuthorizedUser) -> Json<UserResponse> {
     Json(UserResponse::from(user.user))
 }
 
 /// ユーザーが自分自身のパスワードを変更する　
+#[utoipa::path(
+    put,
+    path = "/api/v1/users/me/password",
+    tag = "users",
+    request_body = UpdateUserPasswordRequest,
+    responses(
+        (status = 200, description = "パスワードの変更に成功した"),
+        (status = 400, description = "リクエストの形式が正しくない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 401, description = "認証されていない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "現在のパスワードが誤っている", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("bearer_auth" = []))
+)]
 pub async fn change_password(
     user: AuthorizedUser,
     State(registry): State<AppRegistry>,
@@ -120,10 +225,79 @@ pub async fn change_password(
         )
         .await?;
 
+    // 変更前のパスワードでログインした端末を含め、すべてのセッションを無効にする
+    registry
+        .auth_repository()
+        .delete_all_sessions(user.id())
+        .await?;
+
     Ok(StatusCode::OK)
 }
 
+/// ユーザーが自分自身のログイン中のセッションの一覧を取得する
+#[utoipa::path(
+    get,
+    path = "/api/v1/users/me/sessions",
+    tag = "users",
+    responses(
+        (status = 200, description = "ログイン中のセッションの一覧(新しい順)", body = SessionsResponse),
+        (status = 401, description = "認証されていない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn list_sessions(
+    user: AuthorizedUser,
+    State(registry): State<AppRegistry>,
+) -> AppResult<Json<SessionsResponse>> {
+    let items = registry
+        .auth_repository()
+        .find_sessions(user.id(), &user.access_token)
+        .await?
+        .into_iter()
+        .map(SessionResponse::from)
+        .collect();
+
+    Ok(Json(SessionsResponse { items }))
+}
+
+/// ユーザーが自分自身のセッションを無効にする(他の端末からのログアウト)
+#[utoipa::path(
+    delete,
+    path = "/api/v1/users/me/sessions/{session_id}",
+    tag = "users",
+    params(("session_id" = SessionId, Path, description = "セッションのID")),
+    responses(
+        (status = 204, description = "セッションを無効にした"),
+        (status = 400, description = "IDの形式が正しくない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 401, description = "認証されていない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "セッションが見つからない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn revoke_session(
+    user: AuthorizedUser,
+    Path(session_id): Path<SessionId>,
+    State(registry): State<AppRegistry>,
+) -> AppResult<StatusCode> {
+    registry
+        .auth_repository()
+        .delete_session(user.id(), session_id)
+        .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
 // ユーザーが自身の借りている書籍の一覧を取得する　
+#[utoipa::path(
+    get,
+    path = "/api/v1/users/me/checkouts",
+    tag = "users",
+    responses(
+        (status = 200, description = "借りている蔵書の一覧", body = CheckoutsResponse),
+        (status = 401, description = "認証されていない", body = shared::error::ProblemDetails, content_type = "application/problem+json"),
+    ),
+    security(("bearer_auth" = []))
+)]
 pub async fn get_checkouts(
     user: AuthorizedUser,
     State(registry): State<AppRegistry>,
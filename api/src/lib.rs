@@ -1,4 +1,6 @@
 pub mod handler;
 pub mod model;
 pub mod route;
-pub mod extractor;
\ No newline at end of file
+pub mod extractor;
+pub mod openapi;
+pub mod middleware;
@@ -0,0 +1,89 @@
+// handlerの前後に処理を挟むミドルウェアを定義する
+use std::time::Instant;
+
+use axum::{
+    extract::{MatchedPath, Request, State},
+    http::{header, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use registry::AppRegistry;
+use shared::{
+    i18n::{self, Language},
+    request_id::{self, REQUEST_ID_HEADER},
+};
+
+// リクエストIDの最大長、これを超える値をクライアントから受け取った場合は振り直す
+const MAX_REQUEST_ID_LENGTH: usize = 128;
+
+// リクエストごとにIDを振り、処理中はエラーレスポンスやログから参照できるようにする
+// クライアントが`x-request-id`ヘッダーを送った場合はその値を引き継ぎ、レスポンスのヘッダーにも付与する
+pub async fn assign_request_id(mut request: Request, next: Next) -> Response {
+    let request_id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| !value.is_empty() && value.len() <= MAX_REQUEST_ID_LENGTH)
+        .map(ToOwned::to_owned)
+        .unwrap_or_else(request_id::generate);
+
+    let header_value = HeaderValue::from_str(&request_id).ok();
+    if let Some(value) = &header_value {
+        request.headers_mut().insert(REQUEST_ID_HEADER, value.clone());
+    }
+
+    let mut response = request_id::scope(request_id, next.run(request)).await;
+
+    if let Some(value) = header_value {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    response
+}
+
+// `Accept-Language`ヘッダーからエラーメッセージの言語を決め、処理中はその言語で組み立てる
+// 対応していない言語やヘッダーがない場合は、設定された既定の言語を使う
+pub async fn negotiate_language(
+    State(default_language): State<Language>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let language = request
+        .headers()
+        .get(header::ACCEPT_LANGUAGE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(Language::from_accept_language)
+        .unwrap_or(default_language);
+
+    let mut response = i18n::scope(language, next.run(request)).await;
+
+    response.headers_mut().insert(
+        header::CONTENT_LANGUAGE,
+        HeaderValue::from_static(language.into()),
+    );
+    response
+}
+
+// HTTPリクエストの処理時間を、メソッド・ルート・ステータスコードごとに記録する
+// ルートにはパスパラメーターを埋め込む前のテンプレートを使い、ラベルの種類が増えすぎないようにする
+pub async fn track_http_metrics(
+    State(registry): State<AppRegistry>,
+    matched_path: Option<MatchedPath>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let start = Instant::now();
+    let method = request.method().to_string();
+    let route = matched_path
+        .map(|path| path.as_str().to_owned())
+        .unwrap_or_else(|| "unmatched".into());
+
+    let response = next.run(request).await;
+
+    registry.metrics().observe_http_request(
+        &method,
+        &route,
+        response.status().as_u16(),
+        start.elapsed(),
+    );
+    response
+}
@@ -0,0 +1,110 @@
+// APIキーAPIの入出力の定義
+use chrono::{DateTime, Utc};
+use derive_new::new;
+use garde::Validate;
+use kernel::model::{
+    api_key::{event::CreateApiKey, ApiKey, ApiKeyScope, CreatedApiKey},
+    id::{ApiKeyId, UserId},
+};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Deserialize, Validate, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateApiKeyRequest {
+    // キーの用途を識別するための名前(例: ci-bot)
+    #[garde(length(min = 1, max = 100))]
+    name: String,
+    #[garde(length(min = 1))]
+    scopes: Vec<ApiKeyScope>,
+    // 省略した場合は、失効させるまで使える
+    #[garde(custom(is_in_future))]
+    expires_at: Option<DateTime<Utc>>,
+}
+
+fn is_in_future(value: &Option<DateTime<Utc>>, _: &()) -> garde::Result {
+    match value {
+        Some(expires_at) if *expires_at <= Utc::now() => {
+            Err(garde::Error::new("must be in the future"))
+        }
+        _ => Ok(()),
+    }
+}
+
+#[derive(new)]
+pub struct CreateApiKeyRequestWithUserId(UserId, CreateApiKeyRequest);
+
+impl From<CreateApiKeyRequestWithUserId> for CreateApiKey {
+    fn from(value: CreateApiKeyRequestWithUserId) -> Self {
+        let CreateApiKeyRequestWithUserId(
+            user_id,
+            CreateApiKeyRequest {
+                name,
+                scopes,
+                expires_at,
+            },
+        ) = value;
+        CreateApiKey::new(user_id, name, scopes, expires_at)
+    }
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiKeysResponse {
+    pub items: Vec<ApiKeyResponse>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiKeyResponse {
+    pub id: ApiKeyId,
+    pub name: String,
+    // キーの先頭の数文字。キーそのものは発行時にのみ返す
+    pub key_prefix: String,
+    pub scopes: Vec<ApiKeyScope>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<ApiKey> for ApiKeyResponse {
+    fn from(value: ApiKey) -> Self {
+        let ApiKey {
+            id,
+            name,
+            key_prefix,
+            scopes,
+            expires_at,
+            last_used_at,
+            created_at,
+        } = value;
+        Self {
+            id,
+            name,
+            key_prefix,
+            scopes,
+            expires_at,
+            last_used_at,
+            created_at,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CreatedApiKeyResponse {
+    #[serde(flatten)]
+    pub api_key: ApiKeyResponse,
+    // 一度しか表示しないため、利用者に控えてもらう
+    pub key: String,
+}
+
+impl From<CreatedApiKey> for CreatedApiKeyResponse {
+    fn from(value: CreatedApiKey) -> Self {
+        let CreatedApiKey { api_key, key } = value;
+        Self {
+            api_key: api_key.into(),
+            key,
+        }
+    }
+}
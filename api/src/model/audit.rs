@@ -0,0 +1,132 @@
+// 監査ログの絞り込み条件の受け取りと、監査ログをクライアントにJSONで返すための構造の定義を行う
+
+use chrono::{DateTime, Utc};
+use garde::Validate;
+use kernel::model::{
+    audit::{AuditAction, AuditEvent, AuditEventListOptions},
+    id::{AuditEventId, UserId},
+    list::PaginatedList,
+};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+// 監査ログの絞り込み条件をクエリで受け取るための型
+// 期間はfrom以上to未満で絞り込む
+#[derive(Debug, Deserialize, Validate, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct AuditEventListQuery {
+    #[garde(range(min = 0))]
+    #[serde(default = "default_limit")]
+    pub limit: i64,
+    #[garde(range(min = 0))]
+    #[serde(default)]
+    pub offset: i64,
+    #[garde(skip)]
+    pub actor: Option<UserId>,
+    #[garde(skip)]
+    pub action: Option<AuditAction>,
+    #[garde(length(min = 1))]
+    pub target_id: Option<String>,
+    #[garde(skip)]
+    pub from: Option<DateTime<Utc>>,
+    #[garde(skip)]
+    pub to: Option<DateTime<Utc>>,
+}
+
+const DEFAULT_LIMIT: i64 = 20;
+const fn default_limit() -> i64 {
+    DEFAULT_LIMIT
+}
+
+impl From<AuditEventListQuery> for AuditEventListOptions {
+    fn from(value: AuditEventListQuery) -> Self {
+        let AuditEventListQuery {
+            limit,
+            offset,
+            actor,
+            action,
+            target_id,
+            from,
+            to,
+        } = value;
+        Self {
+            limit,
+            offset,
+            actor,
+            action,
+            target_id,
+            from,
+            to,
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PaginatedAuditEventResponse {
+    pub total: i64,
+    pub limit: i64,
+    pub offset: i64,
+    pub items: Vec<AuditEventResponse>,
+}
+
+impl From<PaginatedList<AuditEvent>> for PaginatedAuditEventResponse {
+    fn from(value: PaginatedList<AuditEvent>) -> Self {
+        let PaginatedList {
+            total,
+            limit,
+            offset,
+            items,
+        } = value;
+
+        Self {
+            total,
+            limit,
+            offset,
+            items: items.into_iter().map(AuditEventResponse::from).collect(),
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEventResponse {
+    pub id: AuditEventId,
+    pub actor: UserId,
+    pub action: AuditAction,
+    pub target_id: String,
+    // 操作の前後の対象の状態。作成の場合はbeforeが、削除の場合はafterが空となる
+    #[schema(value_type = Option<Object>)]
+    pub before: Option<serde_json::Value>,
+    #[schema(value_type = Option<Object>)]
+    pub after: Option<serde_json::Value>,
+    pub request_id: Option<String>,
+    pub occurred_at: DateTime<Utc>,
+}
+
+impl From<AuditEvent> for AuditEventResponse {
+    fn from(value: AuditEvent) -> Self {
+        let AuditEvent {
+            id,
+            actor,
+            action,
+            target_id,
+            before,
+            after,
+            request_id,
+            occurred_at,
+        } = value;
+
+        Self {
+            id,
+            actor,
+            action,
+            target_id,
+            before,
+            after,
+            request_id,
+            occurred_at,
+        }
+    }
+}
@@ -1,17 +1,168 @@
 // ログインAPIの入出力の定義　
-use kernel::model::id::UserId;
+use chrono::{DateTime, Utc};
+use garde::Validate;
+use kernel::model::{
+    auth::{
+        event::{CreatePasswordReset, ResetPassword},
+        AuthTokens, Session,
+    },
+    id::{SessionId, UserId},
+    mfa::{event::VerifyMfaChallenge, MfaChallenge},
+};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct LoginRequest{
     pub email: String,
     pub password: String,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct AccessTokenResponse {
     pub user_id: UserId,
     pub access_token: String,
+    // アクセストークンの期限が切れた後に`/auth/refresh`で使う。再発行のたびに新しい値に変わる
+    pub refresh_token: String,
+}
+
+impl From<AuthTokens> for AccessTokenResponse {
+    fn from(value: AuthTokens) -> Self {
+        let AuthTokens {
+            user_id,
+            access_token,
+            refresh_token,
+        } = value;
+        Self {
+            user_id,
+            access_token: access_token.0,
+            refresh_token: refresh_token.0,
+        }
+    }
+}
+
+// 2段階認証を有効にしているユーザーのログインでは、トークンの代わりに返す
+// `/auth/login/mfa`に認証アプリのコードと合わせて送ると、トークンを発行する
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct MfaChallengeResponse {
+    pub challenge_token: String,
+    // 有効期間(秒)
+    pub expires_in: u64,
+}
+
+impl From<MfaChallenge> for MfaChallengeResponse {
+    fn from(value: MfaChallenge) -> Self {
+        let MfaChallenge {
+            challenge_token,
+            expires_in,
+        } = value;
+        Self {
+            challenge_token,
+            expires_in,
+        }
+    }
+}
+
+#[derive(Deserialize, Validate, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct MfaLoginRequest {
+    #[garde(length(min = 1))]
+    pub challenge_token: String,
+    // 認証アプリに表示されたコード、または未使用のリカバリーコード
+    #[garde(length(min = 1))]
+    pub code: String,
+}
+
+impl From<MfaLoginRequest> for VerifyMfaChallenge {
+    fn from(value: MfaLoginRequest) -> Self {
+        let MfaLoginRequest {
+            challenge_token,
+            code,
+        } = value;
+        Self {
+            challenge_token,
+            code,
+        }
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RefreshTokenRequest {
+    pub refresh_token: String,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionsResponse {
+    pub items: Vec<SessionResponse>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionResponse {
+    pub id: SessionId,
+    pub created_at: DateTime<Utc>,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    // このリクエストに使ったアクセストークンのセッションかどうか
+    pub current: bool,
+}
+
+impl From<Session> for SessionResponse {
+    fn from(value: Session) -> Self {
+        let Session {
+            id,
+            created_at,
+            user_agent,
+            ip_address,
+            current,
+        } = value;
+        Self {
+            id,
+            created_at,
+            user_agent,
+            ip_address,
+            current,
+        }
+    }
+}
+
+#[derive(Deserialize, Validate, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PasswordResetRequest {
+    #[garde(email)]
+    pub email: String,
+}
+
+impl From<PasswordResetRequest> for CreatePasswordReset {
+    fn from(value: PasswordResetRequest) -> Self {
+        CreatePasswordReset::new(value.email)
+    }
+}
+
+#[derive(Deserialize, Validate, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfirmPasswordResetRequest {
+    // メールで送られたトークン
+    #[garde(length(min = 1))]
+    pub token: String,
+    #[garde(length(min = 1))]
+    pub new_password: String,
+}
+
+impl From<ConfirmPasswordResetRequest> for ResetPassword {
+    fn from(value: ConfirmPasswordResetRequest) -> Self {
+        let ConfirmPasswordResetRequest {
+            token,
+            new_password,
+        } = value;
+        Self {
+            token,
+            new_password,
+        }
+    }
 }
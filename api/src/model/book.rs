@@ -2,29 +2,47 @@ use derive_new::new;
 use garde::Validate;
 use kernel::model::{
     book::{
-        event::{CreateBook, UpdateBook},
-        Book, BookListOptions,Checkout,
+        event::{AddBookCopies, CreateBook, UpdateBook},
+        isbn::Isbn,
+        Book, BookListOptions, BookMetadata, BookSearchOptions, BookSortKey,
+        Checkout,
     },
-    id::{BookId, UserId, CheckoutId},
-    list::PaginatedList,
+    id::{BookId, UserId, CheckoutId, CopyId},
+    list::{PaginatedList, SortDirection},
 };
-use serde::{de, Deserialize, Serialize};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
 
 use chrono::{DateTime, Utc};
 use super::user::{BookOwner, CheckoutUser};
 
 
-#[derive(Debug, Deserialize, Validate)]
+// 書誌情報の検索結果を登録用の入力値として返すため、Serializeも導出する
+#[derive(Debug, Deserialize, Serialize, Validate, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct CreateBookRequest {
     #[garde(length(min=1))]
     pub title: String,
+    // 書名の読み(カタカナ・ひらがな)、五十音順の並び替えに使う
     #[garde(length(min=1))]
-    pub author: String,
+    #[serde(default)]
+    pub title_kana: Option<String>,
     #[garde(length(min=1))]
-    pub isbn: String,
+    pub author: String,
+    // ISBN-10・ISBN-13のいずれも受け付け、ISBN-13の正規形に変換する
+    #[garde(skip)]
+    pub isbn: Isbn,
     #[garde(skip)]
     pub description: String,
+    // 登録する冊子の数、省略時は1冊
+    #[garde(range(min=1))]
+    #[serde(default = "default_copies")]
+    pub copies: i32,
+}
+
+const DEFAULT_COPIES: i32 = 1;
+const fn default_copies() -> i32 {
+    DEFAULT_COPIES
 }
 
 // CreateBookへのFromトレイト実装：データの変換を行う
@@ -32,29 +50,135 @@ impl From<CreateBookRequest> for CreateBook {
     fn from(value: CreateBookRequest) -> Self {
         let CreateBookRequest {
             title,
+            title_kana,
             author,
             isbn,
             description,
+            copies,
         } = value;
         Self {
             title,
+            title_kana,
             author,
             isbn,
             description,
+            copies,
+        }
+    }
+}
+
+// 外部APIから取得した書誌情報を、蔵書の登録リクエストの形に埋めて返す
+impl From<BookMetadata> for CreateBookRequest {
+    fn from(value: BookMetadata) -> Self {
+        let BookMetadata {
+            isbn,
+            title,
+            title_kana,
+            author,
+            description,
+        } = value;
+        Self {
+            title,
+            title_kana,
+            author,
+            isbn,
+            description,
+            copies: DEFAULT_COPIES,
+        }
+    }
+}
+
+// 一括登録するCSVの1行分の型
+// 列はヘッダー行の名前で対応付ける
+#[derive(Debug, Deserialize)]
+pub struct ImportBookCsvRow {
+    pub title: String,
+    pub author: String,
+    pub isbn: String,
+    #[serde(default)]
+    pub description: String,
+    pub owner_email: String,
+}
+
+// 一括登録の結果
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportBooksResponse {
+    pub accepted: usize,
+    pub rejected: usize,
+    pub rows: Vec<ImportBookRowReport>,
+}
+
+impl From<Vec<ImportBookRowReport>> for ImportBooksResponse {
+    fn from(rows: Vec<ImportBookRowReport>) -> Self {
+        let accepted = rows
+            .iter()
+            .filter(|r| r.status == ImportBookRowStatus::Accepted)
+            .count();
+        Self {
+            accepted,
+            rejected: rows.len() - accepted,
+            rows,
         }
     }
 }
 
+// CSVの1行ごとの登録結果
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportBookRowReport {
+    // CSVの行番号(ヘッダー行を1行目とする)
+    pub line: u64,
+    pub status: ImportBookRowStatus,
+    pub book_id: Option<BookId>,
+    pub errors: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum ImportBookRowStatus {
+    Accepted,
+    Rejected,
+}
+
+impl ImportBookRowReport {
+    pub fn accepted(line: u64, book_id: BookId) -> Self {
+        Self {
+            line,
+            status: ImportBookRowStatus::Accepted,
+            book_id: Some(book_id),
+            errors: vec![],
+        }
+    }
+
+    pub fn rejected(line: u64, errors: Vec<String>) -> Self {
+        Self {
+            line,
+            status: ImportBookRowStatus::Rejected,
+            book_id: None,
+            errors,
+        }
+    }
+}
+
+// ISBNで書誌情報を検索するためのクエリ
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct BookLookupQuery {
+    pub isbn: Isbn,
+}
+
 // 蔵書データの更新用の型を追加する
-#[derive(Debug, Deserialize, Validate)]
+#[derive(Debug, Deserialize, Validate, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct UpdateBookRequest {
     #[garde(length(min=1))]
     pub title: String,
     #[garde(length(min=1))]
     pub author: String,
-    #[garde(length(min=1))]
-    pub isbn: String,
+    // ISBN-10・ISBN-13のいずれも受け付け、ISBN-13の正規形に変換する
+    #[garde(skip)]
+    pub isbn: Isbn,
     #[garde(skip)]
     pub description: String,
 }
@@ -81,13 +205,42 @@ impl From<UpdateBookRequestWithIds> for UpdateBook {
             isbn,
             description,
             requested_user: user_id,
+            any_owner: false,
+        }
+    }
+}
+
+// 冊子の追加用の型
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AddBookCopiesRequest {
+    #[garde(range(min=1))]
+    pub copies: i32,
+}
+
+#[derive(new)]
+pub struct AddBookCopiesRequestWithIds(BookId, UserId, AddBookCopiesRequest);
+impl From<AddBookCopiesRequestWithIds> for AddBookCopies {
+    fn from(value: AddBookCopiesRequestWithIds) -> Self {
+        let AddBookCopiesRequestWithIds(
+            book_id,
+            user_id,
+            AddBookCopiesRequest { copies },
+        ) = value;
+
+        AddBookCopies {
+            book_id,
+            copies,
+            requested_user: user_id,
         }
     }
 }
 
 // クエリでlimitとoffsetを受け取るための型
 // handler側のメソッドで、クエリのデータを取得できる　
-#[derive(Debug, Deserialize, Validate)]
+// 検索・絞り込み・並び替えの条件も合わせて受け取る
+#[derive(Debug, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct BookListQuery{
     #[garde(range(min=0))]
     #[serde(default = "default_limit")]
@@ -95,6 +248,24 @@ pub struct BookListQuery{
     #[garde(range(min=0))]
     #[serde(default)]
     pub offset: i64,
+    #[garde(length(min=1))]
+    pub q: Option<String>,
+    #[garde(skip)]
+    pub isbn: Option<Isbn>,
+    #[garde(length(min=1))]
+    pub author: Option<String>,
+    #[garde(skip)]
+    pub owner: Option<UserId>,
+    #[garde(skip)]
+    pub available: Option<bool>,
+    #[garde(skip)]
+    #[serde(default)]
+    #[param(inline)]
+    pub sort: BookSortName,
+    #[garde(skip)]
+    #[serde(default)]
+    #[param(inline)]
+    pub direction: SortDirectionName,
 }
 
 const DEFAULT_LIMIT: i64 = 20;
@@ -104,22 +275,103 @@ const fn default_limit() -> i64 {
 
 impl From<BookListQuery> for BookListOptions {
     fn from(value: BookListQuery) -> Self{
-        let BookListQuery { limit, offset} = value;
-        Self { limit, offset }
+        let BookListQuery {
+            limit,
+            offset,
+            q,
+            isbn,
+            author,
+            owner,
+            available,
+            sort,
+            direction,
+        } = value;
+        Self {
+            limit,
+            offset,
+            q,
+            isbn,
+            author,
+            owner,
+            available,
+            sort: sort.into(),
+            direction: direction.into(),
+        }
+    }
+}
+
+// 蔵書の検索条件をクエリで受け取るための型
+#[derive(Debug, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct BookSearchQuery {
+    #[garde(length(min=1))]
+    pub q: String,
+    #[garde(range(min=0))]
+    #[serde(default = "default_limit")]
+    pub limit: i64,
+    #[garde(range(min=0))]
+    #[serde(default)]
+    pub offset: i64,
+}
+
+impl From<BookSearchQuery> for BookSearchOptions {
+    fn from(value: BookSearchQuery) -> Self {
+        let BookSearchQuery { q, limit, offset } = value;
+        Self { q, limit, offset }
+    }
+}
+
+// 並び替えの項目をクエリで受け取るための型
+#[derive(Debug, Default, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BookSortName {
+    Title,
+    Author,
+    #[default]
+    CreatedAt,
+}
+
+impl From<BookSortName> for BookSortKey {
+    fn from(value: BookSortName) -> Self {
+        match value {
+            BookSortName::Title => Self::Title,
+            BookSortName::Author => Self::Author,
+            BookSortName::CreatedAt => Self::CreatedAt,
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SortDirectionName {
+    Asc,
+    #[default]
+    Desc,
+}
+
+impl From<SortDirectionName> for SortDirection {
+    fn from(value: SortDirectionName) -> Self {
+        match value {
+            SortDirectionName::Asc => Self::Asc,
+            SortDirectionName::Desc => Self::Desc,
+        }
     }
 }
 
 // BookResponseの定義：データの取得の際の応答形式を作成
-#[derive(Debug,Deserialize, Serialize)]
+#[derive(Debug,Deserialize, Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct BookResponse {
     pub id: BookId,
     pub title: String,
+    pub title_kana: Option<String>,
     pub author: String,
     pub isbn: String,
     pub description: String,
     pub owner: BookOwner,
-    pub checkout: Option<BookCheckoutResponse>,
+    pub total_copies: i64,
+    pub available_copies: i64,
+    pub checkouts: Vec<BookCheckoutResponse>,
 }
 
 impl From<Book> for BookResponse {
@@ -127,45 +379,63 @@ impl From<Book> for BookResponse {
         let Book {
             id,
             title,
+            title_kana,
             author,
             isbn,
             description,
             owner,
-            checkout,
+            total_copies,
+            available_copies,
+            checkouts,
         } = value;
         
         Self {
             id,
             title,
+            title_kana,
             author,
             isbn,
             description,
             owner: owner.into(),
-            checkout: checkout.map(BookCheckoutResponse::from),
+            total_copies,
+            available_copies,
+            checkouts: checkouts
+                .into_iter()
+                .map(BookCheckoutResponse::from)
+                .collect(),
         }
     }
 }
 
-#[derive(Debug,Deserialize,Serialize)]
+#[derive(Debug,Deserialize,Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct BookCheckoutResponse {
     pub id: CheckoutId,
+    pub copy_id: CopyId,
     pub checked_out_by: CheckoutUser,
     pub checked_out_at: DateTime<Utc>,
+    pub due_at: DateTime<Utc>,
+    pub overdue: bool,
 }
 
 impl From<Checkout> for BookCheckoutResponse {
     fn from(value: Checkout) -> Self{
+        let overdue = value.is_overdue(Utc::now());
         let Checkout{
             checkout_id,
+            copy_id,
             checked_out_by,
             checked_out_at,
+            due_at,
         } = value;
 
         Self {
             id: checkout_id,
+            copy_id,
             checked_out_by: checked_out_by.into(),
             checked_out_at,
+            due_at,
+            overdue,
         }
     }
 }
@@ -185,7 +455,7 @@ impl From<Checkout> for BookCheckoutResponse {
 // 構造体全体のフィールド名を所定のルールでリネームするために使うアトリビュートである
 
 // apiレイヤーでのページネーション表現用の型
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct PaginatedBookResponse{
     pub total: i64, 
@@ -3,12 +3,13 @@
 use chrono::{DateTime, Utc};
 use kernel::model::{
     checkout::{Checkout, CheckoutBook},
-    id::{BookId, CheckoutId, UserId},
+    id::{BookId, CheckoutId, CopyId, UserId},
 };
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
 
-#[derive(Serialize)] // 構造体をシリアライズ可能にする：JSON形式への変換に対応
+#[derive(Serialize, ToSchema)] // 構造体をシリアライズ可能にする：JSON形式への変換に対応
 #[serde(rename_all = "camelCase")] // フィールド名がJSONエンコード時にcamelCaseになる
 pub struct CheckoutsResponse {
     pub items: Vec<CheckoutResponse>,
@@ -26,37 +27,65 @@ impl From<Vec<Checkout>> for CheckoutsResponse {
     }
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct CheckoutResponse {
     pub id: CheckoutId,
+    pub copy_id: CopyId,
     pub checked_out_by: UserId,
     pub checked_out_at: DateTime<Utc>,
+    pub due_at: DateTime<Utc>,
+    // 返却期限を過ぎているか(返却済みの場合は期限を過ぎて返却されたか)
+    pub overdue: bool,
+    pub renewal_count: i32,
     pub returned_at: Option<DateTime<Utc>>,
+    // 貸出・返却の操作を行ったユーザー(職員による代理操作の場合は借りたユーザーと異なる)
+    pub checked_out_performed_by: UserId,
+    pub returned_performed_by: Option<UserId>,
     pub book: CheckoutBookResponse,
 }
 
 impl From<Checkout> for CheckoutResponse {
     fn from(value: Checkout) -> Self {
+        let overdue = value.is_overdue(Utc::now());
         // 構造体の分解：Checkout構造体方各フィールドを取り出す
         let Checkout {
             id,
+            copy_id,
             checked_out_by,
             checked_out_at,
+            due_at,
+            renewal_count,
             returned_at,
+            checked_out_performed_by,
+            returned_performed_by,
             book,
         } = value;
         Self {
             id,
+            copy_id,
             checked_out_by,
             checked_out_at,
+            due_at,
+            overdue,
+            renewal_count,
             returned_at,
+            checked_out_performed_by,
+            returned_performed_by,
             book: book.into(),
         }
     }
 }
 
-#[derive(Serialize)]
+// 職員が指定のユーザーに代わって蔵書を貸し出す際のリクエスト
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CheckoutOnBehalfRequest {
+    // 借りるユーザー
+    pub user_id: UserId,
+}
+
+#[derive(Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct CheckoutBookResponse {
     pub id: BookId,
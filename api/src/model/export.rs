@@ -0,0 +1,223 @@
+// 蔵書の一括エクスポートで使う出力形式と、1冊ごとの書き出し処理を定義する
+// 書き出し処理は1冊分の文字列を返し、ハンドラ側でストリームとしてつなげて送る
+
+use serde::Deserialize;
+use shared::error::{AppError, AppResult};
+use utoipa::{IntoParams, ToSchema};
+
+use super::book::BookResponse;
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ExportQuery {
+    #[serde(default)]
+    #[param(inline)]
+    pub format: ExportFormat,
+}
+
+#[derive(Debug, Default, Clone, Copy, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Csv,
+    Jsonl,
+    Marcxml,
+}
+
+const CSV_HEADER: [&str; 13] = [
+    "book_id",
+    "title",
+    "title_kana",
+    "author",
+    "isbn",
+    "description",
+    "owner_id",
+    "owner_name",
+    "total_copies",
+    "available_copies",
+    "checkout_count",
+    "checked_out_by",
+    "next_due_at",
+];
+
+impl ExportFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Csv => "text/csv; charset=utf-8",
+            Self::Jsonl => "application/x-ndjson",
+            Self::Marcxml => "application/marcxml+xml",
+        }
+    }
+
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            Self::Csv => "books.csv",
+            Self::Jsonl => "books.jsonl",
+            Self::Marcxml => "books.xml",
+        }
+    }
+
+    // 1冊目より前に書き出す内容
+    pub fn header(&self) -> AppResult<String> {
+        match self {
+            Self::Csv => csv_record(CSV_HEADER),
+            Self::Jsonl => Ok(String::new()),
+            Self::Marcxml => Ok(concat!(
+                r#"<?xml version="1.0" encoding="UTF-8"?>"#,
+                "\n",
+                r#"<collection xmlns="http://www.loc.gov/MARC21/slim">"#,
+                "\n"
+            )
+            .into()),
+        }
+    }
+
+    // 最後の1冊の後に書き出す内容
+    pub fn footer(&self) -> String {
+        match self {
+            Self::Csv | Self::Jsonl => String::new(),
+            Self::Marcxml => "</collection>\n".into(),
+        }
+    }
+
+    pub fn encode(&self, book: &BookResponse) -> AppResult<String> {
+        match self {
+            Self::Csv => encode_csv(book),
+            Self::Jsonl => serde_json::to_string(book)
+                .map(|line| line + "\n")
+                .map_err(|e| AppError::ConversionEntityError(e.to_string())),
+            Self::Marcxml => Ok(encode_marcxml(book)),
+        }
+    }
+}
+
+fn csv_record<I, T>(record: I) -> AppResult<String>
+where
+    I: IntoIterator<Item = T>,
+    T: AsRef<[u8]>,
+{
+    let mut writer = csv::Writer::from_writer(vec![]);
+    writer
+        .write_record(record)
+        .map_err(|e| AppError::ConversionEntityError(e.to_string()))?;
+    let bytes = writer
+        .into_inner()
+        .map_err(|e| AppError::ConversionEntityError(e.to_string()))?;
+    String::from_utf8(bytes).map_err(|e| AppError::ConversionEntityError(e.to_string()))
+}
+
+fn encode_csv(book: &BookResponse) -> AppResult<String> {
+    // 貸出中の利用者名は「;」で連結し、返却期限は最も近いものを出力する
+    let checked_out_by = book
+        .checkouts
+        .iter()
+        .map(|c| c.checked_out_by.name.as_str())
+        .collect::<Vec<_>>()
+        .join(";");
+    let next_due_at = book
+        .checkouts
+        .iter()
+        .map(|c| c.due_at)
+        .min()
+        .map(|d| d.to_rfc3339())
+        .unwrap_or_default();
+
+    csv_record([
+        book.id.to_string(),
+        book.title.clone(),
+        book.title_kana.clone().unwrap_or_default(),
+        book.author.clone(),
+        book.isbn.clone(),
+        book.description.clone(),
+        book.owner.id.to_string(),
+        book.owner.name.clone(),
+        book.total_copies.to_string(),
+        book.available_copies.to_string(),
+        book.checkouts.len().to_string(),
+        checked_out_by,
+        next_due_at,
+    ])
+}
+
+// MARC21 XMLの1レコードを組み立てる
+// 所有者(900)と貸出状況(950)はローカルフィールドとして出力する
+fn encode_marcxml(book: &BookResponse) -> String {
+    let mut record = String::from("  <record>\n");
+    record.push_str("    <leader>00000nam a2200000 i 4500</leader>\n");
+    record.push_str(&format!(
+        "    <controlfield tag=\"001\">{}</controlfield>\n",
+        book.id
+    ));
+
+    push_datafield(&mut record, "020", &[('a', &book.isbn)]);
+    push_datafield(&mut record, "100", &[('a', &book.author)]);
+    // 書名の読みは、$6(リンク)で245と対応付けた880(別の表記)として出力する
+    match &book.title_kana {
+        Some(kana) => {
+            push_datafield(&mut record, "245", &[('6', "880-01"), ('a', &book.title)]);
+            push_datafield(&mut record, "880", &[('6', "245-01"), ('a', kana)]);
+        }
+        None => push_datafield(&mut record, "245", &[('a', &book.title)]),
+    }
+    if !book.description.is_empty() {
+        push_datafield(&mut record, "520", &[('a', &book.description)]);
+    }
+    push_datafield(
+        &mut record,
+        "900",
+        &[
+            ('a', &book.owner.name),
+            ('b', &book.owner.id.to_string()),
+            ('c', &book.total_copies.to_string()),
+            ('d', &book.available_copies.to_string()),
+        ],
+    );
+    for checkout in &book.checkouts {
+        push_datafield(
+            &mut record,
+            "950",
+            &[
+                ('a', &checkout.checked_out_by.name),
+                ('b', &checkout.copy_id.to_string()),
+                ('c', &checkout.checked_out_at.to_rfc3339()),
+                ('d', &checkout.due_at.to_rfc3339()),
+            ],
+        );
+    }
+
+    record.push_str("  </record>\n");
+    record
+}
+
+fn push_datafield(record: &mut String, tag: &str, subfields: &[(char, &str)]) {
+    record.push_str(&format!(
+        "    <datafield tag=\"{}\" ind1=\" \" ind2=\" \">\n",
+        tag
+    ));
+    for (code, value) in subfields {
+        record.push_str(&format!(
+            "      <subfield code=\"{}\">{}</subfield>\n",
+            code,
+            escape_xml(value)
+        ));
+    }
+    record.push_str("    </datafield>\n");
+}
+
+fn escape_xml(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            // XML 1.0で使えない制御文字(タブ・改行を除く)は、文書全体を壊さないよう取り除く
+            '\t' | '\n' | '\r' => escaped.push(c),
+            c if c < ' ' || c == '\u{FFFE}' || c == '\u{FFFF}' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,52 @@
+// 2段階認証APIの入出力の定義
+use derive_new::new;
+use garde::Validate;
+use kernel::model::{
+    id::UserId,
+    mfa::{event::VerifyTotp, TotpEnrollment},
+};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TotpEnrollmentResponse {
+    // 認証アプリに読み込ませるURI。QRコードにして表示する
+    pub otpauth_uri: String,
+    pub secret: String,
+    // 一度しか表示しないため、ユーザーに控えてもらう
+    pub recovery_codes: Vec<String>,
+}
+
+impl From<TotpEnrollment> for TotpEnrollmentResponse {
+    fn from(value: TotpEnrollment) -> Self {
+        let TotpEnrollment {
+            otpauth_uri,
+            secret,
+            recovery_codes,
+        } = value;
+        Self {
+            otpauth_uri,
+            secret,
+            recovery_codes,
+        }
+    }
+}
+
+#[derive(Deserialize, Validate, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct VerifyTotpRequest {
+    // 認証アプリに表示されたコード。登録の解除にはリカバリーコードも使える
+    #[garde(length(min = 1))]
+    code: String,
+}
+
+#[derive(new)]
+pub struct VerifyTotpRequestWithUserId(UserId, VerifyTotpRequest);
+
+impl From<VerifyTotpRequestWithUserId> for VerifyTotp {
+    fn from(value: VerifyTotpRequestWithUserId) -> Self {
+        let VerifyTotpRequestWithUserId(user_id, VerifyTotpRequest { code }) = value;
+        Self { user_id, code }
+    }
+}
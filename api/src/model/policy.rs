@@ -0,0 +1,94 @@
+use derive_new::new;
+use garde::Validate;
+use kernel::model::{
+    id::UserId,
+    policy::{event::UpdateBorrowingPolicy, BorrowingPolicy},
+};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::user::RoleName;
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BorrowingPoliciesResponse {
+    pub items: Vec<BorrowingPolicyResponse>,
+}
+
+impl From<Vec<BorrowingPolicy>> for BorrowingPoliciesResponse {
+    fn from(value: Vec<BorrowingPolicy>) -> Self {
+        Self {
+            items: value
+                .into_iter()
+                .map(BorrowingPolicyResponse::from)
+                .collect(),
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BorrowingPolicyResponse {
+    pub role: RoleName,
+    pub max_loans: i32,
+    pub loan_period_days: i32,
+    pub max_renewals: i32,
+}
+
+impl From<BorrowingPolicy> for BorrowingPolicyResponse {
+    fn from(value: BorrowingPolicy) -> Self {
+        let BorrowingPolicy {
+            role,
+            max_loans,
+            loan_period_days,
+            max_renewals,
+        } = value;
+
+        Self {
+            role: RoleName::from(role),
+            max_loans,
+            loan_period_days,
+            max_renewals,
+        }
+    }
+}
+
+#[derive(Deserialize, Validate, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateBorrowingPolicyRequest {
+    #[garde(range(min = 0))]
+    max_loans: i32,
+    #[garde(range(min = 1))]
+    loan_period_days: i32,
+    #[garde(range(min = 0))]
+    max_renewals: i32,
+}
+
+#[derive(new)]
+pub struct UpdateBorrowingPolicyRequestWithIds(
+    RoleName,
+    UserId,
+    UpdateBorrowingPolicyRequest,
+);
+
+impl From<UpdateBorrowingPolicyRequestWithIds> for UpdateBorrowingPolicy {
+    fn from(value: UpdateBorrowingPolicyRequestWithIds) -> Self {
+        let UpdateBorrowingPolicyRequestWithIds(
+            role,
+            requested_user,
+            UpdateBorrowingPolicyRequest {
+                max_loans,
+                loan_period_days,
+                max_renewals,
+            },
+        ) = value;
+
+        Self {
+            role: role.into(),
+            max_loans,
+            loan_period_days,
+            max_renewals,
+            requested_user,
+        }
+    }
+}
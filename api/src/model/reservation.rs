@@ -0,0 +1,63 @@
+// kernelレイヤーで定義されているReservationをクライアントにJSONで返すための構造の定義を行う
+
+use chrono::{DateTime, Utc};
+use kernel::model::{
+    id::{BookId, ReservationId, UserId},
+    reservation::Reservation,
+};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ReservationsResponse {
+    pub items: Vec<ReservationResponse>,
+}
+
+impl From<Vec<Reservation>> for ReservationsResponse {
+    fn from(value: Vec<Reservation>) -> Self {
+        Self {
+            items: value
+                .into_iter()
+                .map(ReservationResponse::from)
+                .collect(),
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ReservationResponse {
+    pub id: ReservationId,
+    pub book_id: BookId,
+    pub reserved_by: UserId,
+    pub reserved_at: DateTime<Utc>,
+    // 取り置き中(受け取り待ち)であるか
+    pub ready: bool,
+    pub ready_at: Option<DateTime<Utc>>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl From<Reservation> for ReservationResponse {
+    fn from(value: Reservation) -> Self {
+        let ready = value.is_ready();
+        let Reservation {
+            id,
+            book_id,
+            reserved_by,
+            reserved_at,
+            ready_at,
+            expires_at,
+        } = value;
+
+        Self {
+            id,
+            book_id,
+            reserved_by,
+            reserved_at,
+            ready,
+            ready_at,
+            expires_at,
+        }
+    }
+}
@@ -0,0 +1,105 @@
+// ロール管理APIの入出力の定義
+use derive_new::new;
+use garde::Validate;
+use kernel::model::{
+    id::UserId,
+    role::{
+        event::{CreateRole, UpdateRolePermissions},
+        Permission, Role, RoleDetail,
+    },
+};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::user::RoleName;
+
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RolesResponse {
+    pub items: Vec<RoleResponse>,
+}
+
+impl From<Vec<RoleDetail>> for RolesResponse {
+    fn from(value: Vec<RoleDetail>) -> Self {
+        Self {
+            items: value.into_iter().map(RoleResponse::from).collect(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RoleResponse {
+    pub name: RoleName,
+    // 組み込みのロール(Admin・User)は変更・削除できない
+    pub builtin: bool,
+    pub permissions: Vec<Permission>,
+}
+
+impl From<RoleDetail> for RoleResponse {
+    fn from(value: RoleDetail) -> Self {
+        let RoleDetail { role, permissions } = value;
+        Self {
+            builtin: role.is_builtin(),
+            name: RoleName::from(role),
+            permissions,
+        }
+    }
+}
+
+#[derive(Deserialize, Validate, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateRoleRequest {
+    // パスに含めて指定するため、区切り文字を含まない名前とする
+    #[garde(length(min = 1, max = 255), custom(has_no_slash))]
+    name: String,
+    #[garde(skip)]
+    permissions: Vec<Permission>,
+}
+
+fn has_no_slash(value: &str, _: &()) -> garde::Result {
+    if value.contains('/') {
+        return Err(garde::Error::new("must not contain '/'"));
+    }
+    Ok(())
+}
+
+// 作成を行うユーザーのIDを添えて、CreateRoleに変換する
+#[derive(new)]
+pub struct CreateRoleRequestWithUserId(UserId, CreateRoleRequest);
+
+impl From<CreateRoleRequestWithUserId> for CreateRole {
+    fn from(value: CreateRoleRequestWithUserId) -> Self {
+        let CreateRoleRequestWithUserId(requested_user, CreateRoleRequest { name, permissions }) =
+            value;
+        Self {
+            role: Role::from(name),
+            permissions,
+            requested_user,
+        }
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateRolePermissionsRequest {
+    permissions: Vec<Permission>,
+}
+
+#[derive(new)]
+pub struct UpdateRolePermissionsRequestWithIds(RoleName, UserId, UpdateRolePermissionsRequest);
+
+impl From<UpdateRolePermissionsRequestWithIds> for UpdateRolePermissions {
+    fn from(value: UpdateRolePermissionsRequestWithIds) -> Self {
+        let UpdateRolePermissionsRequestWithIds(
+            role,
+            requested_user,
+            UpdateRolePermissionsRequest { permissions },
+        ) = value;
+        Self {
+            role: Role::from(role),
+            permissions,
+            requested_user,
+        }
+    }
+}
@@ -9,40 +9,33 @@ use kernel::model::{
     },
 };
 use serde::{Deserialize, Serialize};
-use strum::VariantNames;
+use utoipa::ToSchema;
 
-#[derive(Serialize, Deserialize, VariantNames)]
-#[strum(serialize_all = "kebab-case")]
-pub enum RoleName{
-    Admin,
-    User,
-}
+// ロール名。組み込みのAdmin・Userのほか、管理者が作成したロール(例: Librarian)の名前が入る
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(transparent)]
+#[schema(example = "Librarian")]
+pub struct RoleName(String);
 
 impl From<Role> for RoleName { // Role型をRoleNameに変換
     fn from(value: Role) -> Self {
-        match value{
-            Role::Admin => Self::Admin,
-            Role::User => Self::User,
-        }
+        Self(value.as_ref().to_string())
     }
 }
 
 impl From<RoleName> for Role{
     fn from(value: RoleName) -> Self{
-        match value {
-            RoleName::Admin => Self::Admin,
-            RoleName::User => Self::User,
-        }
+        Role::from(value.0)
     }
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct UsersResponse{
     pub items: Vec<UserResponse>,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct UserResponse{
     pub id: UserId,
@@ -69,7 +62,7 @@ impl From<User> for UserResponse {
     }
 }
 
-#[derive(Deserialize, Validate)]
+#[derive(Deserialize, Validate, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct UpdateUserPasswordRequest {
     // gardeを使うことで制約を宣言できる：current_password, new_passwordはそれぞれ1文字以上であること
@@ -102,7 +95,7 @@ impl From<UpdateUserPasswordRequestWithUserId> for UpdateUserPassword{
     }
 }
 
-#[derive(Deserialize, Validate)]
+#[derive(Deserialize, Validate, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct CreateUserRequest{
     // gardeを使うことで制約を宣言できる：name,email, passwordはそれぞれ1文字以上であること
@@ -113,44 +106,55 @@ pub struct CreateUserRequest{
     #[garde(length(min = 1))]
     password: String,
 }
-impl From<CreateUserRequest> for CreateUser {
-    fn from(value: CreateUserRequest) -> Self{
-        let CreateUserRequest{
-            name,
-            email,
-            password,
-        } = value;
+// 登録を行うユーザーのIDを添えて、CreateUserに変換する
+#[derive(new)]
+pub struct CreateUserRequestWithUserId(UserId, CreateUserRequest);
+
+impl From<CreateUserRequestWithUserId> for CreateUser {
+    fn from(value: CreateUserRequestWithUserId) -> Self{
+        let CreateUserRequestWithUserId(
+            requested_user,
+            CreateUserRequest{
+                name,
+                email,
+                password,
+            },
+        ) = value;
         Self {
             name,
             email,
             password,
+            requested_user,
         }
     }
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct UpdateUserRoleRequest{
     role: RoleName,
 }
 
+// 対象のユーザーのIDと、変更を行うユーザーのIDを添える
 #[derive(new)]
-pub struct UpdateuserRoleRequestWithUserId(UserId, UpdateUserRoleRequest);
+pub struct UpdateUserRoleRequestWithIds(UserId, UserId, UpdateUserRoleRequest);
 
-impl From<UpdateuserRoleRequestWithUserId> for UpdateUserRole{
-    fn from(value: UpdateuserRoleRequestWithUserId) -> Self{
-        let UpdateuserRoleRequestWithUserId(
+impl From<UpdateUserRoleRequestWithIds> for UpdateUserRole{
+    fn from(value: UpdateUserRoleRequestWithIds) -> Self{
+        let UpdateUserRoleRequestWithIds(
             user_id,
+            requested_user,
             UpdateUserRoleRequest{ role },
         ) = value;
         Self{
             user_id,
             role: Role::from(role),
+            requested_user,
         }
     }
 }
 
-#[derive(Debug,Deserialize, Serialize)]
+#[derive(Debug,Deserialize, Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct BookOwner{
     pub id: UserId,
@@ -164,7 +168,7 @@ impl From<kernel::model::user::BookOwner> for BookOwner {
     }
 }
 
-#[derive(Debug,Deserialize,Serialize)]
+#[derive(Debug,Deserialize,Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct CheckoutUser{
     pub id: UserId,
@@ -0,0 +1,172 @@
+// OpenAPIの仕様書を生成する
+// 各handlerに付与したutoipa::pathと、modelで導出したスキーマをここで束ねる
+
+use utoipa::{
+    openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme},
+    Modify, OpenApi,
+};
+
+use crate::{handler, model};
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "Book Management API",
+        description = "蔵書管理アプリケーションのWebAPI",
+    ),
+    paths(
+        handler::health::health_check,
+        handler::health::health_check_db,
+        handler::auth::login,
+        handler::auth::login_mfa,
+        handler::auth::logout,
+        handler::auth::refresh,
+        handler::auth::request_password_reset,
+        handler::auth::confirm_password_reset,
+        handler::book::register_book,
+        handler::book::import_books,
+        handler::book::show_book_list,
+        handler::book::search_books,
+        handler::book::lookup_book,
+        handler::book::export_books,
+        handler::book::show_book,
+        handler::book::update_book,
+        handler::book::delete_book,
+        handler::book::add_book_copies,
+        handler::checkout::checkout_book,
+        handler::checkout::renew_checkout,
+        handler::checkout::return_book,
+        handler::checkout::checkout_book_on_behalf,
+        handler::checkout::force_return_book,
+        handler::checkout::show_checked_out_list,
+        handler::checkout::show_overdue_list,
+        handler::checkout::checkout_history,
+        handler::reservation::reserve_book,
+        handler::reservation::cancel_reservation,
+        handler::reservation::show_reservation_list,
+        handler::user::register_user,
+        handler::user::list_users,
+        handler::user::delete_user,
+        handler::user::change_role,
+        handler::user::unlock_user,
+        handler::user::get_current_user,
+        handler::user::change_password,
+        handler::user::get_checkouts,
+        handler::user::list_sessions,
+        handler::user::revoke_session,
+        handler::mfa::enroll_totp,
+        handler::mfa::confirm_totp,
+        handler::mfa::disable_totp,
+        handler::api_key::list_api_keys,
+        handler::api_key::create_api_key,
+        handler::api_key::delete_api_key,
+        handler::policy::list_policies,
+        handler::policy::update_policy,
+        handler::role::list_roles,
+        handler::role::create_role,
+        handler::role::update_role_permissions,
+        handler::role::delete_role,
+        handler::audit::list_audit_events,
+        handler::metrics::render_metrics,
+    ),
+    components(schemas(
+        kernel::model::id::UserId,
+        kernel::model::id::BookId,
+        kernel::model::id::CheckoutId,
+        kernel::model::id::ReservationId,
+        kernel::model::id::CopyId,
+        kernel::model::id::SessionId,
+        kernel::model::id::ApiKeyId,
+        kernel::model::id::AuditEventId,
+        kernel::model::api_key::ApiKeyScope,
+        kernel::model::role::Permission,
+        kernel::model::audit::AuditAction,
+        kernel::model::book::isbn::Isbn,
+        model::auth::LoginRequest,
+        model::auth::AccessTokenResponse,
+        model::auth::MfaChallengeResponse,
+        model::auth::MfaLoginRequest,
+        model::auth::RefreshTokenRequest,
+        model::auth::PasswordResetRequest,
+        model::auth::ConfirmPasswordResetRequest,
+        model::auth::SessionsResponse,
+        model::auth::SessionResponse,
+        model::book::CreateBookRequest,
+        model::book::UpdateBookRequest,
+        model::book::AddBookCopiesRequest,
+        model::book::BookResponse,
+        model::book::BookCheckoutResponse,
+        model::book::PaginatedBookResponse,
+        model::book::BookSortName,
+        model::book::SortDirectionName,
+        model::book::ImportBooksResponse,
+        model::book::ImportBookRowReport,
+        model::book::ImportBookRowStatus,
+        model::export::ExportFormat,
+        model::checkout::CheckoutsResponse,
+        model::checkout::CheckoutResponse,
+        model::checkout::CheckoutBookResponse,
+        model::checkout::CheckoutOnBehalfRequest,
+        model::reservation::ReservationsResponse,
+        model::reservation::ReservationResponse,
+        model::policy::BorrowingPoliciesResponse,
+        model::policy::BorrowingPolicyResponse,
+        model::policy::UpdateBorrowingPolicyRequest,
+        model::role::RolesResponse,
+        model::role::RoleResponse,
+        model::role::CreateRoleRequest,
+        model::role::UpdateRolePermissionsRequest,
+        model::audit::PaginatedAuditEventResponse,
+        model::audit::AuditEventResponse,
+        model::mfa::TotpEnrollmentResponse,
+        model::mfa::VerifyTotpRequest,
+        model::api_key::CreateApiKeyRequest,
+        model::api_key::ApiKeysResponse,
+        model::api_key::ApiKeyResponse,
+        model::api_key::CreatedApiKeyResponse,
+        model::user::RoleName,
+        model::user::UsersResponse,
+        model::user::UserResponse,
+        model::user::CreateUserRequest,
+        model::user::UpdateUserPasswordRequest,
+        model::user::UpdateUserRoleRequest,
+        model::user::BookOwner,
+        model::user::CheckoutUser,
+        shared::error::ProblemDetails,
+        shared::error::FieldError,
+    )),
+    modifiers(&SecurityAddon),
+    tags(
+        (name = "health", description = "ヘルスチェック"),
+        (name = "auth", description = "ログイン・ログアウト"),
+        (name = "books", description = "蔵書の登録・検索・一括登録・エクスポート"),
+        (name = "checkouts", description = "貸出・返却・延長"),
+        (name = "reservations", description = "予約"),
+        (name = "users", description = "ユーザー管理"),
+        (name = "policies", description = "ロールごとの貸出ポリシー"),
+        (name = "roles", description = "ロールと権限の管理"),
+        (name = "audit", description = "監査ログ"),
+        (name = "metrics", description = "Prometheus向けのメトリクス"),
+    )
+)]
+pub struct ApiDoc;
+
+// ログインで発行したアクセストークン、またはAPIキーを、Bearerトークンとして送る認証方式を登録する
+struct SecurityAddon;
+
+impl Modify for SecurityAddon {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer_auth",
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .description(Some(
+                        "POST /auth/login で発行したアクセストークン、またはPOST /api/v1/users/me/api-keys で発行したAPIキー",
+                    ))
+                    .build(),
+            ),
+        );
+    }
+}
@@ -0,0 +1,8 @@
+use axum::{routing::get, Router};
+use registry::AppRegistry;
+
+use crate::handler::audit::list_audit_events;
+
+pub fn build_audit_routers() -> Router<AppRegistry> {
+    Router::new().route("/audit", get(list_audit_events))
+}
@@ -2,15 +2,21 @@ use axum::{routing::post, Router};
 use registry::AppRegistry;
 use tracing::info;
 
-use crate::handler::auth::{login, logout};
+use crate::handler::auth::{
+    confirm_password_reset, login, login_mfa, logout, refresh, request_password_reset,
+};
 
 pub fn routes() -> Router<AppRegistry> {
 
-    info!("Initializing routes for /auth/login and /auth/logout");
+    info!("Initializing routes for /auth/login, /auth/logout, /auth/refresh and /auth/password-reset");
 
     let auth_router = Router::new()
         .route("/login", post(login))
-        .route("/logout", post(logout));
+        .route("/login/mfa", post(login_mfa))
+        .route("/logout", post(logout))
+        .route("/refresh", post(refresh))
+        .route("/password-reset/request", post(request_password_reset))
+        .route("/password-reset/confirm", post(confirm_password_reset));
     info!("Routes for _auth initialized");
     
     Router::new().nest("/auth", auth_router)
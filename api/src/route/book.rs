@@ -1,38 +1,83 @@
 use axum::{
     routing::{delete, get, post, put},
-    Router,
+    Extension, Router,
 };
+use kernel::model::api_key::ApiKeyScope;
 use registry::AppRegistry;
 
+use crate::extractor::ApiKeyScopes;
 use crate::handler::{
     book::{
-    delete_book, register_book, show_book, show_book_list, update_book
+    add_book_copies, delete_book, export_books, import_books, lookup_book,
+    register_book, search_books, show_book, show_book_list, update_book
     },
     // checkoutの関数のuseを追加する
     checkout::{
-        checkout_book, checkout_history, return_book, show_checked_out_list,
+        checkout_book, checkout_book_on_behalf, checkout_history, force_return_book,
+        renew_checkout, return_book, show_checked_out_list, show_overdue_list,
     },
+    reservation::{cancel_reservation, reserve_book, show_reservation_list},
 };
 
 pub fn build_book_routers() -> Router<AppRegistry> {
     let books_routers = Router::new()
         .route("/", post(register_book))
         .route("/", get(show_book_list))
+        .route("/search", get(search_books))
+        .route("/lookup", get(lookup_book))
+        .route("/import", post(import_books))
+        .route("/export", get(export_books))
         .route("/:book_id", get(show_book))
         .route("/:book_id", put(update_book))
-        .route("/:book_id", delete(delete_book));
+        .route("/:book_id", delete(delete_book))
+        .route("/:book_id/copies", post(add_book_copies))
+        .route_layer(Extension(ApiKeyScopes::new(
+            ApiKeyScope::BooksRead,
+            ApiKeyScope::BooksWrite,
+        )));
 
     let checkout_router = Router::new()
         .route("/checkouts", get(show_checked_out_list))
+        .route("/checkouts/overdue", get(show_overdue_list))
         .route("/:book_id/checkouts", post(checkout_book))
+        .route("/:book_id/checkouts/on-behalf", post(checkout_book_on_behalf))
         .route(
             "/:book_id/checkouts/:checkout_id/returned",
             put(return_book),
         )
-        .route("/:book_id/checkout-history", get(checkout_history));
+        .route(
+            "/:book_id/checkouts/:checkout_id/force-returned",
+            put(force_return_book),
+        )
+        .route(
+            "/:book_id/checkouts/:checkout_id/renew",
+            put(renew_checkout),
+        )
+        .route("/:book_id/checkout-history", get(checkout_history))
+        .route_layer(Extension(ApiKeyScopes::new(
+            ApiKeyScope::CheckoutsRead,
+            ApiKeyScope::CheckoutsWrite,
+        )));
+
+    let reservation_router = Router::new()
+        .route(
+            "/:book_id/reservations",
+            get(show_reservation_list)
+                .post(reserve_book)
+                .delete(cancel_reservation),
+        )
+        .route_layer(Extension(ApiKeyScopes::new(
+            ApiKeyScope::ReservationsRead,
+            ApiKeyScope::ReservationsWrite,
+        )));
 
     //  mergeメソッドでrouterを結合する
-    Router::new().nest("/books", books_routers.merge(checkout_router))
+    Router::new().nest(
+        "/books",
+        books_routers
+            .merge(checkout_router)
+            .merge(reservation_router),
+    )
 }
 
 // Arc：スレッド間で安全に値を渡すための機能
@@ -0,0 +1,8 @@
+use axum::{routing::get, Router};
+use registry::AppRegistry;
+
+use crate::handler::metrics::render_metrics;
+
+pub fn routes() -> Router<AppRegistry> {
+    Router::new().route("/metrics", get(render_metrics))
+}
@@ -2,4 +2,8 @@ pub mod book;
 pub mod health;
 pub mod auth;
 pub mod user;
-pub mod v1;
\ No newline at end of file
+pub mod policy;
+pub mod role;
+pub mod v1;
+pub mod metrics;
+pub mod audit;
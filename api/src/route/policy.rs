@@ -0,0 +1,21 @@
+use axum::{
+    routing::{get, put},
+    Extension, Router,
+};
+use kernel::model::api_key::ApiKeyScope;
+use registry::AppRegistry;
+
+use crate::extractor::ApiKeyScopes;
+use crate::handler::policy::{list_policies, update_policy};
+
+pub fn build_policy_routers() -> Router<AppRegistry> {
+    let routers = Router::new()
+        .route("/", get(list_policies))
+        .route("/:role", put(update_policy))
+        .route_layer(Extension(ApiKeyScopes::new(
+            ApiKeyScope::PoliciesRead,
+            ApiKeyScope::PoliciesWrite,
+        )));
+
+    Router::new().nest("/policies", routers)
+}
@@ -1,19 +1,49 @@
 use axum::{
-    routing::{delete, get, put},
-    Router,
+    routing::{delete, get, post, put},
+    Extension, Router,
 };
+use kernel::model::api_key::ApiKeyScope;
 use registry::AppRegistry;
 
+use crate::extractor::ApiKeyScopes;
+use crate::handler::api_key::{create_api_key, delete_api_key, list_api_keys};
+use crate::handler::mfa::{confirm_totp, disable_totp, enroll_totp};
 use crate::handler::user::{
-    change_password, change_role, delete_user, get_checkouts,get_current_user, list_users, register_user,
+    change_password, change_role, delete_user, get_checkouts,get_current_user, list_sessions,
+    list_users, register_user, revoke_session, unlock_user,
 };
 
 pub fn build_user_router() -> Router<AppRegistry> {
-    Router::new()
+    let user_routers = Router::new()
         .route("/users/me", get(get_current_user))
-        .route("/users/me/password", put(change_password))
-        .route("/users/me/checkouts",get(get(get_checkouts)))
         .route("/users", get(list_users).post(register_user))
         .route("/users/:user_id",delete(delete_user))
         .route("/users/:user_id/role", put(change_role))
-}
\ No newline at end of file
+        .route("/users/:user_id/lockout", delete(unlock_user))
+        .route_layer(Extension(ApiKeyScopes::new(
+            ApiKeyScope::UsersRead,
+            ApiKeyScope::UsersWrite,
+        )));
+
+    let checkout_routers = Router::new()
+        .route("/users/me/checkouts",get(get(get_checkouts)))
+        .route_layer(Extension(ApiKeyScopes::new(
+            ApiKeyScope::CheckoutsRead,
+            ApiKeyScope::CheckoutsWrite,
+        )));
+
+    // パスワード・セッション・2段階認証・APIキーの管理は、ログインしたユーザーのみ行える
+    // スコープを宣言しないため、APIキーでは実行できない
+    let account_routers = Router::new()
+        .route("/users/me/password", put(change_password))
+        .route("/users/me/sessions", get(list_sessions))
+        .route("/users/me/sessions/:session_id", delete(revoke_session))
+        .route("/users/me/mfa/totp", post(enroll_totp).delete(disable_totp))
+        .route("/users/me/mfa/totp/confirm", post(confirm_totp))
+        .route("/users/me/api-keys", get(list_api_keys).post(create_api_key))
+        .route("/users/me/api-keys/:api_key_id", delete(delete_api_key));
+
+    user_routers
+        .merge(checkout_routers)
+        .merge(account_routers)
+}
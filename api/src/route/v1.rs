@@ -4,15 +4,18 @@ use axum::Router;
 use registry::AppRegistry;
 
 use super::{
-    book::build_book_routers, health::build_health_check_routers,
-    user::build_user_router,
+    audit::build_audit_routers, book::build_book_routers, health::build_health_check_routers,
+    policy::build_policy_routers, role::build_role_routers, user::build_user_router,
 };
 
 pub fn routes() -> Router<AppRegistry> {
     let router = Router::new()
         .merge(build_health_check_routers())
         .merge(build_book_routers())
-        .merge(build_user_router());
+        .merge(build_user_router())
+        .merge(build_policy_routers())
+        .merge(build_role_routers())
+        .merge(build_audit_routers());
 
     Router::new().nest("/api/v1", router)
 }
\ No newline at end of file
@@ -0,0 +1,237 @@
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+};
+use chrono::Utc;
+use kernel::{
+    model::{
+        api_key::{event::API_KEY_PREFIX, ApiKey, ApiKeyScope, AuthorizedApiKey, CreatedApiKey},
+        id::{ApiKeyId, UserId},
+    },
+    repository::api_key::MockApiKeyRepository,
+};
+use registry::MockAppRegistryExt;
+use rstest::rstest;
+use shared::{
+    error::{AppError, ProblemDetails},
+    i18n::Message,
+};
+use tower::ServiceExt;
+
+use crate::{
+    deserialize_json,
+    helper::{fixture, make_router, v1, TestRequestExt},
+};
+use api::model::{
+    api_key::{ApiKeysResponse, CreatedApiKeyResponse},
+    user::UserResponse,
+};
+
+const DUMMY_API_KEY: &str = "bmk_0123456789abcdef0123456789abcdef";
+
+// 指定したスコープを持つAPIキーで認証されるようにする
+fn expect_api_key(registry: &mut MockAppRegistryExt, scopes: Vec<ApiKeyScope>) {
+    registry.expect_api_key_repository().returning(move || {
+        let scopes = scopes.clone();
+        let mut mock = MockApiKeyRepository::new();
+        mock.expect_authenticate()
+            .withf(|key| key == DUMMY_API_KEY)
+            .returning(move |_| {
+                Ok(Some(AuthorizedApiKey {
+                    id: ApiKeyId::new(),
+                    user_id: UserId::new(),
+                    scopes: scopes.clone(),
+                    mfa_satisfied: true,
+                }))
+            });
+        Arc::new(mock)
+    });
+}
+
+#[rstest]
+#[tokio::test]
+async fn create_api_key_returns_key_once(mut fixture: MockAppRegistryExt) -> anyhow::Result<()> {
+    fixture.expect_api_key_repository().returning(|| {
+        let mut mock = MockApiKeyRepository::new();
+        mock.expect_create()
+            .withf(|event| {
+                event.name == "ci-bot"
+                    && event.scopes == vec![ApiKeyScope::BooksRead, ApiKeyScope::CheckoutsWrite]
+                    && event.key.starts_with(API_KEY_PREFIX)
+            })
+            .returning(|event| {
+                Ok(CreatedApiKey {
+                    api_key: ApiKey {
+                        id: ApiKeyId::new(),
+                        name: event.name.clone(),
+                        key_prefix: event.key_prefix().to_string(),
+                        scopes: event.scopes.clone(),
+                        expires_at: event.expires_at,
+                        last_used_at: None,
+                        created_at: Utc::now(),
+                    },
+                    key: event.key,
+                })
+            });
+        Arc::new(mock)
+    });
+
+    let app: axum::Router = make_router(fixture);
+
+    let req = Request::post(v1("/users/me/api-keys"))
+        .bearer()
+        .application_json()
+        .body(Body::from(
+            r#"{"name":"ci-bot","scopes":["books:read","checkouts:write"]}"#,
+        ))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+
+    let result = deserialize_json!(resp, CreatedApiKeyResponse);
+    assert!(result.key.starts_with(&result.api_key.key_prefix));
+    assert_eq!(result.api_key.expires_at, None);
+
+    Ok(())
+}
+
+#[rstest]
+#[case(r#"{"name":"ci-bot","scopes":[]}"#)]
+#[case(r#"{"name":"ci-bot","scopes":["books:read"],"expiresAt":"2000-01-01T00:00:00Z"}"#)]
+#[tokio::test]
+async fn create_api_key_with_invalid_request_is_rejected(
+    mut fixture: MockAppRegistryExt,
+    #[case] body: &'static str,
+) -> anyhow::Result<()> {
+    fixture
+        .expect_api_key_repository()
+        .returning(|| Arc::new(MockApiKeyRepository::new()));
+
+    let app: axum::Router = make_router(fixture);
+
+    let req = Request::post(v1("/users/me/api-keys"))
+        .bearer()
+        .application_json()
+        .body(Body::from(body))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn list_api_keys_does_not_return_keys(mut fixture: MockAppRegistryExt) -> anyhow::Result<()> {
+    fixture.expect_api_key_repository().returning(|| {
+        let mut mock = MockApiKeyRepository::new();
+        mock.expect_find_by_user_id().returning(|_| {
+            Ok(vec![ApiKey {
+                id: ApiKeyId::new(),
+                name: "slack-bot".into(),
+                key_prefix: "bmk_01234567".into(),
+                scopes: vec![ApiKeyScope::BooksRead],
+                expires_at: None,
+                last_used_at: None,
+                created_at: Utc::now(),
+            }])
+        });
+        Arc::new(mock)
+    });
+
+    let app: axum::Router = make_router(fixture);
+
+    let req = Request::get(v1("/users/me/api-keys"))
+        .bearer()
+        .body(Body::empty())?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let result = deserialize_json!(resp, ApiKeysResponse);
+    assert_eq!(result.items.len(), 1);
+    assert_eq!(result.items[0].key_prefix, "bmk_01234567");
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn delete_unknown_api_key_returns_not_found(
+    mut fixture: MockAppRegistryExt,
+) -> anyhow::Result<()> {
+    fixture.expect_api_key_repository().returning(|| {
+        let mut mock = MockApiKeyRepository::new();
+        mock.expect_delete().returning(|event| {
+            Err(AppError::EntityNotFound(
+                Message::new("api_key_not_found").arg("api_key_id", event.api_key_id),
+            ))
+        });
+        Arc::new(mock)
+    });
+
+    let app: axum::Router = make_router(fixture);
+
+    let req = Request::delete(v1(&format!("/users/me/api-keys/{}", ApiKeyId::new())))
+        .bearer()
+        .body(Body::empty())?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+    let result = deserialize_json!(resp, ProblemDetails);
+    assert_eq!(result.code, "api_key_not_found");
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn api_key_with_scope_is_accepted(mut fixture: MockAppRegistryExt) -> anyhow::Result<()> {
+    expect_api_key(&mut fixture, vec![ApiKeyScope::UsersRead]);
+
+    let app: axum::Router = make_router(fixture);
+
+    let req = Request::get(v1("/users/me"))
+        .header("Authorization", format!("Bearer {}", DUMMY_API_KEY))
+        .body(Body::empty())?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let result = deserialize_json!(resp, UserResponse);
+    assert_eq!(result.email, "dummy@example.com");
+
+    Ok(())
+}
+
+#[rstest]
+// 読み取りのスコープでは、書き込みの操作は行えない
+#[case(vec![ApiKeyScope::BooksRead], "POST", "/books/00000000-0000-0000-0000-000000000000/checkouts")]
+// 他の種類の操作は行えない
+#[case(vec![ApiKeyScope::BooksRead], "GET", "/users/me")]
+// APIキーの管理は、すべてのスコープを持つキーでも行えない
+#[case(vec![ApiKeyScope::UsersRead, ApiKeyScope::UsersWrite], "GET", "/users/me/api-keys")]
+// スコープを宣言していないルートは、APIキーでは実行できない
+#[case(vec![ApiKeyScope::UsersRead, ApiKeyScope::PoliciesRead], "GET", "/roles")]
+#[tokio::test]
+async fn api_key_without_scope_is_forbidden(
+    mut fixture: MockAppRegistryExt,
+    #[case] scopes: Vec<ApiKeyScope>,
+    #[case] method: &str,
+    #[case] endpoint: &str,
+) -> anyhow::Result<()> {
+    expect_api_key(&mut fixture, scopes);
+
+    let app: axum::Router = make_router(fixture);
+
+    let req = Request::builder()
+        .method(method)
+        .uri(v1(endpoint))
+        .header("Authorization", format!("Bearer {}", DUMMY_API_KEY))
+        .body(Body::empty())?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+    let result = deserialize_json!(resp, ProblemDetails);
+    assert_eq!(result.code, "forbidden_operation");
+
+    Ok(())
+}
@@ -0,0 +1,110 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+};
+use chrono::Utc;
+use kernel::{
+    model::{
+        audit::{AuditAction, AuditEvent},
+        id::{AuditEventId, BookId, UserId},
+        list::PaginatedList,
+    },
+    repository::audit::MockAuditRepository,
+};
+use registry::MockAppRegistryExt;
+use rstest::rstest;
+use tower::ServiceExt;
+
+use crate::{
+    deserialize_json,
+    helper::{fixture, fixture_admin, make_router, v1, TestRequestExt},
+};
+
+// 監査ログのリポジトリが呼ばれた回数を数えるモックを登録する
+fn count_audit_calls(registry: &mut MockAppRegistryExt) -> Arc<AtomicUsize> {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counted = calls.clone();
+    registry.expect_audit_repository().returning(move || {
+        let mut mock = MockAuditRepository::new();
+        let counted = counted.clone();
+        mock.expect_find_all()
+            .withf(|options| {
+                options.action == Some(AuditAction::BookDelete)
+                    && options.limit == 20
+                    && options.offset == 0
+            })
+            .returning(move |options| {
+                counted.fetch_add(1, Ordering::SeqCst);
+                Ok(PaginatedList {
+                    total: 1,
+                    limit: options.limit,
+                    offset: options.offset,
+                    items: vec![AuditEvent {
+                        id: AuditEventId::new(),
+                        actor: UserId::new(),
+                        action: AuditAction::BookDelete,
+                        target_id: BookId::new().to_string(),
+                        before: Some(
+                            serde_json::json!({"title": "RustによるWebアプリケーション開発"}),
+                        ),
+                        after: None,
+                        request_id: Some("dummy-request-id".to_string()),
+                        occurred_at: Utc::now(),
+                    }],
+                })
+            });
+        Arc::new(mock)
+    });
+    calls
+}
+
+fn list_audit_request() -> anyhow::Result<Request<Body>> {
+    let req = Request::get(v1("/audit?action=book.delete"))
+        .bearer()
+        .body(Body::empty())?;
+    Ok(req)
+}
+
+#[rstest]
+#[tokio::test]
+async fn admin_can_list_audit_events(mut fixture_admin: MockAppRegistryExt) -> anyhow::Result<()> {
+    let calls = count_audit_calls(&mut fixture_admin);
+
+    let app: axum::Router = make_router(fixture_admin);
+
+    let resp = app.oneshot(list_audit_request()?).await?;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+    let result = deserialize_json!(resp, serde_json::Value);
+    assert_eq!(result["total"], 1);
+    assert_eq!(result["items"][0]["action"], "book.delete");
+    assert_eq!(
+        result["items"][0]["before"]["title"],
+        "RustによるWebアプリケーション開発"
+    );
+    assert!(result["items"][0]["after"].is_null());
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn user_without_permission_cannot_list_audit_events(
+    mut fixture: MockAppRegistryExt,
+) -> anyhow::Result<()> {
+    let calls = count_audit_calls(&mut fixture);
+
+    let app: axum::Router = make_router(fixture);
+
+    let resp = app.oneshot(list_audit_request()?).await?;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+    assert_eq!(calls.load(Ordering::SeqCst), 0);
+
+    Ok(())
+}
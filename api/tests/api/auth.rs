@@ -0,0 +1,451 @@
+use std::{net::SocketAddr, sync::Arc, time::Duration};
+
+use axum::{
+    body::Body,
+    extract::ConnectInfo,
+    http::{Request, StatusCode},
+};
+use kernel::{
+    model::{
+        auth::{AccessToken, AuthTokens, PasswordReset, PasswordResetToken, RefreshToken},
+        id::UserId,
+        mfa::{MfaChallenge, VerifiedMfaChallenge},
+    },
+    repository::{
+        auth::{AuthRepository, MockAuthRepository},
+        mailer::MockMailer,
+        mfa::MockMfaRepository,
+    },
+};
+use registry::MockAppRegistryExt;
+use rstest::rstest;
+use shared::{
+    error::{AppError, ProblemDetails},
+    i18n::Message,
+};
+use tower::ServiceExt;
+
+use crate::{
+    deserialize_json,
+    helper::{
+        fixture_auth, fixture_registry, make_router, mock_mfa_repository, TestRequestExt,
+        TRUSTED_PROXY,
+    },
+};
+
+#[rstest]
+#[tokio::test]
+async fn login_returns_refresh_token(
+    mut fixture_auth: MockAppRegistryExt,
+) -> anyhow::Result<()> {
+    fixture_auth
+        .expect_mfa_repository()
+        .returning(|| Arc::new(mock_mfa_repository(false)));
+    let app: axum::Router = make_router(fixture_auth);
+
+    let req = Request::post("/auth/login")
+        .application_json()
+        .body(Body::from(
+            r#"{"email":"dummy@example.com","password":"password"}"#,
+        ))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let result = deserialize_json!(resp, serde_json::Value);
+    assert_eq!(result["accessToken"], "dummy");
+    assert_eq!(result["refreshToken"], "dummy-refresh");
+
+    Ok(())
+}
+
+#[rstest]
+#[case(Ok(()), StatusCode::OK)]
+#[case(Err(AppError::UnauthenticatedError), StatusCode::FORBIDDEN)]
+#[case(Err(AppError::TooManyLoginAttempts), StatusCode::TOO_MANY_REQUESTS)]
+#[tokio::test]
+async fn login_counts_failures_by_client_ip(
+    mut fixture_registry: MockAppRegistryExt,
+    #[case] verified: Result<(), AppError>,
+    #[case] expected_status: StatusCode,
+) -> anyhow::Result<()> {
+    // ログインではリポジトリを2回取り出すため、1つのモックを共有する
+    let mut mock = MockAuthRepository::new();
+    mock.expect_verify_user()
+        .withf(|event| {
+            // 信頼するプロキシを経由した場合は、X-Forwarded-Forのアドレスを接続元とする
+            event.email == "dummy@example.com"
+                && event.ip_address.as_deref() == Some("203.0.113.10")
+        })
+        .return_once(move |_| verified.map(|_| UserId::new()));
+    mock.expect_create_token().returning(|event| {
+        Ok(AuthTokens {
+            user_id: event.user_id,
+            access_token: AccessToken("dummy".into()),
+            refresh_token: RefreshToken("dummy-refresh".into()),
+        })
+    });
+    let mock: Arc<dyn AuthRepository> = Arc::new(mock);
+    fixture_registry
+        .expect_auth_repository()
+        .returning(move || mock.clone());
+    fixture_registry
+        .expect_mfa_repository()
+        .returning(|| Arc::new(mock_mfa_repository(false)));
+
+    let app: axum::Router = make_router(fixture_registry);
+
+    let req = Request::post("/auth/login")
+        .application_json()
+        .extension(ConnectInfo(SocketAddr::new(TRUSTED_PROXY.parse()?, 443)))
+        .header("X-Forwarded-For", "203.0.113.10, 10.0.0.1")
+        .body(Body::from(
+            r#"{"email":"dummy@example.com","password":"password"}"#,
+        ))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), expected_status);
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn login_ignores_forwarded_for_from_untrusted_peer(
+    mut fixture_registry: MockAppRegistryExt,
+) -> anyhow::Result<()> {
+    let mut mock = MockAuthRepository::new();
+    mock.expect_verify_user()
+        // 信頼しない接続元が送ったX-Forwarded-Forは使わず、接続元のアドレスで失敗を数える
+        .withf(|event| event.ip_address.as_deref() == Some("198.51.100.7"))
+        .return_once(|_| Err(AppError::UnauthenticatedError));
+    let mock: Arc<dyn AuthRepository> = Arc::new(mock);
+    fixture_registry
+        .expect_auth_repository()
+        .returning(move || mock.clone());
+
+    let app: axum::Router = make_router(fixture_registry);
+
+    let req = Request::post("/auth/login")
+        .application_json()
+        .extension(ConnectInfo(SocketAddr::new("198.51.100.7".parse()?, 50000)))
+        .header("X-Forwarded-For", "203.0.113.10")
+        .body(Body::from(
+            r#"{"email":"dummy@example.com","password":"password"}"#,
+        ))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn refresh_returns_rotated_tokens(
+    mut fixture_registry: MockAppRegistryExt,
+) -> anyhow::Result<()> {
+    let user_id = UserId::new();
+    fixture_registry
+        .expect_auth_repository()
+        .returning(move || {
+            let mut mock = MockAuthRepository::new();
+            mock.expect_refresh_token().returning(move |token| {
+                // 送ったリフレッシュトークンがそのまま渡る
+                assert_eq!(token.0, "refresh-1");
+                Ok(AuthTokens {
+                    user_id,
+                    access_token: AccessToken("access-2".into()),
+                    refresh_token: RefreshToken("refresh-2".into()),
+                })
+            });
+            Arc::new(mock)
+        });
+
+    let app: axum::Router = make_router(fixture_registry);
+
+    let req = Request::post("/auth/refresh")
+        .application_json()
+        .body(Body::from(r#"{"refreshToken":"refresh-1"}"#))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let result = deserialize_json!(resp, serde_json::Value);
+    assert_eq!(result["userId"], user_id.to_string());
+    assert_eq!(result["accessToken"], "access-2");
+    assert_eq!(result["refreshToken"], "refresh-2");
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn refresh_with_used_token_is_rejected(
+    mut fixture_registry: MockAppRegistryExt,
+) -> anyhow::Result<()> {
+    fixture_registry.expect_auth_repository().returning(|| {
+        let mut mock = MockAuthRepository::new();
+        mock.expect_refresh_token()
+            .returning(|_| Err(AppError::UnauthenticatedError));
+        Arc::new(mock)
+    });
+
+    let app: axum::Router = make_router(fixture_registry);
+
+    let req = Request::post("/auth/refresh")
+        .application_json()
+        .body(Body::from(r#"{"refreshToken":"refresh-1"}"#))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+    let result = deserialize_json!(resp, ProblemDetails);
+    assert_eq!(result.code, "unauthenticated");
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn password_reset_request_sends_mail(
+    mut fixture_registry: MockAppRegistryExt,
+) -> anyhow::Result<()> {
+    let user_id = UserId::new();
+    fixture_registry.expect_auth_repository().returning(move || {
+        let mut mock = MockAuthRepository::new();
+        mock.expect_create_password_reset()
+            .withf(|event| event.email == "eleazar.fig@example.com" && !event.token.is_empty())
+            .returning(move |event| {
+                Ok(Some(PasswordReset {
+                    user_id,
+                    email: event.email,
+                    token: PasswordResetToken(event.token),
+                    expires_in: 1800,
+                }))
+            });
+        Arc::new(mock)
+    });
+    // メールは応答を返した後に送るため、送られた宛先をチャネルで受け取る
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    fixture_registry.expect_mailer().returning(move || {
+        let mut mock = MockMailer::new();
+        let tx = tx.clone();
+        mock.expect_send_password_reset().returning(move |reset| {
+            let _ = tx.send(reset.email.clone());
+            Ok(())
+        });
+        Arc::new(mock)
+    });
+
+    let app: axum::Router = make_router(fixture_registry);
+
+    let req = Request::post("/auth/password-reset/request")
+        .application_json()
+        .body(Body::from(r#"{"email":"eleazar.fig@example.com"}"#))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), StatusCode::ACCEPTED);
+
+    let sent = tokio::time::timeout(Duration::from_secs(1), rx.recv()).await?;
+    assert_eq!(sent.as_deref(), Some("eleazar.fig@example.com"));
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn password_reset_request_for_unknown_email_is_accepted(
+    mut fixture_registry: MockAppRegistryExt,
+) -> anyhow::Result<()> {
+    // 登録されていないメールアドレスでも同じ応答を返し、メールは送らない
+    fixture_registry.expect_auth_repository().returning(|| {
+        let mut mock = MockAuthRepository::new();
+        mock.expect_create_password_reset().returning(|_| Ok(None));
+        Arc::new(mock)
+    });
+
+    let app: axum::Router = make_router(fixture_registry);
+
+    let req = Request::post("/auth/password-reset/request")
+        .application_json()
+        .body(Body::from(r#"{"email":"unknown@example.com"}"#))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), StatusCode::ACCEPTED);
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn password_reset_confirm_revokes_all_sessions(
+    mut fixture_registry: MockAppRegistryExt,
+) -> anyhow::Result<()> {
+    let user_id = UserId::new();
+    fixture_registry.expect_auth_repository().returning(move || {
+        let mut mock = MockAuthRepository::new();
+        mock.expect_reset_password()
+            .withf(|event| event.token == "reset-token" && event.new_password == "new-password")
+            .returning(move |_| Ok(user_id));
+        mock.expect_delete_all_sessions()
+            .withf(move |id| *id == user_id)
+            .returning(|_| Ok(()));
+        Arc::new(mock)
+    });
+
+    let app: axum::Router = make_router(fixture_registry);
+
+    let req = Request::post("/auth/password-reset/confirm")
+        .application_json()
+        .body(Body::from(
+            r#"{"token":"reset-token","newPassword":"new-password"}"#,
+        ))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn password_reset_confirm_with_used_token_is_rejected(
+    mut fixture_registry: MockAppRegistryExt,
+) -> anyhow::Result<()> {
+    fixture_registry.expect_auth_repository().returning(|| {
+        let mut mock = MockAuthRepository::new();
+        mock.expect_reset_password().returning(|_| {
+            Err(AppError::UnprocessableEntity(Message::new(
+                "invalid_password_reset_token",
+            )))
+        });
+        Arc::new(mock)
+    });
+
+    let app: axum::Router = make_router(fixture_registry);
+
+    let req = Request::post("/auth/password-reset/confirm")
+        .application_json()
+        .body(Body::from(
+            r#"{"token":"used-token","newPassword":"new-password"}"#,
+        ))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    let result = deserialize_json!(resp, ProblemDetails);
+    assert_eq!(result.code, "invalid_password_reset_token");
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn login_with_mfa_enabled_returns_challenge(
+    mut fixture_registry: MockAppRegistryExt,
+) -> anyhow::Result<()> {
+    // パスワードの検証のみではトークンを発行しない
+    fixture_registry.expect_auth_repository().returning(|| {
+        let mut mock = MockAuthRepository::new();
+        mock.expect_verify_user().returning(|_| Ok(UserId::new()));
+        Arc::new(mock)
+    });
+    fixture_registry.expect_mfa_repository().returning(|| {
+        let mut mock = mock_mfa_repository(true);
+        mock.expect_create_challenge().returning(|event| {
+            Ok(MfaChallenge {
+                challenge_token: event.challenge_token,
+                expires_in: 300,
+            })
+        });
+        Arc::new(mock)
+    });
+
+    let app: axum::Router = make_router(fixture_registry);
+
+    let req = Request::post("/auth/login")
+        .application_json()
+        .body(Body::from(
+            r#"{"email":"admin@example.com","password":"password"}"#,
+        ))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), StatusCode::ACCEPTED);
+
+    let result = deserialize_json!(resp, serde_json::Value);
+    assert!(result["challengeToken"].as_str().is_some_and(|t| !t.is_empty()));
+    assert_eq!(result["expiresIn"], 300);
+    assert!(result.get("accessToken").is_none());
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn login_mfa_issues_mfa_verified_tokens(
+    mut fixture_registry: MockAppRegistryExt,
+) -> anyhow::Result<()> {
+    let user_id = UserId::new();
+    fixture_registry.expect_mfa_repository().returning(move || {
+        let mut mock = MockMfaRepository::new();
+        mock.expect_verify_challenge()
+            .withf(|event| event.challenge_token == "challenge-1" && event.code == "123456")
+            .returning(move |_| {
+                Ok(VerifiedMfaChallenge {
+                    user_id,
+                    user_agent: Some("curl/8.0".into()),
+                    ip_address: Some("203.0.113.10".into()),
+                })
+            });
+        Arc::new(mock)
+    });
+    fixture_registry.expect_auth_repository().returning(move || {
+        let mut mock = MockAuthRepository::new();
+        // 2段階認証を経たセッションとして、パスワードの検証時の接続元でトークンを発行する
+        mock.expect_create_token()
+            .withf(move |event| {
+                event.user_id == user_id
+                    && event.mfa_verified
+                    && event.ip_address.as_deref() == Some("203.0.113.10")
+            })
+            .returning(|event| {
+                Ok(AuthTokens {
+                    user_id: event.user_id,
+                    access_token: AccessToken("access-mfa".into()),
+                    refresh_token: RefreshToken("refresh-mfa".into()),
+                })
+            });
+        Arc::new(mock)
+    });
+
+    let app: axum::Router = make_router(fixture_registry);
+
+    let req = Request::post("/auth/login/mfa")
+        .application_json()
+        .body(Body::from(
+            r#"{"challengeToken":"challenge-1","code":"123456"}"#,
+        ))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let result = deserialize_json!(resp, serde_json::Value);
+    assert_eq!(result["accessToken"], "access-mfa");
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn login_mfa_with_invalid_code_is_rejected(
+    mut fixture_registry: MockAppRegistryExt,
+) -> anyhow::Result<()> {
+    fixture_registry.expect_mfa_repository().returning(|| {
+        let mut mock = MockMfaRepository::new();
+        mock.expect_verify_challenge()
+            .returning(|_| Err(AppError::UnauthenticatedError));
+        Arc::new(mock)
+    });
+
+    let app: axum::Router = make_router(fixture_registry);
+
+    let req = Request::post("/auth/login/mfa")
+        .application_json()
+        .body(Body::from(
+            r#"{"challengeToken":"challenge-1","code":"000000"}"#,
+        ))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+    Ok(())
+}
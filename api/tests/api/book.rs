@@ -7,18 +7,20 @@ use tower::ServiceExt;
 
 use crate::{
     deserialize_json, 
-    helper::{fixture, make_router, v1, TestRequestExt}
+    helper::{fixture, fixture_admin, make_router, v1, TestRequestExt}
+};
+use api::model::book::{
+    CreateBookRequest, ImportBookRowStatus, ImportBooksResponse, PaginatedBookResponse,
 };
-use api::model::book::PaginatedBookResponse;
 
 use kernel::{
     model::{
-        book::Book,
+        book::{Book, BookImportOutcome, BookMetadata},
         id::{BookId, UserId},
         list::PaginatedList,
         user::BookOwner,
     },
-    repository::book::MockBookRepository,
+    repository::{book::MockBookRepository, metadata::MockBookMetadataProvider},
 };
 
 #[rstest]
@@ -26,6 +28,8 @@ use kernel::{
 #[case("/books?limit=50", 50, 0)]
 #[case("/books?limit=50&offset=20", 50, 20)]
 #[case("/books?offset=20", 20, 20)]
+#[case("/books?q=rust&available=true&sort=title&direction=asc", 20, 0)]
+#[case("/books?isbn=4-7980-6170-0", 20, 0)]
 #[tokio::test]
 async fn show_book_list_with_query_200(
     // 1. fixtureとしてmockオブジェクトを渡している
@@ -43,6 +47,7 @@ async fn show_book_list_with_query_200(
             let items = vec![Book {
                 id: book_id,
                 title: "RustによるWebアプリケーション開発".to_string(),
+                title_kana: None,
                 isbn: "".to_string(),
                 author: "Yuki Toyoda".to_string(),
                 description: "RustによるWebアプリケーション開発".to_string(),
@@ -50,7 +55,9 @@ async fn show_book_list_with_query_200(
                     id: UserId::new(),
                     name: "Yuki Toyoda".to_string(),
                 },
-                checkout: None,
+                total_copies: 1,
+                available_copies: 1,
+                checkouts: vec![],
             }];
             Ok(PaginatedList {
                 total: 1, 
@@ -66,7 +73,7 @@ async fn show_book_list_with_query_200(
     let app: axum::Router = make_router(fixture);
 
     // 4.リクエストを作成・送信し、レスポンスのステータスコードを検証する　
-    let req = Request::get(&v1(path)).bearer().body(Body::empty())?;
+    let req = Request::get(v1(path)).bearer().body(Body::empty())?;
     let resp = app.oneshot(req).await?;
     assert_eq!(resp.status(), axum::http::StatusCode::OK);
 
@@ -82,6 +89,9 @@ async fn show_book_list_with_query_200(
 #[rstest]
 #[case("/books?limit=-1")]
 #[case("/books?offset=aaa")]
+#[case("/books?sort=isbn")]
+#[case("/books?q=")]
+#[case("/books?isbn=abc")]
 #[tokio::test]
 async fn show_book_list_with_query_400(
     mut fixture: MockAppRegistryExt,
@@ -96,6 +106,7 @@ async fn show_book_list_with_query_400(
             let items = vec![Book {
                 id: book_id,
                 title: "RustによるWebアプリケーション開発".to_string(),
+                title_kana: None,
                 isbn: "".to_string(),
                 author: "Yuki Toyoda".to_string(),
                 description: "RustによるWebアプリケーション開発".to_string(),
@@ -103,7 +114,9 @@ async fn show_book_list_with_query_400(
                     id: UserId::new(),
                     name: "Yuki Toyoda".to_string(),
                 },
-                checkout: None,
+                total_copies: 1,
+                available_copies: 1,
+                checkouts: vec![],
             }];
             Ok(PaginatedList {
                 total: 1, 
@@ -119,10 +132,246 @@ async fn show_book_list_with_query_400(
     let app: axum::Router = make_router(fixture);
 
     // 4.リクエストを作成・送信し、レスポンスのステータスコードを検証する　
-    let req = Request::get(&v1(path)).bearer().body(Body::empty())?;
+    let req = Request::get(v1(path)).bearer().body(Body::empty())?;
     let resp = app.oneshot(req).await?;
     assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
 
     // 6.テストが成功していることを示す　
     Ok(())
-}
\ No newline at end of file
+}
+#[rstest]
+#[case("/books/lookup?isbn=4-06-536957-6", StatusCode::OK)]
+#[case("/books/lookup?isbn=9784798061702", StatusCode::NOT_FOUND)]
+#[case("/books/lookup?isbn=abc", StatusCode::BAD_REQUEST)]
+#[tokio::test]
+async fn lookup_book_by_isbn(
+    mut fixture: MockAppRegistryExt,
+    #[case] path: &str,
+    #[case] expected_status: StatusCode,
+) -> anyhow::Result<()> {
+    // 1. 特定のISBNの書誌情報のみを返すようにモックを設定する
+    fixture.expect_book_metadata_provider().returning(|| {
+        let mut mock = MockBookMetadataProvider::new();
+        mock.expect_find_by_isbn().returning(|isbn| {
+            Ok((isbn.as_str() == "9784065369579").then(|| BookMetadata {
+                isbn: isbn.clone(),
+                title: "RustによるWebアプリケーション開発".to_string(),
+                title_kana: None,
+                author: "豊田優貴、松本健太郎、吉川哲史".to_string(),
+                description: "".to_string(),
+            }))
+        });
+        Arc::new(mock)
+    });
+
+    let app: axum::Router = make_router(fixture);
+
+    let req = Request::get(v1(path)).bearer().body(Body::empty())?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), expected_status);
+
+    // 2. 見つかった場合は、ISBN-13の正規形を含む登録リクエストの形で返る
+    if expected_status == StatusCode::OK {
+        let result = deserialize_json!(resp, CreateBookRequest);
+        assert_eq!(result.isbn.as_str(), "9784065369579");
+        assert_eq!(result.copies, 1);
+    }
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn import_books_reports_each_row(
+    mut fixture_admin: MockAppRegistryExt,
+) -> anyhow::Result<()> {
+    // 1. 検証を通った行のみがリポジトリに渡され、所有者が存在しない行は拒否される
+    fixture_admin.expect_book_repository().returning(|| {
+        let mut mock = MockBookRepository::new();
+        mock.expect_create_many().returning(|events, _| {
+            assert_eq!(events.len(), 2);
+            Ok(events
+                .into_iter()
+                .map(|e| match e.owner_email.as_str() {
+                    "nobody@example.com" => {
+                        BookImportOutcome::Rejected("not found".into())
+                    }
+                    _ => BookImportOutcome::Created(BookId::new()),
+                })
+                .collect())
+        });
+        Arc::new(mock)
+    });
+
+    let app: axum::Router = make_router(fixture_admin);
+
+    let csv = "title,author,isbn,description,owner_email\n\
+        型システム入門,Benjamin C. Pierce,978-4274069116,,eleazar.fig@example.com\n\
+        不正なISBN,著者,abc,,eleazar.fig@example.com\n\
+        ,書名なし,978-4798061702,,eleazar.fig@example.com\n\
+        実践Rustプログラミング入門,初田直也他,4-7980-6170-0,,nobody@example.com\n";
+    let req = Request::post(v1("/books/import"))
+        .bearer()
+        .header("Content-Type", "text/csv")
+        .body(Body::from(csv))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    // 2. 行番号の順に、行ごとの結果が返る
+    let result = deserialize_json!(resp, ImportBooksResponse);
+    assert_eq!(result.accepted, 1);
+    assert_eq!(result.rejected, 3);
+    let lines = result.rows.iter().map(|r| r.line).collect::<Vec<_>>();
+    assert_eq!(lines, vec![2, 3, 4, 5]);
+    assert_eq!(result.rows[0].status, ImportBookRowStatus::Accepted);
+    assert!(result.rows[1..]
+        .iter()
+        .all(|r| r.status == ImportBookRowStatus::Rejected && !r.errors.is_empty()));
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn import_books_forbidden_for_user(fixture: MockAppRegistryExt) -> anyhow::Result<()> {
+    let app: axum::Router = make_router(fixture);
+
+    let req = Request::post(v1("/books/import"))
+        .bearer()
+        .header("Content-Type", "text/csv")
+        .body(Body::from("title,author,isbn,description,owner_email\n"))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+    Ok(())
+}
+
+
+#[rstest]
+#[case("/books/export", "text/csv; charset=utf-8")]
+#[case("/books/export?format=jsonl", "application/x-ndjson")]
+#[case("/books/export?format=marcxml", "application/marcxml+xml")]
+#[tokio::test]
+async fn export_books_in_each_format(
+    mut fixture: MockAppRegistryExt,
+    #[case] path: &str,
+    #[case] expected_content_type: &str,
+) -> anyhow::Result<()> {
+    // 1. 区切り文字やXMLの特殊文字を含む蔵書を2冊返すようにモックを設定する
+    fixture.expect_book_repository().returning(|| {
+        let mut mock = MockBookRepository::new();
+        mock.expect_export_all().returning(|| {
+            let books = [
+                ("Rust, 基礎編", None),
+                ("<Rust> & 応用編", Some("ラスト オウヨウヘン")),
+            ]
+            .map(|(title, title_kana)| {
+                Ok(Book {
+                    id: BookId::new(),
+                    title: title.to_string(),
+                    title_kana: title_kana.map(ToString::to_string),
+                    isbn: "9784798061702".to_string(),
+                    author: "Yuki Toyoda".to_string(),
+                    description: "".to_string(),
+                    owner: BookOwner {
+                        id: UserId::new(),
+                        name: "Yuki Toyoda".to_string(),
+                    },
+                    total_copies: 1,
+                    available_copies: 1,
+                    checkouts: vec![],
+                })
+            });
+            Ok(futures::StreamExt::boxed(futures::stream::iter(books)))
+        });
+        Arc::new(mock)
+    });
+
+    let app: axum::Router = make_router(fixture);
+
+    let req = Request::get(v1(path)).bearer().body(Body::empty())?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.headers()["Content-Type"], expected_content_type);
+
+    // 2. 形式ごとに、値がエスケープされて出力される
+    let bytes = axum::body::to_bytes(resp.into_body(), usize::MAX).await?;
+    let body = String::from_utf8(bytes.to_vec())?;
+    match expected_content_type {
+        "text/csv; charset=utf-8" => {
+            let lines = body.lines().collect::<Vec<_>>();
+            assert_eq!(lines.len(), 3);
+            assert!(lines[0].starts_with("book_id,title,title_kana,author,isbn"));
+            assert!(lines[1].contains("\"Rust, 基礎編\""));
+        }
+        "application/x-ndjson" => {
+            assert_eq!(body.lines().count(), 2);
+            for line in body.lines() {
+                serde_json::from_str::<serde_json::Value>(line)?;
+            }
+        }
+        _ => {
+            assert!(body.ends_with("</collection>\n"));
+            assert_eq!(body.matches("<record>").count(), 2);
+            assert!(body.contains("&lt;Rust&gt; &amp; 応用編"));
+            // 書名の読みは、245とリンクした880として出力される
+            assert!(body.contains(
+                "<datafield tag=\"245\" ind1=\" \" ind2=\" \">\n      <subfield code=\"6\">880-01</subfield>"
+            ));
+            assert!(body.contains(
+                "<subfield code=\"6\">245-01</subfield>\n      <subfield code=\"a\">ラスト オウヨウヘン</subfield>"
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn export_marcxml_drops_control_characters(
+    mut fixture: MockAppRegistryExt,
+) -> anyhow::Result<()> {
+    // 1. 書名に制御文字を含む蔵書を返すようにモックを設定する
+    fixture.expect_book_repository().returning(|| {
+        let mut mock = MockBookRepository::new();
+        mock.expect_export_all().returning(|| {
+            let book = Book {
+                id: BookId::new(),
+                title: "Rust\u{1}入門\u{1f}".to_string(),
+                title_kana: None,
+                isbn: "9784798061702".to_string(),
+                author: "Yuki Toyoda".to_string(),
+                description: "1行目\n2行目".to_string(),
+                owner: BookOwner {
+                    id: UserId::new(),
+                    name: "Yuki Toyoda".to_string(),
+                },
+                total_copies: 1,
+                available_copies: 1,
+                checkouts: vec![],
+            };
+            Ok(futures::StreamExt::boxed(futures::stream::iter([Ok(book)])))
+        });
+        Arc::new(mock)
+    });
+
+    let app: axum::Router = make_router(fixture);
+
+    let req = Request::get(v1("/books/export?format=marcxml"))
+        .bearer()
+        .body(Body::empty())?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    // 2. XML 1.0で使えない制御文字は取り除かれ、改行はそのまま出力される
+    let bytes = axum::body::to_bytes(resp.into_body(), usize::MAX).await?;
+    let body = String::from_utf8(bytes.to_vec())?;
+    assert!(!body
+        .chars()
+        .any(|c| c < ' ' && !matches!(c, '\t' | '\n' | '\r')));
+    assert!(body.contains("<subfield code=\"a\">Rust入門</subfield>"));
+    assert!(body.contains("<subfield code=\"a\">1行目\n2行目</subfield>"));
+
+    Ok(())
+}
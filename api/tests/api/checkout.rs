@@ -0,0 +1,110 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+};
+use kernel::{
+    model::id::{BookId, CheckoutId, UserId},
+    repository::checkout::MockCheckoutRepository,
+};
+use registry::MockAppRegistryExt;
+use rstest::rstest;
+use tower::ServiceExt;
+
+use crate::helper::{fixture, fixture_admin, make_router, v1, TestRequestExt};
+
+// 貸出のリポジトリが呼ばれた回数を数えるモックを登録する
+fn count_checkout_calls(registry: &mut MockAppRegistryExt, borrower: UserId) -> Arc<AtomicUsize> {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counted = calls.clone();
+    registry.expect_checkout_repository().returning(move || {
+        let mut mock = MockCheckoutRepository::new();
+        let create_calls = counted.clone();
+        mock.expect_create()
+            .withf(move |event| {
+                event.checked_out_by == borrower && event.performed_by != borrower
+            })
+            .returning(move |_| {
+                create_calls.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            });
+        let return_calls = counted.clone();
+        mock.expect_update_returned()
+            .withf(|event| event.any_borrower)
+            .returning(move |_| {
+                return_calls.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            });
+        Arc::new(mock)
+    });
+    calls
+}
+
+fn checkout_on_behalf_request(borrower: UserId) -> anyhow::Result<Request<Body>> {
+    let req = Request::post(v1(&format!("/books/{}/checkouts/on-behalf", BookId::new())))
+        .bearer()
+        .application_json()
+        .body(Body::from(format!(r#"{{"userId":"{}"}}"#, borrower)))?;
+    Ok(req)
+}
+
+fn force_return_request() -> anyhow::Result<Request<Body>> {
+    let req = Request::put(v1(&format!(
+        "/books/{}/checkouts/{}/force-returned",
+        BookId::new(),
+        CheckoutId::new()
+    )))
+    .bearer()
+    .body(Body::empty())?;
+    Ok(req)
+}
+
+#[rstest]
+#[tokio::test]
+async fn staff_can_checkout_and_force_return_for_other_user(
+    mut fixture_admin: MockAppRegistryExt,
+) -> anyhow::Result<()> {
+    let borrower = UserId::new();
+    let calls = count_checkout_calls(&mut fixture_admin, borrower);
+
+    let app: axum::Router = make_router(fixture_admin);
+
+    let resp = app
+        .clone()
+        .oneshot(checkout_on_behalf_request(borrower)?)
+        .await?;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let resp = app.oneshot(force_return_request()?).await?;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn user_without_permission_cannot_act_for_other_user(
+    mut fixture: MockAppRegistryExt,
+) -> anyhow::Result<()> {
+    let borrower = UserId::new();
+    let calls = count_checkout_calls(&mut fixture, borrower);
+
+    let app: axum::Router = make_router(fixture);
+
+    let resp = app
+        .clone()
+        .oneshot(checkout_on_behalf_request(borrower)?)
+        .await?;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+    let resp = app.oneshot(force_return_request()?).await?;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+    assert_eq!(calls.load(Ordering::SeqCst), 0);
+
+    Ok(())
+}
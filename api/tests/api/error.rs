@@ -0,0 +1,155 @@
+use std::sync::Arc;
+
+use axum::{body::Body, http::Request, http::StatusCode};
+use kernel::{model::id::BookId, repository::book::MockBookRepository};
+use registry::MockAppRegistryExt;
+use rstest::rstest;
+use shared::{
+    error::{AppError, ProblemDetails},
+    i18n::Message,
+};
+use tower::ServiceExt;
+
+use crate::{
+    deserialize_json,
+    helper::{fixture, make_router, v1, TestRequestExt},
+};
+
+#[rstest]
+#[tokio::test]
+async fn validation_error_returns_field_errors(
+    fixture: MockAppRegistryExt,
+) -> anyhow::Result<()> {
+    let app: axum::Router = make_router(fixture);
+
+    // 1. クライアントが送ったリクエストIDは、そのままレスポンスに含まれる
+    let req = Request::get(v1("/books?limit=-1"))
+        .bearer()
+        .header("x-request-id", "req-0001")
+        .body(Body::empty())?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(resp.headers()["Content-Type"], "application/problem+json");
+    assert_eq!(resp.headers()["x-request-id"], "req-0001");
+
+    // 2. 検証に失敗した項目が、項目名とともに返る
+    let result = deserialize_json!(resp, ProblemDetails);
+    assert_eq!(result.status, 400);
+    assert_eq!(result.code, "validation_error");
+    assert_eq!(result.request_id.as_deref(), Some("req-0001"));
+    assert_eq!(result.errors.len(), 1);
+    assert_eq!(result.errors[0].field, "limit");
+    // 既定の言語(日本語)で返る
+    assert_eq!(result.detail, "入力値が正しくありません。");
+    assert_eq!(result.errors[0].message, "0以上の値を入力してください。");
+
+    Ok(())
+}
+
+#[rstest]
+#[case("en-US,en;q=0.9,ja;q=0.8", "en", "Book ({book_id}) was not found.")]
+#[case("ja", "ja", "書籍({book_id})が見つかりませんでした。")]
+// 対応していない言語の場合は既定の言語で返る
+#[case("fr", "ja", "書籍({book_id})が見つかりませんでした。")]
+#[tokio::test]
+async fn error_message_follows_accept_language(
+    mut fixture: MockAppRegistryExt,
+    #[case] accept_language: &str,
+    #[case] expected_language: &str,
+    #[case] expected_detail: &str,
+) -> anyhow::Result<()> {
+    fixture.expect_book_repository().returning(|| {
+        let mut mock = MockBookRepository::new();
+        mock.expect_find_by_id().returning(|_| Ok(None));
+        Arc::new(mock)
+    });
+
+    let app: axum::Router = make_router(fixture);
+
+    let book_id: BookId = "9890736e-a4e4-461a-a77d-eac3517ef11b".parse()?;
+    let req = Request::get(v1(&format!("/books/{book_id}")))
+        .bearer()
+        .header("Accept-Language", accept_language)
+        .body(Body::empty())?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    assert_eq!(resp.headers()["Content-Language"], expected_language);
+
+    // エラーコードは言語によらず同じものが返る
+    let result = deserialize_json!(resp, ProblemDetails);
+    assert_eq!(result.code, "book_not_found");
+    // IDはBookIdの表示形式でメッセージに埋め込まれる
+    let expected_detail = expected_detail.replace("{book_id}", &format!("{book_id}"));
+    assert_eq!(result.detail, expected_detail);
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn validation_error_in_english(fixture: MockAppRegistryExt) -> anyhow::Result<()> {
+    let app: axum::Router = make_router(fixture);
+
+    let req = Request::get(v1("/books?limit=-1"))
+        .bearer()
+        .header("Accept-Language", "en")
+        .body(Body::empty())?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    let result = deserialize_json!(resp, ProblemDetails);
+    assert_eq!(result.detail, "The request contains invalid values.");
+    assert_eq!(result.errors[0].message, "lower than 0");
+
+    Ok(())
+}
+
+#[rstest]
+#[case(
+    AppError::UnprocessableEntity(
+        Message::new("checkout_already_exists").arg("book_id", "9890736e-a4e4-461a-a77d-eac3517ef11b")
+    ),
+    StatusCode::UNPROCESSABLE_ENTITY,
+    "checkout_already_exists"
+)]
+#[case(
+    AppError::ConversionEntityError("invalid row: password_hash=...".into()),
+    StatusCode::INTERNAL_SERVER_ERROR,
+    "internal_server_error"
+)]
+#[tokio::test]
+async fn app_error_returns_problem_details(
+    mut fixture: MockAppRegistryExt,
+    #[case] error: AppError,
+    #[case] expected_status: StatusCode,
+    #[case] expected_code: &str,
+) -> anyhow::Result<()> {
+    let message = error.to_string();
+    fixture.expect_book_repository().return_once(move || {
+        let mut mock = MockBookRepository::new();
+        mock.expect_find_by_id().return_once(move |_| Err(error));
+        Arc::new(mock)
+    });
+
+    let app: axum::Router = make_router(fixture);
+
+    let req = Request::get(v1("/books/9890736e-a4e4-461a-a77d-eac3517ef11b"))
+        .bearer()
+        .body(Body::empty())?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), expected_status);
+    // リクエストIDを送らなかった場合は、サーバーで振ったIDが返る
+    let request_id = resp.headers()["x-request-id"].to_str()?.to_string();
+
+    let result = deserialize_json!(resp, ProblemDetails);
+    assert_eq!(result.code, expected_code);
+    assert_eq!(result.request_id, Some(request_id));
+    if expected_status.is_server_error() {
+        // 内部のエラーの内容はクライアントに返さない
+        assert!(!result.detail.contains(&message));
+    } else {
+        assert_eq!(result.detail, message);
+    }
+
+    Ok(())
+}
@@ -1,45 +1,127 @@
 use std::sync::Arc;
 
-use api::route::{auth, v1};
-use axum::{http::request::Builder, Router};
+use api::{
+    extractor::TrustedProxies,
+    middleware::{assign_request_id, negotiate_language, track_http_metrics},
+    route::{auth, metrics, v1},
+};
+use axum::{http::request::Builder, middleware, Extension, Router};
 use kernel::{
-    model::{auth::AccessToken, id::UserId, role::Role, user::User},
-    repository::{auth::MockAuthRepository, user::MockUserRepository},
+    model::{
+        auth::{AccessToken, AuthTokens, AuthorizedSession, RefreshToken},
+        id::UserId,
+        role::{Permission, Role},
+        user::User,
+    },
+    repository::{
+        auth::MockAuthRepository, mfa::MockMfaRepository, role::MockRoleRepository,
+        user::MockUserRepository,
+    },
 };
-use registry::MockAppRegistryExt;
+use registry::{AppRegistry, MockAppRegistryExt};
 use rstest::fixture;
+use shared::{i18n::Language, metrics::Metrics};
 
 pub fn v1(endpoint: &str) -> String {
     format!("/api/v1{}", endpoint)
 }
 
 pub fn make_router(registry: MockAppRegistryExt) -> Router {
+    let registry: AppRegistry = Arc::new(registry);
     Router::new()
         .merge(v1::routes())
         .merge(auth::routes())
-        .with_state(Arc::new(registry))
+        .route_layer(middleware::from_fn_with_state(
+            registry.clone(),
+            track_http_metrics,
+        ))
+        .layer(middleware::from_fn_with_state(
+            Language::Ja,
+            negotiate_language,
+        ))
+        .merge(metrics::routes())
+        .layer(Extension(TrustedProxies(Arc::new(vec![
+            TRUSTED_PROXY.parse().unwrap(),
+        ]))))
+        .layer(middleware::from_fn(assign_request_id))
+        .with_state(registry)
 }
 
+// テストで`X-Forwarded-For`を信頼するプロキシのアドレス
+pub const TRUSTED_PROXY: &str = "10.0.0.1";
+
 #[fixture]
 pub fn fixture_registry() -> MockAppRegistryExt {
-    MockAppRegistryExt::new()
+    let mut registry = MockAppRegistryExt::new();
+    // リクエストの計測はすべてのAPIで行われるため、共通で設定する
+    let metrics = Arc::new(Metrics::new().unwrap());
+    registry
+        .expect_metrics()
+        .returning(move || metrics.clone());
+    registry
+}
+
+// 認証が必要なAPIの呼び出しを通すための、AuthRepositoryのモック
+// テストごとに期待する呼び出しを追加してから使う
+pub fn mock_auth_repository() -> MockAuthRepository {
+    let mut mock_auth_repository = MockAuthRepository::new();
+    mock_auth_repository
+        .expect_fetch_session_from_token()
+        .returning(|_| {
+            Ok(Some(AuthorizedSession {
+                user_id: UserId::new(),
+                mfa_satisfied: true,
+            }))
+        });
+    mock_auth_repository
+        .expect_verify_user()
+        .returning(|_| Ok(UserId::new()));
+    mock_auth_repository
+        .expect_create_token()
+        .returning(|event| {
+            Ok(AuthTokens {
+                user_id: event.user_id,
+                access_token: AccessToken("dummy".into()),
+                refresh_token: RefreshToken("dummy-refresh".into()),
+            })
+        });
+    mock_auth_repository
+        .expect_extend_token()
+        .returning(|_| Ok(()));
+    mock_auth_repository
+}
+
+// ログインの2段階目の認証を求めるかどうかを切り替えるための、MfaRepositoryのモック
+pub fn mock_mfa_repository(totp_enabled: bool) -> MockMfaRepository {
+    let mut mock_mfa_repository = MockMfaRepository::new();
+    mock_mfa_repository
+        .expect_is_totp_enabled()
+        .returning(move |_| Ok(totp_enabled));
+    mock_mfa_repository
+}
+
+// 権限が必要なAPIの呼び出しに使う、RoleRepositoryのモック
+// Adminはすべての権限を持ち、Userは蔵書の登録のみ、それ以外のロールは権限を持たない
+pub fn mock_role_repository() -> MockRoleRepository {
+    let mut mock_role_repository = MockRoleRepository::new();
+    mock_role_repository
+        .expect_find_permissions()
+        .returning(|role| match role {
+            Role::Admin => Ok(Permission::all()),
+            Role::User => Ok(vec![Permission::BookCreate]),
+            _ => Ok(Vec::new()),
+        });
+    mock_role_repository
 }
 
 #[fixture]
 pub fn fixture_auth(mut fixture_registry: MockAppRegistryExt) -> MockAppRegistryExt {
-    fixture_registry.expect_auth_repository().returning(|| {
-        let mut mock_auth_repository = MockAuthRepository::new();
-        mock_auth_repository
-            .expect_fetch_user_id_from_token()
-            .returning(|_| Ok(Some(UserId::new())));
-        mock_auth_repository
-            .expect_verify_user()
-            .returning(|_, _| Ok(UserId::new()));
-        mock_auth_repository
-            .expect_create_token()
-            .returning(|_| Ok(AccessToken("dummy".into())));
-        Arc::new(mock_auth_repository)
-    });
+    fixture_registry
+        .expect_auth_repository()
+        .returning(|| Arc::new(mock_auth_repository()));
+    fixture_registry
+        .expect_role_repository()
+        .returning(|| Arc::new(mock_role_repository()));
     fixture_registry
 }
 
@@ -62,6 +144,26 @@ pub fn fixture(mut fixture_auth: MockAppRegistryExt) -> MockAppRegistryExt {
     fixture_auth
 }
 
+// Adminのみが実行できるAPIのテストに使う
+#[fixture]
+pub fn fixture_admin(mut fixture_auth: MockAppRegistryExt) -> MockAppRegistryExt {
+    fixture_auth.expect_user_repository().returning(|| {
+        let mut mock_user_repository = MockUserRepository::new();
+        mock_user_repository
+            .expect_find_current_user()
+            .returning(|id| {
+                Ok(Some(User {
+                    id,
+                    name: "dummy-admin".to_string(),
+                    email: "admin@example.com".to_string(),
+                    role: Role::Admin,
+                }))
+            });
+        Arc::new(mock_user_repository)
+    });
+    fixture_auth
+}
+
 pub trait TestRequestExt {
     fn bearer(self) -> Builder;
     fn application_json(self) -> Builder;
@@ -1,2 +1,12 @@
+mod api_key;
+mod audit;
+mod auth;
 mod book;
-mod helper;
\ No newline at end of file
+mod checkout;
+mod error;
+mod helper;
+mod metrics;
+mod mfa;
+mod openapi;
+mod role;
+mod user;
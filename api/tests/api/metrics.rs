@@ -0,0 +1,72 @@
+use std::sync::Arc;
+
+use axum::{body::Body, http::Request, http::StatusCode};
+use kernel::{
+    model::{
+        list::PaginatedList,
+        metrics::{DbPoolStatus, MetricsSnapshot},
+    },
+    repository::{book::MockBookRepository, metrics::MockMetricsRepository},
+};
+use registry::MockAppRegistryExt;
+use rstest::rstest;
+use tower::ServiceExt;
+
+use crate::helper::{fixture, make_router, v1, TestRequestExt};
+
+#[rstest]
+#[tokio::test]
+async fn render_metrics_after_request(mut fixture: MockAppRegistryExt) -> anyhow::Result<()> {
+    // 1. 集計値を返すモックと、一覧取得のモックを設定する
+    fixture.expect_metrics_repository().returning(|| {
+        let mut mock = MockMetricsRepository::new();
+        mock.expect_snapshot().returning(|| {
+            Ok(MetricsSnapshot {
+                db_pool: DbPoolStatus {
+                    size: 3,
+                    idle: 1,
+                    max: 10,
+                },
+                active_checkouts: 5,
+                overdue_checkouts: 2,
+            })
+        });
+        Arc::new(mock)
+    });
+    fixture.expect_book_repository().returning(|| {
+        let mut mock = MockBookRepository::new();
+        mock.expect_find_all().returning(|opt| {
+            Ok(PaginatedList {
+                total: 0,
+                limit: opt.limit,
+                offset: opt.offset,
+                items: vec![],
+            })
+        });
+        Arc::new(mock)
+    });
+
+    let app: axum::Router = make_router(fixture);
+
+    // 2. 計測対象のAPIを呼び出した後に、メトリクスを取得する
+    let req = Request::get(v1("/books")).bearer().body(Body::empty())?;
+    let resp = app.clone().oneshot(req).await?;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let req = Request::get("/metrics").body(Body::empty())?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let bytes = axum::body::to_bytes(resp.into_body(), usize::MAX).await?;
+    let body = String::from_utf8(bytes.to_vec())?;
+
+    // 3. ルートのテンプレートとステータスコードごとの処理時間と、現在値が含まれる
+    assert!(body.contains(
+        r#"book_manager_http_request_duration_seconds_count{method="GET",route="/api/v1/books",status="200"} 1"#
+    ));
+    assert!(body.contains("book_manager_active_checkouts 5"));
+    assert!(body.contains("book_manager_overdue_checkouts 2"));
+    assert!(body.contains(r#"book_manager_db_pool_connections{state="active"} 2"#));
+
+    Ok(())
+}
@@ -0,0 +1,195 @@
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+};
+use kernel::{
+    model::{
+        auth::AuthorizedSession,
+        id::UserId,
+        mfa::{event::RECOVERY_CODE_COUNT, TotpEnrollment},
+        role::{Permission, Role},
+        user::User,
+    },
+    repository::{
+        auth::MockAuthRepository, checkout::MockCheckoutRepository, mfa::MockMfaRepository,
+        role::MockRoleRepository, user::MockUserRepository,
+    },
+};
+use registry::MockAppRegistryExt;
+use rstest::rstest;
+use shared::{
+    error::{AppError, ProblemDetails},
+    i18n::Message,
+};
+use tower::ServiceExt;
+
+use crate::{
+    deserialize_json,
+    helper::{fixture, fixture_registry, make_router, v1, TestRequestExt},
+};
+use api::model::mfa::TotpEnrollmentResponse;
+
+#[rstest]
+#[tokio::test]
+async fn enroll_totp_returns_recovery_codes(
+    mut fixture: MockAppRegistryExt,
+) -> anyhow::Result<()> {
+    fixture.expect_mfa_repository().returning(|| {
+        let mut mock = MockMfaRepository::new();
+        // 認証アプリにはログイン中のユーザーのメールアドレスを表示する
+        mock.expect_enroll_totp()
+            .withf(|event| {
+                event.account_name == "dummy@example.com"
+                    && event.recovery_codes.len() == RECOVERY_CODE_COUNT
+            })
+            .returning(|event| {
+                Ok(TotpEnrollment {
+                    otpauth_uri: format!(
+                        "otpauth://totp/BookManagement:{}?secret=JBSWY3DPEHPK3PXP",
+                        event.account_name
+                    ),
+                    secret: "JBSWY3DPEHPK3PXP".into(),
+                    recovery_codes: event.recovery_codes,
+                })
+            });
+        Arc::new(mock)
+    });
+
+    let app: axum::Router = make_router(fixture);
+
+    let req = Request::post(v1("/users/me/mfa/totp"))
+        .bearer()
+        .body(Body::empty())?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+
+    let result = deserialize_json!(resp, TotpEnrollmentResponse);
+    assert!(result.otpauth_uri.starts_with("otpauth://totp/"));
+    assert_eq!(result.recovery_codes.len(), RECOVERY_CODE_COUNT);
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn confirm_totp_with_invalid_code_is_rejected(
+    mut fixture: MockAppRegistryExt,
+) -> anyhow::Result<()> {
+    fixture.expect_mfa_repository().returning(|| {
+        let mut mock = MockMfaRepository::new();
+        mock.expect_confirm_totp().returning(|_| {
+            Err(AppError::UnprocessableEntity(Message::new("invalid_mfa_code")))
+        });
+        Arc::new(mock)
+    });
+
+    let app: axum::Router = make_router(fixture);
+
+    let req = Request::post(v1("/users/me/mfa/totp/confirm"))
+        .bearer()
+        .application_json()
+        .body(Body::from(r#"{"code":"000000"}"#))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    let result = deserialize_json!(resp, ProblemDetails);
+    assert_eq!(result.code, "invalid_mfa_code");
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn admin_without_mfa_cannot_use_admin_operations(
+    mut fixture_registry: MockAppRegistryExt,
+) -> anyhow::Result<()> {
+    // 2段階認証が必須の設定で、2段階認証を経ずにログインしたAdminのセッション
+    fixture_registry.expect_auth_repository().returning(|| {
+        let mut mock = MockAuthRepository::new();
+        mock.expect_fetch_session_from_token().returning(|_| {
+            Ok(Some(AuthorizedSession {
+                user_id: UserId::new(),
+                mfa_satisfied: false,
+            }))
+        });
+        mock.expect_extend_token().returning(|_| Ok(()));
+        Arc::new(mock)
+    });
+    fixture_registry.expect_user_repository().returning(|| {
+        let mut mock = MockUserRepository::new();
+        mock.expect_find_current_user().returning(|id| {
+            Ok(Some(User {
+                id,
+                name: "dummy-admin".to_string(),
+                email: "admin@example.com".to_string(),
+                role: Role::Admin,
+            }))
+        });
+        Arc::new(mock)
+    });
+
+    let app: axum::Router = make_router(fixture_registry);
+
+    let req = Request::delete(v1(&format!("/users/{}/lockout", UserId::new())))
+        .bearer()
+        .body(Body::empty())?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn custom_role_without_mfa_keeps_its_permissions(
+    mut fixture_registry: MockAppRegistryExt,
+) -> anyhow::Result<()> {
+    // 2段階認証が必須の設定で、2段階認証を登録していないLibrarianのセッション
+    fixture_registry.expect_auth_repository().returning(|| {
+        let mut mock = MockAuthRepository::new();
+        mock.expect_fetch_session_from_token().returning(|_| {
+            Ok(Some(AuthorizedSession {
+                user_id: UserId::new(),
+                mfa_satisfied: false,
+            }))
+        });
+        mock.expect_extend_token().returning(|_| Ok(()));
+        Arc::new(mock)
+    });
+    fixture_registry.expect_user_repository().returning(|| {
+        let mut mock = MockUserRepository::new();
+        mock.expect_find_current_user().returning(|id| {
+            Ok(Some(User {
+                id,
+                name: "dummy-librarian".to_string(),
+                email: "librarian@example.com".to_string(),
+                role: Role::from("Librarian"),
+            }))
+        });
+        Arc::new(mock)
+    });
+    fixture_registry.expect_role_repository().returning(|| {
+        let mut mock = MockRoleRepository::new();
+        mock.expect_find_permissions()
+            .returning(|_| Ok(vec![Permission::CheckoutReadAny]));
+        Arc::new(mock)
+    });
+    fixture_registry.expect_checkout_repository().returning(|| {
+        let mut mock = MockCheckoutRepository::new();
+        mock.expect_find_overdue_all().returning(|| Ok(vec![]));
+        Arc::new(mock)
+    });
+
+    let app: axum::Router = make_router(fixture_registry);
+
+    // 2段階認証の必須化はAdminのみが対象のため、ロールの権限はそのまま使える
+    let req = Request::get(v1("/books/checkouts/overdue"))
+        .bearer()
+        .body(Body::empty())?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    Ok(())
+}
@@ -0,0 +1,34 @@
+use api::openapi::ApiDoc;
+use utoipa::OpenApi;
+
+#[test]
+fn openapi_document_covers_routes() -> anyhow::Result<()> {
+    let doc = serde_json::to_value(ApiDoc::openapi())?;
+
+    // 1. 主要なパスが含まれている
+    for path in [
+        "/auth/login",
+        "/auth/refresh",
+        "/api/v1/books",
+        "/api/v1/books/{book_id}",
+        "/api/v1/books/{book_id}/checkouts/{checkout_id}/returned",
+        "/api/v1/books/{book_id}/checkouts/{checkout_id}/force-returned",
+        "/api/v1/users/me",
+        "/api/v1/users/me/api-keys",
+        "/api/v1/policies/{role}",
+        "/api/v1/roles/{role}",
+        "/api/v1/audit",
+    ] {
+        assert!(doc["paths"].get(path).is_some(), "{} is missing", path);
+    }
+
+    // 2. Bearerトークンによる認証方式が登録され、認証が必要なAPIに指定されている
+    assert_eq!(
+        doc["components"]["securitySchemes"]["bearer_auth"]["scheme"],
+        "bearer"
+    );
+    assert!(doc["paths"]["/api/v1/books"]["get"]["security"].is_array());
+    assert!(doc["paths"]["/auth/login"]["post"].get("security").is_none());
+
+    Ok(())
+}
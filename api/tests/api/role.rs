@@ -0,0 +1,220 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+};
+use kernel::{
+    model::{
+        role::{Permission, Role, RoleDetail},
+        user::User,
+    },
+    repository::{
+        book::MockBookRepository, checkout::MockCheckoutRepository, role::MockRoleRepository,
+        user::MockUserRepository,
+    },
+};
+use registry::MockAppRegistryExt;
+use rstest::rstest;
+use tower::ServiceExt;
+
+use crate::{
+    deserialize_json,
+    helper::{
+        fixture_registry, make_router, mock_auth_repository, mock_role_repository, v1,
+        TestRequestExt,
+    },
+};
+use api::model::role::RolesResponse;
+
+// 指定したロールのユーザーとしてログインしている状態にする
+fn login_as(registry: &mut MockAppRegistryExt, role: Role) {
+    registry
+        .expect_auth_repository()
+        .returning(|| Arc::new(mock_auth_repository()));
+    registry.expect_user_repository().returning(move || {
+        let role = role.clone();
+        let mut mock = MockUserRepository::new();
+        mock.expect_find_current_user().returning(move |id| {
+            Ok(Some(User {
+                id,
+                name: "dummy-user".to_string(),
+                email: "dummy@example.com".to_string(),
+                role: role.clone(),
+            }))
+        });
+        Arc::new(mock)
+    });
+}
+
+#[rstest]
+#[case(Role::Admin, StatusCode::CREATED, 1)]
+#[case(Role::User, StatusCode::FORBIDDEN, 0)]
+#[tokio::test]
+async fn create_role_requires_role_manage(
+    mut fixture_registry: MockAppRegistryExt,
+    #[case] role: Role,
+    #[case] expected_status: StatusCode,
+    #[case] expected_calls: usize,
+) -> anyhow::Result<()> {
+    login_as(&mut fixture_registry, role);
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counted = calls.clone();
+    fixture_registry
+        .expect_role_repository()
+        .returning(move || {
+            let mut mock = mock_role_repository();
+            let counted = counted.clone();
+            mock.expect_create()
+                .withf(|event| {
+                    event.role == Role::from("Librarian")
+                        && event.permissions
+                            == vec![Permission::CheckoutReadAny, Permission::CheckoutReturnAny]
+                })
+                .returning(move |_| {
+                    counted.fetch_add(1, Ordering::SeqCst);
+                    Ok(())
+                });
+            Arc::new(mock)
+        });
+
+    let app: axum::Router = make_router(fixture_registry);
+
+    let req = Request::post(v1("/roles"))
+        .bearer()
+        .application_json()
+        .body(Body::from(
+            r#"{"name":"Librarian","permissions":["checkout.read.any","checkout.return.any"]}"#,
+        ))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), expected_status);
+    assert_eq!(calls.load(Ordering::SeqCst), expected_calls);
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn list_roles_marks_builtin_roles(
+    mut fixture_registry: MockAppRegistryExt,
+) -> anyhow::Result<()> {
+    login_as(&mut fixture_registry, Role::Admin);
+    fixture_registry.expect_role_repository().returning(|| {
+        let mut mock = mock_role_repository();
+        mock.expect_find_all().returning(|| {
+            Ok(vec![
+                RoleDetail {
+                    role: Role::Admin,
+                    permissions: Permission::all(),
+                },
+                RoleDetail {
+                    role: Role::from("Librarian"),
+                    permissions: vec![Permission::CheckoutReturnAny],
+                },
+            ])
+        });
+        Arc::new(mock)
+    });
+
+    let app: axum::Router = make_router(fixture_registry);
+
+    let req = Request::get(v1("/roles")).bearer().body(Body::empty())?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let result = deserialize_json!(resp, RolesResponse);
+    assert_eq!(result.items.len(), 2);
+    assert!(result.items[0].builtin);
+    assert!(!result.items[1].builtin);
+    assert_eq!(
+        result.items[1].permissions,
+        vec![Permission::CheckoutReturnAny]
+    );
+
+    Ok(())
+}
+
+#[rstest]
+#[case(vec![Permission::CheckoutReadAny], StatusCode::OK)]
+#[case(vec![Permission::CheckoutReturnAny], StatusCode::FORBIDDEN)]
+#[tokio::test]
+async fn custom_role_is_authorized_by_its_permissions(
+    mut fixture_registry: MockAppRegistryExt,
+    #[case] permissions: Vec<Permission>,
+    #[case] expected_status: StatusCode,
+) -> anyhow::Result<()> {
+    login_as(&mut fixture_registry, Role::from("Librarian"));
+    fixture_registry
+        .expect_role_repository()
+        .returning(move || {
+            let permissions = permissions.clone();
+            let mut mock = MockRoleRepository::new();
+            mock.expect_find_permissions()
+                .withf(|role| *role == Role::from("Librarian"))
+                .returning(move |_| Ok(permissions.clone()));
+            Arc::new(mock)
+        });
+    fixture_registry.expect_checkout_repository().returning(|| {
+        let mut mock = MockCheckoutRepository::new();
+        mock.expect_find_overdue_all().returning(|| Ok(vec![]));
+        Arc::new(mock)
+    });
+
+    let app: axum::Router = make_router(fixture_registry);
+
+    let req = Request::get(v1("/books/checkouts/overdue"))
+        .bearer()
+        .body(Body::empty())?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), expected_status);
+
+    Ok(())
+}
+
+#[rstest]
+#[case(vec![Permission::BookCreate], StatusCode::CREATED)]
+#[case(vec![Permission::BookImport], StatusCode::FORBIDDEN)]
+#[tokio::test]
+async fn book_registration_requires_book_create(
+    mut fixture_registry: MockAppRegistryExt,
+    #[case] permissions: Vec<Permission>,
+    #[case] expected_status: StatusCode,
+) -> anyhow::Result<()> {
+    login_as(&mut fixture_registry, Role::from("Librarian"));
+    fixture_registry
+        .expect_role_repository()
+        .returning(move || {
+            let permissions = permissions.clone();
+            let mut mock = MockRoleRepository::new();
+            mock.expect_find_permissions()
+                .returning(move |_| Ok(permissions.clone()));
+            Arc::new(mock)
+        });
+    fixture_registry.expect_book_repository().returning(|| {
+        let mut mock = MockBookRepository::new();
+        mock.expect_create().returning(|_, _| Ok(()));
+        Arc::new(mock)
+    });
+
+    let app: axum::Router = make_router(fixture_registry);
+
+    let req = Request::post(v1("/books"))
+        .bearer()
+        .application_json()
+        .body(Body::from(
+            serde_json::json!({
+                "title": "実践Rustプログラミング入門",
+                "author": "初田直也他",
+                "isbn": "9784798061702",
+                "description": "",
+            })
+            .to_string(),
+        ))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), expected_status);
+
+    Ok(())
+}
@@ -0,0 +1,251 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+};
+use chrono::Utc;
+use kernel::{
+    model::{
+        auth::Session,
+        id::{SessionId, UserId},
+        role::Role,
+        user::User,
+    },
+    repository::user::MockUserRepository,
+};
+use registry::MockAppRegistryExt;
+use rstest::rstest;
+use tower::ServiceExt;
+
+use crate::{
+    deserialize_json,
+    helper::{
+        fixture_registry, make_router, mock_auth_repository, mock_role_repository, v1,
+        TestRequestExt,
+    },
+};
+use api::model::auth::SessionsResponse;
+
+// リポジトリのモックはメソッドを呼び出すたびに作り直されるため、
+// 呼び出された回数はモックの外で数える
+fn counter() -> (Arc<AtomicUsize>, Arc<AtomicUsize>) {
+    let count = Arc::new(AtomicUsize::new(0));
+    (count.clone(), count)
+}
+
+fn mock_user_repository(role: Role) -> MockUserRepository {
+    let mut mock = MockUserRepository::new();
+    mock.expect_find_current_user().returning(move |id| {
+        Ok(Some(User {
+            id,
+            name: "dummy-user".to_string(),
+            email: "dummy@example.com".to_string(),
+            role: role.clone(),
+        }))
+    });
+    mock
+}
+
+#[rstest]
+#[tokio::test]
+async fn list_my_sessions(mut fixture_registry: MockAppRegistryExt) -> anyhow::Result<()> {
+    let session_id = SessionId::new();
+    fixture_registry.expect_auth_repository().returning(move || {
+        let mut mock = mock_auth_repository();
+        mock.expect_find_sessions().returning(move |_, current| {
+            // リクエストに使ったアクセストークンが渡る
+            assert_eq!(current.0, "dummy");
+            Ok(vec![Session {
+                id: session_id,
+                created_at: Utc::now(),
+                user_agent: Some("curl/8.0".into()),
+                ip_address: Some("192.0.2.1".into()),
+                current: true,
+            }])
+        });
+        Arc::new(mock)
+    });
+    fixture_registry
+        .expect_user_repository()
+        .returning(|| Arc::new(mock_user_repository(Role::User)));
+
+    let app: axum::Router = make_router(fixture_registry);
+
+    let req = Request::get(v1("/users/me/sessions"))
+        .bearer()
+        .body(Body::empty())?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let result = deserialize_json!(resp, SessionsResponse);
+    assert_eq!(result.items.len(), 1);
+    assert_eq!(result.items[0].id, session_id);
+    assert_eq!(result.items[0].user_agent.as_deref(), Some("curl/8.0"));
+    assert!(result.items[0].current);
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn revoke_my_session(mut fixture_registry: MockAppRegistryExt) -> anyhow::Result<()> {
+    let session_id = SessionId::new();
+    let (calls, counted) = counter();
+    fixture_registry.expect_auth_repository().returning(move || {
+        let mut mock = mock_auth_repository();
+        let counted = counted.clone();
+        mock.expect_delete_session()
+            .withf(move |_, id| *id == session_id)
+            .returning(move |_, _| {
+                counted.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            });
+        Arc::new(mock)
+    });
+    fixture_registry
+        .expect_user_repository()
+        .returning(|| Arc::new(mock_user_repository(Role::User)));
+
+    let app: axum::Router = make_router(fixture_registry);
+
+    let req = Request::delete(v1(&format!("/users/me/sessions/{}", session_id)))
+        .bearer()
+        .body(Body::empty())?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn change_password_revokes_all_sessions(
+    mut fixture_registry: MockAppRegistryExt,
+) -> anyhow::Result<()> {
+    let (calls, counted) = counter();
+    fixture_registry.expect_auth_repository().returning(move || {
+        let mut mock = mock_auth_repository();
+        let counted = counted.clone();
+        mock.expect_delete_all_sessions().returning(move |_| {
+            counted.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        });
+        Arc::new(mock)
+    });
+    fixture_registry.expect_user_repository().returning(|| {
+        let mut mock = mock_user_repository(Role::User);
+        mock.expect_update_password().returning(|_| Ok(()));
+        Arc::new(mock)
+    });
+
+    let app: axum::Router = make_router(fixture_registry);
+
+    let req = Request::put(v1("/users/me/password"))
+        .bearer()
+        .application_json()
+        .body(Body::from(
+            r#"{"currentPassword":"old-password","newPassword":"new-password"}"#,
+        ))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+    Ok(())
+}
+
+#[rstest]
+#[tokio::test]
+async fn change_role_updates_role_and_revokes_sessions(
+    mut fixture_registry: MockAppRegistryExt,
+) -> anyhow::Result<()> {
+    let user_id = UserId::new();
+    let (revoked, counted_revoke) = counter();
+    let (updated, counted_update) = counter();
+    fixture_registry.expect_auth_repository().returning(move || {
+        let mut mock = mock_auth_repository();
+        let counted = counted_revoke.clone();
+        mock.expect_delete_all_sessions()
+            .withf(move |id| *id == user_id)
+            .returning(move |_| {
+                counted.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            });
+        Arc::new(mock)
+    });
+    fixture_registry.expect_user_repository().returning(move || {
+        let mut mock = mock_user_repository(Role::Admin);
+        let counted = counted_update.clone();
+        // ユーザーを削除せず、ロールを変更する
+        mock.expect_update_role()
+            .withf(move |event| event.user_id == user_id && event.role == Role::Admin)
+            .returning(move |_| {
+                counted.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            });
+        mock.expect_delete().never();
+        Arc::new(mock)
+    });
+    fixture_registry
+        .expect_role_repository()
+        .returning(|| Arc::new(mock_role_repository()));
+
+    let app: axum::Router = make_router(fixture_registry);
+
+    let req = Request::put(v1(&format!("/users/{}/role", user_id)))
+        .bearer()
+        .application_json()
+        .body(Body::from(r#"{"role":"Admin"}"#))?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(updated.load(Ordering::SeqCst), 1);
+    assert_eq!(revoked.load(Ordering::SeqCst), 1);
+
+    Ok(())
+}
+
+#[rstest]
+#[case(Role::Admin, StatusCode::NO_CONTENT, 1)]
+#[case(Role::User, StatusCode::FORBIDDEN, 0)]
+#[tokio::test]
+async fn unlock_user_is_admin_only(
+    mut fixture_registry: MockAppRegistryExt,
+    #[case] role: Role,
+    #[case] expected_status: StatusCode,
+    #[case] expected_calls: usize,
+) -> anyhow::Result<()> {
+    let user_id = UserId::new();
+    let (calls, counted) = counter();
+    fixture_registry.expect_auth_repository().returning(move || {
+        let mut mock = mock_auth_repository();
+        let counted = counted.clone();
+        mock.expect_unlock_user()
+            .withf(move |id| *id == user_id)
+            .returning(move |_| {
+                counted.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            });
+        Arc::new(mock)
+    });
+    fixture_registry
+        .expect_user_repository()
+        .returning(move || Arc::new(mock_user_repository(role.clone())));
+    fixture_registry
+        .expect_role_repository()
+        .returning(|| Arc::new(mock_role_repository()));
+
+    let app: axum::Router = make_router(fixture_registry);
+
+    let req = Request::delete(v1(&format!("/users/{}/lockout", user_id)))
+        .bearer()
+        .body(Body::empty())?;
+    let resp = app.oneshot(req).await?;
+    assert_eq!(resp.status(), expected_status);
+    assert_eq!(calls.load(Ordering::SeqCst), expected_calls);
+
+    Ok(())
+}
@@ -1,8 +1,8 @@
 INSERT INTO 
-    roles (name)
+    roles (name, permissions)
 VALUES  
-    ('Admin'),
-    ('User')
+    ('Admin', '{book.create}'),
+    ('User', '{book.create}')
 ON CONFLICT DO NOTHING;
 
 INSERT INTO
@@ -15,4 +15,15 @@ SELECT
 FROM 
     roles
 WHERE 
-    name LIKE 'Admin';
\ No newline at end of file
+    name LIKE 'Admin';
+
+INSERT INTO
+    borrowing_policies (role_id, max_loans, loan_period_days, max_renewals)
+SELECT
+    role_id,
+    CASE name WHEN 'Admin' THEN 10 ELSE 5 END,
+    14,
+    2
+FROM
+    roles
+ON CONFLICT DO NOTHING;
@@ -0,0 +1,55 @@
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::model::{
+    api_key::ApiKeyScope,
+    id::{ApiKeyId, UserId},
+};
+
+// APIキーの先頭に付ける文字列。アクセストークンと区別するために使う
+pub const API_KEY_PREFIX: &str = "bmk_";
+// 一覧でキーを識別するために保存する、キーの先頭の文字数
+pub const API_KEY_DISPLAY_LENGTH: usize = 12;
+
+// APIキーの発行
+// キーは推測されないよう、ランダムに生成する
+pub struct CreateApiKey {
+    pub user_id: UserId,
+    pub name: String,
+    pub scopes: Vec<ApiKeyScope>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub key: String,
+}
+
+impl CreateApiKey {
+    pub fn new(
+        user_id: UserId,
+        name: String,
+        scopes: Vec<ApiKeyScope>,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Self {
+        let key = format!(
+            "{}{}{}",
+            API_KEY_PREFIX,
+            Uuid::new_v4().simple(),
+            Uuid::new_v4().simple()
+        );
+        Self {
+            user_id,
+            name,
+            scopes,
+            expires_at,
+            key,
+        }
+    }
+
+    pub fn key_prefix(&self) -> &str {
+        &self.key[..API_KEY_DISPLAY_LENGTH]
+    }
+}
+
+// APIキーの失効
+pub struct DeleteApiKey {
+    pub user_id: UserId,
+    pub api_key_id: ApiKeyId,
+}
@@ -0,0 +1,85 @@
+pub mod event;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use strum::{AsRefStr, EnumIter, EnumString};
+use utoipa::ToSchema;
+
+use crate::model::id::{ApiKeyId, UserId};
+
+// APIキーで許可する操作の範囲
+// APIキーでは、キーに付与したスコープに含まれる操作のみ行える
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    EnumString,
+    AsRefStr,
+    EnumIter,
+    Serialize,
+    Deserialize,
+    ToSchema,
+)]
+pub enum ApiKeyScope {
+    #[strum(serialize = "books:read")]
+    #[serde(rename = "books:read")]
+    BooksRead,
+    #[strum(serialize = "books:write")]
+    #[serde(rename = "books:write")]
+    BooksWrite,
+    #[strum(serialize = "checkouts:read")]
+    #[serde(rename = "checkouts:read")]
+    CheckoutsRead,
+    #[strum(serialize = "checkouts:write")]
+    #[serde(rename = "checkouts:write")]
+    CheckoutsWrite,
+    #[strum(serialize = "reservations:read")]
+    #[serde(rename = "reservations:read")]
+    ReservationsRead,
+    #[strum(serialize = "reservations:write")]
+    #[serde(rename = "reservations:write")]
+    ReservationsWrite,
+    #[strum(serialize = "users:read")]
+    #[serde(rename = "users:read")]
+    UsersRead,
+    #[strum(serialize = "users:write")]
+    #[serde(rename = "users:write")]
+    UsersWrite,
+    #[strum(serialize = "policies:read")]
+    #[serde(rename = "policies:read")]
+    PoliciesRead,
+    #[strum(serialize = "policies:write")]
+    #[serde(rename = "policies:write")]
+    PoliciesWrite,
+}
+
+// 発行済みのAPIキー。キーそのものは発行時にのみ返し、以降は先頭の数文字で識別する
+#[derive(Debug)]
+pub struct ApiKey {
+    pub id: ApiKeyId,
+    pub name: String,
+    pub key_prefix: String,
+    pub scopes: Vec<ApiKeyScope>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+// 発行したAPIキーと、一度だけ返すキーそのもの
+pub struct CreatedApiKey {
+    pub api_key: ApiKey,
+    pub key: String,
+}
+
+// リクエストに使われたAPIキーの持ち主と、許可された操作の範囲
+pub struct AuthorizedApiKey {
+    pub id: ApiKeyId,
+    pub user_id: UserId,
+    pub scopes: Vec<ApiKeyScope>,
+    // 2段階認証に関する設定を満たすかどうか
+    // APIキーは2段階認証を経ずに使えるため、2段階認証が必須の設定では満たさないものとする
+    pub mfa_satisfied: bool,
+}
@@ -0,0 +1,14 @@
+use derive_new::new;
+
+use crate::model::{audit::AuditAction, id::UserId};
+
+// 監査ログに追記する操作の記録
+// 記録は操作と同じトランザクションで行い、リクエストIDは処理中のリクエストから取得する
+#[derive(Debug, new)]
+pub struct CreateAuditEvent {
+    pub actor: UserId,
+    pub action: AuditAction,
+    pub target_id: String,
+    pub before: Option<serde_json::Value>,
+    pub after: Option<serde_json::Value>,
+}
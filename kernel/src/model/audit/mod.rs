@@ -0,0 +1,98 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use strum::{AsRefStr, EnumString};
+use utoipa::ToSchema;
+
+use crate::model::id::{AuditEventId, UserId};
+
+pub mod event;
+
+// 監査ログに記録する操作
+// 蔵書・貸出・予約・ユーザー・ロール・貸出ポリシー・APIキーの状態を変更する操作を記録する
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, EnumString, AsRefStr, Serialize, Deserialize, ToSchema,
+)]
+pub enum AuditAction {
+    #[strum(serialize = "book.create")]
+    #[serde(rename = "book.create")]
+    BookCreate,
+    #[strum(serialize = "book.update")]
+    #[serde(rename = "book.update")]
+    BookUpdate,
+    #[strum(serialize = "book.delete")]
+    #[serde(rename = "book.delete")]
+    BookDelete,
+    #[strum(serialize = "book.copies.add")]
+    #[serde(rename = "book.copies.add")]
+    BookCopiesAdd,
+    #[strum(serialize = "checkout.create")]
+    #[serde(rename = "checkout.create")]
+    CheckoutCreate,
+    #[strum(serialize = "checkout.renew")]
+    #[serde(rename = "checkout.renew")]
+    CheckoutRenew,
+    #[strum(serialize = "checkout.return")]
+    #[serde(rename = "checkout.return")]
+    CheckoutReturn,
+    #[strum(serialize = "reservation.create")]
+    #[serde(rename = "reservation.create")]
+    ReservationCreate,
+    #[strum(serialize = "reservation.delete")]
+    #[serde(rename = "reservation.delete")]
+    ReservationDelete,
+    #[strum(serialize = "user.create")]
+    #[serde(rename = "user.create")]
+    UserCreate,
+    #[strum(serialize = "user.role.update")]
+    #[serde(rename = "user.role.update")]
+    UserRoleUpdate,
+    #[strum(serialize = "user.delete")]
+    #[serde(rename = "user.delete")]
+    UserDelete,
+    #[strum(serialize = "role.create")]
+    #[serde(rename = "role.create")]
+    RoleCreate,
+    #[strum(serialize = "role.permissions.update")]
+    #[serde(rename = "role.permissions.update")]
+    RolePermissionsUpdate,
+    #[strum(serialize = "role.delete")]
+    #[serde(rename = "role.delete")]
+    RoleDelete,
+    #[strum(serialize = "policy.update")]
+    #[serde(rename = "policy.update")]
+    PolicyUpdate,
+    #[strum(serialize = "api_key.create")]
+    #[serde(rename = "api_key.create")]
+    ApiKeyCreate,
+    #[strum(serialize = "api_key.delete")]
+    #[serde(rename = "api_key.delete")]
+    ApiKeyDelete,
+}
+
+// 監査ログの1件
+// before・afterは操作の前後の対象の状態で、作成・削除の場合はそれぞれ片方のみとなる
+#[derive(Debug)]
+pub struct AuditEvent {
+    pub id: AuditEventId,
+    // 操作を行ったユーザー
+    pub actor: UserId,
+    pub action: AuditAction,
+    // 操作の対象のID(蔵書ID・ユーザーID・ロール名など)
+    pub target_id: String,
+    pub before: Option<serde_json::Value>,
+    pub after: Option<serde_json::Value>,
+    pub request_id: Option<String>,
+    pub occurred_at: DateTime<Utc>,
+}
+
+// 監査ログの絞り込み条件。期間はfrom以上to未満とする
+#[derive(Debug, Default)]
+pub struct AuditEventListOptions {
+    pub limit: i64,
+    pub offset: i64,
+    pub actor: Option<UserId>,
+    pub action: Option<AuditAction>,
+    pub target_id: Option<String>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+}
@@ -1,17 +1,67 @@
-use crate::model::id::UserId;
+use chrono::{DateTime, Utc};
 use uuid::Uuid;
 
+use crate::model::id::{SessionId, UserId};
+
+// ログインごとにセッションを作り、トークンを再発行しても同じセッションとして扱う
 pub struct CreateToken{
     pub user_id: UserId,
+    pub session_id: SessionId,
     pub access_token: String,
+    pub refresh_token: String,
+    pub created_at: DateTime<Utc>,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    // 2段階認証を経てログインしたセッションかどうか
+    pub mfa_verified: bool,
 }
 
 impl CreateToken {
-    pub fn new(user_id: UserId) -> Self{
-        let access_token = Uuid::new_v4().simple().to_string();
-        Self{
+    pub fn new(
+        user_id: UserId,
+        user_agent: Option<String>,
+        ip_address: Option<String>,
+    ) -> Self{
+        Self {
             user_id,
-            access_token,
+            session_id: SessionId::new(),
+            access_token: Uuid::new_v4().simple().to_string(),
+            refresh_token: Uuid::new_v4().simple().to_string(),
+            created_at: Utc::now(),
+            user_agent,
+            ip_address,
+            mfa_verified: false,
+        }
+    }
+}
+
+// ログイン時の認証情報
+// 失敗の回数を接続元ごとにも数えるため、接続元のIPアドレスも受け取る
+pub struct VerifyUser {
+    pub email: String,
+    pub password: String,
+    pub ip_address: Option<String>,
+}
+
+// パスワードの再設定の受付
+// 推測されないよう、トークンはランダムに生成する
+pub struct CreatePasswordReset {
+    pub email: String,
+    pub token: String,
+}
+
+impl CreatePasswordReset {
+    pub fn new(email: String) -> Self {
+        Self {
+            email,
+            token: Uuid::new_v4().simple().to_string(),
         }
     }
-}
\ No newline at end of file
+}
+
+// パスワードの再設定
+// トークンは一度使うと無効になる
+pub struct ResetPassword {
+    pub token: String,
+    pub new_password: String,
+}
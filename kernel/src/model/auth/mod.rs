@@ -1,3 +1,49 @@
 pub mod event;
 
-pub struct AccessToken(pub String);
\ No newline at end of file
+use chrono::{DateTime, Utc};
+
+use crate::model::id::{SessionId, UserId};
+
+pub struct AccessToken(pub String);
+
+// アクセストークンが紐づくユーザーと、セッションの状態
+pub struct AuthorizedSession {
+    pub user_id: UserId,
+    // 2段階認証に関する設定を満たしたセッションかどうか
+    // 満たしていないセッションでは、管理者の権限を使えない
+    pub mfa_satisfied: bool,
+}
+
+// アクセストークンの期限が切れた後に、ログインし直さずにトークンを再発行するためのトークン
+pub struct RefreshToken(pub String);
+
+// パスワードを忘れたユーザーが、パスワードを再設定するための一度だけ使えるトークン
+pub struct PasswordResetToken(pub String);
+
+// パスワードの再設定の受付内容。メールでユーザーに再設定用のトークンを送る
+pub struct PasswordReset {
+    pub user_id: UserId,
+    pub email: String,
+    pub token: PasswordResetToken,
+    // トークンの有効期間(秒)
+    pub expires_in: u64,
+}
+
+// ログイン・トークンの再発行で払い出すトークンの組
+pub struct AuthTokens {
+    pub user_id: UserId,
+    pub access_token: AccessToken,
+    pub refresh_token: RefreshToken,
+}
+
+// ログインした端末ごとのセッション
+// トークンを再発行しても同じセッションとして扱い、作成日時や接続元はログイン時のものを引き継ぐ
+#[derive(Debug)]
+pub struct Session {
+    pub id: SessionId,
+    pub created_at: DateTime<Utc>,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    // 一覧を取得したリクエストのアクセストークンが属するセッションかどうか
+    pub current: bool,
+}
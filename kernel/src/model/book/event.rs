@@ -1,10 +1,18 @@
-use crate::model::id::{BookId, UserId};
+use crate::model::{
+    book::isbn::Isbn,
+    id::{BookId, UserId},
+};
 
+#[derive(Debug)]
 pub struct CreateBook {
     pub title: String,
+    // 書名の読み、五十音順の並び替えと検索に使う
+    pub title_kana: Option<String>,
     pub author: String,
-    pub isbn: String,
+    pub isbn: Isbn,
     pub description: String,
+    // 登録する冊子の数
+    pub copies: i32,
 }
 
 #[derive(Debug)]
@@ -12,13 +20,32 @@ pub struct UpdateBook{
     pub book_id: BookId,
     pub title: String,
     pub author: String,
-    pub isbn: String,
+    pub isbn: Isbn,
     pub description: String,
     pub requested_user: UserId,
+    // 他のユーザーが所有する蔵書も更新できるか(book.update.anyの権限を持つ場合)
+    pub any_owner: bool,
 }
 
 #[derive(Debug)]
 pub struct DeleteBook{
     pub book_id: BookId,
     pub requested_user: UserId,
-}
\ No newline at end of file
+    // 他のユーザーが所有する蔵書も削除できるか(book.delete.anyの権限を持つ場合)
+    pub any_owner: bool,
+}
+
+// 所蔵している冊子を追加する
+#[derive(Debug)]
+pub struct AddBookCopies{
+    pub book_id: BookId,
+    pub copies: i32,
+    pub requested_user: UserId,
+}
+
+// 一括登録する蔵書。所有者はメールアドレスで指定する
+#[derive(Debug)]
+pub struct ImportBook{
+    pub owner_email: String,
+    pub book: CreateBook,
+}
@@ -0,0 +1,132 @@
+// ISBNを表す値オブジェクト
+// ISBN-10・ISBN-13のチェックディジットを検証し、ハイフンを除いたISBN-13の形式(正規形)で保持する
+
+use serde::{Deserialize, Serialize};
+use shared::{error::AppError, i18n::Message};
+use std::str::FromStr;
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(try_from = "String", into = "String")]
+#[schema(value_type = String, example = "9784798061702")]
+pub struct Isbn(String);
+
+impl Isbn {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn into_inner(self) -> String {
+        self.0
+    }
+}
+
+impl FromStr for Isbn {
+    type Err = AppError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            AppError::UnprocessableEntity(Message::new("invalid_isbn").arg("isbn", s))
+        };
+
+        // 区切りのハイフンと空白は取り除き、ISBN-10のチェックディジットのxは大文字に揃える
+        let chars = s
+            .chars()
+            .filter(|c| !matches!(c, '-' | ' '))
+            .map(|c| c.to_ascii_uppercase())
+            .collect::<Vec<char>>();
+
+        match chars.len() {
+            10 => {
+                let digits = chars[..9]
+                    .iter()
+                    .map(|c| c.to_digit(10))
+                    .collect::<Option<Vec<u32>>>()
+                    .ok_or_else(invalid)?;
+                let check = match chars[9] {
+                    'X' => 10,
+                    c => c.to_digit(10).ok_or_else(invalid)?,
+                };
+                // 各桁に10から2までの重みを掛けた和とチェックディジットの和が11の倍数になる
+                let sum: u32 = digits
+                    .iter()
+                    .zip((2..=10).rev())
+                    .map(|(d, w)| d * w)
+                    .sum();
+                if (sum + check) % 11 != 0 {
+                    return Err(invalid());
+                }
+                // 先頭に978を付け、ISBN-13のチェックディジットを計算し直す
+                let mut digits13 = vec![9, 7, 8];
+                digits13.extend(digits);
+                digits13.push(isbn13_check_digit(&digits13));
+                Ok(Self(digits13.iter().map(u32::to_string).collect()))
+            }
+            13 => {
+                let digits = chars
+                    .iter()
+                    .map(|c| c.to_digit(10))
+                    .collect::<Option<Vec<u32>>>()
+                    .ok_or_else(invalid)?;
+                if !(digits.starts_with(&[9, 7, 8]) || digits.starts_with(&[9, 7, 9]))
+                    || isbn13_check_digit(&digits[..12]) != digits[12]
+                {
+                    return Err(invalid());
+                }
+                Ok(Self(digits.iter().map(u32::to_string).collect()))
+            }
+            _ => Err(invalid()),
+        }
+    }
+}
+
+// ISBN-13のチェックディジット：先頭12桁に1と3を交互に掛けた和から求める
+fn isbn13_check_digit(digits: &[u32]) -> u32 {
+    let sum: u32 = digits
+        .iter()
+        .zip([1, 3].iter().cycle())
+        .map(|(d, w)| d * w)
+        .sum();
+    (10 - sum % 10) % 10
+}
+
+impl TryFrom<String> for Isbn {
+    type Error = AppError;
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<Isbn> for String {
+    fn from(value: Isbn) -> Self {
+        value.0
+    }
+}
+
+impl std::fmt::Display for Isbn {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_isbn() -> anyhow::Result<()> {
+        // ハイフンの有無に関わらず、ISBN-13の正規形に揃える
+        assert_eq!(Isbn::from_str("978-4798061702")?.as_str(), "9784798061702");
+        assert_eq!(Isbn::from_str("9784798061702")?.as_str(), "9784798061702");
+        // ISBN-10はISBN-13に変換する
+        assert_eq!(Isbn::from_str("4-7980-6170-0")?.as_str(), "9784798061702");
+        assert_eq!(Isbn::from_str("080442957x")?.as_str(), "9780804429573");
+
+        // チェックディジットの誤り、桁数の誤り、数字以外の文字は受け付けない
+        assert!(Isbn::from_str("978-4798061703").is_err());
+        assert!(Isbn::from_str("4-7980-6170-5").is_err());
+        assert!(Isbn::from_str("abc").is_err());
+        assert!(Isbn::from_str("123-4567890128").is_err());
+
+        Ok(())
+    }
+}
@@ -1,34 +1,106 @@
 pub mod event;
+pub mod isbn;
 
 // Bookのの内容を定義する
 use crate::model::{
-    id::{BookId, CheckoutId},
+    id::{BookId, CheckoutId, CopyId, UserId},
+    list::SortDirection,
     user::{BookOwner, CheckoutUser},
 };
 
 use chrono::{DateTime, Utc};
+use futures::stream::BoxStream;
+use isbn::Isbn;
+use shared::error::AppResult;
 
 #[derive(Debug)]
 pub struct Book {
     pub id: BookId,
     pub title: String,
+    // 書名の読み(ひらがなに正規化済み)
+    pub title_kana: Option<String>,
     pub author: String,
     pub isbn: String,
     pub description: String,
     pub owner: BookOwner,
-    pub checkout: Option<Checkout>,
+    // 所蔵している冊子の総数と、貸出中・取り置き中ではない冊子の数
+    pub total_copies: i64,
+    pub available_copies: i64,
+    // 貸出中の冊子ごとの貸出情報
+    pub checkouts: Vec<Checkout>,
 }
 
+// 蔵書を1件ずつ返すストリーム
+// 全件を一度にメモリに載せずに書き出すエクスポートで使う
+pub type BookStream = BoxStream<'static, AppResult<Book>>;
+
 // ページネーションの範囲を指定するための設定値を格納する型を追加　
+// 検索条件はNoneの場合は絞り込みを行わない
 #[derive(Debug)]
 pub struct BookListOptions{
     pub limit: i64,
     pub offset: i64,
+    // タイトル・著者・説明のいずれかに含まれる文字列
+    pub q: Option<String>,
+    pub isbn: Option<Isbn>,
+    // 著者名に含まれる文字列
+    pub author: Option<String>,
+    pub owner: Option<UserId>,
+    // 借りられる冊子が残っているか
+    pub available: Option<bool>,
+    pub sort: BookSortKey,
+    pub direction: SortDirection,
+}
+
+// 外部の書誌情報APIからISBNで取得した書誌情報
+#[derive(Debug, Clone)]
+pub struct BookMetadata {
+    pub isbn: Isbn,
+    pub title: String,
+    pub title_kana: Option<String>,
+    pub author: String,
+    pub description: String,
+}
+
+// 一括登録した蔵書1件ごとの結果
+#[derive(Debug, PartialEq, Eq)]
+pub enum BookImportOutcome {
+    Created(BookId),
+    // 登録できなかった理由を保持する
+    Rejected(String),
+}
+
+// 蔵書の検索条件
+// 表記ゆれを吸収して部分一致・類似度で検索し、関連度の高い順に返す
+#[derive(Debug)]
+pub struct BookSearchOptions {
+    pub q: String,
+    pub limit: i64,
+    pub offset: i64,
+}
+
+// 蔵書の一覧の並び替えに使う項目
+// 書名は読みが登録されていれば五十音順に並ぶ
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum BookSortKey {
+    Title,
+    Author,
+    #[default]
+    CreatedAt,
 }
 
 #[derive(Debug)]
 pub struct Checkout{
     pub checkout_id: CheckoutId,
+    pub copy_id: CopyId,
     pub checked_out_by: CheckoutUser,
     pub checked_out_at: DateTime<Utc>,
+    pub due_at: DateTime<Utc>,
+}
+
+impl Checkout {
+    // 貸出中の冊子のみを扱うため、`checkout::Checkout::is_overdue`の未返却の場合と同じ比較で判断する
+    pub fn is_overdue(&self, now: DateTime<Utc>) -> bool {
+        self.due_at < now
+    }
 }
\ No newline at end of file
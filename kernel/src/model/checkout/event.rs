@@ -1,21 +1,35 @@
 use chrono::{DateTime, Utc};
 use derive_new::new;
-use uuid::fmt::Urn;
 
-use crate::model::{book::Book, id::{BookId, CheckoutId, UserId}};
+use crate::model::id::{BookId, CheckoutId, UserId};
 
+// checked_out_byは借りるユーザー、performed_byは貸出の操作を行ったユーザー
+// 職員が代理で貸し出す場合は両者が異なる
 #[derive(new)]
 pub struct CreateCheckout{
     pub book_id: BookId,
     pub checked_out_by: UserId,
     pub checked_out_at: DateTime<Utc>,
+    pub performed_by: UserId,
 }
 
+// 貸出の延長を行う際の型
+#[derive(new)]
+pub struct RenewCheckout{
+    pub checkout_id: CheckoutId,
+    pub book_id: BookId,
+    pub renewed_by: UserId,
+    pub renewed_at: DateTime<Utc>,
+}
+
+// returned_byは返却の操作を行ったユーザー
+// any_borrowerがtrueの場合は、借りたユーザー以外でも返却できる(職員による強制返却)
 #[derive(new)]
 pub struct UpdateReturned{
     pub checkout_id: CheckoutId,
     pub book_id: BookId,
     pub returned_by: UserId,
     pub returned_at: DateTime<Utc>,
+    pub any_borrower: bool,
 }
 
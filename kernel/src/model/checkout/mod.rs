@@ -1,4 +1,4 @@
-use crate::model::id::{BookId, CheckoutId, UserId};
+use crate::model::id::{BookId, CheckoutId, CopyId, UserId};
 use chrono::{DateTime, Utc};
 
 pub mod event;
@@ -6,12 +6,32 @@ pub mod event;
 #[derive(Debug)]
 pub struct Checkout{
     pub id: CheckoutId,
+    // 貸し出した冊子
+    pub copy_id: CopyId,
     pub checked_out_by: UserId,
     pub checked_out_at: DateTime<Utc>,
+    pub due_at: DateTime<Utc>,
+    // 貸出を延長した回数
+    pub renewal_count: i32,
     pub returned_at: Option<DateTime<Utc>>,
+    // 貸出・返却の操作を行ったユーザー(職員による代理操作の場合は借りたユーザーと異なる)
+    pub checked_out_performed_by: UserId,
+    pub returned_performed_by: Option<UserId>,
     pub book: CheckoutBook,
 }
 
+impl Checkout {
+    // 返却期限を過ぎているかを返す
+    // 未返却の場合は、延滞一覧のSQL(`due_at < CURRENT_TIMESTAMP`)と同じ比較で判断する
+    // 返却済みの場合は、返却日時が返却期限を過ぎていたかで判断する
+    pub fn is_overdue(&self, now: DateTime<Utc>) -> bool {
+        match self.returned_at {
+            Some(returned_at) => self.due_at < returned_at,
+            None => self.due_at < now,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct CheckoutBook {
     pub book_id: BookId,
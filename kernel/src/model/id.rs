@@ -1,14 +1,18 @@
 use serde::{Deserialize, Serialize};
 use shared::error::AppError;
 use std::str::FromStr;
+use utoipa::ToSchema;
 
 // マクロ定義を行う。
 // 型名はマクロの引数として受け取り、NewTypeパターンにでuuid::Uuid型の値を内包した型を定義する
 macro_rules! define_id {
     ($id_type: ident) => {
-        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize, sqlx::Type)]
+        #[derive(
+            Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize, sqlx::Type, ToSchema,
+        )]
         #[serde(into = "String")]
         #[sqlx(transparent)]
+        #[schema(value_type = String, format = Uuid)]
         pub struct $id_type(uuid::Uuid);
 
         impl $id_type {
@@ -63,3 +67,8 @@ macro_rules! define_id {
 define_id!(UserId);
 define_id!(BookId);
 define_id!(CheckoutId);
+define_id!(ReservationId);
+define_id!(CopyId);
+define_id!(SessionId);
+define_id!(ApiKeyId);
+define_id!(AuditEventId);
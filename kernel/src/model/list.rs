@@ -13,3 +13,11 @@ impl<T> PaginatedList<T> {
         self.items
     }
 }
+
+// 一覧の並び順の向き
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SortDirection {
+    Asc,
+    #[default]
+    Desc,
+}
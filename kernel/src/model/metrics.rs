@@ -0,0 +1,17 @@
+// `/metrics`の取得時点の現在値
+// 発生の都度記録するメトリクスと異なり、取得のたびにデータベースから集計する
+
+#[derive(Debug, Clone, Copy)]
+pub struct MetricsSnapshot {
+    pub db_pool: DbPoolStatus,
+    pub active_checkouts: i64,
+    pub overdue_checkouts: i64,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct DbPoolStatus {
+    // 確立済みの接続数(使用中と待機中の合計)
+    pub size: u32,
+    pub idle: usize,
+    pub max: u32,
+}
@@ -0,0 +1,68 @@
+use uuid::Uuid;
+
+use crate::model::id::UserId;
+
+// リカバリーコードの個数
+pub const RECOVERY_CODE_COUNT: usize = 10;
+
+// TOTPの登録の開始
+// リカバリーコードは推測されないよう、ランダムに生成する
+pub struct EnrollTotp {
+    pub user_id: UserId,
+    // 認証アプリに表示するアカウント名(メールアドレス)
+    pub account_name: String,
+    pub recovery_codes: Vec<String>,
+}
+
+impl EnrollTotp {
+    pub fn new(user_id: UserId, account_name: String) -> Self {
+        let recovery_codes = (0..RECOVERY_CODE_COUNT)
+            .map(|_| {
+                // 読み上げ・手入力しやすいよう、5文字ずつ区切る
+                let random = Uuid::new_v4().simple().to_string();
+                format!("{}-{}", &random[..5], &random[5..10])
+            })
+            .collect();
+        Self {
+            user_id,
+            account_name,
+            recovery_codes,
+        }
+    }
+}
+
+// 認証アプリに表示されたコードによる、TOTPの登録の完了・解除
+pub struct VerifyTotp {
+    pub user_id: UserId,
+    pub code: String,
+}
+
+// パスワードの検証に成功したログインの、2段階目の認証の開始
+pub struct CreateMfaChallenge {
+    pub user_id: UserId,
+    pub challenge_token: String,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+}
+
+impl CreateMfaChallenge {
+    pub fn new(
+        user_id: UserId,
+        user_agent: Option<String>,
+        ip_address: Option<String>,
+    ) -> Self {
+        Self {
+            user_id,
+            challenge_token: Uuid::new_v4().simple().to_string(),
+            user_agent,
+            ip_address,
+        }
+    }
+}
+
+// 2段階目の認証
+// コードには認証アプリに表示されたコードか、未使用のリカバリーコードを指定する
+pub struct VerifyMfaChallenge {
+    pub challenge_token: String,
+    pub code: String,
+}
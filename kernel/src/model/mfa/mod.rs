@@ -0,0 +1,29 @@
+pub mod event;
+
+use crate::model::id::UserId;
+
+// TOTPの登録の開始時に、一度だけユーザーに返す情報
+// 秘密鍵とリカバリーコードは以降取得できないため、ユーザーに控えてもらう
+pub struct TotpEnrollment {
+    // 認証アプリに読み込ませるURI(otpauth://totp/...)
+    pub otpauth_uri: String,
+    // URIを読み込めない認証アプリに手入力するための秘密鍵(Base32)
+    pub secret: String,
+    // 認証アプリを使えなくなった場合に、一度だけ使えるコード
+    pub recovery_codes: Vec<String>,
+}
+
+// 2段階目の認証を待っているログイン
+// パスワードの検証に成功した時点で発行し、TOTPのコードを検証するまではトークンを発行しない
+pub struct MfaChallenge {
+    pub challenge_token: String,
+    // 有効期間(秒)
+    pub expires_in: u64,
+}
+
+// 2段階目の認証に成功したログイン。パスワードの検証時の接続元を引き継いでトークンを発行する
+pub struct VerifiedMfaChallenge {
+    pub user_id: UserId,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+}
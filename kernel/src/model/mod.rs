@@ -4,4 +4,10 @@ pub mod auth;
 pub mod role;
 pub mod user;
 pub mod list;
-pub mod checkout;
\ No newline at end of file
+pub mod checkout;
+pub mod reservation;
+pub mod policy;
+pub mod metrics;
+pub mod mfa;
+pub mod api_key;
+pub mod audit;
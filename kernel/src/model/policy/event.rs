@@ -0,0 +1,10 @@
+use crate::model::{id::UserId, role::Role};
+
+#[derive(Debug)]
+pub struct UpdateBorrowingPolicy {
+    pub role: Role,
+    pub max_loans: i32,
+    pub loan_period_days: i32,
+    pub max_renewals: i32,
+    pub requested_user: UserId,
+}
@@ -0,0 +1,15 @@
+use crate::model::role::Role;
+
+pub mod event;
+
+// ロールごとの貸出ポリシー
+#[derive(Debug)]
+pub struct BorrowingPolicy {
+    pub role: Role,
+    // 同時に借りられる冊数の上限
+    pub max_loans: i32,
+    // 貸出日(延長日)から返却期限までの日数
+    pub loan_period_days: i32,
+    // 貸出を延長できる回数の上限
+    pub max_renewals: i32,
+}
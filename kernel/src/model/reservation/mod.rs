@@ -0,0 +1,23 @@
+use crate::model::id::{BookId, ReservationId, UserId};
+use chrono::{DateTime, Utc};
+
+pub mod event;
+
+// 貸出中の蔵書に対する予約を表す型
+// ready_atがSomeの場合、返却済みの蔵書がこの予約者のために取り置かれている状態である
+#[derive(Debug)]
+pub struct Reservation {
+    pub id: ReservationId,
+    pub book_id: BookId,
+    pub reserved_by: UserId,
+    pub reserved_at: DateTime<Utc>,
+    pub ready_at: Option<DateTime<Utc>>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl Reservation {
+    // 取り置き(受け取り待ち)の状態であるかを返す
+    pub fn is_ready(&self) -> bool {
+        self.ready_at.is_some()
+    }
+}
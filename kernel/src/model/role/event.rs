@@ -0,0 +1,24 @@
+use crate::model::{
+    id::UserId,
+    role::{Permission, Role},
+};
+
+#[derive(Debug)]
+pub struct CreateRole {
+    pub role: Role,
+    pub permissions: Vec<Permission>,
+    pub requested_user: UserId,
+}
+
+#[derive(Debug)]
+pub struct UpdateRolePermissions {
+    pub role: Role,
+    pub permissions: Vec<Permission>,
+    pub requested_user: UserId,
+}
+
+#[derive(Debug)]
+pub struct DeleteRole {
+    pub role: Role,
+    pub requested_user: UserId,
+}
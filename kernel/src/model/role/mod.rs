@@ -0,0 +1,135 @@
+use std::{convert::Infallible, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+use strum::{AsRefStr, EnumIter, EnumString, IntoEnumIterator};
+use utoipa::ToSchema;
+
+pub mod event;
+
+// ロールはrolesテーブルに名前で登録する
+// AdminとUserは組み込みのロールで、それ以外は管理者が作成したロール(例: Librarian)とする
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub enum Role{
+    Admin,
+    #[default]
+    User,
+    Custom(String),
+}
+
+impl Role {
+    // 組み込みのロールは、削除や権限の変更ができない
+    pub fn is_builtin(&self) -> bool {
+        !matches!(self, Role::Custom(_))
+    }
+}
+
+impl From<&str> for Role {
+    fn from(value: &str) -> Self {
+        match value {
+            "Admin" => Role::Admin,
+            "User" => Role::User,
+            name => Role::Custom(name.to_string()),
+        }
+    }
+}
+
+impl From<String> for Role {
+    fn from(value: String) -> Self {
+        Role::from(value.as_str())
+    }
+}
+
+impl FromStr for Role {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Role::from(s))
+    }
+}
+
+impl AsRef<str> for Role {
+    fn as_ref(&self) -> &str {
+        match self {
+            Role::Admin => "Admin",
+            Role::User => "User",
+            Role::Custom(name) => name,
+        }
+    }
+}
+
+// ロールに付与する権限
+// 操作ごとにハンドラがこの権限を要求し、ユーザーのロールが持つ権限で実行できるかを判断する
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    EnumString,
+    AsRefStr,
+    EnumIter,
+    Serialize,
+    Deserialize,
+    ToSchema,
+)]
+pub enum Permission {
+    // 蔵書を登録する(自分を所有者として登録する)
+    #[strum(serialize = "book.create")]
+    #[serde(rename = "book.create")]
+    BookCreate,
+    // CSVで蔵書を一括登録する(他のユーザーを所有者として登録できる)
+    #[strum(serialize = "book.import")]
+    #[serde(rename = "book.import")]
+    BookImport,
+    // 他のユーザーが所有する蔵書を更新する
+    #[strum(serialize = "book.update.any")]
+    #[serde(rename = "book.update.any")]
+    BookUpdateAny,
+    // 他のユーザーが所有する蔵書を削除する
+    #[strum(serialize = "book.delete.any")]
+    #[serde(rename = "book.delete.any")]
+    BookDeleteAny,
+    // 全ユーザーの貸出状況(返却期限切れなど)を参照する
+    #[strum(serialize = "checkout.read.any")]
+    #[serde(rename = "checkout.read.any")]
+    CheckoutReadAny,
+    // 指定のユーザーに代わって蔵書を貸し出す
+    #[strum(serialize = "checkout.create.any")]
+    #[serde(rename = "checkout.create.any")]
+    CheckoutCreateAny,
+    // 他のユーザーが借りている蔵書を返却する(強制返却)
+    #[strum(serialize = "checkout.return.any")]
+    #[serde(rename = "checkout.return.any")]
+    CheckoutReturnAny,
+    // ユーザーの登録・削除・ロック解除を行う
+    #[strum(serialize = "user.manage")]
+    #[serde(rename = "user.manage")]
+    UserManage,
+    // ロールの作成・変更・削除と、ユーザーへのロールの割り当てを行う
+    #[strum(serialize = "role.manage")]
+    #[serde(rename = "role.manage")]
+    RoleManage,
+    // ロールごとの貸出ポリシーを変更する
+    #[strum(serialize = "policy.manage")]
+    #[serde(rename = "policy.manage")]
+    PolicyManage,
+    // 監査ログを参照する
+    #[strum(serialize = "audit.read")]
+    #[serde(rename = "audit.read")]
+    AuditRead,
+}
+
+impl Permission {
+    // Adminロールは、常にすべての権限を持つ
+    pub fn all() -> Vec<Permission> {
+        Permission::iter().collect()
+    }
+}
+
+// ロールと、そのロールに付与された権限
+#[derive(Debug, PartialEq, Eq)]
+pub struct RoleDetail {
+    pub role: Role,
+    pub permissions: Vec<Permission>,
+}
@@ -5,12 +5,15 @@ pub struct CreateUser {
     pub name: String,
     pub email: String,
     pub password: String,
+    // 登録を行ったユーザー
+    pub requested_user: UserId,
 }
 
 #[derive(Debug)]
 pub struct UpdateUserRole {
     pub user_id: UserId,
     pub role: Role,
+    pub requested_user: UserId,
 }
 
 #[derive(Debug)]
@@ -23,4 +26,5 @@ pub struct UpdateUserPassword {
 #[derive(Debug)]
 pub struct DeleteUser {
     pub user_id: UserId,
+    pub requested_user: UserId,
 }
@@ -0,0 +1,24 @@
+use async_trait::async_trait;
+use shared::error::AppResult;
+
+use crate::model::{
+    api_key::{
+        event::{CreateApiKey, DeleteApiKey},
+        ApiKey, AuthorizedApiKey, CreatedApiKey,
+    },
+    id::UserId,
+};
+
+#[mockall::automock]
+#[async_trait]
+pub trait ApiKeyRepository: Send + Sync {
+    // APIキーを発行する。キーはハッシュ化して保存する
+    async fn create(&self, event: CreateApiKey) -> AppResult<CreatedApiKey>;
+    // ユーザーが発行したAPIキーの一覧を取得する(期限切れのキーを含む)
+    async fn find_by_user_id(&self, user_id: UserId) -> AppResult<Vec<ApiKey>>;
+    // APIキーを失効させる
+    async fn delete(&self, event: DeleteApiKey) -> AppResult<()>;
+    // APIキーの持ち主とスコープを取得し、最終利用日時を記録する
+    // 存在しないキー・期限切れのキーの場合はNoneを返す
+    async fn authenticate(&self, key: &str) -> AppResult<Option<AuthorizedApiKey>>;
+}
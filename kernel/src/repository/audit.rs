@@ -0,0 +1,19 @@
+use async_trait::async_trait;
+use shared::error::AppResult;
+
+use crate::model::{
+    audit::{AuditEvent, AuditEventListOptions},
+    list::PaginatedList,
+};
+
+// 監査ログは各リポジトリの変更操作と同じトランザクションで追記されるため、
+// このリポジトリは参照のみを提供する
+#[mockall::automock]
+#[async_trait]
+pub trait AuditRepository: Send + Sync {
+    // 絞り込み条件に該当する監査ログを、新しい順に取得する
+    async fn find_all(
+        &self,
+        options: AuditEventListOptions,
+    ) -> AppResult<PaginatedList<AuditEvent>>;
+}
@@ -2,27 +2,51 @@ use async_trait::async_trait;
 use shared::error::AppResult;
 
 use crate::model::{
-    auth::{event::CreateToken, AccessToken},
-    id::UserId,
+    auth::{
+        event::{CreatePasswordReset, CreateToken, ResetPassword, VerifyUser},
+        AccessToken, AuthTokens, AuthorizedSession, PasswordReset, RefreshToken, Session,
+    },
+    id::{SessionId, UserId},
 };
 
 #[mockall::automock]
 #[async_trait]
 pub trait AuthRepository: Send + Sync { // traitにどんな機能を付与するか；trait境界
-    // アクセストークンからユーザーIDを取得する
-    async fn fetch_user_id_from_token(
+    // アクセストークンからユーザーIDとセッションの状態を取得する
+    async fn fetch_session_from_token(
         &self,
         access_token: &AccessToken,
-    ) -> AppResult<Option<UserId>>;
+    ) -> AppResult<Option<AuthorizedSession>>;
     // メアドとパスが正しいかの検証　
-    async fn verify_user (
-        &self,
-        email: &str,
-        password: &str,
-    ) -> AppResult<UserId>;
-    // アクセストークンを生成する　
-    async fn create_token(&self, event: CreateToken) -> AppResult<AccessToken>;
+    // 失敗が続いたメールアドレス・接続元からのログインは、一定期間受け付けない
+    async fn verify_user (&self, event: VerifyUser) -> AppResult<UserId>;
+    // ログインの失敗によるユーザーのロックを解除する
+    async fn unlock_user(&self, user_id: UserId) -> AppResult<()>;
+    // アクセストークンとリフレッシュトークンを生成する　
+    async fn create_token(&self, event: CreateToken) -> AppResult<AuthTokens>;
+    // リフレッシュトークンを使って、トークンの組を再発行する
+    // 使用済みのリフレッシュトークンが再度使われた場合は、セッションごと無効にする
+    async fn refresh_token(&self, refresh_token: RefreshToken) -> AppResult<AuthTokens>;
+    // 操作があったアクセストークンの有効期限を延長する(設定で有効な場合のみ)
+    async fn extend_token(&self, access_token: &AccessToken) -> AppResult<()>;
     // アクセストークンを削除する　
     async fn delete_token(&self, access_token: AccessToken) -> AppResult<()>;
+    // ユーザーの有効なセッションの一覧を取得する
+    async fn find_sessions(
+        &self,
+        user_id: UserId,
+        current: &AccessToken,
+    ) -> AppResult<Vec<Session>>;
+    // ユーザーのセッションを無効にする
+    async fn delete_session(&self, user_id: UserId, session_id: SessionId) -> AppResult<()>;
+    // ユーザーのすべてのセッションを無効にする
+    async fn delete_all_sessions(&self, user_id: UserId) -> AppResult<()>;
+    // パスワードの再設定用のトークンを発行する。発行済みのトークンは無効になる
+    // メールアドレスが登録されていない場合はNoneを返す
+    async fn create_password_reset(
+        &self,
+        event: CreatePasswordReset,
+    ) -> AppResult<Option<PasswordReset>>;
+    // トークンを消費してパスワードを再設定し、再設定したユーザーのIDを返す
+    async fn reset_password(&self, event: ResetPassword) -> AppResult<UserId>;
 }
-
@@ -3,8 +3,8 @@ use shared::error::AppResult;
 
 use crate::model::{
     book::{
-        event::{CreateBook,DeleteBook, UpdateBook},
-        Book, BookListOptions,
+        event::{AddBookCopies, CreateBook, DeleteBook, ImportBook, UpdateBook},
+        Book, BookImportOutcome, BookListOptions, BookSearchOptions, BookStream,
     },
     id::{BookId, UserId}, // BookId型をuseする
     list::PaginatedList,
@@ -19,12 +19,28 @@ pub trait BookRepository: Send + Sync {
         event: CreateBook,
         user_id: UserId,
     ) -> AppResult<()>;
+    // 複数の蔵書を1つのトランザクションで登録し、入力と同じ順序で1件ごとの結果を返す
+    // requested_userは一括登録を行ったユーザーで、監査ログに記録する
+    async fn create_many(
+        &self,
+        events: Vec<ImportBook>,
+        requested_user: UserId,
+    ) -> AppResult<Vec<BookImportOutcome>>;
     // ページネーションするためにoptions引数を追加し,戻り値はVecからPaginatedList型に変更
     async fn find_all(
         &self,
         options: BookListOptions,
     ) -> AppResult<PaginatedList<Book>>;
+    // 表記ゆれを吸収した検索を行い、関連度の高い順に返す
+    async fn search(
+        &self,
+        options: BookSearchOptions,
+    ) -> AppResult<PaginatedList<Book>>;
     async fn find_by_id(&self, book_id: BookId) -> AppResult<Option<Book>>;
+    // 全ての蔵書を登録順に1件ずつ返す
+    async fn export_all(&self) -> AppResult<BookStream>;
     async fn update(&self, event: UpdateBook) -> AppResult<()>;
     async fn delete(&self, event: DeleteBook) -> AppResult<()>;
+    // 蔵書に冊子を追加する
+    async fn add_copies(&self, event: AddBookCopies) -> AppResult<()>;
 }
\ No newline at end of file
@@ -2,7 +2,7 @@
 
 use crate::model::{
     checkout::{
-        event::{CreateCheckout, UpdateReturned},
+        event::{CreateCheckout, RenewCheckout, UpdateReturned},
         Checkout,
     },
     id::{BookId, UserId}
@@ -17,12 +17,18 @@ pub trait CheckoutRepository: Send + Sync {
     // 貸出操作を行う　
     async fn create(&self, event: CreateCheckout) -> AppResult<()>;
 
+    // 貸出の延長操作を行う
+    async fn renew(&self, event: RenewCheckout) -> AppResult<()>;
+
     // 返却操作を行う
     async fn update_returned(&self, event: UpdateReturned) -> AppResult<()>;
 
     // 全ての未返却の貸出情報を取得する
     async fn find_unreturned_all(&self) -> AppResult<Vec<Checkout>>;
 
+    // 返却期限を過ぎた未返却の貸出情報を取得する
+    async fn find_overdue_all(&self) -> AppResult<Vec<Checkout>>;
+
     // ユーザーIDに紐づく未返却の貸出情報を取得する
     async fn find_unreturned_by_user_id(
         &self,
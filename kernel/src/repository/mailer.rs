@@ -0,0 +1,13 @@
+use async_trait::async_trait;
+use shared::error::AppResult;
+
+use crate::model::auth::PasswordReset;
+
+// ユーザーへのメールの送信を抽象化したトレイト
+// 実装を差し替えることで、送信の手段(SMTP・外部のメール配信サービスなど)を切り替えられる
+#[mockall::automock]
+#[async_trait]
+pub trait Mailer: Send + Sync {
+    // パスワードの再設定用のトークンを、ユーザーのメールアドレスに送る
+    async fn send_password_reset(&self, reset: &PasswordReset) -> AppResult<()>;
+}
@@ -0,0 +1,13 @@
+use async_trait::async_trait;
+use shared::error::AppResult;
+
+use crate::model::book::{isbn::Isbn, BookMetadata};
+
+// ISBNから書誌情報を取得する外部サービスを抽象化したトレイト
+// 実装を差し替えることで、取得元のAPIを切り替えられる
+#[mockall::automock]
+#[async_trait]
+pub trait BookMetadataProvider: Send + Sync {
+    // 書誌情報が見つからない場合はNoneを返す
+    async fn find_by_isbn(&self, isbn: &Isbn) -> AppResult<Option<BookMetadata>>;
+}
@@ -0,0 +1,11 @@
+use async_trait::async_trait;
+use shared::error::AppResult;
+
+use crate::model::metrics::MetricsSnapshot;
+
+#[mockall::automock]
+#[async_trait]
+pub trait MetricsRepository: Send + Sync {
+    // コネクションプールの状態と、貸出件数の現在値を取得する
+    async fn snapshot(&self) -> AppResult<MetricsSnapshot>;
+}
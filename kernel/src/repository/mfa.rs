@@ -0,0 +1,32 @@
+use async_trait::async_trait;
+use shared::error::AppResult;
+
+use crate::model::{
+    id::UserId,
+    mfa::{
+        event::{CreateMfaChallenge, EnrollTotp, VerifyMfaChallenge, VerifyTotp},
+        MfaChallenge, TotpEnrollment, VerifiedMfaChallenge,
+    },
+};
+
+// TOTPによる2段階認証
+#[mockall::automock]
+#[async_trait]
+pub trait MfaRepository: Send + Sync {
+    // TOTPの登録を開始し、秘密鍵とリカバリーコードを払い出す
+    // 登録が完了するまでは、ログインに2段階目の認証は求めない
+    async fn enroll_totp(&self, event: EnrollTotp) -> AppResult<TotpEnrollment>;
+    // 認証アプリに表示されたコードを検証し、TOTPの登録を完了する
+    async fn confirm_totp(&self, event: VerifyTotp) -> AppResult<()>;
+    // 認証アプリに表示されたコードを検証し、TOTPの登録を解除する
+    async fn disable_totp(&self, event: VerifyTotp) -> AppResult<()>;
+    // ユーザーがTOTPの登録を完了しているかどうか
+    async fn is_totp_enabled(&self, user_id: UserId) -> AppResult<bool>;
+    // 2段階目の認証を待つログインを作る
+    async fn create_challenge(&self, event: CreateMfaChallenge) -> AppResult<MfaChallenge>;
+    // 2段階目の認証を行う。失敗が続いた場合は、パスワードの検証からやり直させる
+    async fn verify_challenge(
+        &self,
+        event: VerifyMfaChallenge,
+    ) -> AppResult<VerifiedMfaChallenge>;
+}
@@ -0,0 +1,15 @@
+use async_trait::async_trait;
+use shared::error::AppResult;
+
+use crate::model::policy::{event::UpdateBorrowingPolicy, BorrowingPolicy};
+
+#[mockall::automock]
+#[async_trait]
+pub trait PolicyRepository: Send + Sync {
+    // 全てのロールの貸出ポリシーを取得する
+    // ポリシーが登録されていないロールは既定値を返す
+    async fn find_all(&self) -> AppResult<Vec<BorrowingPolicy>>;
+
+    // ロールの貸出ポリシーを登録・更新する
+    async fn update(&self, event: UpdateBorrowingPolicy) -> AppResult<()>;
+}
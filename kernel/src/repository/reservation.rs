@@ -0,0 +1,31 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use shared::error::AppResult;
+
+use crate::model::{
+    id::BookId,
+    reservation::{
+        event::{CreateReservation, DeleteReservation},
+        Reservation,
+    },
+};
+
+#[mockall::automock]
+#[async_trait]
+pub trait ReservationRepository: Send + Sync {
+    // 貸出中の蔵書に予約を追加する
+    async fn create(&self, event: CreateReservation) -> AppResult<()>;
+
+    // 自分の予約を取り消す
+    async fn delete(&self, event: DeleteReservation) -> AppResult<()>;
+
+    // 蔵書に対する予約を予約日時の古い順(待ち行列の順)に取得する
+    async fn find_by_book_id(
+        &self,
+        book_id: BookId,
+    ) -> AppResult<Vec<Reservation>>;
+
+    // 取り置き期限の切れた予約を片付け、次の予約者に取り置きを回す
+    // 蔵書に対する操作がなくても待ち行列が進むよう、定期的に呼び出す
+    async fn expire_holds(&self, now: DateTime<Utc>) -> AppResult<()>;
+}
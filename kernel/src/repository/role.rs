@@ -0,0 +1,27 @@
+use async_trait::async_trait;
+use shared::error::AppResult;
+
+use crate::model::role::{
+    event::{CreateRole, DeleteRole, UpdateRolePermissions},
+    Permission, Role, RoleDetail,
+};
+
+#[mockall::automock]
+#[async_trait]
+pub trait RoleRepository: Send + Sync {
+    // 全てのロールと、付与された権限を取得する
+    async fn find_all(&self) -> AppResult<Vec<RoleDetail>>;
+
+    // ロールに付与された権限を取得する
+    // Adminロールは、登録内容によらずすべての権限を返す
+    async fn find_permissions(&self, role: &Role) -> AppResult<Vec<Permission>>;
+
+    // ロールを作成する
+    async fn create(&self, event: CreateRole) -> AppResult<()>;
+
+    // ロールに付与する権限を変更する
+    async fn update_permissions(&self, event: UpdateRolePermissions) -> AppResult<()>;
+
+    // ロールを削除する。ユーザーが割り当てられているロールは削除できない
+    async fn delete(&self, event: DeleteRole) -> AppResult<()>;
+}
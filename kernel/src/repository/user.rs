@@ -1,5 +1,3 @@
-use std::io::Cursor;
-
 use async_trait::async_trait;
 use shared::error::AppResult;
 
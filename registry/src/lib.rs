@@ -10,14 +10,32 @@ use adapter::{
 };
 use adapter::repository::user::UserRepositoryImpl;
 use adapter::repository::checkout::CheckoutRepositoryImpl;
+use adapter::repository::reservation::ReservationRepositoryImpl;
+use adapter::repository::policy::PolicyRepositoryImpl;
+use adapter::repository::metadata::BookMetadataProviderImpl;
+use adapter::repository::metrics::MetricsRepositoryImpl;
+use adapter::repository::mailer::SmtpMailer;
+use adapter::repository::mfa::MfaRepositoryImpl;
+use adapter::repository::api_key::ApiKeyRepositoryImpl;
+use adapter::repository::role::RoleRepositoryImpl;
+use adapter::repository::audit::AuditRepositoryImpl;
 
 use kernel::repository::{
     auth::AuthRepository, book::BookRepository, health::HealthCheckRepository,
 };
 use kernel::repository::user::UserRepository;
 use kernel::repository::checkout::CheckoutRepository;
+use kernel::repository::reservation::ReservationRepository;
+use kernel::repository::policy::PolicyRepository;
+use kernel::repository::metadata::BookMetadataProvider;
+use kernel::repository::metrics::MetricsRepository;
+use kernel::repository::mailer::Mailer;
+use kernel::repository::mfa::MfaRepository;
+use kernel::repository::api_key::ApiKeyRepository;
+use kernel::repository::role::RoleRepository;
+use kernel::repository::audit::AuditRepository;
 
-use shared::config::AppConfig;
+use shared::{config::AppConfig, metrics::Metrics};
 
 
 // 1. DIコンテナの役割を果たす構造体を定義する。
@@ -28,12 +46,25 @@ pub struct AppRegistryImpl {
     auth_repository: Arc<dyn AuthRepository>,
     user_repository: Arc<dyn UserRepository>,
     checkout_repository: Arc<dyn CheckoutRepository>,
+    reservation_repository: Arc<dyn ReservationRepository>,
+    policy_repository: Arc<dyn PolicyRepository>,
+    book_metadata_provider: Arc<dyn BookMetadataProvider>,
+    metrics_repository: Arc<dyn MetricsRepository>,
+    mailer: Arc<dyn Mailer>,
+    mfa_repository: Arc<dyn MfaRepository>,
+    api_key_repository: Arc<dyn ApiKeyRepository>,
+    role_repository: Arc<dyn RoleRepository>,
+    audit_repository: Arc<dyn AuditRepository>,
+    metrics: Arc<Metrics>,
 }
 
 impl AppRegistryImpl {
     pub fn new(
         pool: ConnectionPool,
         redis_client: Arc<RedisClient>,
+        mailer: Arc<SmtpMailer>,
+        book_metadata_provider: Arc<BookMetadataProviderImpl>,
+        metrics: Arc<Metrics>,
         app_config: AppConfig,
     ) -> Self {
         // 2. 依存解決を行う
@@ -43,10 +74,34 @@ impl AppRegistryImpl {
         let auth_repository = Arc::new(AuthRepositoryImpl::new(
             pool.clone(),
             redis_client.clone(),
-            app_config.auth.ttl,));
+            app_config.auth.clone(),
+        ));
         let user_repository = Arc::new(UserRepositoryImpl::new(pool.clone()));
-        let checkout_repository = 
-            Arc::new(CheckoutRepositoryImpl::new(pool.clone()));
+        let checkout_repository = Arc::new(CheckoutRepositoryImpl::new(
+            pool.clone(),
+            app_config.reservation.hold_ttl,
+            app_config.checkout,
+        ));
+        let reservation_repository = Arc::new(ReservationRepositoryImpl::new(
+            pool.clone(),
+            app_config.reservation.hold_ttl,
+        ));
+        let policy_repository = Arc::new(PolicyRepositoryImpl::new(
+            pool.clone(),
+            app_config.checkout,
+        ));
+        let metrics_repository = Arc::new(MetricsRepositoryImpl::new(pool.clone()));
+        let mfa_repository = Arc::new(MfaRepositoryImpl::new(
+            pool.clone(),
+            redis_client.clone(),
+            app_config.auth.mfa,
+        ));
+        let api_key_repository = Arc::new(ApiKeyRepositoryImpl::new(
+            pool.clone(),
+            app_config.auth.mfa,
+        ));
+        let role_repository = Arc::new(RoleRepositoryImpl::new(pool.clone()));
+        let audit_repository = Arc::new(AuditRepositoryImpl::new(pool.clone()));
 
         Self {
             health_check_repository,
@@ -54,6 +109,16 @@ impl AppRegistryImpl {
             auth_repository,
             user_repository,
             checkout_repository,
+            reservation_repository,
+            policy_repository,
+            book_metadata_provider,
+            metrics_repository,
+            mailer,
+            mfa_repository,
+            api_key_repository,
+            role_repository,
+            audit_repository,
+            metrics,
         }
     }
 }
@@ -67,11 +132,21 @@ pub trait AppRegistryExt {
     fn auth_repository(&self) -> Arc<dyn AuthRepository>;
     fn checkout_repository(&self) -> Arc<dyn CheckoutRepository>;
     fn user_repository(&self) -> Arc<dyn UserRepository>;
+    fn reservation_repository(&self) -> Arc<dyn ReservationRepository>;
+    fn policy_repository(&self) -> Arc<dyn PolicyRepository>;
+    fn book_metadata_provider(&self) -> Arc<dyn BookMetadataProvider>;
+    fn metrics_repository(&self) -> Arc<dyn MetricsRepository>;
+    fn mailer(&self) -> Arc<dyn Mailer>;
+    fn mfa_repository(&self) -> Arc<dyn MfaRepository>;
+    fn api_key_repository(&self) -> Arc<dyn ApiKeyRepository>;
+    fn role_repository(&self) -> Arc<dyn RoleRepository>;
+    fn audit_repository(&self) -> Arc<dyn AuditRepository>;
+    fn metrics(&self) -> Arc<Metrics>;
 }
 
 impl AppRegistryExt for AppRegistryImpl {
     fn health_check_repository(&self) -> Arc<dyn HealthCheckRepository> {
-        self.health_check_repository().clone()
+        self.health_check_repository.clone()
     }
 
     fn book_repository(&self) -> Arc<dyn BookRepository> {
@@ -89,6 +164,46 @@ impl AppRegistryExt for AppRegistryImpl {
     fn checkout_repository(&self) -> Arc<dyn CheckoutRepository> {
         self.checkout_repository.clone()
     }
+
+    fn reservation_repository(&self) -> Arc<dyn ReservationRepository> {
+        self.reservation_repository.clone()
+    }
+
+    fn policy_repository(&self) -> Arc<dyn PolicyRepository> {
+        self.policy_repository.clone()
+    }
+
+    fn book_metadata_provider(&self) -> Arc<dyn BookMetadataProvider> {
+        self.book_metadata_provider.clone()
+    }
+
+    fn metrics_repository(&self) -> Arc<dyn MetricsRepository> {
+        self.metrics_repository.clone()
+    }
+
+    fn mailer(&self) -> Arc<dyn Mailer> {
+        self.mailer.clone()
+    }
+
+    fn mfa_repository(&self) -> Arc<dyn MfaRepository> {
+        self.mfa_repository.clone()
+    }
+
+    fn api_key_repository(&self) -> Arc<dyn ApiKeyRepository> {
+        self.api_key_repository.clone()
+    }
+
+    fn role_repository(&self) -> Arc<dyn RoleRepository> {
+        self.role_repository.clone()
+    }
+
+    fn audit_repository(&self) -> Arc<dyn AuditRepository> {
+        self.audit_repository.clone()
+    }
+
+    fn metrics(&self) -> Arc<Metrics> {
+        self.metrics.clone()
+    }
 }
 
 pub type AppRegistry = Arc<dyn AppRegistryExt + Send + Sync + 'static>;
@@ -1,9 +1,18 @@
+use std::net::IpAddr;
+
 use anyhow::Result;
 
+use crate::i18n::Language;
+
 pub struct AppConfig {
     pub database: DatabaseConfig,
     pub redis: RedisConfig,
     pub auth: AuthConfig,
+    pub reservation: ReservationConfig,
+    pub checkout: CheckoutConfig,
+    pub book_metadata: BookMetadataConfig,
+    pub i18n: I18nConfig,
+    pub mail: MailConfig,
 }
 
 impl AppConfig {
@@ -22,11 +31,108 @@ impl AppConfig {
         };
         let auth = AuthConfig {
             ttl: std::env::var("AUTH_TOKEN_TTL")?.parse::<u64>()?,
+            refresh_ttl: std::env::var("AUTH_REFRESH_TOKEN_TTL")?.parse::<u64>()?,
+            // 未設定の場合は延長しない
+            sliding_expiration: std::env::var("AUTH_SLIDING_EXPIRATION")
+                .ok()
+                .map(|v| v.parse::<bool>())
+                .transpose()?
+                .unwrap_or(false),
+            login: LoginProtectionConfig {
+                max_failures: std::env::var("AUTH_LOGIN_MAX_FAILURES")?.parse::<u64>()?,
+                max_failures_per_ip: std::env::var("AUTH_LOGIN_MAX_FAILURES_PER_IP")?
+                    .parse::<u64>()?,
+                lockout_ttl: std::env::var("AUTH_LOGIN_LOCKOUT_TTL")?.parse::<u64>()?,
+                delay_ms: std::env::var("AUTH_LOGIN_DELAY_MS")?.parse::<u64>()?,
+            },
+            password_reset_ttl: std::env::var("AUTH_PASSWORD_RESET_TTL")?.parse::<u64>()?,
+            // カンマ区切りで指定する。未設定の場合は、どの接続元の`X-Forwarded-For`も信頼しない
+            trusted_proxies: std::env::var("AUTH_TRUSTED_PROXIES")
+                .unwrap_or_default()
+                .split(',')
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+                .map(|v| v.parse::<IpAddr>())
+                .collect::<Result<_, _>>()?,
+            mfa: MfaConfig {
+                // 未設定の場合は、2段階認証を必須としない
+                required_for_admin: std::env::var("AUTH_MFA_REQUIRED_FOR_ADMIN")
+                    .ok()
+                    .map(|v| v.parse::<bool>())
+                    .transpose()?
+                    .unwrap_or(false),
+                challenge_ttl: std::env::var("AUTH_MFA_CHALLENGE_TTL")?.parse::<u64>()?,
+            },
+        };
+        let reservation = ReservationConfig {
+            hold_ttl: std::env::var("RESERVATION_HOLD_TTL")?.parse::<u64>()?,
+            // 未設定の場合は1分ごととする
+            expiry_interval: std::env::var("RESERVATION_EXPIRY_INTERVAL")
+                .ok()
+                .map(|v| v.parse::<u64>())
+                .transpose()?
+                .unwrap_or(60),
+        };
+        let checkout = CheckoutConfig {
+            max_loans: std::env::var("CHECKOUT_MAX_LOANS")?.parse::<i32>()?,
+            loan_period_days: std::env::var("CHECKOUT_LOAN_PERIOD_DAYS")?
+                .parse::<i32>()?,
+            max_renewals: std::env::var("CHECKOUT_MAX_RENEWALS")?
+                .parse::<i32>()?,
+        };
+        let book_metadata = BookMetadataConfig {
+            base_url: std::env::var("BOOK_METADATA_BASE_URL")?,
+            cache_ttl: std::env::var("BOOK_METADATA_CACHE_TTL")?
+                .parse::<u64>()?,
+            // 未設定の場合は5分とする
+            not_found_cache_ttl: std::env::var("BOOK_METADATA_NOT_FOUND_CACHE_TTL")
+                .ok()
+                .map(|v| v.parse::<u64>())
+                .transpose()?
+                .unwrap_or(300),
+            // 未設定の場合は、接続に3秒、応答の受信までに10秒を上限とする
+            connect_timeout_ms: std::env::var("BOOK_METADATA_CONNECT_TIMEOUT_MS")
+                .ok()
+                .map(|v| v.parse::<u64>())
+                .transpose()?
+                .unwrap_or(3000),
+            timeout_ms: std::env::var("BOOK_METADATA_TIMEOUT_MS")
+                .ok()
+                .map(|v| v.parse::<u64>())
+                .transpose()?
+                .unwrap_or(10000),
+        };
+        // 未設定の場合は日本語とする
+        let i18n = I18nConfig {
+            default_language: std::env::var("DEFAULT_LANGUAGE")
+                .ok()
+                .map(|v| v.parse::<Language>())
+                .transpose()?
+                .unwrap_or_default(),
+        };
+        let mail = MailConfig {
+            smtp_host: std::env::var("SMTP_HOST")?,
+            smtp_port: std::env::var("SMTP_PORT")?.parse::<u16>()?,
+            // 認証が不要なSMTPサーバー(ローカルの受信用サーバーなど)では未設定とする
+            smtp_username: std::env::var("SMTP_USERNAME").ok(),
+            smtp_password: std::env::var("SMTP_PASSWORD").ok(),
+            smtp_starttls: std::env::var("SMTP_STARTTLS")
+                .ok()
+                .map(|v| v.parse::<bool>())
+                .transpose()?
+                .unwrap_or(false),
+            from: std::env::var("MAIL_FROM")?,
+            password_reset_url: std::env::var("PASSWORD_RESET_URL")?,
         };
         Ok(Self {
             database,
             redis,
             auth, 
+            reservation,
+            checkout,
+            book_metadata,
+            i18n,
+            mail,
         })
     }
 }
@@ -44,6 +150,97 @@ pub struct RedisConfig{
     pub port: u16,
 }
 
+#[derive(Clone)]
 pub struct AuthConfig{
+    // アクセストークンの有効期間(秒)
     pub ttl: u64,
-}
\ No newline at end of file
+    // リフレッシュトークンの有効期間(秒)、再発行するたびにこの期間だけ延びる
+    pub refresh_ttl: u64,
+    // trueの場合、APIを呼び出すたびにアクセストークンの有効期限をttl秒後まで延長する
+    pub sliding_expiration: bool,
+    pub login: LoginProtectionConfig,
+    // パスワードの再設定用のトークンの有効期間(秒)
+    pub password_reset_ttl: u64,
+    // 接続元がこれらのアドレスの場合に限り、`X-Forwarded-For`から実際の接続元を求める
+    pub trusted_proxies: Vec<IpAddr>,
+    pub mfa: MfaConfig,
+}
+
+// TOTPによる2段階認証
+#[derive(Clone, Copy)]
+pub struct MfaConfig{
+    // trueの場合、Adminは2段階認証を経てログインしたセッションでのみ権限(user.manageなど)を使える
+    // 2段階認証を登録していないAdminは、登録するまで権限を必要としない操作のみ行える
+    // Admin以外のロールには影響しない
+    pub required_for_admin: bool,
+    // パスワードの検証に成功してから、2段階目の認証を受け付ける期間(秒)
+    pub challenge_ttl: u64,
+}
+
+// ログインの総当たり攻撃への対策
+// 失敗の回数はメールアドレスごと・接続元のIPアドレスごとに数える
+#[derive(Clone, Copy)]
+pub struct LoginProtectionConfig{
+    // この回数だけ失敗したメールアドレスはロックする
+    pub max_failures: u64,
+    // この回数だけ失敗したIPアドレスからのログインはロックする
+    pub max_failures_per_ip: u64,
+    // ロックする期間(秒)、失敗の回数は最後に失敗してからこの期間が過ぎるとリセットされる
+    pub lockout_ttl: u64,
+    // 失敗が続いた場合に、ログインの処理の前に待たせる時間の基準(ミリ秒)
+    // 失敗するたびに倍になる
+    pub delay_ms: u64,
+}
+
+pub struct ReservationConfig{
+    // 返却された蔵書を予約者のために取り置いておく期間(秒)
+    pub hold_ttl: u64,
+    // 取り置き期限の切れた予約を片付け、次の予約者に取り置きを回す間隔(秒)
+    pub expiry_interval: u64,
+}
+
+// 貸出ポリシーの既定値
+// DBにポリシーが登録されていないロールのユーザーに適用する
+#[derive(Clone, Copy)]
+pub struct CheckoutConfig{
+    pub max_loans: i32,
+    pub loan_period_days: i32,
+    pub max_renewals: i32,
+}
+
+// ISBNから書誌情報を取得する外部APIの接続情報
+// テスト時はbase_urlにローカルのスタブサーバーを指定できる
+pub struct BookMetadataConfig{
+    pub base_url: String,
+    // 取得した書誌情報をRedisにキャッシュしておく期間(秒)
+    pub cache_ttl: u64,
+    // 外部APIで見つからなかったことをキャッシュしておく期間(秒)
+    // 後から外部APIに登録される場合に備え、cache_ttlより短くする
+    pub not_found_cache_ttl: u64,
+    // 外部APIへの接続を待つ時間の上限(ミリ秒)
+    pub connect_timeout_ms: u64,
+    // 接続からレスポンスの受信を終えるまでの時間の上限(ミリ秒)
+    pub timeout_ms: u64,
+}
+
+// エラーメッセージの言語
+// リクエストの`Accept-Language`が対応していない言語の場合にdefault_languageを使う
+pub struct I18nConfig{
+    pub default_language: Language,
+}
+
+// メールの送信に使うSMTPサーバーの接続情報
+// テスト時はローカルで起動したSMTPサーバーを指定できる
+pub struct MailConfig{
+    pub smtp_host: String,
+    pub smtp_port: u16,
+    pub smtp_username: Option<String>,
+    pub smtp_password: Option<String>,
+    // trueの場合、STARTTLSで暗号化してから送信する
+    pub smtp_starttls: bool,
+    // 送信元のメールアドレス
+    pub from: String,
+    // パスワードの再設定画面のURL。末尾にトークンを付けてメールで送る
+    // 例: https://example.com/password-reset?token=
+    pub password_reset_url: String,
+}
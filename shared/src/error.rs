@@ -1,12 +1,23 @@
-use axum::{http::StatusCode, response::IntoResponse};
+use axum::{
+    http::{header, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
+use utoipa::ToSchema;
+
+use crate::{
+    i18n::{self, localize_validation_message, Message},
+    request_id,
+};
 
 #[derive(Error, Debug)]
 pub enum AppError {
     #[error("{0}")]
-    UnprocessableEntity(String),
+    UnprocessableEntity(Message),
     #[error("{0}")]
-    EntityNotFound(String),
+    EntityNotFound(Message),
     #[error("{0}")]
     ValidationError(#[from] garde::Report),
     #[error("トランザクションを実行できませんでした。")]
@@ -27,13 +38,21 @@ pub enum AppError {
     UnauthorizedError,
     #[error("許可されていない操作です")]
     ForbiddenOperation,
+    #[error("ログインの失敗が続いたため、一時的にログインできません")]
+    TooManyLoginAttempts,
     #[error("{0}")]
     ConversionEntityError(String),
+    #[error("外部サービスとの通信中にエラーが発生しました。")]
+    ExternalServiceError(#[from] reqwest::Error),
+    #[error("メールの送信中にエラーが発生しました: {0}")]
+    MailDeliveryError(String),
+    #[error("{0}")]
+    MetricsError(#[from] prometheus::Error),
 }
 
-impl IntoResponse for AppError {
-    fn into_response(self) -> axum::response::Response {
-        let status_code = match self {
+impl AppError {
+    pub fn status_code(&self) -> StatusCode {
+        match self {
             AppError::UnprocessableEntity(_) => StatusCode::UNPROCESSABLE_ENTITY,
             AppError::EntityNotFound(_) => StatusCode::NOT_FOUND,
             AppError::ValidationError(_) | AppError::ConvertToUuidError(_) => {
@@ -41,21 +60,135 @@ impl IntoResponse for AppError {
             }
             AppError::UnauthenticatedError | AppError::ForbiddenOperation => StatusCode::FORBIDDEN,
             AppError::UnauthorizedError => StatusCode::UNAUTHORIZED,
-            e @ (AppError::TransactionError(_)
+            AppError::TooManyLoginAttempts => StatusCode::TOO_MANY_REQUESTS,
+            AppError::ExternalServiceError(_) | AppError::MailDeliveryError(_) => {
+                StatusCode::BAD_GATEWAY
+            }
+            AppError::TransactionError(_)
             | AppError::SpecificOperationError(_)
             | AppError::NoRowsAffectedError(_)
             | AppError::KeyValueStoreError(_)
             | AppError::BcryptError(_)
-            | AppError::ConversionEntityError(_)) => {
-                tracing::error! {
-                    error.cause_chain = ?e,
-                    error.message = %e,
-                    "unexpected error happend"
-                };
-                StatusCode::INTERNAL_SERVER_ERROR
+            | AppError::ConversionEntityError(_)
+            | AppError::MetricsError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    // クライアントがエラーの種類を判別するための、変更しない識別子
+    // 業務上のエラーはメッセージカタログのキーを、それ以外はエラーの種類ごとの識別子を返す
+    // 500番台のうち内部の処理に起因するものは、種類を区別せずに返す
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::UnprocessableEntity(message) | AppError::EntityNotFound(message) => {
+                message.code()
+            }
+            AppError::ValidationError(_) => "validation_error",
+            AppError::ConvertToUuidError(_) => "invalid_id",
+            AppError::UnauthenticatedError => "unauthenticated",
+            AppError::UnauthorizedError => "unauthorized",
+            AppError::ForbiddenOperation => "forbidden_operation",
+            AppError::TooManyLoginAttempts => "too_many_login_attempts",
+            AppError::ExternalServiceError(_) | AppError::MailDeliveryError(_) => {
+                "external_service_error"
+            }
+            AppError::TransactionError(_)
+            | AppError::SpecificOperationError(_)
+            | AppError::NoRowsAffectedError(_)
+            | AppError::KeyValueStoreError(_)
+            | AppError::BcryptError(_)
+            | AppError::ConversionEntityError(_)
+            | AppError::MetricsError(_) => "internal_server_error",
+        }
+    }
+
+    // クライアントに返す説明を、リクエストの言語で組み立てる
+    // 500番台は内部の情報を含みうるため、エラーの内容ではなく定型の文言を返す
+    fn detail(&self) -> String {
+        let language = i18n::current();
+        match self {
+            AppError::UnprocessableEntity(message) | AppError::EntityNotFound(message) => {
+                message.localize(language)
             }
+            e => Message::new(e.code()).localize(language),
+        }
+    }
+
+    fn field_errors(&self) -> Vec<FieldError> {
+        let language = i18n::current();
+        match self {
+            AppError::ValidationError(report) => report
+                .iter()
+                .map(|(path, error)| FieldError {
+                    field: path.to_string(),
+                    message: localize_validation_message(error.message(), language)
+                        .into_owned(),
+                })
+                .collect(),
+            _ => vec![],
+        }
+    }
+}
+
+// RFC 7807(Problem Details for HTTP APIs)の形式のエラーレスポンス
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ProblemDetails {
+    #[serde(rename = "type")]
+    pub problem_type: String,
+    pub title: String,
+    pub status: u16,
+    pub code: String,
+    pub detail: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+    // 入力値の検証に失敗した項目ごとのエラー
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<FieldError>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldError {
+    // 項目のパス(ネストした項目は`items[0].title`のように表す)
+    pub field: String,
+    pub message: String,
+}
+
+pub const PROBLEM_JSON_CONTENT_TYPE: &str = "application/problem+json";
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> axum::response::Response {
+        let status_code = self.status_code();
+        let request_id = request_id::current();
+
+        if status_code.is_server_error() {
+            tracing::error! {
+                error.cause_chain = ?self,
+                error.message = %self,
+                request_id = request_id.as_deref().unwrap_or_default(),
+                "unexpected error happend"
+            };
+        }
+
+        let body = ProblemDetails {
+            problem_type: "about:blank".into(),
+            title: status_code
+                .canonical_reason()
+                .unwrap_or_default()
+                .to_string(),
+            status: status_code.as_u16(),
+            code: self.code().to_string(),
+            detail: self.detail(),
+            request_id,
+            errors: self.field_errors(),
         };
-        status_code.into_response()
+
+        (
+            status_code,
+            [(header::CONTENT_TYPE, PROBLEM_JSON_CONTENT_TYPE)],
+            Json(body),
+        )
+            .into_response()
     }
 }
 
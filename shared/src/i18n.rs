@@ -0,0 +1,317 @@
+// エラーメッセージの多言語対応
+// メッセージはエラーコードをキーとしたカタログから、リクエストの言語に合わせて組み立てる
+// 言語は`Accept-Language`ヘッダーから決め、処理中のタスクから参照できるようにする
+
+use std::{borrow::Cow, fmt, future::Future};
+
+use strum::{AsRefStr, EnumString, IntoStaticStr};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, EnumString, AsRefStr, IntoStaticStr)]
+#[strum(serialize_all = "lowercase", ascii_case_insensitive)]
+pub enum Language {
+    #[default]
+    Ja,
+    En,
+}
+
+impl Language {
+    // `Accept-Language`ヘッダーの値から、対応している言語のうち最も優先度の高いものを選ぶ
+    // 例: "en-US,en;q=0.9,ja;q=0.8" -> En
+    pub fn from_accept_language(header: &str) -> Option<Self> {
+        let mut candidates = header
+            .split(',')
+            .filter_map(|item| {
+                let mut parts = item.trim().split(';');
+                let tag = parts.next()?.trim();
+                let quality = parts
+                    .find_map(|p| p.trim().strip_prefix("q="))
+                    .map_or(Some(1.0), |q| q.trim().parse::<f32>().ok())?;
+                // 地域のサブタグ(en-USのUS)は区別しない
+                let primary = tag.split('-').next()?;
+                let language = primary.parse::<Language>().ok()?;
+                (quality > 0.0).then_some((language, quality))
+            })
+            .collect::<Vec<_>>();
+        // 同じ優先度の場合はヘッダーに書かれた順を保つ
+        candidates.sort_by(|a, b| b.1.total_cmp(&a.1));
+        candidates.first().map(|(language, _)| *language)
+    }
+}
+
+tokio::task_local! {
+    static LANGUAGE: Language;
+}
+
+// 指定した言語でメッセージを組み立てる状態で処理を実行する
+pub async fn scope<F: Future>(language: Language, f: F) -> F::Output {
+    LANGUAGE.scope(language, f).await
+}
+
+// 処理中のリクエストの言語を返す。リクエストの外から呼ばれた場合は既定の言語となる
+pub fn current() -> Language {
+    LANGUAGE.try_with(|language| *language).unwrap_or_default()
+}
+
+// カタログのキーとなるエラーコードと、メッセージに埋め込む値の組
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Message {
+    code: &'static str,
+    args: Vec<(&'static str, String)>,
+}
+
+impl Message {
+    pub fn new(code: &'static str) -> Self {
+        Self { code, args: vec![] }
+    }
+
+    // メッセージ中の`{name}`を置き換える値を追加する
+    pub fn arg(mut self, name: &'static str, value: impl ToString) -> Self {
+        self.args.push((name, value.to_string()));
+        self
+    }
+
+    pub fn code(&self) -> &'static str {
+        self.code
+    }
+
+    pub fn localize(&self, language: Language) -> String {
+        let template = catalog(self.code, language).unwrap_or(self.code);
+        self.args
+            .iter()
+            .fold(template.to_string(), |message, (name, value)| {
+                message.replace(&format!("{{{}}}", name), value)
+            })
+    }
+}
+
+// 処理中のリクエストの言語で書き出す
+impl fmt::Display for Message {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.localize(current()))
+    }
+}
+
+fn catalog(code: &str, language: Language) -> Option<&'static str> {
+    use Language::*;
+    let message = match (code, language) {
+        // AppErrorの種類ごとの既定のメッセージ
+        ("validation_error", Ja) => "入力値が正しくありません。",
+        ("validation_error", En) => "The request contains invalid values.",
+        ("invalid_id", Ja) => "IDの形式が正しくありません。",
+        ("invalid_id", En) => "The ID is not in a valid format.",
+        ("unauthenticated", Ja) => "ログインに失敗しました。",
+        ("unauthenticated", En) => "Authentication failed.",
+        ("unauthorized", Ja) => "認可情報が誤っています。",
+        ("unauthorized", En) => "The authorization credentials are invalid.",
+        ("forbidden_operation", Ja) => "許可されていない操作です。",
+        ("forbidden_operation", En) => "This operation is not permitted.",
+        ("too_many_login_attempts", Ja) => {
+            "ログインの失敗が続いたため、一時的にログインできません。しばらくしてから再度お試しください。"
+        }
+        ("too_many_login_attempts", En) => {
+            "Too many failed login attempts. Please try again later."
+        }
+        ("external_service_error", Ja) => "外部サービスとの通信中にエラーが発生しました。",
+        ("external_service_error", En) => {
+            "An error occurred while communicating with an external service."
+        }
+        ("internal_server_error", Ja) => "サーバー内部でエラーが発生しました。",
+        ("internal_server_error", En) => "An internal server error occurred.",
+
+        // 蔵書
+        ("book_not_found", Ja) => "書籍({book_id})が見つかりませんでした。",
+        ("book_not_found", En) => "Book ({book_id}) was not found.",
+        ("book_already_registered", Ja) => {
+            "ISBN({isbn})の蔵書({book_id})はすでに登録されています。冊子を追加してください。"
+        }
+        ("book_already_registered", En) => {
+            "A book ({book_id}) with ISBN ({isbn}) is already registered. Add copies to it instead."
+        }
+        ("invalid_isbn", Ja) => "ISBN({isbn})の形式が正しくありません。",
+        ("invalid_isbn", En) => "ISBN ({isbn}) is not valid.",
+        ("book_metadata_not_found", Ja) => "ISBN({isbn})の書誌情報が見つかりませんでした。",
+        ("book_metadata_not_found", En) => "No bibliographic data was found for ISBN ({isbn}).",
+        ("invalid_csv", Ja) => "CSVを読み込めませんでした: {reason}",
+        ("invalid_csv", En) => "The CSV could not be read: {reason}",
+
+        // 貸出
+        ("checkout_not_found", Ja) => "貸出({checkout_id})が見つかりませんでした。",
+        ("checkout_not_found", En) => "Checkout ({checkout_id}) was not found.",
+        ("book_already_checked_out_by_user", Ja) => {
+            "書籍({book_id})はすでにユーザー({user_id})に貸出中です。"
+        }
+        ("book_already_checked_out_by_user", En) => {
+            "Book ({book_id}) is already checked out to user ({user_id})."
+        }
+        ("checkout_already_exists", Ja) => "書籍({book_id})に対する貸出がすでに存在しています。",
+        ("checkout_already_exists", En) => "No copy of book ({book_id}) is available for checkout.",
+        ("book_on_hold_for_other_user", Ja) => {
+            "書籍({book_id})は他のユーザーのために取り置かれています。"
+        }
+        ("book_on_hold_for_other_user", En) => "Book ({book_id}) is on hold for another user.",
+        ("loan_limit_reached", Ja) => {
+            "ユーザー({user_id})の貸出冊数が上限({max_loans}冊)に達しています。"
+        }
+        ("loan_limit_reached", En) => {
+            "User ({user_id}) has reached the loan limit ({max_loans} books)."
+        }
+        ("checkout_not_renewable", Ja) => {
+            "指定の貸出ID(({checkout_id}), ユーザー({user_id}), 書籍({book_id}))は延長できません。"
+        }
+        ("checkout_not_renewable", En) => {
+            "Checkout ({checkout_id}) of book ({book_id}) by user ({user_id}) cannot be renewed."
+        }
+        ("renewal_limit_reached", Ja) => {
+            "貸出({checkout_id})は延長回数の上限({max_renewals}回)に達しています。"
+        }
+        ("renewal_limit_reached", En) => {
+            "Checkout ({checkout_id}) has reached the renewal limit ({max_renewals} times)."
+        }
+        ("book_reserved_by_other_user", Ja) => {
+            "書籍({book_id})は他のユーザーが予約しているため延長できません。"
+        }
+        ("book_reserved_by_other_user", En) => {
+            "Book ({book_id}) cannot be renewed because another user has reserved it."
+        }
+        ("checkout_not_returnable", Ja) => {
+            "指定の貸出ID(({checkout_id}), ユーザー({user_id}), 書籍({book_id}))は返却できません。"
+        }
+        ("checkout_not_returnable", En) => {
+            "Checkout ({checkout_id}) of book ({book_id}) by user ({user_id}) cannot be returned."
+        }
+
+        // 予約
+        ("book_already_available", Ja) => "書籍({book_id})はすでにユーザー({user_id})が利用できる状態です。",
+        ("book_already_available", En) => "Book ({book_id}) is already available to user ({user_id}).",
+        ("book_available_for_checkout", Ja) => "書籍({book_id})は貸出可能なため予約できません。",
+        ("book_available_for_checkout", En) => {
+            "Book ({book_id}) is available for checkout and cannot be reserved."
+        }
+        ("reservation_already_exists", Ja) => "書籍({book_id})に対する予約がすでに存在しています。",
+        ("reservation_already_exists", En) => "A reservation for book ({book_id}) already exists.",
+        ("reservation_not_found", Ja) => "書籍({book_id})に対する予約が見つかりませんでした。",
+        ("reservation_not_found", En) => "No reservation for book ({book_id}) was found.",
+
+        // ユーザー・ロール
+        ("user_not_found", Ja) => "ユーザー({user})が見つかりませんでした。",
+        ("user_not_found", En) => "User ({user}) was not found.",
+        ("role_not_found", Ja) => "ロール({role})が見つかりませんでした。",
+        ("role_not_found", En) => "Role ({role}) was not found.",
+        ("role_already_exists", Ja) => "ロール({role})はすでに存在しています。",
+        ("role_already_exists", En) => "Role ({role}) already exists.",
+        ("builtin_role_cannot_be_modified", Ja) => "組み込みのロール({role})は変更・削除できません。",
+        ("builtin_role_cannot_be_modified", En) => "Built-in role ({role}) cannot be modified or deleted.",
+        ("role_in_use", Ja) => "ロール({role})はユーザーに割り当てられているため削除できません。",
+        ("role_in_use", En) => "Role ({role}) is assigned to users and cannot be deleted.",
+        ("session_not_found", Ja) => "セッション({session_id})が見つかりませんでした。",
+        ("session_not_found", En) => "Session ({session_id}) was not found.",
+        ("api_key_not_found", Ja) => "APIキー({api_key_id})が見つかりませんでした。",
+        ("api_key_not_found", En) => "API key ({api_key_id}) was not found.",
+        ("invalid_password_reset_token", Ja) => {
+            "パスワードの再設定用のトークンが無効か、有効期限が切れています。"
+        }
+        ("invalid_password_reset_token", En) => {
+            "The password reset token is invalid or has expired."
+        }
+        ("mfa_already_enabled", Ja) => "2段階認証はすでに有効になっています。",
+        ("mfa_already_enabled", En) => "Two-factor authentication is already enabled.",
+        ("mfa_not_enrolled", Ja) => "2段階認証の登録が開始されていません。",
+        ("mfa_not_enrolled", En) => "Two-factor authentication enrollment has not been started.",
+        ("mfa_not_enabled", Ja) => "2段階認証は有効になっていません。",
+        ("mfa_not_enabled", En) => "Two-factor authentication is not enabled.",
+        ("invalid_mfa_code", Ja) => "認証コードが正しくありません。",
+        ("invalid_mfa_code", En) => "The authentication code is invalid.",
+
+        // メール
+        ("password_reset_mail_subject", Ja) => "パスワードの再設定",
+        ("password_reset_mail_subject", En) => "Reset your password",
+        ("password_reset_mail_body", Ja) => {
+            "パスワードの再設定を受け付けました。\n\n\
+             以下のURLから、{minutes}分以内に新しいパスワードを設定してください。\n\
+             {url}\n\n\
+             このメールに心当たりがない場合は、このメールを破棄してください。パスワードは変更されません。\n"
+        }
+        ("password_reset_mail_body", En) => {
+            "We received a request to reset your password.\n\n\
+             Set a new password within {minutes} minutes using the link below.\n\
+             {url}\n\n\
+             If you did not request this, you can ignore this email. Your password will not be changed.\n"
+        }
+
+        _ => return None,
+    };
+    Some(message)
+}
+
+// garde の検証エラーのメッセージ(英語)を、指定した言語に置き換える
+// 対応していない形式のメッセージはそのまま返す
+pub fn localize_validation_message(message: &str, language: Language) -> Cow<'_, str> {
+    if language == Language::En {
+        return Cow::Borrowed(message);
+    }
+
+    let translated = if let Some(min) = message.strip_prefix("length is lower than ") {
+        format!("{}文字以上で入力してください。", min)
+    } else if let Some(max) = message.strip_prefix("length is greater than ") {
+        format!("{}文字以下で入力してください。", max)
+    } else if let Some(min) = message.strip_prefix("lower than ") {
+        format!("{}以上の値を入力してください。", min)
+    } else if let Some(max) = message.strip_prefix("greater than ") {
+        format!("{}以下の値を入力してください。", max)
+    } else if message.starts_with("not a valid email") {
+        "メールアドレスの形式が正しくありません。".to_string()
+    } else {
+        return Cow::Borrowed(message);
+    };
+    Cow::Owned(translated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_accept_language() {
+        assert_eq!(Language::from_accept_language("en"), Some(Language::En));
+        assert_eq!(Language::from_accept_language("ja-JP"), Some(Language::Ja));
+        assert_eq!(
+            Language::from_accept_language("en-US,en;q=0.9,ja;q=0.8"),
+            Some(Language::En)
+        );
+        assert_eq!(
+            Language::from_accept_language("fr;q=1.0, ja;q=0.5, en;q=0.7"),
+            Some(Language::En)
+        );
+        assert_eq!(Language::from_accept_language("en;q=0, ja;q=0.1"), Some(Language::Ja));
+        assert_eq!(Language::from_accept_language("fr, *"), None);
+    }
+
+    #[test]
+    fn test_localize_message() {
+        let message = Message::new("loan_limit_reached")
+            .arg("user_id", "u1")
+            .arg("max_loans", 5);
+        assert_eq!(
+            message.localize(Language::Ja),
+            "ユーザー(u1)の貸出冊数が上限(5冊)に達しています。"
+        );
+        assert_eq!(
+            message.localize(Language::En),
+            "User (u1) has reached the loan limit (5 books)."
+        );
+        // カタログにないコードは、コードをそのまま返す
+        assert_eq!(Message::new("unknown_code").localize(Language::En), "unknown_code");
+    }
+
+    #[test]
+    fn test_localize_validation_message() {
+        assert_eq!(
+            localize_validation_message("length is lower than 1", Language::Ja),
+            "1文字以上で入力してください。"
+        );
+        assert_eq!(
+            localize_validation_message("length is lower than 1", Language::En),
+            "length is lower than 1"
+        );
+    }
+}
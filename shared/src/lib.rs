@@ -1,3 +1,6 @@
 pub mod config;
 pub mod env;
 pub mod error;
+pub mod i18n;
+pub mod metrics;
+pub mod request_id;
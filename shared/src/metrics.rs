@@ -0,0 +1,102 @@
+// Prometheusに公開するメトリクスを保持する
+// HTTPリクエストやRedisの呼び出しは発生の都度記録し、
+// コネクションプールや貸出件数のような現在値は`/metrics`の取得時に更新する
+
+use std::time::Duration;
+
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntGauge, IntGaugeVec, Opts, Registry, TextEncoder,
+};
+
+use crate::error::AppResult;
+
+pub const METRICS_CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+
+pub struct Metrics {
+    registry: Registry,
+    http_request_duration: HistogramVec,
+    redis_command_duration: HistogramVec,
+    db_pool_connections: IntGaugeVec,
+    active_checkouts: IntGauge,
+    overdue_checkouts: IntGauge,
+}
+
+impl Metrics {
+    pub fn new() -> AppResult<Self> {
+        let registry = Registry::new_custom(Some("book_manager".into()), None)?;
+
+        let http_request_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "http_request_duration_seconds",
+                "HTTPリクエストの処理時間",
+            ),
+            &["method", "route", "status"],
+        )?;
+        let redis_command_duration = HistogramVec::new(
+            HistogramOpts::new("redis_command_duration_seconds", "Redisの呼び出し時間")
+                .buckets(vec![0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25]),
+            &["command", "result"],
+        )?;
+        let db_pool_connections = IntGaugeVec::new(
+            Opts::new("db_pool_connections", "データベースのコネクションプールの接続数"),
+            &["state"],
+        )?;
+        let active_checkouts =
+            IntGauge::new("active_checkouts", "貸出中(未返却)の冊子の数")?;
+        let overdue_checkouts =
+            IntGauge::new("overdue_checkouts", "返却期限を過ぎた貸出の数")?;
+
+        registry.register(Box::new(http_request_duration.clone()))?;
+        registry.register(Box::new(redis_command_duration.clone()))?;
+        registry.register(Box::new(db_pool_connections.clone()))?;
+        registry.register(Box::new(active_checkouts.clone()))?;
+        registry.register(Box::new(overdue_checkouts.clone()))?;
+
+        Ok(Self {
+            registry,
+            http_request_duration,
+            redis_command_duration,
+            db_pool_connections,
+            active_checkouts,
+            overdue_checkouts,
+        })
+    }
+
+    // routeにはパスパラメーターを含まないルートのテンプレート(/api/v1/books/:book_id など)を渡す
+    pub fn observe_http_request(&self, method: &str, route: &str, status: u16, elapsed: Duration) {
+        self.http_request_duration
+            .with_label_values(&[method, route, &status.to_string()])
+            .observe(elapsed.as_secs_f64());
+    }
+
+    pub fn observe_redis_command(&self, command: &str, succeeded: bool, elapsed: Duration) {
+        let result = if succeeded { "ok" } else { "error" };
+        self.redis_command_duration
+            .with_label_values(&[command, result])
+            .observe(elapsed.as_secs_f64());
+    }
+
+    pub fn set_db_pool_connections(&self, size: u32, idle: usize, max: u32) {
+        let size = i64::from(size);
+        let idle = idle as i64;
+        self.db_pool_connections
+            .with_label_values(&["active"])
+            .set(size - idle);
+        self.db_pool_connections.with_label_values(&["idle"]).set(idle);
+        self.db_pool_connections
+            .with_label_values(&["max"])
+            .set(i64::from(max));
+    }
+
+    pub fn set_checkouts(&self, active: i64, overdue: i64) {
+        self.active_checkouts.set(active);
+        self.overdue_checkouts.set(overdue);
+    }
+
+    // Prometheusのテキスト形式で書き出す
+    pub fn render(&self) -> AppResult<String> {
+        let mut buffer = vec![];
+        TextEncoder::new().encode(&self.registry.gather(), &mut buffer)?;
+        Ok(String::from_utf8_lossy(&buffer).into_owned())
+    }
+}
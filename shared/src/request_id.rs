@@ -0,0 +1,24 @@
+// リクエストごとに振るIDを、処理中のタスクから参照できるようにする
+// エラーレスポンスやログに含め、クライアントからの問い合わせとサーバーのログを突き合わせるために使う
+
+use std::future::Future;
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+pub fn generate() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
+
+// 指定したリクエストIDを参照できる状態で処理を実行する
+pub async fn scope<F: Future>(request_id: String, f: F) -> F::Output {
+    REQUEST_ID.scope(request_id, f).await
+}
+
+// 処理中のリクエストのIDを返す。リクエストの外から呼ばれた場合はNoneとなる
+pub fn current() -> Option<String> {
+    REQUEST_ID.try_with(Clone::clone).ok()
+}
@@ -1,21 +1,41 @@
 use std::{
     net::{Ipv4Addr, SocketAddr},
     sync::Arc,
+    time::Duration,
 };
 
-use adapter::{database::connect_database_with, redis::RedisClient};
+use adapter::{
+    database::connect_database_with,
+    redis::RedisClient,
+    repository::{mailer::SmtpMailer, metadata::BookMetadataProviderImpl},
+};
 use anyhow::{Context, Result};
-use axum::{http::Method, Router};
-use registry::AppRegistryImpl;
-use shared::config::AppConfig;
+use axum::{
+    http::{HeaderMap, Method, Request},
+    middleware,
+    routing::get,
+    Extension, Json, Router,
+};
+use opentelemetry::{global, propagation::Extractor};
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use registry::{AppRegistry, AppRegistryImpl};
+use shared::{config::AppConfig, metrics::Metrics, request_id::REQUEST_ID_HEADER};
 use shared::env::{which, Environment};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
 use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::util::SubscriberInitExt;
 use tracing_subscriber::EnvFilter;
 
-use api::route::{auth, v1};
+use api::{
+    extractor::TrustedProxies,
+    middleware::{assign_request_id, negotiate_language, track_http_metrics},
+    openapi::ApiDoc,
+    route::{auth, metrics, v1},
+};
+use utoipa::OpenApi;
+use utoipa_redoc::{Redoc, Servable};
 
-use tower_http::trace::{DefaultMakeSpan, DefaultOnRequest, DefaultOnResponse, TraceLayer};
+use tower_http::trace::{DefaultOnRequest, DefaultOnResponse, TraceLayer};
 use tower_http::LatencyUnit;
 use tower_http::cors::{self, CorsLayer};
 use tracing::Level;
@@ -23,7 +43,10 @@ use tracing::Level;
 #[tokio::main]
 async fn main() -> Result<()> {
     init_logger()?;
-    bootstrap().await
+    let result = bootstrap().await;
+    // 終了前に、送信待ちのスパンを書き出す
+    global::shutdown_tracer_provider();
+    result
 }
 
 // ロガーを初期化する関数
@@ -42,14 +65,69 @@ fn init_logger() -> Result<()> {
         .with_line_number(true)
         .with_target(false);
 
+    // 受け取ったリクエストのtraceparentヘッダーから、トレースIDを引き継ぐ
+    global::set_text_map_propagator(TraceContextPropagator::new());
+
+    // JAEGER_HOSTとJAEGER_PORTが設定されている場合のみ、スパンをJaegerに送信する
+    let opentelemetry = match (std::env::var("JAEGER_HOST"), std::env::var("JAEGER_PORT")) {
+        (Ok(host), Ok(port)) => {
+            let tracer = opentelemetry_jaeger::new_agent_pipeline()
+                .with_endpoint(format!("{host}:{port}"))
+                .with_service_name("book-manager")
+                .with_auto_split_batch(true)
+                .with_max_packet_size(8192)
+                .install_simple()?;
+            Some(tracing_opentelemetry::layer().with_tracer(tracer))
+        }
+        _ => None,
+    };
+
     tracing_subscriber::registry()
         .with(subscriber)
         .with(env_filter)
+        .with(opentelemetry)
         .try_init()?;
 
     Ok(())
 }
 
+// リクエストごとのスパンを作成する
+// traceparentヘッダーがあれば、呼び出し元のトレースの子スパンとする
+fn make_request_span<B>(request: &Request<B>) -> tracing::Span {
+    let parent = global::get_text_map_propagator(|propagator| {
+        propagator.extract(&HeaderExtractor(request.headers()))
+    });
+
+    let request_id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+
+    let span = tracing::info_span!(
+        "request",
+        method = %request.method(),
+        uri = %request.uri(),
+        version = ?request.version(),
+        request_id,
+    );
+    span.set_parent(parent);
+    span
+}
+
+// HTTPヘッダーからトレースの伝搬情報を読み出すための型
+struct HeaderExtractor<'a>(&'a HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|key| key.as_str()).collect()
+    }
+}
+
 fn cors() -> CorsLayer {
     CorsLayer::new()
         .allow_headers(cors::Any)
@@ -57,6 +135,27 @@ fn cors() -> CorsLayer {
         .allow_origin(cors::Any)
 }
 
+// 取り置き期限の切れた予約を片付け、次の予約者に取り置きを回す処理を、interval秒ごとに実行する
+// 失敗した場合はログに残し、次の実行で改めて片付ける
+fn spawn_reservation_expiry(registry: AppRegistry, interval: u64) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(Duration::from_secs(interval.max(1)));
+        loop {
+            ticker.tick().await;
+            if let Err(e) = registry
+                .reservation_repository()
+                .expire_holds(chrono::Utc::now())
+                .await
+            {
+                tracing::error!(
+                    error.message = %e,
+                    "Failed to expire reservation holds"
+                );
+            }
+        }
+    });
+}
+
 // サーバー起動分のログを生成する
 async fn bootstrap() -> Result<()> {
     // `AppConfig`を生成させる
@@ -65,18 +164,62 @@ async fn bootstrap() -> Result<()> {
     // DBへの接続を行う、コネクションプールを取り出す
     let pool = connect_database_with(&app_config.database);
     
-    let kv = Arc::new(RedisClient::new(&app_config.redis)?);
+    // メトリクスはRedisの呼び出しとHTTPリクエストの双方で記録するため、先に生成して共有する
+    let metrics = Arc::new(Metrics::new()?);
+
+    let kv = Arc::new(RedisClient::new(&app_config.redis, metrics.clone())?);
+
+    // SMTPサーバーへの接続はメールの送信時に行う
+    let mailer = Arc::new(SmtpMailer::new(&app_config.mail)?);
+
+    // 外部APIへの接続は書誌情報の取得時に行う
+    let book_metadata_provider = Arc::new(BookMetadataProviderImpl::new(
+        kv.clone(),
+        &app_config.book_metadata,
+    )?);
+
+    // エラーメッセージの既定の言語は、AppConfigを`AppRegistry`に渡す前に取り出しておく
+    let default_language = app_config.i18n.default_language;
+    let trusted_proxies = TrustedProxies(Arc::new(app_config.auth.trusted_proxies.clone()));
+    let reservation_expiry_interval = app_config.reservation.expiry_interval;
 
     // `AppRegistry`を生成する
-    let registry = Arc::new(AppRegistryImpl::new(pool, kv, app_config));
+    let registry: AppRegistry = Arc::new(AppRegistryImpl::new(
+        pool,
+        kv,
+        mailer,
+        book_metadata_provider,
+        metrics,
+        app_config,
+    ));
+
+    // 蔵書に対する操作がなくても予約の待ち行列が進むよう、取り置き期限を定期的に確認する
+    spawn_reservation_expiry(registry.clone(), reservation_expiry_interval);
 
     // `build_health_check_routers`関数をcall. `AppRegistry`を`Router`に登録。
     let app = Router::new()
         .merge(v1::routes())
         .merge(auth::routes())
+        // ここまでに登録したルートのリクエストを計測する
+        .route_layer(middleware::from_fn_with_state(
+            registry.clone(),
+            track_http_metrics,
+        ))
+        // エラーメッセージをリクエストの言語で返す
+        .layer(middleware::from_fn_with_state(
+            default_language,
+            negotiate_language,
+        ))
+        .merge(metrics::routes())
+        // OpenAPIの仕様書をJSONで返し、ReDocで閲覧できるようにする
+        .merge(Redoc::with_url("/docs", ApiDoc::openapi()))
+        .route(
+            "/api-docs/openapi.json",
+            get(|| async { Json(ApiDoc::openapi()) }),
+        )
         .layer(
             TraceLayer::new_for_http()
-                .make_span_with(DefaultMakeSpan::new().level(Level::INFO))
+                .make_span_with(make_request_span)
                 .on_request(DefaultOnRequest::new().level(Level::INFO))
                 .on_response(
                     DefaultOnResponse::new()
@@ -84,6 +227,10 @@ async fn bootstrap() -> Result<()> {
                         .latency_unit(LatencyUnit::Millis),
                 ),
         )
+        // ログインした端末の接続元を求める際に、`X-Forwarded-For`を信頼するプロキシを参照できるようにする
+        .layer(Extension(trusted_proxies))
+        // ログやエラーレスポンスから参照できるよう、最初にリクエストIDを振る
+        .layer(middleware::from_fn(assign_request_id))
         // 以下にリクエストとレスポンス時にログを出力するレイヤーを追加する
         .layer(cors())
         .with_state(registry); // AppRegistry
@@ -94,7 +241,11 @@ async fn bootstrap() -> Result<()> {
 
     // prinln!からtracing::info!に変更
     tracing::info!("Listening on {}", addr);
-    axum::serve(listener, app)
+    // ログインした端末の接続元を記録できるよう、接続元のアドレスをリクエストに含める
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
         .await
         .context("Unexpected error happened in server")
         // 起動失敗した際のエラーログをtracing::error!で出力